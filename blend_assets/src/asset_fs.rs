@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// What `AssetFs::metadata` reports about a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetMetadata {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Filesystem access used when checking, resolving and reading linked assets.
+///
+/// `LocalFs` goes through `std::fs`; other implementations can serve assets
+/// from object stores or memory.
+pub trait AssetFs {
+    fn metadata(&self, path: &Path) -> io::Result<AssetMetadata>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Full paths of the entries directly inside `dir`
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Missing files are `Ok(false)`; other failures are errors
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        match self.metadata(path) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stream a file's contents into `out`, returning the bytes copied
+    fn copy_in(&self, path: &Path, out: &mut dyn Write) -> io::Result<u64> {
+        let data = self.read(path)?;
+        out.write_all(&data)?;
+        Ok(data.len() as u64)
+    }
+}
+
+/// `AssetFs` over `std::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalFs;
+
+impl AssetFs for LocalFs {
+    fn metadata(&self, path: &Path) -> io::Result<AssetMetadata> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(AssetMetadata {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect()
+    }
+
+    fn copy_in(&self, path: &Path, out: &mut dyn Write) -> io::Result<u64> {
+        io::copy(&mut std::fs::File::open(path)?, out)
+    }
+}
+
+/// In-memory `AssetFs` for tests. Paths are normalized lexically and
+/// directories exist implicitly as parents of files.
+#[derive(Debug, Clone, Default)]
+pub struct MemFs {
+    files: BTreeMap<PathBuf, MemFile>,
+}
+
+#[derive(Debug, Clone)]
+struct MemFile {
+    data: Vec<u8>,
+    modified: Option<SystemTime>,
+}
+
+impl MemFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, data);
+        self
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+        self.files.insert(
+            normalize(path.as_ref()),
+            MemFile {
+                data: data.into(),
+                modified: None,
+            },
+        );
+    }
+
+    /// Set a file's modification time; returns false if it doesn't exist
+    pub fn set_modified(&mut self, path: impl AsRef<Path>, modified: SystemTime) -> bool {
+        match self.files.get_mut(&normalize(path.as_ref())) {
+            Some(file) => {
+                file.modified = Some(modified);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn file(&self, path: &Path) -> io::Result<&MemFile> {
+        self.files
+            .get(&normalize(path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    fn is_dir(&self, dir: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|path| path.starts_with(dir) && path != dir)
+    }
+}
+
+impl AssetFs for MemFs {
+    fn metadata(&self, path: &Path) -> io::Result<AssetMetadata> {
+        let file = self.file(path)?;
+        Ok(AssetMetadata {
+            len: file.data.len() as u64,
+            modified: file.modified,
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        Ok(self.file(path)?.data.clone())
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let normalized = normalize(path);
+        if self.files.contains_key(&normalized) || self.is_dir(&normalized) {
+            Ok(normalized)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                path.display().to_string(),
+            ))
+        }
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let dir = normalize(dir);
+        if !self.is_dir(&dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                dir.display().to_string(),
+            ));
+        }
+        let mut entries: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|path| {
+                let child = path.strip_prefix(&dir).ok()?.components().next()?;
+                Some(dir.join(child))
+            })
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+}
+
+/// Drop `.` and fold `..` without touching the disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
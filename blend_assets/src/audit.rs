@@ -0,0 +1,196 @@
+//! An audit trail kept inside the file: saves made with
+//! `SaveOptions::with_audit` append a line to an `RBAT` block recording when
+//! the file was saved, by which tool, and hashes of each changed field before
+//! and after. Blender skips block codes it doesn't know when loading, though
+//! it won't write them back; this crate keeps the block like any other.
+
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::consistency::holds_structs;
+use crate::dna::Dna;
+use crate::error::{FormatError, Result};
+use crate::index::{fnv1a, FNV_OFFSET};
+use crate::merge::id_name;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+
+/// Code of the block holding the audit log
+pub const AUDIT_CODE: [u8; 4] = *b"RBAT";
+
+/// Tool recorded when `SaveOptions::audit_tool` isn't set
+pub const DEFAULT_AUDIT_TOOL: &str =
+    concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// One audited save
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Name and version of the tool that saved
+    pub tool: String,
+    pub changes: Vec<AuditChange>,
+}
+
+/// A field, or a whole block when the change couldn't be traced to fields,
+/// with FNV-1a hashes of its bytes as 16 hex digits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditChange {
+    /// ID name of the block (`OBhero`), or its code for blocks without one
+    pub block: String,
+    /// Path of the field in the block's struct, e.g. `id.name`; `[i].` leads
+    /// it in blocks of several structs
+    pub field: Option<String>,
+    /// `None` when the bytes before the change weren't kept; open the file
+    /// with `keep_originals` to have them
+    pub before: Option<String>,
+    pub after: String,
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {}, {} change(s)",
+            self.timestamp.to_rfc3339(),
+            self.tool,
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            writeln!(f, "  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AuditChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.block)?;
+        if let Some(field) = &self.field {
+            write!(f, " {field}")?;
+        }
+        let before = self.before.as_deref().unwrap_or("?");
+        write!(f, ": {before} -> {}", self.after)
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(FNV_OFFSET, bytes))
+}
+
+impl BlendFile {
+    /// Every audited save recorded in the file, oldest first; empty when
+    /// none was
+    pub fn audit_log(&self) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+        for block in self.blocks.iter().filter(|b| b.code == AUDIT_CODE) {
+            for line in block.data.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+                let entry = serde_json::from_slice(line)
+                    .map_err(|e| FormatError::ParseError(format!("audit log entry: {e}")))?;
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// The changes a save would write now
+    pub(crate) fn audit_changes(&self) -> Vec<AuditChange> {
+        let mut changes = Vec::new();
+        for index in self.modified_blocks() {
+            let Some(block) = self.blocks.get(index).filter(|b| b.code != AUDIT_CODE) else {
+                continue;
+            };
+            let name = id_name(&self.dna, block)
+                .unwrap_or_else(|| block.get_type_name().trim_end_matches('\0').to_string());
+            let original = self.original_data(index);
+            let ranges = self.changed_ranges(index).unwrap_or_default();
+            if original.is_some() && ranges.is_empty() {
+                continue;
+            }
+            // Without the original nothing says which bytes changed, so the
+            // whole block is recorded
+            let fields = changed_fields(&self.dna, block, &ranges);
+            if fields.is_empty() {
+                changes.push(AuditChange {
+                    block: name,
+                    field: None,
+                    before: original.map(hash),
+                    after: hash(&block.data),
+                });
+                continue;
+            }
+            for (field, span) in fields {
+                changes.push(AuditChange {
+                    block: name.clone(),
+                    field: Some(field),
+                    before: original.and_then(|data| data.get(span.clone())).map(hash),
+                    after: hash(block.data.get(span).unwrap_or_default()),
+                });
+            }
+        }
+        changes
+    }
+
+    /// Append an entry for the unsaved changes to the `RBAT` block, adding
+    /// the block if the file has none. Nothing is recorded without changes.
+    pub(crate) fn record_audit_entry(&mut self, tool: &str) -> Result<()> {
+        let changes = self.audit_changes();
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            changes,
+        };
+        let mut line = serde_json::to_vec(&entry)
+            .map_err(|e| FormatError::ParseError(format!("audit log entry: {e}")))?;
+        line.push(b'\n');
+        match self.blocks.iter_mut().rfind(|b| b.code == AUDIT_CODE) {
+            Some(block) => {
+                block.data.extend_from_slice(&line);
+                block.size = block.data.len() as u32;
+            }
+            None => {
+                self.push_block(AUDIT_CODE, 0, line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fields of `block` overlapping `ranges`, through embedded structs, with
+/// their byte ranges in the payload
+fn changed_fields(
+    dna: &Dna,
+    block: &Block,
+    ranges: &[Range<usize>],
+) -> Vec<(String, Range<usize>)> {
+    let mut out = Vec::new();
+    if !holds_structs(block) {
+        return out;
+    }
+    let Some(layout) = dna.struct_by_index(block.sdna_index as usize) else {
+        return out;
+    };
+    let fields = layout.flattened_fields(dna);
+    for i in 0..block.count as usize {
+        let base = i * layout.size;
+        for flat in &fields {
+            let span = base + flat.offset..base + flat.offset + flat.field.size;
+            if !ranges
+                .iter()
+                .any(|r| r.start < span.end && span.start < r.end)
+            {
+                continue;
+            }
+            let path = if block.count > 1 {
+                format!("[{i}].{}", flat.path)
+            } else {
+                flat.path.clone()
+            };
+            out.push((path, span));
+        }
+    }
+    out
+}
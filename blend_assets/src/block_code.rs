@@ -0,0 +1,160 @@
+use serde::Serialize;
+
+/// What a block code stands for in the versions `first..end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BlockCodeInfo {
+    /// Code as written in the block header, without padding
+    pub code: &'static str,
+    /// Name shown to users, as in Blender's UI
+    pub name: &'static str,
+    /// DNA struct of the block payload, for codes that have one
+    pub struct_name: Option<&'static str>,
+    /// First file version using this meaning
+    pub first: u32,
+    /// First file version no longer using it; `u32::MAX` if still current
+    pub end: u32,
+}
+
+impl BlockCodeInfo {
+    pub fn applies_to(&self, version: u32) -> bool {
+        (self.first..self.end).contains(&version)
+    }
+}
+
+const fn entry(
+    code: &'static str,
+    name: &'static str,
+    struct_name: &'static str,
+    first: u32,
+    end: u32,
+) -> BlockCodeInfo {
+    BlockCodeInfo {
+        code,
+        name,
+        struct_name: Some(struct_name),
+        first,
+        end,
+    }
+}
+
+/// A code with no DNA struct, like `DNA1` or `ENDB`
+const fn raw(code: &'static str, name: &'static str) -> BlockCodeInfo {
+    BlockCodeInfo {
+        code,
+        name,
+        struct_name: None,
+        first: 0,
+        end: u32::MAX,
+    }
+}
+
+const ALWAYS: u32 = u32::MAX;
+
+/// ID codes from `DNA_ID_enums.h`, followed by the file-level codes.
+/// Codes whose meaning changed appear once per version range.
+const REGISTRY: &[BlockCodeInfo] = &[
+    entry("AC", "Action", "bAction", 0, ALWAYS),
+    entry("AR", "Armature", "bArmature", 0, ALWAYS),
+    entry("BR", "Brush", "Brush", 0, ALWAYS),
+    entry("CA", "Camera", "Camera", 0, ALWAYS),
+    entry("CF", "Cache File", "CacheFile", 278, ALWAYS),
+    entry("CU", "Curve", "Curve", 0, ALWAYS),
+    entry("CV", "Curves", "Curves", 301, ALWAYS),
+    entry("GD", "Grease Pencil (legacy)", "bGPdata", 0, ALWAYS),
+    entry("GP", "Grease Pencil", "GreasePencil", 403, ALWAYS),
+    entry("GR", "Group", "Group", 0, 280),
+    entry("GR", "Collection", "Collection", 280, ALWAYS),
+    entry("HA", "Hair", "Hair", 283, 301),
+    entry("IM", "Image", "Image", 0, ALWAYS),
+    entry("IP", "Ipo", "Ipo", 0, 250),
+    entry("KE", "Shape Key", "Key", 0, ALWAYS),
+    entry("LA", "Lamp", "Lamp", 0, 280),
+    entry("LA", "Light", "Light", 280, ALWAYS),
+    entry("LI", "Library", "Library", 0, ALWAYS),
+    entry("LP", "Light Probe", "LightProbe", 280, ALWAYS),
+    entry("LS", "Line Style", "FreestyleLineStyle", 267, ALWAYS),
+    entry("LT", "Lattice", "Lattice", 0, ALWAYS),
+    entry("MA", "Material", "Material", 0, ALWAYS),
+    entry("MB", "Metaball", "MetaBall", 0, ALWAYS),
+    entry("MC", "Movie Clip", "MovieClip", 261, ALWAYS),
+    entry("ME", "Mesh", "Mesh", 0, ALWAYS),
+    entry("MS", "Mask", "Mask", 263, ALWAYS),
+    entry("NT", "Node Tree", "bNodeTree", 250, ALWAYS),
+    entry("OB", "Object", "Object", 0, ALWAYS),
+    entry("PA", "Particle Settings", "ParticleSettings", 246, ALWAYS),
+    entry("PC", "Paint Curve", "PaintCurve", 272, ALWAYS),
+    entry("PL", "Palette", "Palette", 272, ALWAYS),
+    entry("PT", "Point Cloud", "PointCloud", 283, ALWAYS),
+    entry("SC", "Scene", "Scene", 0, ALWAYS),
+    entry("SI", "Simulation", "Simulation", 291, 300),
+    entry("SK", "Speaker", "Speaker", 256, ALWAYS),
+    entry("SO", "Sound", "bSound", 0, ALWAYS),
+    entry("SR", "Screen", "bScreen", 0, ALWAYS),
+    entry("TE", "Texture", "Tex", 0, ALWAYS),
+    entry("TX", "Text", "Text", 0, ALWAYS),
+    entry("VF", "Font", "VFont", 0, ALWAYS),
+    entry("VO", "Volume", "Volume", 283, ALWAYS),
+    entry("WM", "Window Manager", "wmWindowManager", 250, ALWAYS),
+    entry("WO", "World", "World", 0, ALWAYS),
+    entry("WS", "Workspace", "WorkSpace", 280, ALWAYS),
+    raw("DATA", "Data"),
+    raw("DNA1", "SDNA"),
+    raw("ENDB", "End of File"),
+    entry("GLOB", "File Global", "FileGlobal", 0, ALWAYS),
+    // Written by this crate, not Blender; see `audit`
+    raw("RBAT", "Audit Log"),
+    raw("REND", "Render Info"),
+    raw("TEST", "Thumbnail"),
+    entry("USER", "User Preferences", "UserDef", 0, ALWAYS),
+];
+
+/// Every known block code: ID codes by code and version, then file-level codes
+pub fn block_code_registry() -> &'static [BlockCodeInfo] {
+    REGISTRY
+}
+
+/// What `code` means in files saved by `version`.
+///
+/// ID blocks are matched on their first two bytes, so `GR\0\0` and `GRxx`
+/// both find the collection entry.
+pub fn lookup(code: &[u8], version: u32) -> Option<&'static BlockCodeInfo> {
+    let trimmed = trim_padding(code);
+    REGISTRY.iter().find(|info| {
+        let known = info.code.as_bytes();
+        let matches = if known.len() == 2 {
+            trimmed.len() >= 2 && &trimmed[..2] == known && !is_file_code(trimmed)
+        } else {
+            trimmed == known
+        };
+        matches && info.applies_to(version)
+    })
+}
+
+/// Friendly name of `code` in `version`, or the code itself when unknown
+pub fn display_name(code: &[u8], version: u32) -> String {
+    lookup(code, version).map_or_else(|| raw_code(code), |info| info.name.to_string())
+}
+
+/// `code` without its NUL padding, with non-printable bytes escaped as `\xNN`
+pub fn raw_code(code: &[u8]) -> String {
+    trim_padding(code)
+        .iter()
+        .map(|&b| match b {
+            b' '..=b'~' => (b as char).to_string(),
+            _ => format!("\\x{b:02x}"),
+        })
+        .collect()
+}
+
+fn trim_padding(code: &[u8]) -> &[u8] {
+    let end = code.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    &code[..end]
+}
+
+/// Four-letter codes whose first two letters could be mistaken for an ID code
+fn is_file_code(code: &[u8]) -> bool {
+    code.len() == 4
+        && REGISTRY
+            .iter()
+            .any(|info| info.code.len() == 4 && info.code.as_bytes() == code)
+}
@@ -0,0 +1,216 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::bytes::read_f32_at;
+use crate::convert::read_uint;
+use crate::debug::id_name;
+use crate::dna::{Dna, DnaField};
+use crate::error::{FormatError, Result};
+use crate::scene::locate;
+use serde::Serialize;
+
+/// Projection of a camera, `Camera.type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CameraType {
+    Persp,
+    Ortho,
+    Pano,
+    /// A value this reader does not know
+    Other(u8),
+}
+
+impl CameraType {
+    fn from_raw(value: u8) -> Self {
+        match value {
+            0 => CameraType::Persp,
+            1 => CameraType::Ortho,
+            2 => CameraType::Pano,
+            other => CameraType::Other(other),
+        }
+    }
+}
+
+impl std::fmt::Display for CameraType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CameraType::Persp => write!(f, "persp"),
+            CameraType::Ortho => write!(f, "ortho"),
+            CameraType::Pano => write!(f, "pano"),
+            CameraType::Other(value) => write!(f, "type {value}"),
+        }
+    }
+}
+
+/// Intrinsics of a camera datablock
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CameraInfo {
+    /// Camera name without the `CA` ID prefix
+    pub name: String,
+    /// Old memory address of the `CA` block
+    pub address: u64,
+    #[serde(rename = "type")]
+    pub camera_type: CameraType,
+    /// Focal length in millimetres
+    pub lens: f32,
+    /// Sensor size in millimetres
+    pub sensor_width: f32,
+    pub sensor_height: f32,
+    /// Lens shift, as a fraction of the larger sensor dimension
+    pub shift_x: f32,
+    pub shift_y: f32,
+    pub clip_start: f32,
+    pub clip_end: f32,
+}
+
+/// The camera a scene renders through
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActiveCamera {
+    /// Scene name without the `SC` ID prefix
+    pub scene: String,
+    /// Name of the camera object, without the `OB` ID prefix
+    pub object: String,
+    /// World location from the object's transform matrix
+    pub location: [f32; 3],
+    pub camera: CameraInfo,
+}
+
+impl BlendFile {
+    /// Read every `CA` block through DNA. Files without DNA have no readable cameras.
+    pub fn cameras(&self) -> Result<Vec<CameraInfo>> {
+        if self.dna.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_blocks_by_type(b"CA")?
+            .into_iter()
+            .map(|block| self.camera_info(block))
+            .collect()
+    }
+
+    /// Follow `Scene.camera` to its object and the object's camera data.
+    /// `None` when the scene has no camera or it points at a missing block.
+    pub fn scene_active_camera(&self, scene_name: &str) -> Result<Option<ActiveCamera>> {
+        let scene = self
+            .get_blocks_by_type(b"SC")?
+            .into_iter()
+            .find(|block| id_name(&self.dna, block).as_deref() == Some(scene_name))
+            .ok_or_else(|| FormatError::BlockError(format!("no scene named {scene_name}")))?;
+        self.active_camera_of(scene, scene_name)
+    }
+
+    /// The active camera of every scene that has one
+    pub fn active_cameras(&self) -> Result<Vec<ActiveCamera>> {
+        if self.dna.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut cameras = Vec::new();
+        for scene in self.get_blocks_by_type(b"SC")? {
+            let name = id_name(&self.dna, scene).unwrap_or_default();
+            cameras.extend(self.active_camera_of(scene, &name)?);
+        }
+        Ok(cameras)
+    }
+
+    fn active_camera_of(&self, scene: &Block, scene_name: &str) -> Result<Option<ActiveCamera>> {
+        let Some(object) = self.pointee(scene, "Scene", "camera", b"OB")? else {
+            return Ok(None);
+        };
+        let Some(camera) = self.pointee(object, "Object", "data", b"CA")? else {
+            return Ok(None);
+        };
+
+        Ok(Some(ActiveCamera {
+            scene: scene_name.to_string(),
+            object: id_name(&self.dna, object).unwrap_or_default(),
+            location: self.object_location(object)?,
+            camera: self.camera_info(camera)?,
+        }))
+    }
+
+    fn camera_info(&self, block: &Block) -> Result<CameraInfo> {
+        let float = |names: &[&str]| -> Result<f32> {
+            let (offset, field) = locate_any(&self.dna, "Camera", names)?;
+            self.read_f32(block, offset, field)
+        };
+        let (type_offset, _) = locate(&self.dna, "Camera", &["type"])?;
+        let camera_type = *block.data.get(type_offset).ok_or_else(|| {
+            FormatError::BlockError("camera block shorter than Camera.type".to_string())
+        })?;
+
+        Ok(CameraInfo {
+            name: id_name(&self.dna, block).unwrap_or_default(),
+            address: block.old_memory_address,
+            camera_type: CameraType::from_raw(camera_type),
+            lens: float(&["lens"])?,
+            sensor_width: float(&["sensor_x"])?,
+            sensor_height: float(&["sensor_y"])?,
+            shift_x: float(&["shiftx"])?,
+            shift_y: float(&["shifty"])?,
+            // Blender renamed these in 2.80; files keep the old names
+            clip_start: float(&["clipsta", "clip_start"])?,
+            clip_end: float(&["clipend", "clip_end"])?,
+        })
+    }
+
+    /// Translation row of `Object.obmat`, or `Object.loc` in DNA without it
+    fn object_location(&self, object: &Block) -> Result<[f32; 3]> {
+        let (offset, field, start) =
+            match locate_any(&self.dna, "Object", &["obmat", "object_to_world"]) {
+                Ok((offset, field)) => (offset, field, 12),
+                Err(_) => {
+                    let (offset, field) = locate(&self.dna, "Object", &["loc"])?;
+                    (offset, field, 0)
+                }
+            };
+        let mut location = [0.0; 3];
+        for (i, value) in location.iter_mut().enumerate() {
+            *value = self.read_f32(object, offset + (start + i) * 4, field)?;
+        }
+        Ok(location)
+    }
+
+    /// The block `struct_name.field` in `block` points at, if it has code `code`
+    fn pointee(
+        &self,
+        block: &Block,
+        struct_name: &str,
+        field: &str,
+        code: &[u8; 2],
+    ) -> Result<Option<&Block>> {
+        let (offset, dna_field) = locate(&self.dna, struct_name, &[field])?;
+        let bytes = block
+            .data
+            .get(offset..offset + dna_field.size)
+            .ok_or_else(|| {
+                FormatError::BlockError(format!("block shorter than {struct_name}.{field}"))
+            })?;
+        let address = read_uint(bytes, self.header.endianness);
+        if address == 0 {
+            return Ok(None);
+        }
+        Ok(self
+            .blocks
+            .iter()
+            .find(|b| b.old_memory_address == address && b.code.starts_with(code)))
+    }
+
+    fn read_f32(&self, block: &Block, offset: usize, field: &DnaField) -> Result<f32> {
+        read_f32_at(&block.data, offset, self.header.endianness).map_err(|_| {
+            FormatError::BlockError(format!("no float for {} at offset {offset}", field.name))
+                .into()
+        })
+    }
+}
+
+/// `locate` for the first of `names` present in `struct_name`
+fn locate_any<'a>(
+    dna: &'a Dna,
+    struct_name: &str,
+    names: &[&str],
+) -> Result<(usize, &'a DnaField)> {
+    names
+        .iter()
+        .find_map(|name| locate(dna, struct_name, &[name]).ok())
+        .ok_or_else(|| {
+            FormatError::DnaError(format!("{struct_name}.{} not in DNA", names.join("/"))).into()
+        })
+}
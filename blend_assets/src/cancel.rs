@@ -0,0 +1,103 @@
+use crate::error::{BlendFileError, Result};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Stops a long-running operation, either on request or once a deadline passes.
+///
+/// Clones share the cancelled state, so one can be handed to the operation
+/// and another kept to call `cancel` from elsewhere. Operations check it at
+/// block boundaries and return `BlendFileError::Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel once `timeout` has passed from now
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        // A timeout too large to represent never expires
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.with_deadline(deadline),
+            None => self,
+        }
+    }
+
+    /// Cancel once `deadline` is reached
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Cancel every operation holding this handle or a clone of it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// `Err(BlendFileError::Cancelled)` once cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(BlendFileError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// Check an optional handle, for options structs where cancellation is opt-in
+pub(crate) fn check(cancellation: Option<&Cancellation>) -> Result<()> {
+    cancellation.map_or(Ok(()), Cancellation::check)
+}
+
+/// A reader that fails once cancelled, so decompressing a huge stream stops
+/// between reads instead of after the whole file
+pub(crate) struct CancellableReader<R> {
+    inner: R,
+    cancellation: Cancellation,
+}
+
+impl<R> CancellableReader<R> {
+    pub(crate) fn new(inner: R, cancellation: Cancellation) -> Self {
+        Self {
+            inner,
+            cancellation,
+        }
+    }
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancellation.is_cancelled() {
+            return Err(io::Error::other("read cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Read `reader` to the end, returning `BlendFileError::Cancelled` if a
+/// `CancellableReader` inside it was cancelled
+pub(crate) fn read_all(
+    reader: &mut dyn Read,
+    cancellation: Option<&Cancellation>,
+) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match reader.read_to_end(&mut data) {
+        Ok(_) => Ok(data),
+        Err(_) if cancellation.is_some_and(Cancellation::is_cancelled) => {
+            Err(BlendFileError::Cancelled)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,1319 @@
+//! The `blend-file-reader` command line
+
+use crate::asset_fs::LocalFs;
+use crate::blend_file::{OpenOptions, SummaryOrder};
+use crate::block_code::{self, block_code_registry, BlockCodeInfo};
+use crate::cancel::Cancellation;
+use crate::config::RbatConfig;
+use crate::consistency::incomplete_structs;
+use crate::debug::{decode_block, DecodedStruct};
+use crate::dependencies::{dependency_cycles, DependencyCycle};
+use crate::dna::duplicate_dna_warning;
+use crate::dna_release::DnaReleases;
+use crate::layout::layout_anomalies;
+use crate::library_link::{
+    file_references_path, LinkExtractionOptions, PathMatch, PathPlatform, ResolveOptions,
+};
+use crate::link_status::LinkStatusOptions;
+use crate::links_report::{
+    LinksReportV1, LinksReportV2, LinksReportV3, LinksTable, TableLayout, LINKS_REPORT_V3_SCHEMA,
+};
+use crate::naming::{NameCharset, NamePolicy};
+use crate::ownership::BlockSize;
+use crate::query::Query;
+use crate::scene::{ConformRules, UnitSystem};
+use crate::{BlendFile, BlendFileError};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "blend-file-reader")]
+#[command(about = "A tool to read and analyze Blender .blend files")]
+#[command(version = "1.0")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Give up with an error after this many seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<f64>,
+
+    /// Read settings from this file instead of the rbat.toml nearest the blend file
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+/// The blend file, given either positionally or with `-f/--file`
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct FileArg {
+    /// Path to the blend file, or - to read it from stdin
+    #[arg(value_name = "FILE")]
+    path: Option<PathBuf>,
+
+    /// Path to the blend file, or - to read it from stdin
+    #[arg(short, long = "file", value_name = "FILE")]
+    file: Option<PathBuf>,
+}
+
+impl FileArg {
+    fn into_path(self) -> PathBuf {
+        // clap's group guarantees exactly one of the two is set
+        self.path.or(self.file).unwrap_or_default()
+    }
+
+    fn path(&self) -> &Path {
+        self.path
+            .as_deref()
+            .or(self.file.as_deref())
+            .unwrap_or(Path::new(""))
+    }
+}
+
+/// `links --max-width`
+#[derive(Debug, Clone, Copy)]
+enum MaxWidth {
+    /// The terminal's width, or no limit when stdout isn't a terminal
+    Auto,
+    Columns(usize),
+}
+
+impl MaxWidth {
+    fn columns(self) -> Option<usize> {
+        match self {
+            MaxWidth::Auto => terminal_width(),
+            MaxWidth::Columns(columns) => Some(columns),
+        }
+    }
+}
+
+fn parse_max_width(s: &str) -> Result<MaxWidth, String> {
+    if s == "auto" {
+        return Ok(MaxWidth::Auto);
+    }
+    s.parse()
+        .map(MaxWidth::Columns)
+        .map_err(|_| format!("expected auto or a number of columns, got '{s}'"))
+}
+
+/// Columns of the terminal stdout is attached to; `COLUMNS` wins when set
+fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    #[cfg(unix)]
+    {
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCGWINSZ only writes a winsize through the pointer
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 {
+            return Some(size.ws_col.into());
+        }
+    }
+    None
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List all library links in a blend file
+    #[command(visible_alias = "ls")]
+    Links {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Output format (table, json, json-v1, json-v2, json-v3); json is the latest versioned schema
+        #[arg(short = 'o', long, default_value = "table")]
+        format: String,
+
+        /// Print the JSON Schema of the json output and exit
+        // Joining the file group lets it stand in for the otherwise required file
+        #[arg(long, exclusive = true, group = "FileArg")]
+        schema: bool,
+
+        /// Include absolute paths
+        #[arg(short, long)]
+        absolute: bool,
+
+        /// Add an Exists column, checking each unique target once
+        #[arg(long)]
+        status: bool,
+
+        /// Include size and modification time of existing assets
+        #[arg(long)]
+        stat: bool,
+
+        /// Resolve symlinked directories to their real location (implies --stat)
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Match asset filenames case-insensitively when the exact path is missing (implies --stat)
+        #[arg(long)]
+        ignore_case: bool,
+
+        /// List the IDs linked from each library instead of the links themselves
+        #[arg(long)]
+        usage: bool,
+
+        /// List links that reach the same file through different spellings instead
+        #[arg(long, conflicts_with = "usage")]
+        show_duplicates: bool,
+
+        /// Fail when a path can only be read from guessed offsets
+        #[arg(long)]
+        strict: bool,
+
+        /// Widest the table may get: auto (the terminal's width) or a number of columns
+        #[arg(long, default_value = "auto", value_parser = parse_max_width)]
+        max_width: MaxWidth,
+
+        /// Continue long paths on the following lines instead of shortening them
+        #[arg(long)]
+        wrap: bool,
+
+        /// Print only the resolved path of each link, one per line
+        #[arg(long, conflicts_with_all = ["usage", "show_duplicates"])]
+        paths_only: bool,
+    },
+
+    /// List all blocks in a blend file
+    #[command(visible_alias = "blk")]
+    Blocks {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Filter by block type
+        #[arg(short = 't', long)]
+        filter: Option<String>,
+
+        /// Only blocks matching an expression over code, name, struct, size,
+        /// count and sdna, e.g. "code == 'IM' && size > 1048576"
+        #[arg(long = "where", value_name = "EXPR")]
+        query: Option<String>,
+    },
+
+    /// Show the byte range of every block, then any gaps or overlaps between them
+    Layout {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Output format (table, json)
+        #[arg(short = 'o', long, default_value = "table")]
+        format: String,
+    },
+
+    /// Show file summary
+    #[command(visible_alias = "sum")]
+    Summary {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Output format (json, table)
+        #[arg(short = 'o', long, default_value = "table")]
+        format: String,
+
+        /// Order block types by (code, count)
+        #[arg(short, long, default_value = "code")]
+        sort: String,
+
+        /// Also list the N largest blocks with the datablock owning each DATA block,
+        /// followed by orphaned DATA blocks
+        #[arg(long, value_name = "N")]
+        largest: Option<usize>,
+
+        /// Extra DNA fingerprints to recognise, one "<fingerprint> <release>" per line
+        #[arg(long, value_name = "FILE")]
+        dna_releases: Option<PathBuf>,
+    },
+
+    /// Show summary, scenes, link counts and warnings in one pass
+    Info {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+
+        /// Also count link targets missing on disk
+        #[arg(long)]
+        check_assets: bool,
+
+        /// Extra DNA fingerprints to recognise, one "<fingerprint> <release>" per line
+        #[arg(long, value_name = "FILE")]
+        dna_releases: Option<PathBuf>,
+    },
+
+    /// List cameras with their lens, sensor and clipping, and each scene's active camera
+    Cameras {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Output format (table, json)
+        #[arg(short = 'o', long, default_value = "table")]
+        format: String,
+    },
+
+    /// Check whether any link references a path; exits 0 if found, 1 if not, 2 on errors
+    GrepPath {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Path, filename or fragment to look for
+        #[arg(long = "path", value_name = "PATH")]
+        query: String,
+
+        /// How to compare: exact, suffix (filename) or substring
+        #[arg(long = "match", default_value = "exact")]
+        match_mode: PathMatch,
+    },
+
+    /// Check block headers and DNA structs for anomalies; exits 1 if any issue is found
+    Validate {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Also check block and struct sizes against the DNA
+        #[arg(long)]
+        deep: bool,
+
+        /// Follow library links from file to file and report cycles
+        #[arg(long)]
+        recursive: bool,
+
+        /// Report datablock names using characters outside this set
+        /// (any, ascii, identifier)
+        #[arg(long)]
+        name_charset: Option<NameCharset>,
+
+        /// Report datablock names longer than this many bytes
+        #[arg(long)]
+        max_name_len: Option<usize>,
+    },
+
+    /// Dump blocks with their strings and DNA fields (LI blocks by default)
+    Debug {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Block code or prefix to select (LI, IM, OB, DATA, ...)
+        #[arg(long)]
+        code: Option<String>,
+
+        /// Select the block at this position in the file instead
+        #[arg(long, conflicts_with = "code")]
+        index: Option<usize>,
+
+        /// Print the inspections as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Decode every field of the selected blocks through the DNA
+    Show {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Block code or prefix to select (OB, ME, ...)
+        #[arg(long, required_unless_present_any = ["name", "index"])]
+        code: Option<String>,
+
+        /// ID name to select, without the code prefix (e.g. Cube)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Select the block at this position in the file instead
+        #[arg(long, conflicts_with_all = ["code", "name"])]
+        index: Option<usize>,
+
+        /// Print the decoded blocks as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print the DNA catalog as JSON: structs sorted by name, with field
+    /// types, offsets and sizes
+    #[command(group = clap::ArgGroup::new("report").args(["diff", "validate"]))]
+    Dna {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Instead list the structs and fields that differ in this file's DNA
+        #[arg(long, value_name = "OTHER")]
+        diff: Option<PathBuf>,
+
+        /// Print the diff or the validation report as JSON
+        #[arg(long, requires = "report")]
+        json: bool,
+
+        /// Instead print the SDNA NAME, TYPE, TLEN and STRC tables as stored,
+        /// one indexed entry per line
+        #[arg(long, conflicts_with_all = ["diff", "validate"])]
+        raw: bool,
+
+        /// Instead check every block's size against its struct's size times
+        /// its count; exits 1 on a mismatch
+        #[arg(long, conflicts_with = "diff")]
+        validate: bool,
+    },
+
+    /// Print the saves recorded in the file's audit log, oldest first
+    AuditLog {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Print the entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check every scene's frame rate and units; lists the settings that
+    /// differ and exits 1 if any do
+    CheckConform {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Frames per second, e.g. 24 or 23.976
+        #[arg(long, required_unless_present_any = ["unit", "scale"])]
+        fps: Option<f32>,
+
+        /// Unit system: none, metric or imperial
+        #[arg(long)]
+        unit: Option<UnitSystem>,
+
+        /// Unit scale (`scale_length`)
+        #[arg(long)]
+        scale: Option<f32>,
+
+        /// Print the violations as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write blocks, links and pointers to a SQLite database
+    #[cfg(feature = "sqlite-export")]
+    ExportSqlite {
+        #[command(flatten)]
+        file: FileArg,
+
+        /// Database to create; replaced if it exists
+        #[arg(long, value_name = "DB")]
+        out: PathBuf,
+    },
+
+    /// List the known block codes with their names and DNA structs
+    Codes {
+        /// Only show meanings that apply to files saved by this version (e.g. 279, 402)
+        #[arg(long = "for-version", value_name = "VERSION")]
+        for_version: Option<u32>,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a shell completion script to stdout
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate completions for (bash, zsh, fish)
+        #[arg(long, value_enum)]
+        shell: Shell,
+    },
+}
+
+impl Commands {
+    /// The blend file the command reads, if it reads one
+    fn file(&self) -> Option<&FileArg> {
+        match self {
+            Commands::Links { file, .. }
+            | Commands::Blocks { file, .. }
+            | Commands::Layout { file, .. }
+            | Commands::Summary { file, .. }
+            | Commands::Info { file, .. }
+            | Commands::Cameras { file, .. }
+            | Commands::GrepPath { file, .. }
+            | Commands::Validate { file, .. }
+            | Commands::Debug { file, .. }
+            | Commands::Show { file, .. }
+            | Commands::Dna { file, .. }
+            | Commands::AuditLog { file, .. }
+            | Commands::CheckConform { file, .. } => Some(file),
+            #[cfg(feature = "sqlite-export")]
+            Commands::ExportSqlite { file, .. } => Some(file),
+            Commands::Codes { .. } | Commands::Completions { .. } => None,
+        }
+    }
+}
+
+/// Parse the command line and run the command, as the `blend-file-reader`
+/// binary does
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    // Warnings (e.g. extraction fallbacks) go to stderr; RUST_LOG overrides the level
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .without_time()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::builder()
+                .with_default_directive(tracing::Level::WARN.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    let mut cancellation = Cancellation::new();
+    if let Some(secs) = cli.timeout {
+        let timeout = Duration::try_from_secs_f64(secs)
+            .map_err(|e| format!("invalid --timeout {secs}: {e}"))?;
+        cancellation = cancellation.with_timeout(timeout);
+    }
+
+    // Settings come from the rbat.toml nearest the blend file (or the working
+    // directory for stdin), then RBAT_* variables
+    let config = match &cli.config {
+        Some(path) => {
+            let mut config = RbatConfig::load(path)?;
+            config.apply_env(std::env::vars())?;
+            config
+        }
+        None => match cli.command.file().map(FileArg::path) {
+            Some(path) if path != Path::new("-") => RbatConfig::discover(path)?,
+            _ => RbatConfig::discover(".")?,
+        },
+    };
+    for warning in &config.warnings {
+        tracing::warn!("{warning}");
+    }
+
+    let open_options = OpenOptions::from_config(&config).with_cancellation(cancellation.clone());
+    let open = |path: &Path| -> crate::Result<BlendFile> {
+        if path != Path::new("-") {
+            return BlendFile::open_with_options(path, &open_options);
+        }
+        let mut data = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut data)?;
+        BlendFile::from_bytes_with_options(&data, &open_options)
+    };
+
+    match cli.command {
+        Commands::Links {
+            file,
+            format,
+            absolute,
+            status,
+            stat,
+            follow_symlinks,
+            ignore_case,
+            usage,
+            show_duplicates,
+            schema,
+            strict,
+            max_width,
+            wrap,
+            paths_only,
+        } => {
+            if schema {
+                print!("{LINKS_REPORT_V3_SCHEMA}");
+                return Ok(());
+            }
+            let file = file.into_path();
+            let blend_file = open(&file)?;
+
+            if usage {
+                let usage = blend_file.library_usage()?;
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&usage)?);
+                    return Ok(());
+                }
+                if usage.is_empty() {
+                    println!("No libraries linked in {file}", file = file.display());
+                }
+                for library in usage {
+                    let note = if library.is_packed { " (packed)" } else { "" };
+                    println!("{}{note}", library.path);
+                    for (code, ids) in &library.ids {
+                        for id in ids {
+                            let note = if id.unreferenced {
+                                " (unreferenced)"
+                            } else {
+                                ""
+                            };
+                            println!("  {code} {name}{note}", name = id.name);
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            if show_duplicates {
+                let groups = blend_file.duplicate_links()?;
+                let platform = PathPlatform::native();
+                if format == "json" {
+                    let json: Vec<_> = groups
+                        .iter()
+                        .map(|group| {
+                            serde_json::json!({
+                                "key": group[0].canonical_key(platform),
+                                "links": group,
+                            })
+                        })
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&json)?);
+                    return Ok(());
+                }
+                if groups.is_empty() {
+                    println!("No duplicate links in {file}", file = file.display());
+                }
+                for group in groups {
+                    println!("{}", group[0].canonical_key(platform));
+                    for link in group {
+                        let name = link.block_name.as_deref().unwrap_or("N/A");
+                        println!("  {} {name}: {}", link.block_type, link.path);
+                    }
+                }
+                return Ok(());
+            }
+            let stat = stat || follow_symlinks || ignore_case;
+            let (mut links, stats) = blend_file.get_library_links_with_stats(
+                &LinkExtractionOptions::from_config(&config)
+                    .with_strict(strict || config.strict_links)
+                    .with_cancellation(cancellation.clone()),
+            )?;
+            if stat {
+                let extractor = blend_file.link_extractor().with_resolve_options(
+                    ResolveOptions::from_config(&config)
+                        .with_follow_symlinks(follow_symlinks || config.follow_symlinks)
+                        .with_case_insensitive_fallback(
+                            ignore_case || config.case_insensitive_fallback,
+                        ),
+                );
+                extractor.resolve_relative_paths(&mut links)?;
+                extractor.stat_links(&mut links)?;
+            }
+            if status {
+                blend_file.link_extractor().fill_status(
+                    &mut links,
+                    &LinkStatusOptions::from_config(&config),
+                    &LocalFs,
+                );
+            }
+
+            if paths_only {
+                for link in &links {
+                    println!("{}", link.absolute_path.as_deref().unwrap_or(&link.path));
+                }
+                return Ok(());
+            }
+            if links.is_empty() && format == "table" {
+                println!("No library links found in {file}", file = file.display());
+                return Ok(());
+            }
+
+            match format.as_str() {
+                "json" | "json-v3" => {
+                    let report = LinksReportV3::new(&blend_file, &links, stats);
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "json-v2" => {
+                    let report = LinksReportV2::new(&blend_file, &links, stats);
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "json-v1" => {
+                    let report = LinksReportV1::new(&blend_file, &links);
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "table" => {
+                    println!("Library links in {file}:", file = file.display());
+                    let mut layout = TableLayout::default().with_wrap(wrap);
+                    if let Some(max_width) = max_width.columns() {
+                        layout = layout.with_max_width(max_width);
+                    }
+                    print!("{}", LinksTable::new(&links, absolute).render(&layout));
+                }
+                _ => {}
+            }
+        }
+
+        Commands::Blocks {
+            file,
+            filter,
+            query,
+        } => {
+            let query = match query.as_deref().map(Query::parse).transpose() {
+                Ok(query) => query,
+                Err(e @ BlendFileError::InvalidQuery { offset, .. }) => {
+                    eprintln!("Error: {e}");
+                    eprintln!("  {}", query.unwrap_or_default());
+                    eprintln!("  {}^", " ".repeat(offset));
+                    std::process::exit(2);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let file = file.into_path();
+            let blend_file = open(&file)?;
+
+            let blocks_result: Result<Vec<&crate::block::Block>, _> = match filter {
+                Some(ref filter_type) => match filter_type.as_str() {
+                    "library" => blend_file.get_library_blocks(),
+                    "image" => blend_file.get_image_blocks(),
+                    "sound" => blend_file.get_sound_blocks(),
+                    "movieclip" => blend_file.get_movie_clip_blocks(),
+                    "mesh" => blend_file.get_mesh_blocks(),
+                    "material" => blend_file.get_material_blocks(),
+                    "texture" => blend_file.get_texture_blocks(),
+                    _ => {
+                        eprintln!("Unknown block type: {filter_type}");
+                        return Ok(());
+                    }
+                },
+                None => Ok(blend_file.blocks.iter().collect()),
+            };
+
+            let blocks_result = blocks_result.map(|blocks| match &query {
+                Some(query) => blocks
+                    .into_iter()
+                    .filter(|block| query.matches(&blend_file.dna, block))
+                    .collect(),
+                None => blocks,
+            });
+            match blocks_result {
+                Ok(blocks) => {
+                    if blocks.is_empty() {
+                        println!("No blocks found");
+                        return Ok(());
+                    }
+
+                    println!("Blocks in {file}:", file = file.display());
+                    println!(
+                        "{:<8} {:<22} {:<10} {:<15} {:<10} Struct",
+                        "Code", "Name", "Size", "Address", "Count"
+                    );
+                    println!(
+                        "{:-<8} {:-<22} {:-<10} {:-<15} {:-<10} {:-<10}",
+                        "", "", "", "", "", ""
+                    );
+
+                    for block in blocks {
+                        println!(
+                            "{:<8} {:<22} {:<10} 0x{:<13x} {:<10} {}",
+                            block_code::raw_code(&block.code),
+                            block_code::display_name(&block.code, blend_file.header.version),
+                            block.size,
+                            block.old_memory_address,
+                            block.count,
+                            block
+                                .dna_struct(&blend_file.dna)
+                                .map_or("-", |layout| layout.name.as_str())
+                        );
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error getting blocks: {e}");
+                    return Ok(());
+                }
+            }
+        }
+
+        Commands::Layout { file, format } => {
+            let blend_file = open(&file.into_path())?;
+            let spans = blend_file.layout();
+            let anomalies = layout_anomalies(&spans);
+
+            if format == "json" {
+                let json = serde_json::json!({ "spans": spans, "anomalies": anomalies });
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
+            println!(
+                "{:>6} {:<4} {:>12} {:>12} {:>12}",
+                "Index", "Code", "Header", "Data", "End"
+            );
+            for span in &spans {
+                println!(
+                    "{:>6} {:<4} {:>12} {:>12} {:>12}",
+                    span.index, span.code, span.header_offset, span.data_offset, span.end_offset
+                );
+            }
+            if anomalies.is_empty() {
+                println!("No gaps or overlaps");
+            }
+            for anomaly in &anomalies {
+                println!("{anomaly}");
+            }
+        }
+
+        Commands::Summary {
+            file,
+            format,
+            sort,
+            largest,
+            dna_releases,
+        } => {
+            let file = file.into_path();
+            let blend_file = open(&file)?;
+            let order = match sort.as_str() {
+                "code" => SummaryOrder::Code,
+                "count" => SummaryOrder::Count,
+                _ => {
+                    eprintln!("Unknown sort order: {sort}");
+                    return Ok(());
+                }
+            };
+
+            let size_stats = largest.map(|n| blend_file.size_stats(n)).transpose()?;
+            let mut summary = blend_file.summary(order);
+            if let Some(path) = dna_releases.or(config.dna_releases.clone()) {
+                summary.dna = blend_file.dna_report(&load_dna_releases(&path)?);
+            }
+
+            match format.as_str() {
+                "json" => {
+                    let json = match size_stats {
+                        Some(size_stats) => serde_json::to_string_pretty(&serde_json::json!({
+                            "summary": summary,
+                            "size_stats": size_stats,
+                        }))?,
+                        None => serde_json::to_string_pretty(&summary)?,
+                    };
+                    println!("{json}");
+                }
+                _ => {
+                    print!("{summary}");
+                    if let Some(size_stats) = size_stats {
+                        println!();
+                        println!("Largest blocks:");
+                        print_block_sizes(&size_stats.largest);
+                        println!();
+                        println!("Orphaned DATA blocks: {}", size_stats.orphans.len());
+                        print_block_sizes(&size_stats.orphans);
+                    }
+                }
+            }
+        }
+
+        Commands::Info {
+            file,
+            json,
+            check_assets,
+            dna_releases,
+        } => {
+            let blend_file = open(&file.into_path())?;
+            let mut info = if check_assets {
+                blend_file.info_with_asset_check()
+            } else {
+                blend_file.info()
+            };
+            if let Some(path) = dna_releases.or(config.dna_releases.clone()) {
+                info.summary.dna = blend_file.dna_report(&load_dna_releases(&path)?);
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                print!("{info}");
+            }
+        }
+
+        Commands::Cameras { file, format } => {
+            let blend_file = open(&file.into_path())?;
+            let cameras = blend_file.cameras()?;
+            let active = blend_file.active_cameras()?;
+
+            if format == "json" {
+                let json = serde_json::json!({ "cameras": cameras, "active": active });
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
+            println!(
+                "{:<20} {:<6} {:>8} {:>15} {:>15} {:>17}",
+                "Name", "Type", "Lens", "Sensor", "Shift", "Clip"
+            );
+            println!(
+                "{:-<20} {:-<6} {:->8} {:->15} {:->15} {:->17}",
+                "", "", "", "", "", ""
+            );
+            for camera in &cameras {
+                println!(
+                    "{:<20} {:<6} {:>6}mm {:>15} {:>15} {:>17}",
+                    camera.name,
+                    camera.camera_type.to_string(),
+                    camera.lens,
+                    format!("{} x {}", camera.sensor_width, camera.sensor_height),
+                    format!("{}, {}", camera.shift_x, camera.shift_y),
+                    format!("{} - {}", camera.clip_start, camera.clip_end)
+                );
+            }
+            for camera in &active {
+                let [x, y, z] = camera.location;
+                println!(
+                    "Scene {}: {} ({}) at ({x}, {y}, {z})",
+                    camera.scene, camera.object, camera.camera.name
+                );
+            }
+        }
+
+        Commands::GrepPath {
+            file,
+            query,
+            match_mode,
+        } => {
+            let file = file.into_path();
+            let found = if file == Path::new("-") {
+                open(&file).and_then(|blend_file| blend_file.references_path(&query, match_mode))
+            } else {
+                file_references_path(&file, &query, match_mode)
+            };
+            match found {
+                Ok(true) => println!("{file} references {query}", file = file.display()),
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(2);
+                }
+            }
+        }
+
+        Commands::Validate {
+            file,
+            deep,
+            recursive,
+            name_charset,
+            max_name_len,
+        } => {
+            let path = file.into_path();
+            let blend_file = open(&path)?;
+            let mut issues: Vec<String> = blend_file
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            // The deep check reports incomplete structs along with the sizes
+            let checked = if deep {
+                blend_file.consistency_check(&blend_file.dna)
+            } else {
+                incomplete_structs(&blend_file.dna)
+            };
+            issues.extend(checked.iter().map(ToString::to_string));
+            let dna_offsets: Vec<u64> = blend_file
+                .dna_blocks
+                .iter()
+                .map(|range| range.start)
+                .collect();
+            issues.extend(duplicate_dna_warning(&dna_offsets));
+            issues.extend(
+                blend_file
+                    .layout_anomalies()
+                    .iter()
+                    .map(ToString::to_string),
+            );
+            if name_charset.is_some() || max_name_len.is_some() {
+                let mut policy =
+                    NamePolicy::default().with_charset(name_charset.unwrap_or_default());
+                policy.max_len = max_name_len;
+                issues.extend(
+                    blend_file
+                        .lint_names(&policy)
+                        .iter()
+                        .map(ToString::to_string),
+                );
+            }
+            // A file linking itself is a cycle whether or not we follow links
+            if recursive && !blend_file.path.as_os_str().is_empty() {
+                let cycles = dependency_cycles(&blend_file.path)?;
+                issues.extend(cycles.into_iter().map(|cycle| {
+                    format!("circular library dependency: {}", DependencyCycle(cycle))
+                }));
+            } else {
+                for link in blend_file.self_references()? {
+                    issues.push(format!("library links its own file: {}", link.path));
+                }
+            }
+
+            if issues.is_empty() {
+                println!("{}: OK", path.display());
+            } else {
+                for issue in &issues {
+                    println!("{issue}");
+                }
+                println!("{}: {} issue(s)", path.display(), issues.len());
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Debug {
+            file,
+            code,
+            index,
+            json,
+        } => {
+            let blend_file = open(&file.into_path())?;
+            let inspections = match index {
+                Some(index) => vec![blend_file.inspect_block(index)?],
+                None => blend_file.inspect_blocks(code.as_deref().unwrap_or("LI"))?,
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&inspections)?);
+            } else {
+                for (i, inspection) in inspections.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    print!("{inspection}");
+                }
+            }
+        }
+
+        Commands::Show {
+            file,
+            code,
+            name,
+            index,
+            json,
+        } => {
+            let blend_file = open(&file.into_path())?;
+            let indices = match index {
+                Some(index) if index < blend_file.blocks.len() => vec![index],
+                Some(index) => return Err(format!("no block {index}").into()),
+                None => blend_file.select_blocks(code.as_deref(), name.as_deref()),
+            };
+            if indices.is_empty() {
+                return Err("no block matches the selection".into());
+            }
+
+            let decoded: Vec<(usize, DecodedStruct)> = indices
+                .into_iter()
+                .map(|index| {
+                    let block = &blend_file.blocks[index];
+                    (index, decode_block(block, &blend_file.dna, &blend_file))
+                })
+                .collect();
+            if json {
+                let blocks: Vec<serde_json::Value> = decoded
+                    .iter()
+                    .map(|(index, decoded)| {
+                        let block = &blend_file.blocks[*index];
+                        serde_json::json!({
+                            "index": index,
+                            "code": block_code::raw_code(&block.code),
+                            "address": block.old_memory_address,
+                            "decoded": decoded,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&blocks)?);
+            } else {
+                for (i, (index, decoded)) in decoded.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    let block = &blend_file.blocks[*index];
+                    println!(
+                        "--- {} block {index} @{:#x} ---",
+                        block_code::raw_code(&block.code),
+                        block.old_memory_address
+                    );
+                    print!("{decoded}");
+                }
+            }
+        }
+
+        Commands::Dna {
+            file,
+            diff,
+            json,
+            raw,
+            validate,
+        } => {
+            let path = file.into_path();
+            let blend_file = open(&path)?;
+            if raw {
+                print!("{}", blend_file.dna.raw);
+                return Ok(());
+            }
+            if validate {
+                let validation = blend_file.validate_dna();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&validation)?);
+                } else {
+                    for mismatch in &validation.mismatches {
+                        println!("{mismatch}");
+                    }
+                    println!(
+                        "{}: {} of {} block(s) don't match the DNA",
+                        path.display(),
+                        validation.mismatches.len(),
+                        validation.blocks_checked
+                    );
+                }
+                if !validation.is_ok() {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            match diff {
+                Some(other) => {
+                    let diff = blend_file.dna.diff(&open(&other)?.dna);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&diff)?);
+                    } else if diff.is_empty() {
+                        println!("No DNA differences");
+                    } else {
+                        print!("{diff}");
+                    }
+                }
+                None => println!("{}", blend_file.dna.to_json()?),
+            }
+        }
+
+        Commands::AuditLog { file, json } => {
+            let file = file.into_path();
+            let entries = open(&file)?.audit_log()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else if entries.is_empty() {
+                println!("No audit log in {}", file.display());
+            } else {
+                for entry in entries {
+                    print!("{entry}");
+                }
+            }
+        }
+
+        Commands::CheckConform {
+            file,
+            fps,
+            unit,
+            scale,
+            json,
+        } => {
+            let path = file.into_path();
+            let rules = ConformRules {
+                fps,
+                unit_system: unit,
+                scale_length: scale,
+            };
+            let violations = open(&path)?.check_conform(&rules)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&violations)?);
+            } else if violations.is_empty() {
+                println!("{}: OK", path.display());
+            } else {
+                for violation in &violations {
+                    println!("{violation}");
+                }
+                println!("{}: {} violation(s)", path.display(), violations.len());
+            }
+            if !violations.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        #[cfg(feature = "sqlite-export")]
+        Commands::ExportSqlite { file, out } => {
+            let blend_file = open(&file.into_path())?;
+            crate::export::to_sqlite(&blend_file, &out)?;
+            println!(
+                "Exported {} blocks to {}",
+                blend_file.blocks.len(),
+                out.display()
+            );
+        }
+
+        Commands::Codes { for_version, json } => {
+            let codes: Vec<&BlockCodeInfo> = block_code_registry()
+                .iter()
+                .filter(|info| for_version.is_none_or(|version| info.applies_to(version)))
+                .collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&codes)?);
+                return Ok(());
+            }
+            println!("{:<6} {:<24} {:<20} Versions", "Code", "Name", "Struct");
+            println!("{:-<6} {:-<24} {:-<20} {:-<10}", "", "", "", "");
+            for info in codes {
+                println!(
+                    "{:<6} {:<24} {:<20} {}",
+                    info.code,
+                    info.name,
+                    info.struct_name.unwrap_or("-"),
+                    version_range(info)
+                );
+            }
+        }
+
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+        }
+    }
+
+    Ok(())
+}
+
+/// `first..end` of a registry entry, e.g. `2.80+` or `before 2.80`
+/// The built-in DNA release table plus the entries in `path`
+fn load_dna_releases(path: &Path) -> crate::Result<DnaReleases> {
+    let mut releases = DnaReleases::builtin();
+    releases.extend_from_file(path)?;
+    Ok(releases)
+}
+
+fn version_range(info: &BlockCodeInfo) -> String {
+    let version = |v: u32| format!("{}.{:02}", v / 100, v % 100);
+    match (info.first, info.end) {
+        (0, u32::MAX) => "all".to_string(),
+        (first, u32::MAX) => format!("{}+", version(first)),
+        (0, end) => format!("before {}", version(end)),
+        (first, end) => format!("{} to before {}", version(first), version(end)),
+    }
+}
+
+fn print_block_sizes(rows: &[BlockSize]) {
+    for row in rows {
+        let owner = match &row.owner {
+            Some(owner) => format!("{}{} ({})", owner.code, owner.name, owner.field),
+            None => String::new(),
+        };
+        println!(
+            "  {:<8} 0x{:<14x} {:>12} {owner}",
+            row.code, row.address, row.size
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed_file(args: &[&str]) -> PathBuf {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let file = cli.command.file().expect("expected a file command");
+        file.path().to_path_buf()
+    }
+
+    #[test]
+    fn test_file_flag_and_positional() {
+        for args in [
+            &["blend-file-reader", "links", "-f", "scene.blend"][..],
+            &["blend-file-reader", "links", "--file", "scene.blend"],
+            &["blend-file-reader", "links", "scene.blend"],
+            &[
+                "blend-file-reader",
+                "summary",
+                "scene.blend",
+                "--sort",
+                "count",
+            ],
+            &["blend-file-reader", "debug", "scene.blend"],
+        ] {
+            assert_eq!(parsed_file(args), PathBuf::from("scene.blend"), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_file_is_required_once() {
+        assert!(Cli::try_parse_from(["blend-file-reader", "links"]).is_err());
+        assert!(
+            Cli::try_parse_from(["blend-file-reader", "links", "a.blend", "-f", "b.blend"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        assert!(matches!(
+            Cli::try_parse_from(["blend-file-reader", "ls", "scene.blend", "--absolute"])
+                .unwrap()
+                .command,
+            Commands::Links { absolute: true, .. }
+        ));
+        assert!(matches!(
+            Cli::try_parse_from([
+                "blend-file-reader",
+                "blk",
+                "-f",
+                "scene.blend",
+                "-t",
+                "image"
+            ])
+            .unwrap()
+            .command,
+            Commands::Blocks { .. }
+        ));
+        assert_eq!(
+            parsed_file(&["blend-file-reader", "sum", "scene.blend"]),
+            PathBuf::from("scene.blend")
+        );
+    }
+
+    #[test]
+    fn test_schema_needs_no_file() {
+        let cli = Cli::try_parse_from(["blend-file-reader", "links", "--schema"]).unwrap();
+        assert!(matches!(cli.command, Commands::Links { schema: true, .. }));
+        assert!(
+            Cli::try_parse_from(["blend-file-reader", "links", "a.blend", "--schema"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_grep_path_args() {
+        let cli = Cli::try_parse_from([
+            "blend-file-reader",
+            "grep-path",
+            "--file",
+            "scene.blend",
+            "--path",
+            "old_rig.blend",
+            "--match",
+            "suffix",
+        ])
+        .unwrap();
+        let Commands::GrepPath {
+            file,
+            query,
+            match_mode,
+        } = cli.command
+        else {
+            panic!("expected grep-path");
+        };
+        assert_eq!(file.into_path(), PathBuf::from("scene.blend"));
+        assert_eq!(query, "old_rig.blend");
+        assert_eq!(match_mode, PathMatch::Suffix);
+
+        assert!(Cli::try_parse_from([
+            "blend-file-reader",
+            "grep-path",
+            "scene.blend",
+            "--path",
+            "x",
+            "--match",
+            "fuzzy",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_completions_generate() {
+        for shell in ["bash", "zsh", "fish"] {
+            let cli = Cli::try_parse_from(["blend-file-reader", "completions", "--shell", shell])
+                .unwrap();
+            let Commands::Completions { shell } = cli.command else {
+                panic!("expected completions");
+            };
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "blend-file-reader", &mut script);
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("summary"), "{shell}");
+        }
+    }
+}
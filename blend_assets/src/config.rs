@@ -0,0 +1,371 @@
+//! Project-wide defaults from an `rbat.toml`, overridable per run through
+//! `RBAT_*` environment variables.
+//!
+//! ```toml
+//! [open]
+//! validation = "strict"        # or "permissive"
+//! keep_originals = true
+//!
+//! [links]
+//! include_placeholders = false
+//! strict = true
+//! follow_symlinks = true
+//! case_insensitive_fallback = false
+//!
+//! [save]
+//! compression = "zstd:3"       # kind[:level[:threads]]
+//! preserve_mtime = true
+//!
+//! [summary]
+//! dna_releases = "tools/dna_releases.txt"   # relative to this file
+//! ```
+//!
+//! Every key has an environment override named after its path, e.g.
+//! `RBAT_SAVE_COMPRESSION` or `RBAT_LINKS_STRICT`, which wins over the file.
+//! Unknown keys are collected in `warnings` whatever their value, so a newer
+//! config still loads; a known key with a value of the wrong type, or text
+//! that isn't TOML, is an error naming its line and column.
+
+use crate::blend_file::{
+    CompressionSettings, OpenOptions, ReadWriteOptions, SaveOptions, ValidationMode,
+};
+use crate::error::{BlendFileError, FormatError, Result};
+use crate::library_link::{LinkExtractionOptions, ResolveOptions};
+use crate::link_status::LinkStatusOptions;
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::de::{DeTable, DeValue, ValueDeserializer};
+use toml::Spanned;
+
+/// Name of the config file `discover` looks for
+pub const CONFIG_FILE_NAME: &str = "rbat.toml";
+
+/// Prefix of the environment variables that override config keys
+const ENV_PREFIX: &str = "RBAT_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Str,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Bool => "boolean",
+            Kind::Str => "string",
+        }
+    }
+}
+
+/// Every key the config understands, as a dotted path
+const KEYS: &[(&str, Kind)] = &[
+    ("open.validation", Kind::Str),
+    ("open.keep_originals", Kind::Bool),
+    ("links.include_placeholders", Kind::Bool),
+    ("links.strict", Kind::Bool),
+    ("links.follow_symlinks", Kind::Bool),
+    ("links.case_insensitive_fallback", Kind::Bool),
+    ("save.compression", Kind::Str),
+    ("save.preserve_mtime", Kind::Bool),
+    ("summary.dna_releases", Kind::Str),
+];
+
+/// Defaults for the library's option builders and the CLI
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RbatConfig {
+    /// File the settings came from; `None` when only defaults and the
+    /// environment apply
+    pub source: Option<PathBuf>,
+    pub validation: ValidationMode,
+    pub keep_originals: bool,
+    pub include_placeholders: bool,
+    pub strict_links: bool,
+    pub follow_symlinks: bool,
+    pub case_insensitive_fallback: bool,
+    pub compression: CompressionSettings,
+    pub preserve_mtime: bool,
+    /// Extra DNA release table, resolved against the config file's directory
+    pub dna_releases: Option<PathBuf>,
+    /// Unknown keys and variables, which are ignored rather than rejected
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "string",
+            Value::Bool(_) => "boolean",
+        }
+    }
+}
+
+impl RbatConfig {
+    /// The nearest `rbat.toml` in `start` or one of its ancestors. `start`
+    /// may be a file, whose directory is searched first.
+    pub fn find<P: AsRef<Path>>(start: P) -> Option<PathBuf> {
+        let start = start.as_ref();
+        let start = if start.is_file() {
+            start.parent()?
+        } else {
+            start
+        };
+        let start = std::path::absolute(start).ok()?;
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Load the config nearest to `start` (defaults when there is none),
+    /// then apply `RBAT_*` overrides from the environment
+    pub fn discover<P: AsRef<Path>>(start: P) -> Result<Self> {
+        let mut config = match Self::find(start) {
+            Some(path) => Self::load(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(std::env::vars())?;
+        Ok(config)
+    }
+
+    /// Read the config file at `path`, without environment overrides
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| FormatError::IoError(format!("{}: {e}", path.display())))?;
+        Self::parse(&text, path)
+    }
+
+    /// Parse config text; `source` names it in messages and anchors relative
+    /// paths
+    pub fn parse(text: &str, source: &Path) -> Result<Self> {
+        let mut config = Self {
+            source: Some(source.to_path_buf()),
+            ..Self::default()
+        };
+        let base_dir = source.parent().unwrap_or(Path::new(""));
+        // 1-based line and column of a byte offset into `text`
+        let at = |offset: usize| {
+            let before = &text[..offset.min(text.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            format!("{}:{line}:{column}", source.display())
+        };
+
+        let document = DeTable::parse(text).map_err(|e| {
+            let offset = e.span().map_or(0, |span| span.start);
+            invalid(format!("{}: {}", at(offset), e.message().trim_end()))
+        })?;
+        let mut entries = Vec::new();
+        flatten("", document.into_inner(), &mut entries);
+        // Tables iterate by key; report in file order instead
+        entries.sort_by_key(|(_, key, _)| key.start);
+
+        for (path, key, value) in entries {
+            let Some(&(_, kind)) = KEYS.iter().find(|(known, _)| *known == path) else {
+                config
+                    .warnings
+                    .push(format!("{}: unknown key `{path}`", at(key.start)));
+                continue;
+            };
+            let location = at(value.span().start);
+            let found = value.get_ref().type_str();
+            let value = read_value(kind, value).map_err(|_| {
+                invalid(format!(
+                    "{location}: `{path}` must be a {}, found {found}",
+                    kind.name()
+                ))
+            })?;
+            config
+                .set(&path, value, base_dir)
+                .map_err(|message| invalid(format!("{location}: {message}")))?;
+        }
+        Ok(config)
+    }
+
+    /// Apply `RBAT_<TABLE>_<KEY>` variables from `vars` over the current
+    /// settings. Relative paths from the environment stay relative to the
+    /// working directory.
+    pub fn apply_env<I>(&mut self, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        // Stable order for warnings
+        vars.sort();
+        for (name, raw) in vars {
+            let wanted = name[ENV_PREFIX.len()..].to_ascii_lowercase();
+            let Some(&(path, kind)) = KEYS
+                .iter()
+                .find(|(path, _)| path.replace('.', "_") == wanted)
+            else {
+                self.warnings.push(format!("unknown variable {name}"));
+                continue;
+            };
+            let value = match kind {
+                Kind::Str => Value::Str(raw),
+                Kind::Bool => match raw.to_ascii_lowercase().as_str() {
+                    "true" | "1" => Value::Bool(true),
+                    "false" | "0" => Value::Bool(false),
+                    _ => {
+                        return Err(invalid(format!(
+                            "{name}: `{path}` must be true or false, found '{raw}'"
+                        )))
+                    }
+                },
+            };
+            self.set(path, value, Path::new(""))
+                .map_err(|message| invalid(format!("{name}: {message}")))?;
+        }
+        Ok(())
+    }
+
+    /// Store `value` under the known key `path`; errors are messages without
+    /// a location
+    fn set(
+        &mut self,
+        path: &str,
+        value: Value,
+        base_dir: &Path,
+    ) -> std::result::Result<(), String> {
+        let found = value.type_name();
+        let wrong_type = |expected: &str| format!("`{path}` must be a {expected}, found {found}");
+        let flag = |value: Value| match value {
+            Value::Bool(flag) => Ok(flag),
+            _ => Err(wrong_type("boolean")),
+        };
+        let text = |value: Value| match value {
+            Value::Str(text) => Ok(text),
+            _ => Err(wrong_type("string")),
+        };
+
+        match path {
+            "open.validation" => {
+                self.validation = match text(value)?.as_str() {
+                    "permissive" => ValidationMode::Permissive,
+                    "strict" => ValidationMode::Strict,
+                    other => {
+                        return Err(format!(
+                            "`{path}` must be \"permissive\" or \"strict\", found \"{other}\""
+                        ))
+                    }
+                }
+            }
+            "open.keep_originals" => self.keep_originals = flag(value)?,
+            "links.include_placeholders" => self.include_placeholders = flag(value)?,
+            "links.strict" => self.strict_links = flag(value)?,
+            "links.follow_symlinks" => self.follow_symlinks = flag(value)?,
+            "links.case_insensitive_fallback" => self.case_insensitive_fallback = flag(value)?,
+            "save.compression" => {
+                self.compression = text(value)?
+                    .parse()
+                    .map_err(|e: BlendFileError| format!("`{path}`: {e}"))?
+            }
+            "save.preserve_mtime" => self.preserve_mtime = flag(value)?,
+            "summary.dna_releases" => self.dna_releases = Some(base_dir.join(text(value)?)),
+            _ => unreachable!("{path} is listed in KEYS"),
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> BlendFileError {
+    BlendFileError::InvalidConfig(message)
+}
+
+/// A TOML value read as the type `kind` asks for
+fn read_value(
+    kind: Kind,
+    value: Spanned<DeValue<'_>>,
+) -> std::result::Result<Value, toml::de::Error> {
+    let value = ValueDeserializer::from(value);
+    match kind {
+        Kind::Bool => bool::deserialize(value).map(Value::Bool),
+        Kind::Str => String::deserialize(value).map(Value::Str),
+    }
+}
+
+/// Every value in `table` that isn't itself a table, with its dotted path
+/// and the span of its key. Known keys stop the descent so a table in
+/// their place is reported as a wrong type rather than walked.
+fn flatten<'i>(
+    prefix: &str,
+    table: DeTable<'i>,
+    entries: &mut Vec<(String, Range<usize>, Spanned<DeValue<'i>>)>,
+) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.get_ref().to_string()
+        } else {
+            format!("{prefix}.{}", key.get_ref())
+        };
+        let known = KEYS.iter().any(|(known, _)| *known == path);
+        let span = value.span();
+        match value.into_inner() {
+            DeValue::Table(table) if !known => flatten(&path, table, entries),
+            other => entries.push((path, key.span(), Spanned::new(span, other))),
+        }
+    }
+}
+impl OpenOptions {
+    /// Validation and original-keeping from `config`
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self::default()
+            .with_validation(config.validation)
+            .with_keep_originals(config.keep_originals)
+    }
+}
+
+impl ReadWriteOptions {
+    /// Validation and original-keeping from `config`; locking stays at its
+    /// default
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self::default()
+            .with_validation(config.validation)
+            .with_keep_originals(config.keep_originals)
+    }
+}
+
+impl LinkExtractionOptions {
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self::default()
+            .with_placeholders(config.include_placeholders)
+            .with_strict(config.strict_links)
+    }
+}
+
+impl ResolveOptions {
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self::default()
+            .with_follow_symlinks(config.follow_symlinks)
+            .with_case_insensitive_fallback(config.case_insensitive_fallback)
+    }
+}
+
+impl LinkStatusOptions {
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self {
+            extraction: LinkExtractionOptions::from_config(config),
+            ..Self::default()
+        }
+    }
+}
+
+impl SaveOptions {
+    pub fn from_config(config: &RbatConfig) -> Self {
+        Self::default()
+            .with_compression(config.compression)
+            .with_preserve_mtime(config.preserve_mtime)
+    }
+}
@@ -0,0 +1,193 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::dna::Dna;
+use serde::Serialize;
+use std::fmt;
+
+/// A disagreement between block sizes and the DNA, or within the DNA itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConsistencyIssue {
+    /// A block's payload isn't `count` instances of its struct, usually
+    /// because a tool truncated it
+    BlockSize {
+        /// Position of the block in `BlendFile::blocks`
+        index: usize,
+        code: String,
+        struct_name: String,
+        size: usize,
+        expected: usize,
+    },
+    /// A block's SDNA index names no struct
+    UnknownStruct {
+        index: usize,
+        code: String,
+        sdna_index: u32,
+    },
+    /// A struct's declared size (TLEN) isn't the sum of its field sizes
+    StructSize {
+        /// Position of the struct in the STRC table
+        sdna_index: usize,
+        struct_name: String,
+        declared: usize,
+        fields: usize,
+    },
+    /// A struct field's type has no known size, so the fields after it can't
+    /// be located; usually a newer Blender's DNA
+    IncompleteStruct {
+        sdna_index: usize,
+        struct_name: String,
+        /// The field of unknown size
+        field: String,
+        type_name: String,
+    },
+}
+
+impl fmt::Display for ConsistencyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsistencyIssue::BlockSize {
+                index,
+                code,
+                struct_name,
+                size,
+                expected,
+            } => write!(
+                f,
+                "block {index} ({code}): {size} bytes, {struct_name} x count needs {expected}"
+            ),
+            ConsistencyIssue::UnknownStruct {
+                index,
+                code,
+                sdna_index,
+            } => write!(f, "block {index} ({code}): no struct at SDNA index {sdna_index}"),
+            ConsistencyIssue::StructSize {
+                sdna_index,
+                struct_name,
+                declared,
+                fields,
+            } => write!(
+                f,
+                "struct {struct_name} (SDNA {sdna_index}): declared {declared} bytes, fields add up to {fields}"
+            ),
+            ConsistencyIssue::IncompleteStruct {
+                sdna_index,
+                struct_name,
+                field,
+                type_name,
+            } => write!(
+                f,
+                "struct {struct_name} (SDNA {sdna_index}): {field} has unknown type {type_name}, later fields can't be read"
+            ),
+        }
+    }
+}
+
+/// Every block holding structs checked against the file's DNA, from
+/// `BlendFile::validate_dna`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DnaValidation {
+    /// Blocks that should hold `count` structs; raw data blocks aren't checked
+    pub blocks_checked: usize,
+    /// `BlockSize` and `UnknownStruct` issues, in block order
+    pub mismatches: Vec<ConsistencyIssue>,
+}
+
+impl DnaValidation {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl BlendFile {
+    /// Check every block's size against `struct size * count`, looking its
+    /// struct up by `sdna_index` in the file's own DNA. Files without DNA
+    /// have nothing to check against and pass.
+    pub fn validate_dna(&self) -> DnaValidation {
+        block_sizes(&self.blocks, &self.dna)
+    }
+
+    /// Compare every block's size with `struct size * count` under `dna`, and
+    /// every struct's declared size with the sum of its fields.
+    ///
+    /// Blocks that aren't struct arrays (raw `DATA`, `TEST`, `REND`, `ENDB`)
+    /// are skipped. An empty `dna` reports nothing. Incomplete structs are
+    /// reported as such instead of by size.
+    pub fn consistency_check(&self, dna: &Dna) -> Vec<ConsistencyIssue> {
+        if dna.is_empty() {
+            return Vec::new();
+        }
+
+        let mut issues = incomplete_structs(dna);
+        issues.extend(
+            dna.validate()
+                .into_iter()
+                .map(|mismatch| ConsistencyIssue::StructSize {
+                    sdna_index: mismatch.sdna_index,
+                    struct_name: mismatch.struct_name,
+                    declared: mismatch.expected,
+                    fields: mismatch.actual,
+                }),
+        );
+
+        issues.extend(block_sizes(&self.blocks, dna).mismatches);
+        issues
+    }
+}
+
+fn block_sizes(blocks: &[Block], dna: &Dna) -> DnaValidation {
+    let mut validation = DnaValidation::default();
+    if dna.is_empty() {
+        return validation;
+    }
+    for (index, block) in blocks.iter().enumerate() {
+        if !holds_structs(block) {
+            continue;
+        }
+        validation.blocks_checked += 1;
+        let code = block.get_type_name().trim_end_matches('\0').to_string();
+        let Some(layout) = dna.struct_by_index(block.sdna_index as usize) else {
+            validation.mismatches.push(ConsistencyIssue::UnknownStruct {
+                index,
+                code,
+                sdna_index: block.sdna_index,
+            });
+            continue;
+        };
+        let expected = layout.size * block.count as usize;
+        if block.data.len() != expected {
+            validation.mismatches.push(ConsistencyIssue::BlockSize {
+                index,
+                code,
+                struct_name: layout.name.clone(),
+                size: block.data.len(),
+                expected,
+            });
+        }
+    }
+    validation
+}
+
+/// Whether the payload should be `count` DNA structs. Raw arrays, thumbnails,
+/// render info and the audit log are written with SDNA index 0 and sized freely.
+pub(crate) fn holds_structs(block: &Block) -> bool {
+    let freeform = matches!(&block.code, b"DATA" | b"TEST" | b"REND" | b"RBAT");
+    !(freeform && block.sdna_index == 0)
+}
+
+/// Structs with a field of unknown type, in STRC order
+pub fn incomplete_structs(dna: &Dna) -> Vec<ConsistencyIssue> {
+    dna.structs_by_index
+        .iter()
+        .enumerate()
+        .filter_map(|(sdna_index, layout)| {
+            let field = layout.unsized_field()?;
+            Some(ConsistencyIssue::IncompleteStruct {
+                sdna_index,
+                struct_name: layout.name.clone(),
+                field: field.name.clone(),
+                type_name: field.type_name.clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,360 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::dna::{Dna, DnaStruct, FlatField};
+use crate::error::{FormatError, Result};
+use crate::header::{Endianness, PointerSize};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// What `BlendFile::convert_pointer_size` or `BlendFile::convert_endianness` changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConversionReport {
+    /// Blocks re-laid out struct by struct, or pointer by pointer for raw
+    /// arrays of pointers
+    pub converted_blocks: usize,
+    /// Pointer values rewritten at the new width; endianness conversion
+    /// doesn't count them
+    pub pointers_converted: usize,
+    /// Indices of blocks copied unchanged because their layout isn't known:
+    /// raw DATA blocks that no `**` field points at, and payloads that don't
+    /// match `struct size * count`
+    pub raw_blocks: Vec<usize>,
+}
+
+impl BlendFile {
+    /// Rewrite the file for another pointer size.
+    ///
+    /// Every DNA-typed block is re-laid out so pointers grow or shrink in place
+    /// and the following fields shift accordingly; the DNA TLEN table and the
+    /// header flag are updated to match. Pointer values are opaque handles, so
+    /// widening is lossless, but narrowing fails if any address exceeds `u32`.
+    pub fn convert_pointer_size(&mut self, target: PointerSize) -> Result<ConversionReport> {
+        let mut report = ConversionReport::default();
+        if self.header.pointer_size == target {
+            return Ok(report);
+        }
+        if self.dna.is_empty() {
+            return Err(FormatError::DnaError(
+                "pointer size conversion requires the file's DNA".to_string(),
+            )
+            .into());
+        }
+
+        let source = self.header.pointer_size;
+        let target_dna = self.dna.with_pointer_size(target)?;
+        let pointer_arrays =
+            pointer_array_blocks(&self.dna, &self.blocks, source, self.header.endianness);
+        let mut converter = PointerConverter {
+            source_dna: &self.dna,
+            target_dna: &target_dna,
+            source,
+            target,
+            endianness: self.header.endianness,
+            pointers: 0,
+        };
+
+        let mut converted = Vec::with_capacity(self.blocks.len());
+        for (index, block) in self.blocks.iter().enumerate() {
+            converter.check_fits(block.old_memory_address)?;
+
+            let data = match block_layout(&self.dna, block) {
+                Some(layout) => {
+                    let mut out = Vec::with_capacity(block.data.len() * 2);
+                    for instance in block.data.chunks_exact(layout.size) {
+                        converter.convert_struct(&layout.name, instance, &mut out)?;
+                    }
+                    report.converted_blocks += 1;
+                    Some(out)
+                }
+                None if pointer_arrays.contains(&index) => {
+                    report.converted_blocks += 1;
+                    Some(converter.convert_pointers(&block.data)?)
+                }
+                None => {
+                    report.raw_blocks.push(index);
+                    None
+                }
+            };
+            converted.push(data);
+        }
+        report.pointers_converted = converter.pointers;
+
+        for (index, (block, data)) in self.blocks.iter_mut().zip(converted).enumerate() {
+            if let Some(data) = data {
+                block.size = data.len() as u32;
+                let original = std::mem::replace(&mut block.data, data);
+                self.modified.keep_original(index, || original);
+            }
+        }
+        self.header.pointer_size = target;
+        self.dna = target_dna;
+        self.modified.mark_all();
+
+        Ok(report)
+    }
+
+    /// Byte-swap the file into another endianness.
+    ///
+    /// Every numeric field of every DNA-typed struct instance (shorts, ints,
+    /// floats, doubles, pointers) is swapped in place while char arrays are
+    /// left alone; the header flag is updated so the save path writes block
+    /// headers and the DNA1 block in the new byte order. Raw DATA blocks that
+    /// a `**` field points at are swapped pointer by pointer; other blocks
+    /// without a DNA layout are copied unchanged and listed in the report.
+    /// Refuses to run without DNA, since swapping blindly would corrupt strings.
+    pub fn convert_endianness(&mut self, target: Endianness) -> Result<ConversionReport> {
+        let mut report = ConversionReport::default();
+        if self.header.endianness == target {
+            return Ok(report);
+        }
+        if self.dna.is_empty() {
+            return Err(FormatError::DnaError(
+                "endianness conversion requires the file's DNA".to_string(),
+            )
+            .into());
+        }
+
+        let pointer_size = self.header.pointer_size;
+        let pointer_arrays = pointer_array_blocks(
+            &self.dna,
+            &self.blocks,
+            pointer_size,
+            self.header.endianness,
+        );
+
+        // Swap into copies first so a DNA mismatch leaves the file untouched
+        let mut swapped = Vec::with_capacity(self.blocks.len());
+        for (index, block) in self.blocks.iter().enumerate() {
+            let data = match block_layout(&self.dna, block) {
+                Some(layout) => {
+                    let mut data = block.data.clone();
+                    for instance in data.chunks_exact_mut(layout.size) {
+                        swap_struct(&self.dna, &layout.name, instance)?;
+                    }
+                    Some(data)
+                }
+                None if pointer_arrays.contains(&index) => {
+                    let mut data = block.data.clone();
+                    for pointer in data.chunks_exact_mut(pointer_size.bytes()) {
+                        pointer.reverse();
+                    }
+                    Some(data)
+                }
+                None => {
+                    report.raw_blocks.push(index);
+                    None
+                }
+            };
+            report.converted_blocks += usize::from(data.is_some());
+            swapped.push(data);
+        }
+
+        for (index, (block, data)) in self.blocks.iter_mut().zip(swapped).enumerate() {
+            if let Some(data) = data {
+                let original = std::mem::replace(&mut block.data, data);
+                self.modified.keep_original(index, || original);
+            }
+        }
+        self.header.endianness = target;
+        self.dna.endianness = target;
+        self.modified.mark_all();
+
+        Ok(report)
+    }
+}
+
+/// Indices of the raw blocks holding arrays of pointers: those a `**` field
+/// of a DNA-typed block (e.g. `Mesh.mat`) points at. The DNA doesn't describe
+/// them, but every element is a pointer.
+fn pointer_array_blocks(
+    dna: &Dna,
+    blocks: &[Block],
+    pointer_size: PointerSize,
+    endianness: Endianness,
+) -> HashSet<usize> {
+    let width = pointer_size.bytes();
+    let mut targets = HashSet::new();
+    for block in blocks {
+        let Some(layout) = block_layout(dna, block) else {
+            continue;
+        };
+        let fields: Vec<FlatField> = layout
+            .flattened_fields(dna)
+            .into_iter()
+            .filter(|flat| flat.field.dna_name.name_full.starts_with("**"))
+            .collect();
+        for instance in block.data.chunks_exact(layout.size) {
+            for flat in &fields {
+                let Some(bytes) = instance.get(flat.offset..flat.offset + flat.field.size) else {
+                    continue;
+                };
+                targets.extend(
+                    bytes
+                        .chunks_exact(width)
+                        .map(|value| read_uint(value, endianness))
+                        .filter(|&address| address != 0),
+                );
+            }
+        }
+    }
+    blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| {
+            block_layout(dna, block).is_none()
+                && block.data.len() % width == 0
+                && targets.contains(&block.old_memory_address)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The DNA struct describing a block's payload, if the payload really is
+/// `count` instances of it
+pub(crate) fn block_layout<'a>(dna: &'a Dna, block: &Block) -> Option<&'a DnaStruct> {
+    block
+        .dna_struct(dna)
+        .filter(|s| s.size > 0 && s.size * block.count as usize == block.data.len())
+}
+
+fn swap_struct(dna: &Dna, name: &str, data: &mut [u8]) -> Result<()> {
+    let layout = dna
+        .get_struct(name)
+        .ok_or_else(|| FormatError::DnaError(format!("unknown struct {name}")))?;
+
+    // Bytes past a field of unknown size can't be told apart
+    layout.check_complete()?;
+    for field in &layout.fields {
+        let offset = layout.offset_of(field)?;
+        if offset + field.size > data.len() {
+            return Err(FormatError::DnaError(format!(
+                "field {}.{} extends past the struct",
+                name, field.name
+            ))
+            .into());
+        }
+        let bytes = &mut data[offset..offset + field.size];
+        let element_size = field.element_size();
+        if element_size == 0 {
+            continue;
+        }
+
+        if !field.dna_name.is_pointer() && dna.get_struct(&field.type_name).is_some() {
+            for element in bytes.chunks_exact_mut(element_size) {
+                swap_struct(dna, &field.type_name, element)?;
+            }
+        } else {
+            // char arrays have one-byte elements, so reversing them is a no-op
+            for element in bytes.chunks_exact_mut(element_size) {
+                element.reverse();
+            }
+        }
+    }
+    Ok(())
+}
+
+struct PointerConverter<'a> {
+    source_dna: &'a Dna,
+    target_dna: &'a Dna,
+    source: PointerSize,
+    target: PointerSize,
+    endianness: Endianness,
+    pointers: usize,
+}
+
+impl PointerConverter<'_> {
+    fn check_fits(&self, address: u64) -> Result<()> {
+        if self.target == PointerSize::Bits32 && address > u32::MAX as u64 {
+            return Err(FormatError::BlockError(format!(
+                "address 0x{address:x} does not fit in a 32-bit pointer"
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// A raw array of pointers at the target width
+    fn convert_pointers(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() / self.source.bytes() * self.target.bytes());
+        for value in data.chunks_exact(self.source.bytes()) {
+            let address = read_uint(value, self.endianness);
+            self.check_fits(address)?;
+            write_uint(&mut out, address, self.target.bytes(), self.endianness);
+            self.pointers += 1;
+        }
+        Ok(out)
+    }
+
+    fn convert_struct(&mut self, name: &str, data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let source_dna = self.source_dna;
+        let layout = source_dna
+            .get_struct(name)
+            .ok_or_else(|| FormatError::DnaError(format!("unknown struct {name}")))?;
+        let start = out.len();
+
+        layout.check_complete()?;
+        for field in &layout.fields {
+            let offset = layout.offset_of(field)?;
+            // A permissive open keeps structs whose TLEN is short of their fields
+            if offset + field.size > data.len() {
+                return Err(FormatError::DnaError(format!(
+                    "field {}.{} extends past the struct",
+                    name, field.name
+                ))
+                .into());
+            }
+            let bytes = &data[offset..offset + field.size];
+            let count = field.dna_name.array_size();
+
+            if field.dna_name.is_pointer() {
+                for value in bytes.chunks_exact(self.source.bytes()) {
+                    let address = read_uint(value, self.endianness);
+                    self.check_fits(address)?;
+                    write_uint(out, address, self.target.bytes(), self.endianness);
+                    self.pointers += 1;
+                }
+            } else if source_dna.get_struct(&field.type_name).is_some() {
+                for element in bytes.chunks_exact(field.size / count) {
+                    self.convert_struct(&field.type_name, element, out)?;
+                }
+            } else {
+                out.extend_from_slice(bytes);
+            }
+        }
+
+        let expected = self.target_dna.get_struct(name).map(|s| s.size);
+        if expected != Some(out.len() - start) {
+            return Err(FormatError::DnaError(format!(
+                "struct {name} converted to {} bytes, DNA expects {expected:?}",
+                out.len() - start
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_uint(bytes: &[u8], endianness: Endianness) -> u64 {
+    let mut value = 0u64;
+    match endianness {
+        Endianness::Little => {
+            for &b in bytes.iter().rev() {
+                value = (value << 8) | b as u64;
+            }
+        }
+        Endianness::Big => {
+            for &b in bytes {
+                value = (value << 8) | b as u64;
+            }
+        }
+    }
+    value
+}
+
+fn write_uint(out: &mut Vec<u8>, value: u64, size: usize, endianness: Endianness) {
+    let bytes = value.to_le_bytes();
+    match endianness {
+        Endianness::Little => out.extend_from_slice(&bytes[..size]),
+        Endianness::Big => out.extend(bytes[..size].iter().rev()),
+    }
+}
@@ -0,0 +1,317 @@
+use crate::blend_file::{
+    replace_file, BlendFile, Compression, CompressionSettings, SaveOptions, SaveReport,
+};
+use crate::block::Block;
+use crate::dna::{Dna, RawSdna};
+use crate::error::{FormatError, Result};
+use crate::header::{Endianness, Header, PointerSize};
+use crate::pointers::write_uint_at;
+use crate::scene::locate;
+use std::path::{Path, PathBuf};
+
+/// First file version whose `ID.name` holds 258 bytes instead of 66
+const LONG_ID_NAME_VERSION: u32 = 500;
+
+/// Types the minimal SDNA knows, with their sizes; structs get theirs computed
+const TYPES: &[(&str, u16)] = &[
+    ("char", 1),
+    ("short", 2),
+    ("int", 4),
+    ("uint64_t", 8),
+    ("void", 0),
+    ("ID", 0),
+    ("Library", 0),
+    ("FileData", 0),
+    ("RenderData", 0),
+    ("Scene", 0),
+    ("Object", 0),
+    ("World", 0),
+    ("FileGlobal", 0),
+    ("bScreen", 0),
+    ("ViewLayer", 0),
+];
+
+/// The SDNA `BlendFile::new` embeds: ID, Library, Scene with its RenderData
+/// and FileGlobal, cut down to their leading members under Blender's names
+pub fn minimal_sdna(version: u32, pointer_size: PointerSize) -> Dna {
+    let id_name = if version >= LONG_ID_NAME_VERSION {
+        "name[258]"
+    } else {
+        "name[66]"
+    };
+    let structs: [(&str, Vec<(&str, &str)>); 5] = [
+        (
+            "ID",
+            vec![
+                ("void", "*next"),
+                ("void", "*prev"),
+                ("ID", "*newid"),
+                ("Library", "*lib"),
+                ("char", id_name),
+                ("short", "flag"),
+                ("int", "tag"),
+                ("int", "us"),
+                ("int", "icon_id"),
+                ("int", "recalc"),
+                ("char", "_pad0[4]"),
+            ],
+        ),
+        (
+            "Library",
+            vec![
+                ("ID", "id"),
+                ("FileData", "*filedata"),
+                ("char", "filepath[1024]"),
+                ("Library", "*parent"),
+            ],
+        ),
+        (
+            "RenderData",
+            vec![
+                ("int", "cfra"),
+                ("int", "sfra"),
+                ("int", "efra"),
+                ("char", "_pad0[4]"),
+            ],
+        ),
+        (
+            "Scene",
+            vec![
+                ("ID", "id"),
+                ("Object", "*camera"),
+                ("World", "*world"),
+                ("Scene", "*set"),
+                ("RenderData", "r"),
+            ],
+        ),
+        (
+            "FileGlobal",
+            vec![
+                ("char", "subvstr[4]"),
+                ("short", "subversion"),
+                ("short", "minversion"),
+                ("short", "minsubversion"),
+                ("char", "_pad[6]"),
+                ("bScreen", "*curscreen"),
+                ("Scene", "*curscene"),
+                ("ViewLayer", "*cur_view_layer"),
+                ("void", "*_pad1"),
+                ("int", "fileflags"),
+                ("int", "globalf"),
+                ("uint64_t", "build_commit_timestamp"),
+                ("char", "build_hash[16]"),
+                ("char", "filepath[1024]"),
+            ],
+        ),
+    ];
+
+    let type_index = |name: &str| TYPES.iter().position(|(t, _)| *t == name).unwrap() as u16;
+    let mut names: Vec<String> = Vec::new();
+    let mut raw_structs = Vec::new();
+    for (struct_name, fields) in &structs {
+        let mut raw_fields = Vec::new();
+        for (type_name, field_name) in fields {
+            let name_index = match names.iter().position(|n| n == field_name) {
+                Some(index) => index,
+                None => {
+                    names.push(field_name.to_string());
+                    names.len() - 1
+                }
+            };
+            raw_fields.push((type_index(type_name), name_index as u16));
+        }
+        raw_structs.push((type_index(struct_name), raw_fields));
+    }
+    let raw = RawSdna {
+        names,
+        types: TYPES.iter().map(|(name, _)| name.to_string()).collect(),
+        tlens: TYPES.iter().map(|&(_, len)| len).collect(),
+        structs: raw_structs,
+    };
+
+    // Struct sizes start at zero; relaying them out fills in TLEN
+    Dna::from_raw(raw, pointer_size)
+        .and_then(|dna| dna.with_pointer_size(pointer_size))
+        .expect("the minimal SDNA tables are consistent")
+}
+
+impl BlendFile {
+    /// A new file in memory holding only a `GLOB` block, described by
+    /// [`minimal_sdna`]. Add datablocks with `add_library` and `add_scene`,
+    /// then write it out with `save_as`.
+    pub fn new(version: u32, pointer_size: PointerSize, endianness: Endianness) -> BlendFile {
+        let header = Header {
+            magic: *b"BLENDER",
+            pointer_size,
+            endianness,
+            version,
+        };
+        let mut blend_file = BlendFile {
+            path: PathBuf::new(),
+            header,
+            dna: minimal_sdna(version, pointer_size),
+            blocks: Vec::new(),
+            dna_blocks: Vec::new(),
+            warnings: Vec::new(),
+            lock_warnings: Vec::new(),
+            incomplete: None,
+            modified: Default::default(),
+            mmap: None,
+            file: None,
+        };
+
+        let (sdna_index, mut data) = blend_file
+            .zeroed_struct("FileGlobal")
+            .expect("FileGlobal is in the minimal SDNA");
+        let (minversion, _) = locate(&blend_file.dna, "FileGlobal", &["minversion"]).unwrap();
+        let endianness = blend_file.header.endianness;
+        write_uint_at(
+            &mut data[minversion..minversion + 2],
+            version as u64,
+            endianness,
+        );
+        blend_file.push_block(*b"GLOB", sdna_index, data);
+        blend_file
+    }
+
+    /// Add a `Library` datablock for the file at `filepath`, usually
+    /// `//`-relative, and return its address
+    pub fn add_library(&mut self, name: &str, filepath: &str) -> Result<u64> {
+        let (sdna_index, mut data) = self.zeroed_struct("Library")?;
+        self.write_id_name(&mut data, "Library", &format!("LI{name}"))?;
+        let (offset, field) = locate(&self.dna, "Library", &["filepath"])?;
+        write_fixed_string(
+            &mut data[offset..offset + field.size],
+            filepath,
+            "Library.filepath",
+        )?;
+        Ok(self.push_block(*b"LI\0\0", sdna_index, data))
+    }
+
+    /// Add an empty `Scene` with Blender's default frame range 1-250 and
+    /// return its address. The first scene becomes the file's current scene.
+    pub fn add_scene(&mut self, name: &str) -> Result<u64> {
+        let (sdna_index, mut data) = self.zeroed_struct("Scene")?;
+        self.write_id_name(&mut data, "Scene", &format!("SC{name}"))?;
+        let endianness = self.header.endianness;
+        for (field, value) in [("cfra", 1), ("sfra", 1), ("efra", 250)] {
+            let (offset, _) = locate(&self.dna, "Scene", &["r", field])?;
+            write_uint_at(&mut data[offset..offset + 4], value, endianness);
+        }
+        let address = self.push_block(*b"SC\0\0", sdna_index, data);
+
+        let (curscene, field) = locate(&self.dna, "FileGlobal", &["curscene"])?;
+        let range = curscene..curscene + field.size;
+        if let Some(glob) = self.blocks.iter_mut().find(|b| &b.code == b"GLOB") {
+            if glob.data[range.clone()].iter().all(|&b| b == 0) {
+                write_uint_at(&mut glob.data[range], address, endianness);
+            }
+        }
+        Ok(address)
+    }
+
+    /// Write the file to `path` and make it the file's path. Unlike `save`
+    /// this needs no open handle, so it also works for files made with `new`.
+    pub fn save_as<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.save_as_with_options(path, &SaveOptions::default())
+    }
+
+    /// `save_as`, compressed with `kind` as Blender would: gzip as 2.7x wrote
+    /// it, anything else at its usual level
+    pub fn save_as_with_compression<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        kind: Compression,
+    ) -> Result<()> {
+        let compression = match kind {
+            Compression::Gzip => CompressionSettings::legacy(),
+            kind => CompressionSettings::new(kind),
+        };
+        self.save_as_with_options(path, &SaveOptions::default().with_compression(compression))
+    }
+
+    /// `save_as`, compressed as `options` asks
+    pub fn save_as_with_options<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &SaveOptions,
+    ) -> Result<()> {
+        self.save_as_with_report(path, options).map(|_| ())
+    }
+
+    /// `save_as_with_options`, reporting which metadata of a file already at
+    /// `path` survived being overwritten
+    pub fn save_as_with_report<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &SaveOptions,
+    ) -> Result<SaveReport> {
+        let path = path.as_ref();
+        options.record_audit(self)?;
+        let mut data = Vec::new();
+        self.write_to_writer(&mut data)?;
+        let mut compressed = Vec::new();
+        options.compression.write(&data, &mut compressed)?;
+
+        let (_, report) = replace_file(path, &compressed, options.preserve_mtime)?;
+        self.path = path.to_path_buf();
+        self.record_offsets(data.len());
+        self.modified.clear();
+        Ok(report)
+    }
+
+    /// SDNA index of `struct_name` and a zeroed payload of its size
+    fn zeroed_struct(&self, struct_name: &str) -> Result<(u32, Vec<u8>)> {
+        let index = self
+            .dna
+            .structs_by_index
+            .iter()
+            .position(|s| s.name == struct_name)
+            .ok_or_else(|| FormatError::DnaError(format!("struct {struct_name} not in DNA")))?;
+        Ok((index as u32, vec![0; self.dna.structs_by_index[index].size]))
+    }
+
+    fn write_id_name(&self, data: &mut [u8], struct_name: &str, name: &str) -> Result<()> {
+        let (offset, field) = locate(&self.dna, struct_name, &["id", "name"])?;
+        write_fixed_string(&mut data[offset..offset + field.size], name, "ID.name")
+    }
+
+    /// Append a block at a fresh address past every existing block
+    pub(crate) fn push_block(&mut self, code: [u8; 4], sdna_index: u32, data: Vec<u8>) -> u64 {
+        let address = self
+            .blocks
+            .iter()
+            .map(|b| b.old_memory_address + b.data.len() as u64)
+            .max()
+            .unwrap_or(0)
+            .next_multiple_of(16)
+            .max(0x1000);
+        let block = Block {
+            code,
+            size: data.len() as u32,
+            old_memory_address: address,
+            sdna_index,
+            count: 1,
+            data_offset: 0,
+            data,
+        };
+        self.blocks.push(block);
+        self.modified.mark_all();
+        address
+    }
+}
+
+/// Copy `value` into a null-terminated char array, failing if it doesn't fit
+fn write_fixed_string(field: &mut [u8], value: &str, field_name: &str) -> Result<()> {
+    if value.len() >= field.len() {
+        return Err(FormatError::InvalidFormat(format!(
+            "{field_name} holds {} bytes, {value:?} needs {}",
+            field.len() - 1,
+            value.len()
+        ))
+        .into());
+    }
+    field.fill(0);
+    field[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(())
+}
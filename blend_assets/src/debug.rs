@@ -0,0 +1,578 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::block_code;
+use crate::bytes::{
+    read_f32_at, read_f64_at, read_i16_at, read_i32_at, read_i64_at, read_u16_at, read_u32_at,
+    read_u64_at,
+};
+use crate::convert::{block_layout, read_uint};
+use crate::dna::{Dna, DnaField};
+use crate::error::{FormatError, Result};
+use crate::header::Endianness;
+use crate::library_usage::id_offset;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Bytes of payload shown in the hexdump and searched for strings
+const PREVIEW_LEN: usize = 256;
+/// Array elements shown in a field's value preview
+const PREVIEW_ELEMENTS: usize = 4;
+
+/// A block's header, payload preview and, when the DNA describes it, its fields
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockInspection {
+    /// Position of the block in `BlendFile::blocks`
+    pub index: usize,
+    pub code: String,
+    pub size: usize,
+    pub address: u64,
+    pub sdna_index: u32,
+    pub count: u32,
+    /// DNA struct of the payload, when its size matches `count` instances
+    pub struct_name: Option<String>,
+    /// Hexdump lines of the first 256 bytes
+    pub hexdump: Vec<String>,
+    /// Printable ASCII runs in the first 256 bytes
+    pub strings: Vec<FoundString>,
+    /// Fields of the first instance
+    pub fields: Vec<FieldValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FoundString {
+    pub offset: usize,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldValue {
+    /// Name as declared, e.g. `*parent` or `loc[3]`
+    pub name: String,
+    pub type_name: String,
+    pub offset: usize,
+    pub size: usize,
+    /// Short decoded form: a string, numbers, an address or `{Struct}`
+    pub value: String,
+}
+
+impl BlendFile {
+    /// Describe the block at `index` for debugging
+    pub fn inspect_block(&self, index: usize) -> Result<BlockInspection> {
+        let block = self.blocks.get(index).ok_or_else(|| {
+            FormatError::BlockError(format!(
+                "no block {index}; the file has {}",
+                self.blocks.len()
+            ))
+        })?;
+        let preview = &block.data[..block.data.len().min(PREVIEW_LEN)];
+        let layout = block_layout(&self.dna, block);
+        let fields = layout.map_or_else(Vec::new, |layout| {
+            layout
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    let offset = field.offset?;
+                    let bytes = block.data.get(offset..offset + field.size)?;
+                    Some(FieldValue {
+                        name: field.dna_name.name_full.clone(),
+                        type_name: field.type_name.clone(),
+                        offset,
+                        size: field.size,
+                        value: preview_value(&self.dna, field, bytes, self.header.endianness),
+                    })
+                })
+                .collect()
+        });
+
+        Ok(BlockInspection {
+            index,
+            code: block.get_type_name().trim_end_matches('\0').to_string(),
+            size: block.data.len(),
+            address: block.old_memory_address,
+            sdna_index: block.sdna_index,
+            count: block.count,
+            struct_name: layout.map(|layout| layout.name.clone()),
+            hexdump: hexdump(preview),
+            strings: find_strings(preview),
+            fields,
+        })
+    }
+
+    /// Inspect every block whose code starts with `code` (`LI`, `OB`, `DATA`, ...)
+    pub fn inspect_blocks(&self, code: &str) -> Result<Vec<BlockInspection>> {
+        let code = code.as_bytes();
+        (0..self.blocks.len())
+            .filter(|&index| {
+                let block_code = &self.blocks[index].code;
+                code.len() <= 4 && block_code.starts_with(code)
+            })
+            .map(|index| self.inspect_block(index))
+            .collect()
+    }
+}
+
+impl fmt::Display for BlockInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {} block {} ---", self.code, self.index)?;
+        writeln!(
+            f,
+            "Size: {} bytes  Address: {:#x}  SDNA: {}  Count: {}",
+            self.size, self.address, self.sdna_index, self.count
+        )?;
+        writeln!(
+            f,
+            "Struct: {}",
+            self.struct_name.as_deref().unwrap_or("(unknown)")
+        )?;
+
+        writeln!(f, "Data (first {} bytes):", self.size.min(PREVIEW_LEN))?;
+        for line in &self.hexdump {
+            writeln!(f, "{line}")?;
+        }
+
+        if !self.strings.is_empty() {
+            writeln!(f, "Strings:")?;
+            for found in &self.strings {
+                writeln!(f, "  {:>4}: \"{}\"", found.offset, found.value)?;
+            }
+        }
+
+        if !self.fields.is_empty() {
+            writeln!(f, "Fields:")?;
+            for field in &self.fields {
+                writeln!(
+                    f,
+                    "  {:>6}  {:<24} {:<12} {}",
+                    field.offset, field.name, field.type_name, field.value
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Print every LI block of the file at `file_path`
+pub fn debug_library_blocks<P: AsRef<Path>>(file_path: P) -> Result<()> {
+    let blend_file = BlendFile::open(file_path.as_ref())?;
+    println!("File: {}", file_path.as_ref().display());
+    for inspection in blend_file.inspect_blocks("LI")? {
+        println!("\n{inspection}");
+    }
+    Ok(())
+}
+
+fn hexdump(data: &[u8]) -> Vec<String> {
+    data.chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if (32..=126).contains(&b) {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:04x}: {:<47}  {ascii}", row * 16, hex.join(" "))
+        })
+        .collect()
+}
+
+/// Null-terminated printable runs of at least 3 characters
+fn find_strings(data: &[u8]) -> Vec<FoundString> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    for (offset, &byte) in data.iter().enumerate() {
+        if (32..=126).contains(&byte) {
+            continue;
+        }
+        if byte == 0 && offset - start >= 3 {
+            let value = String::from_utf8_lossy(&data[start..offset]);
+            if !value.trim().is_empty() {
+                found.push(FoundString {
+                    offset: start,
+                    value: value.into_owned(),
+                });
+            }
+        }
+        start = offset + 1;
+    }
+    found
+}
+
+fn preview_value(dna: &Dna, field: &DnaField, bytes: &[u8], endianness: Endianness) -> String {
+    let count = field.dna_name.array_size().max(1);
+    if field.dna_name.is_pointer() {
+        let width = bytes.len() / count;
+        return preview_elements(bytes, width, |element| {
+            match read_uint(element, endianness) {
+                0 => Ok("null".to_string()),
+                address => Ok(format!("{address:#x}")),
+            }
+        });
+    }
+    if dna.get_struct(&field.type_name).is_some() {
+        return format!("{{{}}}", field.type_name);
+    }
+    match field.type_name.as_str() {
+        "char" if count > 1 => {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            format!("{:?}", String::from_utf8_lossy(&bytes[..end]))
+        }
+        "char" | "uchar" | "int8_t" | "uint8_t" => {
+            preview_elements(bytes, 1, |b| Ok(b[0].to_string()))
+        }
+        "short" => preview_elements(bytes, 2, |b| Ok(read_i16_at(b, 0, endianness)?.to_string())),
+        "ushort" => preview_elements(bytes, 2, |b| Ok(read_u16_at(b, 0, endianness)?.to_string())),
+        "int" => preview_elements(bytes, 4, |b| Ok(read_i32_at(b, 0, endianness)?.to_string())),
+        "float" => preview_elements(bytes, 4, |b| Ok(read_f32_at(b, 0, endianness)?.to_string())),
+        "double" => preview_elements(bytes, 8, |b| Ok(read_f64_at(b, 0, endianness)?.to_string())),
+        "int64_t" => preview_elements(bytes, 8, |b| Ok(read_i64_at(b, 0, endianness)?.to_string())),
+        "uint64_t" => {
+            preview_elements(bytes, 8, |b| Ok(read_u64_at(b, 0, endianness)?.to_string()))
+        }
+        _ => hex_preview(bytes),
+    }
+}
+
+/// `value`, or `[a, b, c, d, ...]` for arrays
+fn preview_elements(
+    bytes: &[u8],
+    width: usize,
+    decode: impl Fn(&[u8]) -> Result<String>,
+) -> String {
+    if width == 0 || bytes.len() < width {
+        return String::new();
+    }
+    let elements: Vec<String> = bytes
+        .chunks_exact(width)
+        .take(PREVIEW_ELEMENTS)
+        .map(|element| decode(element).unwrap_or_else(|_| "?".to_string()))
+        .collect();
+    if bytes.len() == width {
+        return elements.join("");
+    }
+    let more = if bytes.len() / width > PREVIEW_ELEMENTS {
+        ", ..."
+    } else {
+        ""
+    };
+    format!("[{}{more}]", elements.join(", "))
+}
+
+/// Array elements decoded by `decode_block` before the rest is summarized
+const DECODED_ELEMENTS: usize = 16;
+/// Levels of embedded structs `decode_block` expands
+const DECODE_DEPTH: usize = 1;
+
+/// A struct instance decoded field by field through the DNA
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedStruct {
+    /// `None` when the DNA doesn't describe the block
+    pub struct_name: Option<String>,
+    pub fields: Vec<DecodedField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedField {
+    /// Name as declared, e.g. `*data` or `loc[3]`
+    pub name: String,
+    pub type_name: String,
+    pub offset: usize,
+    pub value: DecodedValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum DecodedValue {
+    Int(i64),
+    Float(f32),
+    Double(f64),
+    /// A `char` array up to its first NUL
+    String(String),
+    Pointer(PointerValue),
+    /// An embedded struct within the expansion depth
+    Struct(DecodedStruct),
+    /// An embedded struct past the expansion depth, by type name
+    Elided(String),
+    /// The first elements of an array and its full length
+    Array {
+        elements: Vec<DecodedValue>,
+        len: usize,
+    },
+    /// Hex of the first bytes of a type the decoder doesn't know
+    Opaque(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PointerValue {
+    pub address: u64,
+    /// The block at `address`, when there is one
+    pub target: Option<PointerTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PointerTarget {
+    /// Position of the block in `BlendFile::blocks`
+    pub index: usize,
+    pub code: String,
+    /// ID name without its code prefix, for ID blocks
+    pub name: Option<String>,
+}
+
+/// Decode the first struct instance in `block`, resolving pointers to the
+/// blocks of `blend_file` they address
+pub fn decode_block(block: &Block, dna: &Dna, blend_file: &BlendFile) -> DecodedStruct {
+    let decoder = Decoder {
+        dna,
+        blend_file,
+        by_address: blend_file
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.old_memory_address != 0)
+            .map(|(index, b)| (b.old_memory_address, index))
+            .collect(),
+    };
+    match block_layout(dna, block) {
+        Some(layout) => decoder.decode_struct(&layout.name, &block.data, 0),
+        None => DecodedStruct {
+            struct_name: None,
+            fields: vec![DecodedField {
+                name: "data".to_string(),
+                type_name: String::new(),
+                offset: 0,
+                value: DecodedValue::Opaque(hex_preview(&block.data)),
+            }],
+        },
+    }
+}
+
+impl BlendFile {
+    /// Indices of blocks whose code starts with `code` and whose ID name
+    /// (without the code prefix) is `name`; either filter may be left out
+    pub fn select_blocks(&self, code: Option<&str>, name: Option<&str>) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&index| {
+                let block = &self.blocks[index];
+                let code_matches =
+                    code.is_none_or(|code| block_code::raw_code(&block.code).starts_with(code));
+                let name_matches =
+                    name.is_none_or(|name| id_name(&self.dna, block).as_deref() == Some(name));
+                code_matches && name_matches
+            })
+            .collect()
+    }
+}
+
+struct Decoder<'a> {
+    dna: &'a Dna,
+    blend_file: &'a BlendFile,
+    by_address: HashMap<u64, usize>,
+}
+
+impl Decoder<'_> {
+    fn decode_struct(&self, struct_name: &str, data: &[u8], depth: usize) -> DecodedStruct {
+        let fields = self
+            .dna
+            .get_struct(struct_name)
+            .map_or_else(Vec::new, |layout| {
+                layout
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        let offset = field.offset?;
+                        let bytes = data.get(offset..offset + field.size)?;
+                        Some(DecodedField {
+                            name: field.dna_name.name_full.clone(),
+                            type_name: field.type_name.clone(),
+                            offset,
+                            value: self.decode_field(field, bytes, depth),
+                        })
+                    })
+                    .collect()
+            });
+        DecodedStruct {
+            struct_name: Some(struct_name.to_string()),
+            fields,
+        }
+    }
+
+    fn decode_field(&self, field: &DnaField, bytes: &[u8], depth: usize) -> DecodedValue {
+        let count = field.dna_name.element_count().max(1);
+        let is_char = field.type_name == "char" && !field.dna_name.is_pointer();
+        // char arrays are strings, one per row of the last dimension
+        let width = match field.dna_name.dimensions().last() {
+            Some(&last) if is_char && last > 0 => bytes.len() / count * last,
+            _ => bytes.len() / count,
+        };
+        if width == 0 {
+            return DecodedValue::Opaque(String::new());
+        }
+        let elements = bytes.len() / width;
+        if elements == 1 {
+            return self.decode_element(field, bytes, is_char && count > 1, depth);
+        }
+        DecodedValue::Array {
+            elements: bytes
+                .chunks_exact(width)
+                .take(DECODED_ELEMENTS)
+                .map(|element| self.decode_element(field, element, is_char, depth))
+                .collect(),
+            len: elements,
+        }
+    }
+
+    fn decode_element(
+        &self,
+        field: &DnaField,
+        bytes: &[u8],
+        string: bool,
+        depth: usize,
+    ) -> DecodedValue {
+        let endianness = self.blend_file.header.endianness;
+        if field.dna_name.is_pointer() {
+            let address = read_uint(bytes, endianness);
+            return DecodedValue::Pointer(PointerValue {
+                address,
+                target: self.target(address),
+            });
+        }
+        if string {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            return DecodedValue::String(String::from_utf8_lossy(&bytes[..end]).into_owned());
+        }
+        if self.dna.get_struct(&field.type_name).is_some() {
+            if depth < DECODE_DEPTH {
+                return DecodedValue::Struct(self.decode_struct(
+                    &field.type_name,
+                    bytes,
+                    depth + 1,
+                ));
+            }
+            return DecodedValue::Elided(field.type_name.clone());
+        }
+        let int = |value: Result<i64, FormatError>| value.map(DecodedValue::Int);
+        let decoded = match (field.type_name.as_str(), bytes.len()) {
+            ("char" | "int8_t", 1) => Ok(DecodedValue::Int(bytes[0] as i8 as i64)),
+            ("uchar" | "uint8_t", 1) => Ok(DecodedValue::Int(bytes[0] as i64)),
+            ("short" | "int16_t", 2) => int(read_i16_at(bytes, 0, endianness).map(i64::from)),
+            ("ushort" | "uint16_t", 2) => int(read_u16_at(bytes, 0, endianness).map(i64::from)),
+            ("int" | "int32_t", 4) => int(read_i32_at(bytes, 0, endianness).map(i64::from)),
+            ("uint" | "uint32_t", 4) => int(read_u32_at(bytes, 0, endianness).map(i64::from)),
+            ("int64_t" | "uint64_t", 8) => int(read_i64_at(bytes, 0, endianness)),
+            ("float", 4) => read_f32_at(bytes, 0, endianness).map(DecodedValue::Float),
+            ("double", 8) => read_f64_at(bytes, 0, endianness).map(DecodedValue::Double),
+            _ => return DecodedValue::Opaque(hex_preview(bytes)),
+        };
+        decoded.unwrap_or_else(|_| DecodedValue::Opaque(hex_preview(bytes)))
+    }
+
+    fn target(&self, address: u64) -> Option<PointerTarget> {
+        let &index = self.by_address.get(&address)?;
+        let block = &self.blend_file.blocks[index];
+        Some(PointerTarget {
+            index,
+            code: block_code::raw_code(&block.code),
+            name: id_name(self.dna, block),
+        })
+    }
+}
+
+/// ID name of a block without its two-letter code prefix
+pub(crate) fn id_name(dna: &Dna, block: &Block) -> Option<String> {
+    let layout = block_layout(dna, block)?;
+    let offset = id_offset(dna, &layout.name)?;
+    let name_field = dna.get_struct("ID")?.field("name")?;
+    let start = offset + name_field.offset?;
+    let raw = block.data.get(start..start + name_field.size)?;
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    raw.get(2..end)
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+fn hex_preview(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().take(8).map(|b| format!("{b:02x}")).collect();
+    let more = if bytes.len() > 8 { " ..." } else { "" };
+    format!("{}{more}", hex.join(" "))
+}
+
+impl fmt::Display for DecodedStruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            self.struct_name.as_deref().unwrap_or("(unknown struct)")
+        )?;
+        self.write_fields(f, 1)
+    }
+}
+
+impl DecodedStruct {
+    fn write_fields(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        for field in &self.fields {
+            writeln!(
+                f,
+                "{:indent$}{}: {}",
+                "",
+                field.name,
+                field.value,
+                indent = indent * 2
+            )?;
+            if let DecodedValue::Struct(nested) = &field.value {
+                nested.write_fields(f, indent + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DecodedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedValue::Int(value) => write!(f, "{value}"),
+            DecodedValue::Float(value) => write!(f, "{value:?}"),
+            DecodedValue::Double(value) => write!(f, "{value:?}"),
+            DecodedValue::String(value) => write!(f, "{value:?}"),
+            DecodedValue::Pointer(pointer) => write!(f, "{pointer}"),
+            DecodedValue::Struct(nested) => {
+                write!(f, "{{{}}}", nested.struct_name.as_deref().unwrap_or("?"))
+            }
+            DecodedValue::Elided(type_name) => write!(f, "{{{type_name}}}"),
+            DecodedValue::Array { elements, len } => {
+                let shown: Vec<String> = elements.iter().map(ToString::to_string).collect();
+                if *len > elements.len() {
+                    write!(f, "[{}, ... ({len} total)]", shown.join(", "))
+                } else {
+                    write!(f, "[{}]", shown.join(", "))
+                }
+            }
+            DecodedValue::Opaque(hex) => write!(f, "<{hex}>"),
+        }
+    }
+}
+
+impl fmt::Display for PointerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.target, self.address) {
+            (_, 0) => write!(f, "null"),
+            (
+                Some(PointerTarget {
+                    code,
+                    name: Some(name),
+                    ..
+                }),
+                address,
+            ) => {
+                write!(f, "→ {code} '{name}' @{address:#x}")
+            }
+            (Some(PointerTarget { code, .. }), address) => write!(f, "→ {code} @{address:#x}"),
+            (None, address) => write!(f, "@{address:#x} (no block)"),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Decompression as a forward-only stream, so reads that only need the start
+//! of a file (`BlendFile::sniff`, `BlendFile::peek`, streaming link
+//! extraction) stop decompressing where they stop reading. Full opens read
+//! the same stream to the end.
+
+use crate::blend_file::{BlendFile, Compression, OpenOptions};
+use crate::block::{Block, BlockIterator};
+use crate::cancel;
+use crate::error::Result;
+use crate::header::Header;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// Decompressed bytes buffered at a time; backward seeks can only reach the
+/// start of the current buffer
+pub const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Uncompressed blend data from a zlib, gzip, zstd or plain source, decoded
+/// as it's read. Seeking forward decodes and drops the bytes in between;
+/// seeking back only works within the buffered bytes.
+pub struct DecompressingReader<'a> {
+    decoder: Box<dyn Read + 'a>,
+    compression: Compression,
+    /// Most decompressed bytes to decode before failing
+    limit: Option<u64>,
+    /// Decompressed bytes taken from `decoder` so far
+    decoded: u64,
+    buffer: Vec<u8>,
+    /// Decompressed offset of `buffer[0]`
+    buffer_start: u64,
+    position: u64,
+}
+
+impl<'a> DecompressingReader<'a> {
+    /// Pick the decoder from the first four bytes of `source`
+    pub fn new<R: Read + 'a>(mut source: R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        source.read_exact(&mut magic)?;
+        let compression = Compression::from_magic(&magic);
+        let source = io::Cursor::new(magic).chain(source);
+        let decoder: Box<dyn Read + 'a> = match compression {
+            Compression::Zlib => Box::new(ZlibDecoder::new(source)),
+            Compression::Zstd => Box::new(ZstdDecoder::new(source)?),
+            Compression::Gzip => Box::new(GzDecoder::new(source)),
+            Compression::None => Box::new(source),
+        };
+        Ok(Self {
+            decoder,
+            compression,
+            limit: None,
+            decoded: 0,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            position: 0,
+        })
+    }
+
+    /// Fail reads once the stream decompresses to more than `limit` bytes.
+    /// Checked as data is decoded, so a decompression bomb stops at the limit.
+    pub fn with_limit(mut self, limit: Option<u64>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Decompressed bytes read or skipped so far
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn buffer_end(&self) -> u64 {
+        self.buffer_start + self.buffer.len() as u64
+    }
+
+    /// Read from the decoder into `buf`, up to one byte past the limit so
+    /// going over it is noticed
+    fn decode(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = match self.limit {
+            Some(limit) => {
+                let allowed = limit.saturating_sub(self.decoded).saturating_add(1);
+                buf.len()
+                    .min(usize::try_from(allowed).unwrap_or(usize::MAX))
+            }
+            None => buf.len(),
+        };
+        let n = self.decoder.read(&mut buf[..len])?;
+        self.decoded += n as u64;
+        match self.limit {
+            Some(limit) if self.decoded > limit => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decompressed data exceeds the {limit} byte limit"),
+            )),
+            _ => Ok(n),
+        }
+    }
+
+    /// Replace the buffer with the next decoded bytes; 0 at the end of the stream
+    fn fill(&mut self) -> io::Result<usize> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        self.buffer_start += buffer.len() as u64;
+        buffer.resize(STREAM_BUFFER_SIZE, 0);
+        let n = self.decode(&mut buffer);
+        buffer.truncate(*n.as_ref().unwrap_or(&0));
+        self.buffer = buffer;
+        n
+    }
+}
+
+impl Read for DecompressingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer_end() {
+            // Large reads skip the buffer, as in `BufReader`
+            if buf.len() >= STREAM_BUFFER_SIZE {
+                let n = self.decode(buf)?;
+                self.buffer.clear();
+                self.position += n as u64;
+                self.buffer_start = self.position;
+                return Ok(n);
+            }
+            if self.fill()? == 0 {
+                return Ok(0);
+            }
+        }
+        let start = (self.position - self.buffer_start) as usize;
+        let n = buf.len().min(self.buffer.len() - start);
+        buf[..n].copy_from_slice(&self.buffer[start..start + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for DecompressingReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "a decompressing stream has no known end",
+                ))
+            }
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the stream"))?;
+        if target < self.buffer_start {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("offset {target} is no longer buffered"),
+            ));
+        }
+        // Buffer the byte at `target` too, so reading it doesn't drop what
+        // came before
+        while target >= self.buffer_end() {
+            self.position = self.buffer_end();
+            if self.fill()? == 0 {
+                if target > self.buffer_end() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                break;
+            }
+        }
+        self.position = target;
+        Ok(target)
+    }
+}
+
+/// Compression and header of a blend file
+#[derive(Debug, Clone)]
+pub struct Sniff {
+    pub compression: Compression,
+    pub header: Header,
+}
+
+impl BlendFile {
+    /// Read only the compression and header, decompressing no further than
+    /// the first buffer
+    pub fn sniff<P: AsRef<Path>>(path: P) -> Result<Sniff> {
+        Self::sniff_reader(File::open(path)?)
+    }
+
+    /// `sniff`, reading compressed or plain blend data from `source`
+    pub fn sniff_reader<R: Read>(source: R) -> Result<Sniff> {
+        let mut reader = DecompressingReader::new(source)?;
+        let header = Header::from_reader(&mut reader)?;
+        Ok(Sniff {
+            compression: reader.compression(),
+            header,
+        })
+    }
+
+    /// The header and first `count` blocks with their payloads, decompressing
+    /// only as far as those blocks. `DNA1` is left out, as in `blocks`, and
+    /// reading stops at `ENDB`.
+    pub fn peek<P: AsRef<Path>>(path: P, count: usize) -> Result<(Header, Vec<Block>)> {
+        Self::peek_reader(File::open(path)?, count, &OpenOptions::default())
+    }
+
+    /// `peek`, reading from `source` with the decompressed-size limit and
+    /// cancellation from `options`; cancellation is checked between blocks
+    pub fn peek_reader<R: Read>(
+        source: R,
+        count: usize,
+        options: &OpenOptions,
+    ) -> Result<(Header, Vec<Block>)> {
+        let cancellation = options.cancellation.as_ref();
+        cancel::check(cancellation)?;
+        let mut reader =
+            DecompressingReader::new(source)?.with_limit(options.max_decompressed_size);
+        let header = Header::from_reader(&mut reader)?;
+        let mut blocks = Vec::with_capacity(count.min(1024));
+        let mut iter = BlockIterator::new(&mut reader, &header);
+        while blocks.len() < count {
+            cancel::check(cancellation)?;
+            let Some(block) = iter.next().transpose()? else {
+                break;
+            };
+            match &block.code {
+                b"ENDB" => break,
+                b"DNA1" => continue,
+                _ => blocks.push(block),
+            }
+        }
+        Ok((header, blocks))
+    }
+}
@@ -0,0 +1,125 @@
+//! Library files a blend file depends on, followed from file to file to find
+//! chains that lead back where they started. Blender loads such files with
+//! warnings, and anything copying a file together with its libraries has to
+//! stop somewhere.
+
+use crate::blend_file::BlendFile;
+use crate::error::{FormatError, Result};
+use crate::library_link::LibraryLink;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A chain of library links returning to its first file; a file listing
+/// itself as a library is a chain of one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DependencyCycle(pub Vec<PathBuf>);
+
+impl fmt::Display for DependencyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chain = self.0.iter().chain(self.0.first());
+        if let Some(first) = chain.next() {
+            write!(f, "{}", first.display())?;
+        }
+        for path in chain {
+            write!(f, " -> {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl BlendFile {
+    /// Library files this file links, resolved and canonicalized; packed
+    /// libraries and files that don't exist are left out
+    pub fn library_dependencies(&self) -> Result<Vec<PathBuf>> {
+        let mut dependencies = Vec::new();
+        for link in self.get_library_links()? {
+            if let Some(path) = library_file(&link) {
+                if !dependencies.contains(&path) {
+                    dependencies.push(path);
+                }
+            }
+        }
+        Ok(dependencies)
+    }
+
+    /// Library links naming this file itself
+    pub fn self_references(&self) -> Result<Vec<LibraryLink>> {
+        let Ok(own) = self.path.canonicalize() else {
+            return Ok(Vec::new());
+        };
+        Ok(self
+            .get_library_links()?
+            .into_iter()
+            .filter(|link| library_file(link).as_ref() == Some(&own))
+            .collect())
+    }
+}
+
+/// The file on disk a library link loads, if any
+fn library_file(link: &LibraryLink) -> Option<PathBuf> {
+    if link.block_type != "Library" || link.is_packed || link.is_placeholder {
+        return None;
+    }
+    let target = link.absolute_path.as_deref().unwrap_or(&link.path);
+    Path::new(target).canonicalize().ok()
+}
+
+/// Each distinct cycle of library links reachable from `path`, as the files
+/// in link order. A cycle is reported once, starting from the file the walk
+/// reached first, however many of its files link into it.
+pub fn dependency_cycles<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<PathBuf>>> {
+    let start = path
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| FormatError::IoError(format!("{}: {e}", path.as_ref().display())))?;
+    let mut walk = CycleWalk::default();
+    walk.visit(start)?;
+    Ok(walk.cycles.into_iter().map(|cycle| cycle.0).collect())
+}
+
+#[derive(Default)]
+struct CycleWalk {
+    /// Files on the current chain, and their position in it
+    chain: Vec<PathBuf>,
+    on_chain: HashMap<PathBuf, usize>,
+    done: HashSet<PathBuf>,
+    cycles: Vec<DependencyCycle>,
+    /// Cycles rotated to start at their smallest path, to spot repeats
+    seen: HashSet<Vec<PathBuf>>,
+}
+
+impl CycleWalk {
+    fn visit(&mut self, file: PathBuf) -> Result<()> {
+        // Libraries that don't parse have no links to follow; the start
+        // file's own errors still surface
+        let dependencies = match BlendFile::open(&file).and_then(|b| b.library_dependencies()) {
+            Ok(dependencies) => dependencies,
+            Err(e) if self.chain.is_empty() => return Err(e),
+            Err(_) => Vec::new(),
+        };
+
+        self.on_chain.insert(file.clone(), self.chain.len());
+        self.chain.push(file.clone());
+        for dependency in dependencies {
+            if let Some(&position) = self.on_chain.get(&dependency) {
+                self.record(self.chain[position..].to_vec());
+            } else if !self.done.contains(&dependency) {
+                self.visit(dependency)?;
+            }
+        }
+        self.chain.pop();
+        self.on_chain.remove(&file);
+        self.done.insert(file);
+        Ok(())
+    }
+
+    fn record(&mut self, cycle: Vec<PathBuf>) {
+        let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+        let mut key = cycle.clone();
+        key.rotate_left(smallest);
+        if self.seen.insert(key) {
+            self.cycles.push(DependencyCycle(cycle));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Which Blender release wrote a file, told apart by its DNA fingerprint
+//! rather than the three-digit header version. Files whose DNA isn't in the
+//! table come from builds nobody has catalogued, often patched or forked ones.
+
+use crate::blend_file::BlendFile;
+use crate::dna::DnaFingerprint;
+use crate::error::{BlendFileError, FormatError, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Table shipped with the crate, in the format `DnaReleases::parse` reads
+const BUILTIN_RELEASES: &str = include_str!("../data/dna_releases.txt");
+
+/// Known DNA fingerprints and the release that writes each
+#[derive(Debug, Clone, Default)]
+pub struct DnaReleases {
+    releases: HashMap<DnaFingerprint, String>,
+}
+
+impl DnaReleases {
+    /// The table shipped with the crate
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_RELEASES).expect("built-in DNA release table is valid")
+    }
+
+    /// Parse lines of `<fingerprint> <release>`; blank lines and lines
+    /// starting with `#` are skipped
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut table = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (fingerprint, release) = line
+                .split_once(char::is_whitespace)
+                .map(|(fingerprint, release)| (fingerprint, release.trim()))
+                .unwrap_or((line, ""));
+            if release.is_empty() {
+                return Err(FormatError::ParseError(format!(
+                    "line {}: expected '<fingerprint> <release>'",
+                    number + 1
+                ))
+                .into());
+            }
+            let fingerprint: DnaFingerprint = fingerprint
+                .parse()
+                .map_err(|e| FormatError::ParseError(format!("line {}: {e}", number + 1)))?;
+            table.insert(fingerprint, release);
+        }
+        Ok(table)
+    }
+
+    /// Add the entries in the file at `path`, which replace built-in ones
+    /// with the same fingerprint
+    pub fn extend_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| FormatError::IoError(format!("{}: {e}", path.display())))?;
+        let extra = Self::parse(&text).map_err(|e| match e {
+            BlendFileError::Format(FormatError::ParseError(message)) => {
+                FormatError::ParseError(format!("{}: {message}", path.display())).into()
+            }
+            e => e,
+        })?;
+        self.releases.extend(extra.releases);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, fingerprint: DnaFingerprint, release: impl Into<String>) {
+        self.releases.insert(fingerprint, release.into());
+    }
+
+    pub fn lookup(&self, fingerprint: DnaFingerprint) -> Option<&str> {
+        self.releases.get(&fingerprint).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.releases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.releases.is_empty()
+    }
+}
+
+/// A file's DNA fingerprint and statistics, and the release it matches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DnaReport {
+    /// `DnaFingerprint` as 16 hex digits
+    pub fingerprint: String,
+    pub struct_count: usize,
+    pub field_count: usize,
+    pub max_struct_size: usize,
+    /// `None` when the fingerprint isn't in the table
+    pub release: Option<String>,
+}
+
+impl fmt::Display for DnaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.release {
+            Some(release) => write!(f, "DNA matches Blender {release}"),
+            None => write!(f, "unknown DNA variant"),
+        }
+    }
+}
+
+impl BlendFile {
+    /// Fingerprint the file's DNA and look it up in `releases`; `None` for
+    /// files without DNA
+    pub fn dna_report(&self, releases: &DnaReleases) -> Option<DnaReport> {
+        if self.dna.is_empty() {
+            return None;
+        }
+        let fingerprint = self.dna.fingerprint();
+        let stats = self.dna.stats();
+        Some(DnaReport {
+            fingerprint: fingerprint.to_string(),
+            struct_count: stats.struct_count,
+            field_count: stats.field_count,
+            max_struct_size: stats.max_struct_size,
+            release: releases.lookup(fingerprint).map(str::to_string),
+        })
+    }
+}
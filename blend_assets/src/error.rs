@@ -0,0 +1,83 @@
+//! Errors from the reader. Failures of the binary format itself come from
+//! blend-raw as `FormatError` and are wrapped in `BlendFileError::Format`;
+//! the other variants belong to the features built on top of it.
+
+use crate::naming::NameViolation;
+use std::fmt;
+use std::io;
+
+pub use blend_raw::error::FormatError;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BlendFileError {
+    /// The file, its DNA or its blocks can't be read or written
+    Format(FormatError),
+    /// Another handle holds the file's exclusive lock
+    Locked { holder_hint: String },
+    /// The file looks like it was read while Blender was still saving it
+    PossiblyIncomplete { reason: String },
+    /// The operation was cancelled or ran past its deadline
+    Cancelled,
+    /// Compression kind, level or thread count out of range
+    InvalidCompression(String),
+    /// A block query that doesn't parse; `offset` counts characters from 0
+    InvalidQuery { offset: usize, message: String },
+    /// A config file or environment override that doesn't fit the schema;
+    /// the message starts with where, e.g. `rbat.toml:3:18:`
+    InvalidConfig(String),
+    /// Datablock names a `NamePolicy` rejects, every violation listed
+    InvalidNames(Vec<NameViolation>),
+}
+
+/// The error parameter defaults to `BlendFileError`, so a glob import of this
+/// alias still accepts `Result<T, OtherError>`
+pub type Result<T, E = BlendFileError> = std::result::Result<T, E>;
+
+impl fmt::Display for BlendFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlendFileError::Format(err) => err.fmt(f),
+            BlendFileError::Locked { holder_hint } => write!(f, "File locked: {holder_hint}"),
+            BlendFileError::PossiblyIncomplete { reason } => {
+                write!(f, "File may be incomplete: {reason}")
+            }
+            BlendFileError::Cancelled => write!(f, "Operation cancelled"),
+            BlendFileError::InvalidCompression(msg) => write!(f, "Invalid compression: {msg}"),
+            BlendFileError::InvalidQuery { offset, message } => {
+                write!(f, "Invalid query at character {offset}: {message}")
+            }
+            BlendFileError::InvalidConfig(msg) => write!(f, "Invalid config: {msg}"),
+            BlendFileError::InvalidNames(violations) => {
+                let listed: Vec<String> = violations.iter().map(ToString::to_string).collect();
+                write!(f, "Invalid names: {}", listed.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlendFileError {}
+
+impl From<FormatError> for BlendFileError {
+    fn from(err: FormatError) -> Self {
+        BlendFileError::Format(err)
+    }
+}
+
+impl From<io::Error> for BlendFileError {
+    fn from(err: io::Error) -> Self {
+        FormatError::from(err).into()
+    }
+}
+
+impl From<std::str::Utf8Error> for BlendFileError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        FormatError::from(err).into()
+    }
+}
+
+impl From<std::string::FromUtf8Error> for BlendFileError {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        FormatError::from(err).into()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::blend_file::BlendFile;
+use crate::block_code;
+use crate::convert::block_layout;
+use crate::debug::id_name;
+use crate::error::{BlendFileError, FormatError, Result};
+use crate::library_link::LinkExtractionOptions;
+use crate::pointers::PointerWalker;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE blocks (
+    id INTEGER PRIMARY KEY,
+    code TEXT NOT NULL,
+    struct_name TEXT,
+    name TEXT,
+    size INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    address INTEGER NOT NULL
+);
+CREATE TABLE links (
+    block_id INTEGER NOT NULL REFERENCES blocks(id),
+    type TEXT NOT NULL,
+    path TEXT NOT NULL,
+    abs_path TEXT,
+    relative INTEGER NOT NULL,
+    packed INTEGER NOT NULL
+);
+CREATE TABLE pointers (
+    src_block INTEGER NOT NULL REFERENCES blocks(id),
+    field TEXT NOT NULL,
+    dst_block INTEGER NOT NULL REFERENCES blocks(id)
+);
+CREATE INDEX pointers_dst ON pointers(dst_block);
+";
+
+/// Write `blend`'s blocks, links and pointers to a new SQLite database at `db_path`.
+///
+/// Tables:
+/// - `blocks(id, code, struct_name, name, size, count, address)`: `id` is the
+///   block's position in `BlendFile::blocks`, `name` the ID name without its
+///   code prefix, `address` the old memory address as a signed 64-bit integer
+/// - `links(block_id, type, path, abs_path, relative, packed)`: one row per
+///   library, image, sound and movie clip path
+/// - `pointers(src_block, field, dst_block)`: every pointer the DNA describes
+///   whose target is a block in the file, with its field path (`mtex.tex`)
+///
+/// The database is written next to `db_path` and renamed over it once
+/// complete, so a failed export leaves no partial file behind.
+pub fn to_sqlite(blend: &BlendFile, db_path: &Path) -> Result<()> {
+    let dir = db_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp = tempfile::NamedTempFile::new_in(dir)?;
+    let mut db = Connection::open(temp.path()).map_err(sqlite_error)?;
+    let tx = db.transaction().map_err(sqlite_error)?;
+    tx.execute_batch(SCHEMA).map_err(sqlite_error)?;
+    insert_blocks(blend, &tx).map_err(sqlite_error)?;
+    insert_links(blend, &tx)?;
+    insert_pointers(blend, &tx).map_err(sqlite_error)?;
+    tx.commit().map_err(sqlite_error)?;
+    db.close().map_err(|(_, e)| sqlite_error(e))?;
+    temp.persist(db_path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+fn insert_blocks(blend: &BlendFile, tx: &rusqlite::Transaction) -> rusqlite::Result<()> {
+    let mut insert = tx.prepare("INSERT INTO blocks VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+    for (index, block) in blend.blocks.iter().enumerate() {
+        insert.execute(params![
+            index as i64,
+            block_code::raw_code(&block.code),
+            block_layout(&blend.dna, block).map(|layout| &layout.name),
+            id_name(&blend.dna, block),
+            block.size,
+            block.count,
+            block.old_memory_address as i64,
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_links(blend: &BlendFile, tx: &rusqlite::Transaction) -> Result<()> {
+    let extractor = blend.link_extractor();
+    let options = LinkExtractionOptions::default();
+    let mut insert = tx
+        .prepare("INSERT INTO links VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+        .map_err(sqlite_error)?;
+    for (index, block) in blend.blocks.iter().enumerate() {
+        let Some(link) = extractor.extract_block_link(block, &blend.dna, &options)? else {
+            continue;
+        };
+        let mut links = [link];
+        extractor.resolve_relative_paths(&mut links)?;
+        let [link] = links;
+        insert
+            .execute(params![
+                index as i64,
+                link.block_type,
+                link.path,
+                link.absolute_path,
+                link.is_relative,
+                link.is_packed,
+            ])
+            .map_err(sqlite_error)?;
+    }
+    Ok(())
+}
+
+fn insert_pointers(blend: &BlendFile, tx: &rusqlite::Transaction) -> rusqlite::Result<()> {
+    let by_address: HashMap<u64, usize> = blend
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| block.old_memory_address != 0)
+        .map(|(index, block)| (block.old_memory_address, index))
+        .collect();
+    let walker = PointerWalker::new(&blend.dna, &blend.header);
+    let mut insert = tx.prepare("INSERT INTO pointers VALUES (?1, ?2, ?3)")?;
+    for (index, block) in blend.blocks.iter().enumerate() {
+        let Some(layout) = block_layout(&blend.dna, block) else {
+            continue;
+        };
+        let mut rows = Vec::new();
+        walker.walk(block, &layout.name, &mut |field, address| {
+            if let Some(&target) = by_address.get(&address) {
+                rows.push((field.to_string(), target));
+            }
+        });
+        for (field, target) in rows {
+            insert.execute(params![index as i64, field, target as i64])?;
+        }
+    }
+    Ok(())
+}
+
+fn sqlite_error(err: rusqlite::Error) -> BlendFileError {
+    FormatError::IoError(format!("SQLite: {err}")).into()
+}
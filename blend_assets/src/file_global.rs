@@ -0,0 +1,70 @@
+use crate::blend_file::BlendFile;
+use crate::convert::read_uint;
+use crate::error::{FormatError, Result};
+use crate::scene::{locate, read_i32};
+use serde::Serialize;
+
+/// `G_FILE_AUTOPACK`: "Automatically Pack Resources" is enabled
+pub const G_FILE_AUTOPACK: i32 = 1 << 0;
+/// `G_FILE_COMPRESS`: Blender compresses the file when saving it
+pub const G_FILE_COMPRESS: i32 = 1 << 1;
+
+/// Settings Blender stores in the file's `GLOB` block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FileGlobal {
+    /// `G.fileflags` at save time
+    pub fileflags: i32,
+    /// Old address of the active scene
+    pub curscene: u64,
+    /// Old address of the active screen
+    pub curscreen: u64,
+}
+
+impl FileGlobal {
+    pub fn autopack_enabled(&self) -> bool {
+        self.fileflags & G_FILE_AUTOPACK != 0
+    }
+
+    pub fn compress_on_save(&self) -> bool {
+        self.fileflags & G_FILE_COMPRESS != 0
+    }
+}
+
+impl BlendFile {
+    /// Read the `GLOB` block through DNA. `None` when the file has no `GLOB`
+    /// block or no DNA to read it with.
+    pub fn file_global(&self) -> Result<Option<FileGlobal>> {
+        let Some(block) = self.blocks.iter().find(|b| &b.code == b"GLOB") else {
+            return Ok(None);
+        };
+        if self.dna.is_empty() {
+            return Ok(None);
+        }
+
+        let endianness = self.header.endianness;
+        let pointer = |name: &str| -> Result<u64> {
+            let (offset, field) = locate(&self.dna, "FileGlobal", &[name])?;
+            let bytes = block.data.get(offset..offset + field.size).ok_or_else(|| {
+                FormatError::BlockError(format!("GLOB block shorter than FileGlobal.{name}"))
+            })?;
+            Ok(read_uint(bytes, endianness))
+        };
+        let (fileflags, _) = locate(&self.dna, "FileGlobal", &["fileflags"])?;
+
+        Ok(Some(FileGlobal {
+            fileflags: read_i32(&block.data, fileflags, endianness)?,
+            curscene: pointer("curscene")?,
+            curscreen: pointer("curscreen")?,
+        }))
+    }
+
+    /// True when the file was saved with "Automatically Pack Resources" on
+    pub fn autopack_enabled(&self) -> bool {
+        matches!(self.file_global(), Ok(Some(global)) if global.autopack_enabled())
+    }
+
+    /// True when Blender will compress the file on its next save
+    pub fn compress_on_save(&self) -> bool {
+        matches!(self.file_global(), Ok(Some(global)) if global.compress_on_save())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::block::Block;
+use crate::convert::{block_layout, read_uint};
+use crate::dna::Dna;
+use crate::header::Endianness;
+use crate::library_usage::id_offset;
+use bitflags::bitflags;
+use serde::Serialize;
+
+bitflags! {
+    /// `ID.flag`, with bits that meant something else in the saving version dropped
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+    pub struct IdFlags: u32 {
+        /// `LIB_EXTERN`: directly linked; a runtime tag since 2.77
+        const EXTERN = 1 << 0;
+        /// `LIB_INDIRECT`: linked through another library; a runtime tag since 2.77
+        const INDIRECT = 1 << 1;
+        /// `LIB_FAKEUSER`
+        const FAKE_USER = 1 << 9;
+        /// `LIB_EMBEDDED_DATA`: owned by another ID, like a node tree (2.80+)
+        const EMBEDDED_DATA = 1 << 10;
+        /// `LIB_INDIRECT_WEAK_LINK` (2.80+)
+        const INDIRECT_WEAK_LINK = 1 << 11;
+        /// `LIB_EMBEDDED_DATA_LIB_OVERRIDE` (2.90+)
+        const EMBEDDED_DATA_LIB_OVERRIDE = 1 << 12;
+    }
+}
+
+bitflags! {
+    /// `Image.flag`, laid out as in 2.80+; bits only older versions set keep
+    /// their old position where 2.80 left it unused
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+    pub struct ImageFlags: u32 {
+        /// `IMA_HIGH_BITDEPTH` (2.80+; bit 0 was `IMA_FIELDS` before)
+        const HIGH_BITDEPTH = 1 << 0;
+        /// `IMA_NOCOLLECT`: skipped when collecting files
+        const NOCOLLECT = 1 << 5;
+        /// `IMA_OLD_PREMUL`
+        const OLD_PREMUL = 1 << 7;
+        /// `IMA_USED_FOR_RENDER`
+        const USED_FOR_RENDER = 1 << 9;
+        /// `IMA_USER_FRAME_IN_RANGE`
+        const USER_FRAME_IN_RANGE = 1 << 10;
+        /// `IMA_VIEW_AS_RENDER`
+        const VIEW_AS_RENDER = 1 << 11;
+        /// `IMA_IGNORE_ALPHA` (before 2.80, then moved to `alpha_mode`)
+        const IGNORE_ALPHA = 1 << 12;
+        /// `IMA_DEINTERLACE`
+        const DEINTERLACE = 1 << 13;
+        /// `IMA_USE_VIEWS`: multi-view image
+        const USE_VIEWS = 1 << 14;
+        /// `IMA_IS_STEREO` (before 2.80)
+        const IS_STEREO = 1 << 15;
+        /// `IMA_IS_MULTIVIEW` (before 2.80)
+        const IS_MULTIVIEW = 1 << 16;
+    }
+}
+
+bitflags! {
+    /// `Library.tag` (3.0+)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+    pub struct LibraryFlags: u32 {
+        /// `LIBRARY_TAG_RESYNC_REQUIRED`: overrides from this library need a resync
+        const RESYNC_REQUIRED = 1 << 0;
+    }
+}
+
+/// Stored bit, its meaning, and the versions in `first..end` that use it that way
+type BitMeaning<F> = (u32, F, u32, u32);
+
+const ID_BITS: &[BitMeaning<IdFlags>] = &[
+    (1 << 0, IdFlags::EXTERN, 0, 277),
+    (1 << 1, IdFlags::INDIRECT, 0, 277),
+    (1 << 9, IdFlags::FAKE_USER, 0, u32::MAX),
+    (1 << 10, IdFlags::EMBEDDED_DATA, 280, u32::MAX),
+    (1 << 11, IdFlags::INDIRECT_WEAK_LINK, 280, u32::MAX),
+    (1 << 12, IdFlags::EMBEDDED_DATA_LIB_OVERRIDE, 290, u32::MAX),
+];
+
+const IMAGE_BITS: &[BitMeaning<ImageFlags>] = &[
+    (1 << 0, ImageFlags::HIGH_BITDEPTH, 280, u32::MAX),
+    (1 << 5, ImageFlags::NOCOLLECT, 0, u32::MAX),
+    (1 << 7, ImageFlags::OLD_PREMUL, 0, u32::MAX),
+    (1 << 9, ImageFlags::USED_FOR_RENDER, 0, u32::MAX),
+    (1 << 10, ImageFlags::USER_FRAME_IN_RANGE, 0, u32::MAX),
+    (1 << 11, ImageFlags::VIEW_AS_RENDER, 0, u32::MAX),
+    (1 << 12, ImageFlags::IGNORE_ALPHA, 0, 280),
+    (1 << 13, ImageFlags::DEINTERLACE, 0, u32::MAX),
+    (1 << 14, ImageFlags::USE_VIEWS, 0, u32::MAX),
+    (1 << 15, ImageFlags::IS_STEREO, 0, 280),
+    (1 << 16, ImageFlags::IS_MULTIVIEW, 0, 280),
+];
+
+const LIBRARY_BITS: &[BitMeaning<LibraryFlags>] =
+    &[(1 << 0, LibraryFlags::RESYNC_REQUIRED, 300, u32::MAX)];
+
+fn from_table<F: bitflags::Flags + Copy>(table: &[BitMeaning<F>], raw: u32, version: u32) -> F {
+    let mut flags = F::empty();
+    for &(bit, flag, first, end) in table {
+        if raw & bit != 0 && (first..end).contains(&version) {
+            flags.insert(flag);
+        }
+    }
+    flags
+}
+
+impl IdFlags {
+    /// Interpret a stored `ID.flag` written by `version` (e.g. 279, 402)
+    pub fn from_raw(raw: u32, version: u32) -> Self {
+        from_table(ID_BITS, raw, version)
+    }
+}
+
+impl ImageFlags {
+    /// Interpret a stored `Image.flag` written by `version`
+    pub fn from_raw(raw: u32, version: u32) -> Self {
+        from_table(IMAGE_BITS, raw, version)
+    }
+}
+
+impl LibraryFlags {
+    /// Interpret a stored `Library.tag` written by `version`
+    pub fn from_raw(raw: u32, version: u32) -> Self {
+        from_table(LIBRARY_BITS, raw, version)
+    }
+}
+
+/// Flags of the datablock a link comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LinkFlags {
+    pub id: IdFlags,
+    /// Set for Image blocks whose DNA has `Image.flag`
+    pub image: Option<ImageFlags>,
+    /// Set for Library blocks whose DNA has `Library.tag`
+    pub library: Option<LibraryFlags>,
+}
+
+/// `ID.flag` of an ID block, read through the DNA
+pub fn id_flags(block: &Block, dna: &Dna, version: u32, endianness: Endianness) -> Option<IdFlags> {
+    let layout = block_layout(dna, block)?;
+    let offset = id_offset(dna, &layout.name)?;
+    let raw = read_field(block, dna, "ID", "flag", offset, endianness)?;
+    Some(IdFlags::from_raw(raw, version))
+}
+
+/// `Image.flag` of an IM block, read through the DNA
+pub fn image_flags(
+    block: &Block,
+    dna: &Dna,
+    version: u32,
+    endianness: Endianness,
+) -> Option<ImageFlags> {
+    if !block.code.starts_with(b"IM") {
+        return None;
+    }
+    let raw = read_field(block, dna, "Image", "flag", 0, endianness)?;
+    Some(ImageFlags::from_raw(raw, version))
+}
+
+/// `Library.tag` of an LI block, read through the DNA
+pub fn library_flags(
+    block: &Block,
+    dna: &Dna,
+    version: u32,
+    endianness: Endianness,
+) -> Option<LibraryFlags> {
+    if !block.code.starts_with(b"LI") {
+        return None;
+    }
+    let raw = read_field(block, dna, "Library", "tag", 0, endianness)?;
+    Some(LibraryFlags::from_raw(raw, version))
+}
+
+/// All flags of a link block; `None` when the DNA doesn't describe `ID.flag`
+pub fn link_flags(
+    block: &Block,
+    dna: &Dna,
+    version: u32,
+    endianness: Endianness,
+) -> Option<LinkFlags> {
+    Some(LinkFlags {
+        id: id_flags(block, dna, version, endianness)?,
+        image: image_flags(block, dna, version, endianness),
+        library: library_flags(block, dna, version, endianness),
+    })
+}
+
+/// An integer field of `struct_name` placed `base` bytes into the block
+fn read_field(
+    block: &Block,
+    dna: &Dna,
+    struct_name: &str,
+    field_name: &str,
+    base: usize,
+    endianness: Endianness,
+) -> Option<u32> {
+    let field = dna.get_struct(struct_name)?.field(field_name)?;
+    if field.dna_name.is_pointer() || field.size > 4 {
+        return None;
+    }
+    let start = base + field.offset?;
+    let bytes = block.data.get(start..start + field.size)?;
+    Some(read_uint(bytes, endianness) as u32)
+}
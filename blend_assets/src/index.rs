@@ -0,0 +1,467 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::dna::Dna;
+use crate::error::{FormatError, Result};
+use crate::header::Header;
+use crate::payload_cache::{CacheOptions, PayloadCache};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the on-disk layout of `BlockIndex` changes
+pub const INDEX_VERSION: u32 = 3;
+
+/// Bytes hashed at each end of the blend file for the fingerprint
+const FINGERPRINT_SPAN: u64 = 4096;
+
+/// Persisted block layout of a blend file (`.rbatidx`), so re-opening skips
+/// walking every block header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockIndex {
+    pub version: u32,
+    pub fingerprint: FileFingerprint,
+    /// The 12 header bytes as stored
+    pub header: Vec<u8>,
+    pub blocks: Vec<IndexEntry>,
+    /// Where the DNA1 block starts, if the file has one
+    pub dna_offset: Option<u64>,
+    /// Directory of the payloads hashed in `blocks`, as an absolute path
+    pub payload_cache: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub code: [u8; 4],
+    pub size: u32,
+    /// Offset of the block header (its code) in the file
+    pub header_offset: u64,
+    pub sdna_index: u32,
+    pub count: u32,
+    pub address: u64,
+    /// FNV-1a of the payload, for blocks whose payload went to a payload cache
+    pub payload_hash: Option<u64>,
+}
+
+/// Identifies the exact file contents an index was written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub size: u64,
+    /// Modification time as (seconds, nanoseconds) since the Unix epoch
+    pub mtime: (u64, u32),
+    /// FNV-1a over the first and last 4 KB
+    pub edge_hash: u64,
+}
+
+impl FileFingerprint {
+    pub fn of<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut file = File::open(path)?;
+        Self::from_file(&mut file)
+    }
+
+    fn from_file(file: &mut File) -> Result<Self> {
+        let metadata = file.metadata()?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| (d.as_secs(), d.subsec_nanos()))
+            .unwrap_or((0, 0));
+        let size = metadata.len();
+
+        let mut hash = FNV_OFFSET;
+        let mut buffer = vec![0u8; FINGERPRINT_SPAN.min(size) as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut buffer)?;
+        hash = fnv1a(hash, &buffer);
+        file.seek(SeekFrom::Start(size - buffer.len() as u64))?;
+        file.read_exact(&mut buffer)?;
+        hash = fnv1a(hash, &buffer);
+
+        Ok(Self {
+            size,
+            mtime,
+            edge_hash: hash,
+        })
+    }
+}
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+impl BlendFile {
+    /// Persist the block layout of the file on disk so `open_with_index` can skip
+    /// re-parsing block headers. Only meaningful for uncompressed files whose
+    /// blocks haven't been resized since they were opened or last saved;
+    /// blocks added since then fail with a `BlockError`.
+    pub fn write_index<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_index_file(&self.build_index(|_| false)?, path.as_ref())
+    }
+
+    /// Like `write_index`, also keeping the payloads of the block types in
+    /// `options` in `cache_dir`, which the index records so `open_with_index`
+    /// reads them from there instead of the blend file. Entries past
+    /// `options.max_size` are evicted, least recently used first.
+    pub fn write_index_with_cache<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        index_path: P,
+        cache_dir: Q,
+        options: &CacheOptions,
+    ) -> Result<()> {
+        let cache_dir = std::path::absolute(cache_dir)?;
+        let cache = PayloadCache::new(&cache_dir, options.compress);
+        let mut index = self.build_index(|block| options.caches(block))?;
+        for (block, entry) in self.blocks.iter().zip(&index.blocks) {
+            if let Some(hash) = entry.payload_hash {
+                cache.store(hash, &block.data)?;
+            }
+        }
+        cache.evict(options.max_size)?;
+        index.payload_cache = Some(cache_dir);
+        write_index_file(&index, index_path.as_ref())
+    }
+
+    /// The index of the file as opened, hashing the payloads of blocks for
+    /// which `hashed` holds
+    fn build_index(&self, hashed: impl Fn(&Block) -> bool) -> Result<BlockIndex> {
+        let header_size = Block::header_size(&self.header) as u64;
+        let blocks = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| {
+                // Blocks added in memory have no place in the file until saved
+                let header_offset = block
+                    .data_offset
+                    .checked_sub(header_size)
+                    .filter(|&offset| offset >= Header::SIZE as u64)
+                    .ok_or_else(|| {
+                        FormatError::BlockError(format!(
+                            "block {index} isn't in the file on disk; save before indexing"
+                        ))
+                    })?;
+                Ok(IndexEntry {
+                    code: block.code,
+                    size: block.size,
+                    header_offset,
+                    sdna_index: block.sdna_index,
+                    count: block.count,
+                    address: block.old_memory_address,
+                    payload_hash: hashed(block).then(|| fnv1a(FNV_OFFSET, &block.data)),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        // The DNA in use comes from the last DNA1 block
+        let dna_offset = self.dna_blocks.last().map(|range| range.start);
+
+        let mut header = Vec::with_capacity(Header::SIZE);
+        self.header.write_to_writer(&mut header)?;
+
+        Ok(BlockIndex {
+            version: INDEX_VERSION,
+            fingerprint: FileFingerprint::of(&self.path)?,
+            header,
+            blocks,
+            dna_offset,
+            payload_cache: None,
+        })
+    }
+
+    /// Open using a block index written by `write_index`, falling back to a
+    /// full parse when the index is unreadable, from another version, or was
+    /// written for different file contents. Payloads the index has cached
+    /// (see `write_index_with_cache`) come from its cache directory; missing
+    /// or damaged entries are read from the file.
+    pub fn open_with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        blend_path: P,
+        index_path: Q,
+    ) -> Result<Self> {
+        let blend_path = blend_path.as_ref();
+        match Self::try_open_indexed(blend_path, index_path.as_ref()) {
+            Some(blend_file) => Ok(blend_file),
+            None => Self::open(blend_path),
+        }
+    }
+
+    fn try_open_indexed(blend_path: &Path, index_path: &Path) -> Option<Self> {
+        let index: BlockIndex =
+            bincode::deserialize_from(BufReader::new(File::open(index_path).ok()?)).ok()?;
+        if index.version != INDEX_VERSION {
+            return None;
+        }
+
+        let mut file = File::open(blend_path).ok()?;
+        if FileFingerprint::from_file(&mut file).ok()? != index.fingerprint {
+            return None;
+        }
+        // Entries are read in whichever form they were stored
+        let cache = index
+            .payload_cache
+            .as_deref()
+            .map(|dir| PayloadCache::new(dir, false));
+        let indexed = read_indexed(&mut BufReader::new(file), &index, cache.as_ref()).ok()?;
+
+        Some(BlendFile {
+            path: blend_path.to_path_buf(),
+            header: indexed.header,
+            dna: indexed.dna,
+            blocks: indexed.blocks,
+            dna_blocks: indexed.dna_block.into_iter().collect(),
+            warnings: Vec::new(),
+            lock_warnings: Vec::new(),
+            incomplete: None,
+            modified: Default::default(),
+            mmap: None,
+            file: None,
+        })
+    }
+}
+
+fn write_index_file(index: &BlockIndex, path: &Path) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(writer, index)
+        .map_err(|e| FormatError::IoError(format!("writing index: {e}")).into())
+}
+
+/// What `read_indexed` loads: the file's header, blocks and DNA, and where
+/// the DNA1 block lies
+struct Indexed {
+    header: Header,
+    blocks: Vec<Block>,
+    dna: Dna,
+    dna_block: Option<Range<u64>>,
+}
+
+/// Load the header, payloads and DNA at the offsets recorded in `index`,
+/// taking payloads `cache` holds from there
+fn read_indexed<R: Read + Seek>(
+    reader: &mut R,
+    index: &BlockIndex,
+    cache: Option<&PayloadCache>,
+) -> Result<Indexed> {
+    reader.seek(SeekFrom::Start(0))?;
+    let header = Header::from_reader(reader)?;
+    let mut stored = Vec::with_capacity(Header::SIZE);
+    header.write_to_writer(&mut stored)?;
+    if stored != index.header {
+        return Err(
+            FormatError::InvalidFormat("index header doesn't match file".to_string()).into(),
+        );
+    }
+
+    let header_size = Block::header_size(&header) as u64;
+    let mut blocks = Vec::with_capacity(index.blocks.len());
+    for entry in &index.blocks {
+        let payload_offset = entry.header_offset + header_size;
+        let cached = entry
+            .payload_hash
+            .zip(cache)
+            .and_then(|(hash, cache)| cache.load(hash, entry.size as usize));
+        let data = match cached {
+            Some(data) => data,
+            None => {
+                reader.seek(SeekFrom::Start(payload_offset))?;
+                let mut data = vec![0u8; entry.size as usize];
+                reader.read_exact(&mut data)?;
+                data
+            }
+        };
+        blocks.push(Block {
+            code: entry.code,
+            size: entry.size,
+            old_memory_address: entry.address,
+            sdna_index: entry.sdna_index,
+            count: entry.count,
+            data_offset: payload_offset,
+            data,
+        });
+    }
+
+    let (dna, dna_block) = match index.dna_offset {
+        Some(offset) => {
+            reader.seek(SeekFrom::Start(offset))?;
+            let dna = Dna::parse_dna_block(reader, &header)?;
+            (dna, Some(offset..reader.stream_position()?))
+        }
+        None => (Dna::default(), None),
+    };
+
+    Ok(Indexed {
+        header,
+        blocks,
+        dna,
+        dna_block,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Records which byte ranges were read
+    struct TrackingReader<R> {
+        inner: R,
+        position: u64,
+        reads: Vec<(u64, u64)>,
+    }
+
+    impl<R: Read> Read for TrackingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            let range = (self.position, self.position + n as u64);
+            // Merge back-to-back reads so each range is one logical read
+            match self.reads.last_mut() {
+                Some(last) if last.1 == range.0 => last.1 = range.1,
+                _ if n > 0 => self.reads.push(range),
+                _ => {}
+            }
+            self.position += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl<R: Seek> Seek for TrackingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.position = self.inner.seek(pos)?;
+            Ok(self.position)
+        }
+    }
+
+    fn block_bytes(code: &[u8; 4], payload: &[u8], address: u64) -> Vec<u8> {
+        let mut out = code.to_vec();
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&address.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_indexed_read_touches_only_payloads() {
+        let mut data = b"BLENDER-v300".to_vec();
+        let first = data.len() as u64;
+        data.extend(block_bytes(b"OB\0\0", &[1; 40], 0x10));
+        let second = data.len() as u64;
+        data.extend(block_bytes(b"ME\0\0", &[2; 24], 0x20));
+        data.extend(block_bytes(b"ENDB", &[], 0));
+
+        let entry = |code: &[u8; 4], size, header_offset, address| IndexEntry {
+            code: *code,
+            size,
+            header_offset,
+            sdna_index: 0,
+            count: 1,
+            address,
+            payload_hash: None,
+        };
+        let index = BlockIndex {
+            version: INDEX_VERSION,
+            fingerprint: FileFingerprint {
+                size: 0,
+                mtime: (0, 0),
+                edge_hash: 0,
+            },
+            header: b"BLENDER-v300".to_vec(),
+            blocks: vec![
+                entry(b"OB\0\0", 40, first, 0x10),
+                entry(b"ME\0\0", 24, second, 0x20),
+            ],
+            dna_offset: None,
+            payload_cache: None,
+        };
+
+        let mut reader = TrackingReader {
+            inner: Cursor::new(data),
+            position: 0,
+            reads: Vec::new(),
+        };
+        let blocks = read_indexed(&mut reader, &index, None).unwrap().blocks;
+
+        assert_eq!(blocks[0].data, vec![1; 40]);
+        assert_eq!(blocks[1].old_memory_address, 0x20);
+        // The 12-byte header, then the two payloads; no block headers
+        let header_size = 24;
+        assert_eq!(
+            reader.reads,
+            vec![
+                (0, 12),
+                (first + header_size, first + header_size + 40),
+                (second + header_size, second + header_size + 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cached_payload_not_read_from_file() {
+        let mut data = b"BLENDER-v300".to_vec();
+        let first = data.len() as u64;
+        data.extend(block_bytes(b"OB\0\0", &[1; 40], 0x10));
+        let second = data.len() as u64;
+        data.extend(block_bytes(b"DATA", &[2; 600], 0x20));
+        data.extend(block_bytes(b"ENDB", &[], 0));
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PayloadCache::new(dir.path(), true);
+        let hash = fnv1a(FNV_OFFSET, &[2; 600]);
+        cache.store(hash, &[2; 600]).unwrap();
+
+        let entry = |code: &[u8; 4], size, header_offset, address, payload_hash| IndexEntry {
+            code: *code,
+            size,
+            header_offset,
+            sdna_index: 0,
+            count: 1,
+            address,
+            payload_hash,
+        };
+        let index = BlockIndex {
+            version: INDEX_VERSION,
+            fingerprint: FileFingerprint {
+                size: 0,
+                mtime: (0, 0),
+                edge_hash: 0,
+            },
+            header: b"BLENDER-v300".to_vec(),
+            blocks: vec![
+                entry(b"OB\0\0", 40, first, 0x10, None),
+                entry(b"DATA", 600, second, 0x20, Some(hash)),
+            ],
+            dna_offset: None,
+            payload_cache: None,
+        };
+
+        let mut reader = TrackingReader {
+            inner: Cursor::new(data),
+            position: 0,
+            reads: Vec::new(),
+        };
+        let blocks = read_indexed(&mut reader, &index, Some(&cache))
+            .unwrap()
+            .blocks;
+        assert_eq!(blocks[1].data, vec![2; 600]);
+        assert_eq!(blocks[1].data_offset, second + 24);
+        // Only the header and the uncached OB payload come from the file
+        assert_eq!(reader.reads, vec![(0, 12), (first + 24, first + 64)]);
+
+        // Without an entry the payload comes from the file after all
+        let empty = dir.path().join("empty");
+        let missing = PayloadCache::new(&empty, true);
+        reader.reads.clear();
+        let blocks = read_indexed(&mut reader, &index, Some(&missing))
+            .unwrap()
+            .blocks;
+        assert_eq!(blocks[1].data, vec![2; 600]);
+        assert_eq!(reader.reads.len(), 3);
+    }
+}
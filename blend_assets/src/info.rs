@@ -0,0 +1,114 @@
+use crate::blend_file::{BlendFile, Compression, FileSummary, SummaryOrder};
+use crate::scene::{SceneInfo, UnitSystem};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Everything `info` reports, gathered from a single parse
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileInfo {
+    #[serde(flatten)]
+    pub summary: FileSummary,
+    pub compression: Compression,
+    pub scenes: Vec<SceneInfo>,
+    /// Library links per block type ("Library", "Image", ...)
+    pub link_counts: BTreeMap<String, usize>,
+    pub total_links: usize,
+    /// Links whose target doesn't exist; only computed on request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_assets: Option<usize>,
+    /// Parse warnings plus anything that couldn't be read
+    pub warnings: Vec<String>,
+}
+
+impl BlendFile {
+    /// Summary, scenes and link counts in one go
+    pub fn info(&self) -> FileInfo {
+        self.collect_info(false)
+    }
+
+    /// Like `info`, additionally checking which link targets are missing on disk
+    pub fn info_with_asset_check(&self) -> FileInfo {
+        self.collect_info(true)
+    }
+
+    fn collect_info(&self, check_assets: bool) -> FileInfo {
+        let mut warnings: Vec<String> = self.warnings.iter().map(|w| w.to_string()).collect();
+        if let Some(reason) = &self.incomplete {
+            warnings.push(format!("possibly incomplete: {reason}"));
+        }
+
+        let scenes = self.scenes().unwrap_or_else(|e| {
+            warnings.push(format!("scenes: {e}"));
+            Vec::new()
+        });
+
+        let links = if check_assets {
+            self.get_library_links_with_stat()
+        } else {
+            self.get_library_links()
+        }
+        .unwrap_or_else(|e| {
+            warnings.push(format!("links: {e}"));
+            Vec::new()
+        });
+
+        let mut link_counts = BTreeMap::new();
+        for link in &links {
+            *link_counts.entry(link.block_type.clone()).or_insert(0) += 1;
+        }
+        let missing_assets = check_assets.then(|| {
+            links
+                .iter()
+                // Packed libraries are stored in the file itself
+                .filter(|l| !l.is_packed && l.asset_status.as_ref().is_some_and(|s| !s.exists))
+                .count()
+        });
+
+        FileInfo {
+            summary: self.summary(SummaryOrder::Code),
+            compression: self.compression(),
+            scenes,
+            link_counts,
+            total_links: links.len(),
+            missing_assets,
+            warnings,
+        }
+    }
+}
+
+impl fmt::Display for FileInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary)?;
+        writeln!(f, "Compression: {:?}", self.compression)?;
+        writeln!(f, "Scenes:")?;
+        for scene in &self.scenes {
+            write!(
+                f,
+                "  {}: frames {}-{}",
+                scene.name, scene.frame_start, scene.frame_end
+            )?;
+            if let Some(fps) = scene.fps {
+                write!(f, ", {} fps", (fps * 1000.0).round() / 1000.0)?;
+            }
+            if scene.unit_system != UnitSystem::Unknown {
+                write!(f, ", {}", scene.unit_system)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "Links: {}", self.total_links)?;
+        for (block_type, count) in &self.link_counts {
+            writeln!(f, "  {block_type}: {count}")?;
+        }
+        if let Some(missing) = self.missing_assets {
+            writeln!(f, "Missing Assets: {missing}")?;
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f, "Warnings:")?;
+            for warning in &self.warnings {
+                writeln!(f, "  {warning}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::header::Header;
+use serde::Serialize;
+use std::fmt;
+
+/// Byte range a block occupies in the uncompressed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockSpan {
+    /// Position in `BlendFile::blocks`
+    pub index: usize,
+    pub code: String,
+    /// Where the block header (its code) starts
+    pub header_offset: u64,
+    /// Where the payload starts
+    pub data_offset: u64,
+    /// One past the last payload byte
+    pub end_offset: u64,
+}
+
+/// Bytes between consecutive spans that no block accounts for, or that two
+/// blocks both claim
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LayoutAnomaly {
+    /// Unclaimed bytes `start..end`; `after` is `None` for a gap between the
+    /// file header and the first block
+    Gap {
+        after: Option<usize>,
+        start: u64,
+        end: u64,
+    },
+    /// Bytes `start..end` lie in both blocks
+    Overlap {
+        first: usize,
+        second: usize,
+        start: u64,
+        end: u64,
+    },
+}
+
+impl fmt::Display for LayoutAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutAnomaly::Gap { after, start, end } => {
+                write!(f, "gap of {} bytes at {start}..{end}", end - start)?;
+                match after {
+                    Some(index) => write!(f, " after block {index}"),
+                    None => write!(f, " after the file header"),
+                }
+            }
+            LayoutAnomaly::Overlap {
+                first,
+                second,
+                start,
+                end,
+            } => write!(
+                f,
+                "blocks {first} and {second} overlap by {} bytes at {start}..{end}",
+                end - start
+            ),
+        }
+    }
+}
+
+/// Writers may pad block headers to this alignment; shorter gaps ending on
+/// it are not anomalies
+const ALIGNMENT: u64 = 4;
+
+impl BlendFile {
+    /// Where each block read from the file lies in its uncompressed bytes.
+    /// Blocks added since opening have no place in the file and are left out.
+    pub fn layout(&self) -> Vec<BlockSpan> {
+        let header_size = Block::header_size(&self.header) as u64;
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.data_offset != 0)
+            .map(|(index, block)| BlockSpan {
+                index,
+                code: block.get_type_name().trim_end_matches('\0').to_string(),
+                header_offset: block.data_offset - header_size,
+                data_offset: block.data_offset,
+                end_offset: block.data_offset + block.size as u64,
+            })
+            .collect()
+    }
+
+    /// Gaps and overlaps in `layout`. A DNA1 block between data blocks, as
+    /// in repaired files, fills its gap.
+    pub fn layout_anomalies(&self) -> Vec<LayoutAnomaly> {
+        layout_anomalies(&self.layout())
+            .into_iter()
+            .filter(|anomaly| match anomaly {
+                LayoutAnomaly::Gap { start, end, .. } => !self.dna_blocks.contains(&(*start..*end)),
+                LayoutAnomaly::Overlap { .. } => true,
+            })
+            .collect()
+    }
+}
+
+/// Gaps and overlaps between consecutive `spans`, which start after the file
+/// header in file order
+pub fn layout_anomalies(spans: &[BlockSpan]) -> Vec<LayoutAnomaly> {
+    let mut anomalies = Vec::new();
+    let mut previous: Option<&BlockSpan> = None;
+    for span in spans {
+        let (after, expected) = match previous {
+            Some(previous) => (Some(previous.index), previous.end_offset),
+            None => (None, Header::SIZE as u64),
+        };
+        let start = span.header_offset;
+        if start > expected {
+            let padding = start - expected < ALIGNMENT && start % ALIGNMENT == 0;
+            if !padding {
+                anomalies.push(LayoutAnomaly::Gap {
+                    after,
+                    start: expected,
+                    end: start,
+                });
+            }
+        } else if start < expected {
+            if let Some(previous) = previous {
+                anomalies.push(LayoutAnomaly::Overlap {
+                    first: previous.index,
+                    second: span.index,
+                    start,
+                    end: expected.min(span.end_offset),
+                });
+            }
+        }
+        previous = Some(span);
+    }
+    anomalies
+}
@@ -0,0 +1,50 @@
+#![deny(unnameable_types, private_interfaces, private_bounds)]
+
+pub mod asset_fs;
+pub mod audit;
+pub mod blend_file;
+pub mod block_code;
+pub mod camera;
+pub mod cancel;
+pub mod cli;
+pub mod config;
+pub mod consistency;
+pub mod convert;
+pub mod create;
+pub mod debug;
+pub mod decompress;
+pub mod dependencies;
+pub mod dna_release;
+pub mod error;
+#[cfg(feature = "sqlite-export")]
+pub mod export;
+pub mod file_global;
+pub mod flags;
+pub mod index;
+pub mod info;
+pub mod layout;
+pub mod library_link;
+pub mod library_usage;
+pub mod link_status;
+pub mod links_report;
+pub mod merge;
+pub mod modified;
+pub mod naming;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod ownership;
+pub mod packed_library;
+pub mod payload_cache;
+mod pointers;
+pub mod prelude;
+pub mod query;
+pub mod remap;
+pub mod scene;
+pub mod truncation;
+
+// The binary format lives in blend-raw; re-export it so existing paths keep working
+pub use blend_raw::{block, bytes, dna, dna_diff, dna_io, dna_name, header};
+
+pub use blend_file::BlendFile;
+pub use error::{BlendFileError, FormatError, Result};
+pub use library_link::LibraryLink;
@@ -0,0 +1,1188 @@
+use crate::asset_fs::{AssetFs, LocalFs};
+use crate::blend_file::Compression;
+use crate::block::Block;
+use crate::cancel::{self, Cancellation};
+use crate::decompress::DecompressingReader;
+use crate::dna::{Dna, OffsetTable};
+use crate::error::{FormatError, Result};
+use crate::flags::{link_flags, LinkFlags};
+use crate::header::{Endianness, Header};
+use crate::link_status::LinkStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct LibraryLink {
+    pub path: String,
+    pub absolute_path: Option<String>,
+    pub block_type: String,
+    pub block_name: Option<String>,
+    pub is_relative: bool,
+    pub is_placeholder: bool,
+    /// Library whose .blend is stored inside this file (`Library.packedfile` is set)
+    pub is_packed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_status: Option<AssetStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<LinkStatus>,
+    /// Flags of the datablock, when the DNA describes them and the file version is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<LinkFlags>,
+}
+
+impl LibraryLink {
+    /// A link to `path` from a block of `block_type` ("Library", "Image", ...).
+    /// Paths starting with "//" or not rooted at "/" count as relative.
+    pub fn new(path: impl Into<String>, block_type: impl Into<String>) -> Self {
+        let path = path.into();
+        Self {
+            is_relative: path.starts_with("//") || !path.starts_with('/'),
+            is_placeholder: is_placeholder_path(&path),
+            path,
+            absolute_path: None,
+            block_type: block_type.into(),
+            block_name: None,
+            is_packed: false,
+            asset_status: None,
+            status: None,
+            flags: None,
+        }
+    }
+
+    pub fn with_block_name(mut self, name: impl Into<String>) -> Self {
+        self.block_name = Some(name.into());
+        self
+    }
+}
+
+/// Filesystem status of the file a link resolves to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetStatus {
+    /// True when the link resolved to a file, exactly or via the case-insensitive fallback
+    pub exists: bool,
+    pub resolution: Resolution,
+    pub size: Option<u64>,
+    pub mtime: Option<DateTime<Utc>>,
+    pub newer_than_blend: bool,
+}
+
+impl AssetStatus {
+    /// True for existing assets with a size of zero bytes
+    pub fn is_empty(&self) -> bool {
+        self.exists && self.size == Some(0)
+    }
+}
+
+/// How a link's target was found on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Resolution {
+    Found,
+    /// Only a file whose name differs in case exists; `actual` is its full path
+    FoundWithDifferentCase {
+        actual: String,
+    },
+    Missing,
+}
+
+/// Filesystem lookups applied when resolving and statting links
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ResolveOptions {
+    /// Canonicalize the blend directory and resolved paths, so links under a
+    /// symlinked project root report their real location
+    pub follow_symlinks: bool,
+    /// When the exact path is missing, search its directory for a filename
+    /// matching case-insensitively
+    pub case_insensitive_fallback: bool,
+}
+
+impl ResolveOptions {
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    pub fn with_case_insensitive_fallback(mut self, case_insensitive_fallback: bool) -> Self {
+        self.case_insensitive_fallback = case_insensitive_fallback;
+        self
+    }
+}
+
+/// Block codes that never yield links in any extraction mode. Window
+/// managers, screens, workspaces and user preferences keep recent-file lists
+/// and other UI paths that read like links at the fallback offsets.
+pub const NON_LINK_CODES: &[&[u8; 2]] = &[b"WM", b"SR", b"WS", b"US"];
+
+/// True when blocks with `code` are in `NON_LINK_CODES`
+pub fn is_non_link_code(code: &[u8; 4]) -> bool {
+    NON_LINK_CODES
+        .iter()
+        .any(|denied| code.starts_with(*denied))
+}
+
+/// Path values Blender leaves in datablocks that don't point at a real file
+pub const PLACEHOLDER_PATHS: &[&str] = &["Untitled", "untitled"];
+
+/// True for empty, whitespace-only, bare "//" and known placeholder paths
+pub fn is_placeholder_path(path: &str) -> bool {
+    let trimmed = path.trim();
+    trimmed.is_empty()
+        || trimmed == "//"
+        || PLACEHOLDER_PATHS.contains(&trimmed.trim_start_matches("//"))
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct LinkExtractionOptions {
+    /// Report placeholder paths (flagged with `is_placeholder`) instead of skipping them
+    pub include_placeholders: bool,
+    /// Fail instead of guessing field offsets without DNA or a known file version
+    pub strict: bool,
+    /// Checked before each block
+    pub cancellation: Option<Cancellation>,
+}
+
+impl LinkExtractionOptions {
+    pub fn with_placeholders(mut self, include_placeholders: bool) -> Self {
+        self.include_placeholders = include_placeholders;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: Cancellation) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+}
+
+/// How often extraction couldn't read a field through the DNA.
+///
+/// Each fallback is counted once by cause (`dna_missing`, `field_missing`)
+/// and once by the offsets used instead (`version_table`, `raw_heuristic`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ExtractionStats {
+    /// The file has no DNA to consult
+    pub dna_missing: usize,
+    /// The DNA lacks the struct or field
+    pub field_missing: usize,
+    /// Offsets came from the table for the file's version
+    pub version_table: usize,
+    /// Offsets were guessed with no version to go by
+    pub raw_heuristic: usize,
+}
+
+impl ExtractionStats {
+    pub fn fallbacks(&self) -> usize {
+        self.dna_missing + self.field_missing
+    }
+}
+
+/// Oldest file version the fixed offsets in `fallback_location` were checked against
+const VERSION_TABLE_MIN: u32 = 280;
+
+#[derive(Debug)]
+pub struct LibraryLinkExtractor {
+    blend_file_path: PathBuf,
+    resolve_options: ResolveOptions,
+    /// Version from the file header, used to vet fallback offsets and read flags
+    file_version: Option<u32>,
+    endianness: Endianness,
+}
+
+impl LibraryLinkExtractor {
+    pub fn new<P: AsRef<Path>>(blend_file_path: P) -> Self {
+        Self {
+            blend_file_path: blend_file_path.as_ref().to_path_buf(),
+            resolve_options: ResolveOptions::default(),
+            file_version: None,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// Version of the file being read (`Header::version`); without it, field
+    /// reads that can't use the DNA count as `raw_heuristic`
+    pub fn with_file_version(mut self, version: u32) -> Self {
+        self.file_version = Some(version);
+        self
+    }
+
+    /// Byte order of the file being read, for flag fields; little-endian by default
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Use `options` for `resolve_relative_paths` and `stat_links`
+    pub fn with_resolve_options(mut self, options: ResolveOptions) -> Self {
+        self.resolve_options = options;
+        self
+    }
+
+    pub fn extract_links(&self, blocks: &[Block], dna: &Dna) -> Result<Vec<LibraryLink>> {
+        self.extract_links_with_options(blocks, dna, &LinkExtractionOptions::default())
+    }
+
+    pub fn extract_links_with_options(
+        &self,
+        blocks: &[Block],
+        dna: &Dna,
+        options: &LinkExtractionOptions,
+    ) -> Result<Vec<LibraryLink>> {
+        Ok(self.extract_links_with_stats(blocks, dna, options)?.0)
+    }
+
+    /// Like `extract_links_with_options`, also reporting how often field
+    /// offsets had to be guessed. Logs one warning summarizing any fallbacks.
+    pub fn extract_links_with_stats(
+        &self,
+        blocks: &[Block],
+        dna: &Dna,
+        options: &LinkExtractionOptions,
+    ) -> Result<(Vec<LibraryLink>, ExtractionStats)> {
+        let (links, stats) = self.extract_links_in(blocks, &mut OffsetTable::new(dna), options)?;
+        if stats.fallbacks() > 0 {
+            tracing::warn!(
+                file = %self.blend_file_path.display(),
+                dna_missing = stats.dna_missing,
+                field_missing = stats.field_missing,
+                version_table = stats.version_table,
+                raw_heuristic = stats.raw_heuristic,
+                "link fields read without DNA"
+            );
+        }
+        Ok((links, stats))
+    }
+
+    /// `extract_links_with_stats` without the warning, looking fields up
+    /// through `offsets` so each is located once however many blocks hold it
+    fn extract_links_in(
+        &self,
+        blocks: &[Block],
+        offsets: &mut OffsetTable,
+        options: &LinkExtractionOptions,
+    ) -> Result<(Vec<LibraryLink>, ExtractionStats)> {
+        let mut links = Vec::new();
+        let mut stats = ExtractionStats::default();
+
+        // Library links (LI), then images (IM), sounds (SO) and movie clips (MC)
+        for code in [b"LI", b"IM", b"SO", b"MC"] {
+            // Anomalous blocks (zero size/count) only ever yield empty strings
+            for block in blocks
+                .iter()
+                .filter(|b| &b.code[..2] == code && b.anomaly().is_none())
+            {
+                cancel::check(options.cancellation.as_ref())?;
+                if let Some(link) =
+                    self.extract_link_counted(block, offsets, options, &mut stats)?
+                {
+                    links.push(link);
+                }
+            }
+        }
+        Ok((links, stats))
+    }
+
+    /// Parse a single block into a link if its type can carry a file path
+    pub fn extract_block_link(
+        &self,
+        block: &Block,
+        dna: &Dna,
+        options: &LinkExtractionOptions,
+    ) -> Result<Option<LibraryLink>> {
+        self.extract_link_counted(
+            block,
+            &mut OffsetTable::new(dna),
+            options,
+            &mut ExtractionStats::default(),
+        )
+    }
+
+    fn extract_link_counted(
+        &self,
+        block: &Block,
+        offsets: &mut OffsetTable,
+        options: &LinkExtractionOptions,
+        stats: &mut ExtractionStats,
+    ) -> Result<Option<LibraryLink>> {
+        if is_non_link_code(&block.code) {
+            return Ok(None);
+        }
+        // Library blocks contain Library structures, the others Image, bSound
+        // and MovieClip; all of them keep the path in their 'filepath' field
+        let block_type = match &block.code[..2] {
+            b"LI" => "Library",
+            b"IM" => "Image",
+            b"SO" => "Sound",
+            b"MC" => "MovieClip",
+            _ => return Ok(None),
+        };
+
+        let dna = offsets.dna();
+        let mut read = FieldReader {
+            extractor: self,
+            offsets,
+            options,
+            stats,
+        };
+        let path_str = match read.raw_string(block, "filepath")? {
+            Some(path_str) => path_str,
+            None => return Ok(None),
+        };
+
+        let is_placeholder = is_placeholder_path(&path_str);
+        if is_placeholder && !options.include_placeholders {
+            return Ok(None);
+        }
+        // Placeholders are reported exactly as stored
+        let path_str = if is_placeholder {
+            path_str
+        } else {
+            path_str.trim().to_string()
+        };
+
+        // Blender uses "//" prefix for relative paths
+        let is_relative = path_str.starts_with("//") || !path_str.starts_with('/');
+        Ok(Some(LibraryLink {
+            path: path_str,
+            absolute_path: None,
+            block_type: block_type.to_string(),
+            block_name: read.string_field(block, "name")?,
+            is_relative,
+            is_placeholder,
+            is_packed: block_type == "Library" && is_packed_library(block, dna),
+            asset_status: None,
+            status: None,
+            flags: self
+                .file_version
+                .and_then(|version| link_flags(block, dna, version, self.endianness)),
+        }))
+    }
+
+    pub fn resolve_relative_paths(&self, links: &mut [LibraryLink]) -> Result<()> {
+        self.resolve_relative_paths_in(links, &LocalFs)
+    }
+
+    /// Like `resolve_relative_paths`, following symlinks through `fs`.
+    /// Without a blend file path (`BlendFile::from_bytes`) relative links
+    /// are left unresolved.
+    pub fn resolve_relative_paths_in(
+        &self,
+        links: &mut [LibraryLink],
+        fs: &dyn AssetFs,
+    ) -> Result<()> {
+        if self.blend_file_path.as_os_str().is_empty() {
+            return Ok(());
+        }
+        let blend_dir = self
+            .blend_file_path
+            .parent()
+            .ok_or_else(|| FormatError::InvalidFormat("Invalid blend file path".to_string()))?;
+
+        let canonical_dir;
+        let blend_dir = if self.resolve_options.follow_symlinks {
+            canonical_dir = fs
+                .canonicalize(blend_dir)
+                .unwrap_or_else(|_| blend_dir.to_path_buf());
+            canonical_dir.as_path()
+        } else {
+            blend_dir
+        };
+
+        for link in links {
+            Self::resolve_link(blend_dir, link);
+            if self.resolve_options.follow_symlinks {
+                let target = link.absolute_path.as_deref().unwrap_or(&link.path);
+                // Missing targets can't be canonicalized and keep their joined path
+                if let Ok(real) = fs.canonicalize(Path::new(target)) {
+                    link.absolute_path = Some(real.to_string_lossy().into_owned());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A relative link of a file with no path, which names nothing on disk
+    pub(crate) fn is_unresolvable(&self, link: &LibraryLink) -> bool {
+        link.is_relative
+            && link.absolute_path.is_none()
+            && self.blend_file_path.as_os_str().is_empty()
+    }
+
+    fn resolve_link(blend_dir: &Path, link: &mut LibraryLink) {
+        if link.is_relative {
+            // Blender marks blend-relative paths with a leading "//"
+            let relative = link.path.strip_prefix("//").unwrap_or(&link.path);
+            let resolved_path = blend_dir.join(relative);
+            link.absolute_path = Some(resolved_path.to_string_lossy().into_owned());
+        }
+    }
+
+    /// Attach an `AssetStatus` to every link, using one metadata call per
+    /// unique `canonical_key`
+    pub fn stat_links(&self, links: &mut [LibraryLink]) -> Result<()> {
+        self.stat_links_in(links, &LocalFs)
+    }
+
+    /// Like `stat_links`, querying `fs` instead of the local disk
+    pub fn stat_links_in(&self, links: &mut [LibraryLink], fs: &dyn AssetFs) -> Result<()> {
+        let blend_mtime = fs
+            .metadata(&self.blend_file_path)
+            .ok()
+            .and_then(|m| m.modified);
+        let mut cache: HashMap<String, (Resolution, Option<u64>, Option<SystemTime>)> =
+            HashMap::new();
+
+        for link in links {
+            if self.is_unresolvable(link) {
+                link.asset_status = Some(AssetStatus {
+                    exists: false,
+                    resolution: Resolution::Missing,
+                    size: None,
+                    mtime: None,
+                    newer_than_blend: false,
+                });
+                continue;
+            }
+            let target = link.absolute_path.as_deref().unwrap_or(&link.path);
+            let (resolution, size, modified) = cache
+                .entry(link.canonical_key(PathPlatform::native()))
+                .or_insert_with(|| self.stat_target(Path::new(target), fs))
+                .clone();
+
+            let newer_than_blend = match (modified, blend_mtime) {
+                (Some(asset), Some(blend)) => asset > blend,
+                _ => false,
+            };
+
+            link.asset_status = Some(AssetStatus {
+                exists: resolution != Resolution::Missing,
+                resolution,
+                size,
+                mtime: modified.map(DateTime::<Utc>::from),
+                newer_than_blend,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn stat_target(
+        &self,
+        target: &Path,
+        fs: &dyn AssetFs,
+    ) -> (Resolution, Option<u64>, Option<SystemTime>) {
+        if let Ok(metadata) = fs.metadata(target) {
+            return (Resolution::Found, Some(metadata.len), metadata.modified);
+        }
+        if self.resolve_options.case_insensitive_fallback {
+            if let Some(actual) = find_case_insensitive(target, fs) {
+                if let Ok(metadata) = fs.metadata(&actual) {
+                    let resolution = Resolution::FoundWithDifferentCase {
+                        actual: actual.to_string_lossy().into_owned(),
+                    };
+                    return (resolution, Some(metadata.len), metadata.modified);
+                }
+            }
+        }
+        (Resolution::Missing, None, None)
+    }
+}
+
+/// Reads string fields of one link block, through the DNA when it describes
+/// them and from fixed offsets otherwise, counting each fallback
+struct FieldReader<'a, 'd> {
+    extractor: &'a LibraryLinkExtractor,
+    offsets: &'a mut OffsetTable<'d>,
+    options: &'a LinkExtractionOptions,
+    stats: &'a mut ExtractionStats,
+}
+
+impl FieldReader<'_, '_> {
+    /// Read a string field as stored, keeping whitespace and short values so
+    /// callers can apply their own filtering
+    fn raw_string(&mut self, block: &Block, field_name: &str) -> Result<Option<String>> {
+        if block.data.is_empty() {
+            return Ok(None);
+        }
+        let Some(struct_name) = link_struct_name(block) else {
+            return Ok(None);
+        };
+
+        let located = if self.offsets.dna().is_empty() {
+            self.stats.dna_missing += 1;
+            None
+        } else {
+            let located = dna_location(self.offsets, struct_name, field_name);
+            if located.is_none() {
+                self.stats.field_missing += 1;
+            }
+            located
+        };
+        let (offset, max_len) = match located {
+            Some(location) => location,
+            None => {
+                let Some(location) = fallback_location(block, field_name) else {
+                    return Ok(None);
+                };
+                let known_version = self
+                    .extractor
+                    .file_version
+                    .is_some_and(|version| version >= VERSION_TABLE_MIN);
+                if known_version {
+                    self.stats.version_table += 1;
+                } else {
+                    self.stats.raw_heuristic += 1;
+                    if self.options.strict {
+                        return Err(FormatError::DnaError(format!(
+                            "{struct_name}.{field_name} can only be read from guessed offsets"
+                        ))
+                        .into());
+                    }
+                }
+                location
+            }
+        };
+
+        if offset >= block.data.len() {
+            return Ok(None);
+        }
+
+        // Find null-terminated string starting at offset
+        let start = offset;
+        let search_end = (start + max_len).min(block.data.len());
+        let end = block.data[start..search_end]
+            .iter()
+            .position(|&b| b == 0)
+            .map(|pos| start + pos)
+            .unwrap_or(search_end);
+
+        let string_bytes = &block.data[start..end];
+
+        // Filter out non-printable characters and control codes
+        let filtered: Vec<u8> = string_bytes
+            .iter()
+            .copied()
+            .filter(|&b| (32..=126).contains(&b))
+            .collect();
+
+        match String::from_utf8(filtered) {
+            Ok(s) => Ok(Some(s)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn string_field(&mut self, block: &Block, field_name: &str) -> Result<Option<String>> {
+        match self.raw_string(block, field_name)? {
+            Some(s) => {
+                let trimmed = s.trim();
+                if trimmed.is_empty() || trimmed.len() < 3 {
+                    Ok(None)
+                } else {
+                    Ok(Some(trimmed.to_string()))
+                }
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// DNA struct stored in a link-carrying block
+fn link_struct_name(block: &Block) -> Option<&'static str> {
+    if is_non_link_code(&block.code) {
+        return None;
+    }
+    match &block.code[..2] {
+        b"LI" => Some("Library"),
+        b"IM" => Some("Image"),
+        b"SO" => Some("bSound"),
+        b"MC" => Some("MovieClip"),
+        _ => None,
+    }
+}
+
+/// Offset and size of `filepath` or the ID `name` according to the DNA
+fn dna_location(
+    offsets: &mut OffsetTable,
+    struct_name: &str,
+    field_name: &str,
+) -> Option<(usize, usize)> {
+    let path = match field_name {
+        "filepath" => "filepath",
+        "name" => "id.name",
+        _ => return None,
+    };
+    let location = offsets.field_offset(struct_name, path)?;
+    Some((location.offset, location.field.size))
+}
+
+/// Offset and size of a link block's `filepath`, through the DNA when possible
+pub(crate) fn filepath_location(
+    block: &Block,
+    offsets: &mut OffsetTable,
+) -> Option<(usize, usize)> {
+    let struct_name = link_struct_name(block)?;
+    if !offsets.dna().is_empty() {
+        if let Some(location) = dna_location(offsets, struct_name, "filepath") {
+            return Some(location);
+        }
+    }
+    fallback_location(block, "filepath")
+}
+
+/// Fixed offsets used when the DNA can't locate a field
+fn fallback_location(block: &Block, field_name: &str) -> Option<(usize, usize)> {
+    // Based on debug analysis of actual .blend files:
+    // - Library blocks (LI): filepath at offset 144 (0x90), name at 32
+    // - Image blocks (IM): filepath at offset 104 (0x68), name at 0
+    // - Sound blocks (SO): filepath at offset 104 (0x68), name at 0
+    // - Movie clip blocks (MC): filepath at offset 104 (0x68), name at 0
+    let is_library = block.code.starts_with(b"LI");
+    match field_name {
+        // Library filepath can be long
+        "filepath" if is_library => Some((144, 1024)),
+        "filepath" => Some((104, 1024)),
+        "name" if is_library => Some((32, 64)),
+        "name" => Some((0, 64)),
+        _ => None,
+    }
+}
+
+/// True when an LI block's `Library.packedfile` pointer is set. Needs the file's DNA.
+pub fn is_packed_library(block: &Block, dna: &Dna) -> bool {
+    dna.get_struct("Library")
+        .and_then(|library| library.field("packedfile"))
+        .and_then(|field| block.data.get(field.offset?..field.offset? + field.size))
+        .is_some_and(|pointer| pointer.iter().any(|&b| b != 0))
+}
+
+/// Find a file in `path`'s directory whose name matches case-insensitively
+fn find_case_insensitive(path: &Path, fs: &dyn AssetFs) -> Option<PathBuf> {
+    let wanted = path.file_name()?.to_string_lossy().to_lowercase();
+    let dir = path.parent()?;
+    fs.read_dir(dir).ok()?.into_iter().find(|entry| {
+        entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().to_lowercase() == wanted)
+    })
+}
+
+/// Counters describing how much of a file `extract_links_streaming` touched
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LinkStreamStats {
+    pub blocks_scanned: usize,
+    pub bytes_read: u64,
+    pub links_found: usize,
+    pub stopped_early: bool,
+}
+
+/// Stream library links out of a blend file without materializing its blocks.
+///
+/// Only payloads of link-carrying blocks are loaded; everything else is skipped
+/// as it streams past. Returning `ControlFlow::Break` from `on_link` stops reading.
+pub fn extract_links_streaming<P, F>(path: P, on_link: F) -> Result<LinkStreamStats>
+where
+    P: AsRef<Path>,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    extract_links_streaming_with_options(path, &LinkExtractionOptions::default(), on_link)
+}
+
+/// `extract_links_streaming` with extraction behaviour controlled by `options`.
+///
+/// DNA1 is written after the data blocks, so a first pass over the block
+/// headers finds it before the links are read. Compressed files are
+/// decompressed for both passes.
+pub fn extract_links_streaming_with_options<P, F>(
+    path: P,
+    options: &LinkExtractionOptions,
+    on_link: F,
+) -> Result<LinkStreamStats>
+where
+    P: AsRef<Path>,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    let path = path.as_ref();
+    let mut reader = DecompressingReader::new(File::open(path)?)?;
+    if reader.compression() == Compression::None {
+        return extract_links_streaming_from_reader_with_options(
+            BufReader::new(File::open(path)?),
+            path,
+            options,
+            on_link,
+        );
+    }
+    let header = Header::from_reader(&mut reader)?;
+    let dna = Dna::from_reader(&mut reader, &header)?;
+    let mut reader = CountingReader::new(DecompressingReader::new(File::open(path)?)?);
+    let header = Header::from_reader(&mut reader)?;
+    stream_links(reader, &header, path, &dna, options, on_link)
+}
+
+/// Same as `extract_links_streaming`, reading uncompressed blend data from `reader`.
+/// `blend_path` is only used to resolve relative links.
+pub fn extract_links_streaming_from_reader<R, F>(
+    reader: R,
+    blend_path: &Path,
+    on_link: F,
+) -> Result<LinkStreamStats>
+where
+    R: Read + Seek,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    extract_links_streaming_from_reader_with_options(
+        reader,
+        blend_path,
+        &LinkExtractionOptions::default(),
+        on_link,
+    )
+}
+
+/// `extract_links_streaming_from_reader` with extraction behaviour controlled
+/// by `options`. The DNA is found by seeking over the block headers, then
+/// `reader` is rewound to the first block.
+pub fn extract_links_streaming_from_reader_with_options<R, F>(
+    reader: R,
+    blend_path: &Path,
+    options: &LinkExtractionOptions,
+    on_link: F,
+) -> Result<LinkStreamStats>
+where
+    R: Read + Seek,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    let mut reader = CountingReader::new(reader);
+    let header = Header::from_reader(&mut reader)?;
+    let dna = Dna::from_reader(&mut reader, &header)?;
+    reader.seek(SeekFrom::Start(Header::SIZE as u64))?;
+    stream_links(reader, &header, blend_path, &dna, options, on_link)
+}
+
+/// Stream links from a reader that can't be rewound, such as a pipe. The DNA
+/// comes after the blocks, so `filepath` and `name` are read from the fixed
+/// offsets `get_library_links` falls back to, which don't fit every Blender
+/// version; with `options.strict` this fails instead.
+pub fn extract_links_streaming_from_unseekable_reader<R, F>(
+    reader: R,
+    blend_path: &Path,
+    options: &LinkExtractionOptions,
+    on_link: F,
+) -> Result<LinkStreamStats>
+where
+    R: Read,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    if options.strict {
+        return Err(FormatError::DnaError(
+            "the DNA follows the blocks, so an unseekable stream can only be read from guessed offsets"
+                .to_string(),
+        ).into());
+    }
+    let mut reader = CountingReader::new(reader);
+    let header = Header::from_reader(&mut reader)?;
+    stream_links(
+        reader,
+        &header,
+        blend_path,
+        &Dna::default(),
+        options,
+        on_link,
+    )
+}
+
+/// Read links block by block from `reader`, positioned at the first block
+fn stream_links<R, F>(
+    mut reader: CountingReader<R>,
+    header: &Header,
+    blend_path: &Path,
+    dna: &Dna,
+    options: &LinkExtractionOptions,
+    mut on_link: F,
+) -> Result<LinkStreamStats>
+where
+    R: Read,
+    F: FnMut(LibraryLink) -> ControlFlow<()>,
+{
+    let mut stats = LinkStreamStats::default();
+    let extractor = LibraryLinkExtractor::new(blend_path)
+        .with_file_version(header.version)
+        .with_endianness(header.endianness);
+    let blend_dir = blend_path
+        .parent()
+        .ok_or_else(|| FormatError::InvalidFormat("Invalid blend file path".to_string()))?;
+    let mut offsets = OffsetTable::new(dna);
+    let mut fields = ExtractionStats::default();
+
+    while let Some(mut block) = Block::header_from_reader(&mut reader, header)? {
+        cancel::check(options.cancellation.as_ref())?;
+        // Counted like `BlendFile::blocks`, which holds neither
+        match &block.code {
+            b"ENDB" => break,
+            b"DNA1" => {
+                io::copy(&mut (&mut reader).take(block.size as u64), &mut io::sink())?;
+                continue;
+            }
+            _ => stats.blocks_scanned += 1,
+        }
+
+        if is_non_link_code(&block.code)
+            || !matches!(&block.code[..2], b"LI" | b"IM" | b"SO" | b"MC")
+            || block.anomaly().is_some()
+        {
+            io::copy(&mut (&mut reader).take(block.size as u64), &mut io::sink())?;
+            continue;
+        }
+
+        block.data = vec![0u8; block.size as usize];
+        reader.read_exact(&mut block.data)?;
+
+        if let Some(mut link) =
+            extractor.extract_link_counted(&block, &mut offsets, options, &mut fields)?
+        {
+            LibraryLinkExtractor::resolve_link(blend_dir, &mut link);
+            stats.links_found += 1;
+            if on_link(link).is_break() {
+                stats.stopped_early = true;
+                break;
+            }
+        }
+    }
+
+    stats.bytes_read = reader.count;
+    Ok(stats)
+}
+
+/// How `references_path` compares a query against stored and resolved link paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathMatch {
+    /// The whole path equals the query
+    #[default]
+    Exact,
+    /// The path ends with the query at a path separator, e.g. `old_rig.blend`
+    /// or `rigs/old_rig.blend`
+    Suffix,
+    /// The query appears anywhere in the path
+    Substring,
+}
+
+impl PathMatch {
+    pub fn matches(self, path: &str, query: &str) -> bool {
+        match self {
+            PathMatch::Exact => path == query,
+            PathMatch::Suffix => path
+                .strip_suffix(query)
+                .is_some_and(|rest| rest.is_empty() || rest.ends_with('/') || rest.ends_with('\\')),
+            PathMatch::Substring => path.contains(query),
+        }
+    }
+}
+
+impl std::str::FromStr for PathMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(PathMatch::Exact),
+            "suffix" => Ok(PathMatch::Suffix),
+            "substring" => Ok(PathMatch::Substring),
+            _ => Err(format!(
+                "unknown match mode '{s}' (exact, suffix, substring)"
+            )),
+        }
+    }
+}
+
+/// Path rules `LibraryLink::canonical_key` compares links under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathPlatform {
+    /// Case-sensitive names, as on Linux
+    Posix,
+    /// Case-insensitive names
+    Windows,
+    /// Case-insensitive names, as on a default APFS volume
+    MacOs,
+}
+
+impl PathPlatform {
+    /// The platform this binary was built for
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            PathPlatform::Windows
+        } else if cfg!(target_os = "macos") {
+            PathPlatform::MacOs
+        } else {
+            PathPlatform::Posix
+        }
+    }
+
+    pub fn is_case_insensitive(self) -> bool {
+        !matches!(self, PathPlatform::Posix)
+    }
+}
+
+/// `path` with `\` as `/`, repeated separators collapsed and `.` and `..`
+/// folded lexically, lowercased on case-insensitive platforms. A leading
+/// `//` (relative to the blend file) or `/` is kept; `..` never climbs
+/// above it.
+pub fn canonical_path(path: &str, platform: PathPlatform) -> String {
+    let path = path.trim().replace('\\', "/");
+    let root = if path.starts_with("//") {
+        "//"
+    } else if path.starts_with('/') {
+        "/"
+    } else {
+        ""
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path[root.len()..].split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => match segments.last() {
+                Some(&last) if last != ".." => {
+                    segments.pop();
+                }
+                _ if root.is_empty() => segments.push(".."),
+                _ => {}
+            },
+            _ => segments.push(segment),
+        }
+    }
+    let key = format!("{root}{}", segments.join("/"));
+    if platform.is_case_insensitive() {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// Groups of links whose canonical keys collide although their stored paths
+/// differ, in order of first appearance. Every link with the key is in the
+/// group, including repeats of the same spelling.
+pub fn duplicate_links(links: &[LibraryLink], platform: PathPlatform) -> Vec<Vec<&LibraryLink>> {
+    let mut groups: Vec<Vec<&LibraryLink>> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for link in links {
+        let index = *by_key
+            .entry(link.canonical_key(platform))
+            .or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+        groups[index].push(link);
+    }
+    groups.retain(|group| group.iter().any(|link| link.path != group[0].path));
+    groups
+}
+
+impl LibraryLink {
+    /// Key under which links to the same file compare equal, whatever their
+    /// spelling: `canonical_path` of the resolved path, or of the stored one
+    /// when it hasn't been resolved
+    pub fn canonical_key(&self, platform: PathPlatform) -> String {
+        canonical_path(
+            self.absolute_path.as_deref().unwrap_or(&self.path),
+            platform,
+        )
+    }
+
+    /// True when the stored or the resolved path matches `query`
+    pub fn matches_path(&self, query: &str, mode: PathMatch) -> bool {
+        mode.matches(&self.path, query)
+            || self
+                .absolute_path
+                .as_deref()
+                .is_some_and(|path| mode.matches(path, query))
+    }
+}
+
+/// Whether any link in the blend file at `path` matches `query`; reading stops at the first match
+pub fn file_references_path<P: AsRef<Path>>(path: P, query: &str, mode: PathMatch) -> Result<bool> {
+    let mut found = false;
+    extract_links_streaming(path, |link| {
+        found = link.matches_path(query, mode);
+        if found {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+    Ok(found)
+}
+
+/// Same as `file_references_path`, reading uncompressed blend data from `reader`
+pub fn references_path_from_reader<R: Read + Seek>(
+    reader: R,
+    blend_path: &Path,
+    query: &str,
+    mode: PathMatch,
+) -> Result<(bool, LinkStreamStats)> {
+    let mut found = false;
+    let stats = extract_links_streaming_from_reader(reader, blend_path, |link| {
+        found = link.matches_path(query, mode);
+        if found {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+    Ok((found, stats))
+}
+
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R> CountingReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_link_extractor_new() {
+        let extractor = LibraryLinkExtractor::new("/path/to/file.blend");
+        assert_eq!(
+            extractor.blend_file_path.to_string_lossy(),
+            "/path/to/file.blend"
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_paths() {
+        let mut links = vec![
+            LibraryLink {
+                path: "textures/wood.jpg".to_string(),
+                absolute_path: None,
+                block_type: "Image".to_string(),
+                block_name: None,
+                is_relative: true,
+                is_placeholder: false,
+                is_packed: false,
+                asset_status: None,
+                status: None,
+                flags: None,
+            },
+            LibraryLink {
+                path: "/absolute/path/file.blend".to_string(),
+                absolute_path: None,
+                block_type: "Library".to_string(),
+                block_name: None,
+                is_relative: false,
+                is_placeholder: false,
+                is_packed: false,
+                asset_status: None,
+                status: None,
+                flags: None,
+            },
+        ];
+
+        let extractor = LibraryLinkExtractor::new("/home/user/project/scene.blend");
+        extractor.resolve_relative_paths(&mut links).unwrap();
+
+        assert_eq!(
+            links[0].absolute_path,
+            Some("/home/user/project/textures/wood.jpg".to_string())
+        );
+        assert_eq!(links[1].absolute_path, None);
+    }
+
+    #[test]
+    fn test_extraction_locates_fields_once() {
+        use crate::dna::RawSdna;
+        use crate::header::PointerSize;
+
+        // ID { char name[66] }, Image { ID id, char filepath[1024] }
+        let raw = RawSdna {
+            names: vec!["name[66]".into(), "id".into(), "filepath[1024]".into()],
+            types: vec!["char".into(), "ID".into(), "Image".into()],
+            tlens: vec![1, 66, 1090],
+            structs: vec![(1, vec![(0, 0)]), (2, vec![(1, 1), (0, 2)])],
+        };
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        let blocks: Vec<Block> = (0..400)
+            .map(|i| {
+                let mut data = vec![0u8; 1090];
+                let name = format!("IMtex{i}");
+                data[..name.len()].copy_from_slice(name.as_bytes());
+                let path = format!("//textures/tex{i}.png");
+                data[66..66 + path.len()].copy_from_slice(path.as_bytes());
+                Block {
+                    code: *b"IM\0\0",
+                    size: data.len() as u32,
+                    old_memory_address: 0x1000 + i * 0x1000,
+                    sdna_index: 1,
+                    count: 1,
+                    data_offset: 0,
+                    data,
+                }
+            })
+            .collect();
+
+        let extractor = LibraryLinkExtractor::new("/project/scene.blend");
+        let mut offsets = OffsetTable::new(&dna);
+        let (links, stats) = extractor
+            .extract_links_in(&blocks, &mut offsets, &LinkExtractionOptions::default())
+            .unwrap();
+        assert_eq!(links.len(), 400);
+        assert_eq!(links[399].path, "//textures/tex399.png");
+        assert_eq!(links[399].block_name.as_deref(), Some("IMtex399"));
+        assert_eq!(stats.fallbacks(), 0);
+        // Image.filepath and Image.id.name, each walked for the first block only
+        assert_eq!(offsets.walks(), 2);
+    }
+
+    #[test]
+    fn test_resolve_blend_relative_prefix() {
+        let mut links = vec![LibraryLink {
+            path: "//textures/wood.jpg".to_string(),
+            absolute_path: None,
+            block_type: "Image".to_string(),
+            block_name: None,
+            is_relative: true,
+            is_placeholder: false,
+            is_packed: false,
+            asset_status: None,
+            status: None,
+            flags: None,
+        }];
+
+        let extractor = LibraryLinkExtractor::new("/home/user/project/scene.blend");
+        extractor.resolve_relative_paths(&mut links).unwrap();
+
+        assert_eq!(
+            links[0].absolute_path,
+            Some("/home/user/project/textures/wood.jpg".to_string())
+        );
+    }
+}
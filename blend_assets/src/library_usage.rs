@@ -0,0 +1,138 @@
+use crate::blend_file::BlendFile;
+use crate::convert::{block_layout, read_uint};
+use crate::dna::Dna;
+use crate::error::{BlendFileError, FormatError, Result};
+use crate::library_link::LinkExtractionOptions;
+use crate::pointers::PointerWalker;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The IDs a file pulls from one library
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LibraryUsage {
+    /// Library path as stored in the LI block
+    pub path: String,
+    /// The library's .blend is packed into this file
+    pub is_packed: bool,
+    /// Linked IDs grouped by ID code ("OB", "ME", ...)
+    pub ids: BTreeMap<String, Vec<LinkedId>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkedId {
+    /// Name without the ID code prefix
+    pub name: String,
+    /// No local data points at this ID, so it's a candidate for removal
+    pub unreferenced: bool,
+}
+
+impl BlendFile {
+    /// List the IDs linked from each library.
+    ///
+    /// An ID is linked when its `ID.lib` pointer holds the address of an LI
+    /// block; it counts as referenced when any pointer in local (non-linked)
+    /// DNA-typed data holds its address.
+    pub fn library_usage(&self) -> Result<Vec<LibraryUsage>> {
+        let extractor = self.link_extractor();
+        let options = LinkExtractionOptions::default().with_placeholders(true);
+        let mut libraries: HashMap<u64, LibraryUsage> = HashMap::new();
+        let mut order = Vec::new();
+        for block in self.get_library_blocks()? {
+            let (path, is_packed) = extractor
+                .extract_block_link(block, &self.dna, &options)?
+                .map(|link| (link.path, link.is_packed))
+                .unwrap_or_default();
+            order.push(block.old_memory_address);
+            libraries.insert(
+                block.old_memory_address,
+                LibraryUsage {
+                    path,
+                    is_packed,
+                    ids: BTreeMap::new(),
+                },
+            );
+        }
+        if libraries.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.dna.is_empty() {
+            return Err(
+                FormatError::DnaError("library usage requires the file's DNA".to_string()).into(),
+            );
+        }
+
+        let id = self
+            .dna
+            .get_struct("ID")
+            .ok_or_else(|| FormatError::DnaError("ID not in DNA".to_string()))?;
+        let field = |name: &str| {
+            let field = id
+                .field(name)
+                .ok_or_else(|| FormatError::DnaError(format!("ID.{name} not in DNA")))?;
+            Ok::<_, BlendFileError>((id.offset_of(field)?, field))
+        };
+        let ((lib_offset, lib_field), (name_offset, name_field)) = (field("lib")?, field("name")?);
+        let walker = PointerWalker::new(&self.dna, &self.header);
+
+        // (library address, id address, code, name)
+        let mut linked = Vec::new();
+        let mut referenced = HashSet::new();
+        for block in &self.blocks {
+            let Some(layout) = block_layout(&self.dna, block) else {
+                continue;
+            };
+            let id_offset = match id_offset(&self.dna, &layout.name) {
+                Some(offset) => offset,
+                None => {
+                    walker.collect(block, &layout.name, &mut referenced);
+                    continue;
+                }
+            };
+
+            let lib_at = id_offset + lib_offset;
+            let lib = read_uint(
+                &block.data[lib_at..lib_at + lib_field.size],
+                walker.endianness,
+            );
+            if lib != 0 && libraries.contains_key(&lib) {
+                let name_at = id_offset + name_offset;
+                let raw = &block.data[name_at..name_at + name_field.size];
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                let full = String::from_utf8_lossy(&raw[..end]).into_owned();
+                linked.push((lib, block.old_memory_address, full));
+            } else {
+                walker.collect(block, &layout.name, &mut referenced);
+            }
+        }
+
+        for (lib, address, full_name) in linked {
+            let (code, name) = full_name.split_at(full_name.len().min(2));
+            if let Some(usage) = libraries.get_mut(&lib) {
+                usage
+                    .ids
+                    .entry(code.to_string())
+                    .or_default()
+                    .push(LinkedId {
+                        name: name.to_string(),
+                        unreferenced: !referenced.contains(&address),
+                    });
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .filter_map(|address| libraries.remove(&address))
+            .collect())
+    }
+}
+
+/// Offset of the embedded ID within a struct: the struct itself, or its leading `id` member
+pub(crate) fn id_offset(dna: &Dna, struct_name: &str) -> Option<usize> {
+    if struct_name == "ID" {
+        return Some(0);
+    }
+    let first = dna.get_struct(struct_name)?.fields.first()?;
+    (first.type_name == "ID" && !first.dna_name.is_pointer())
+        .then_some(first.offset)
+        .flatten()
+}
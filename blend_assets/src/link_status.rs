@@ -0,0 +1,129 @@
+use crate::asset_fs::{AssetFs, LocalFs};
+use crate::blend_file::BlendFile;
+use crate::error::Result;
+use crate::library_link::{LibraryLink, LibraryLinkExtractor, LinkExtractionOptions, PathPlatform};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// Whether a link's target exists, attached by `get_library_links_with_status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkStatus {
+    pub exists: bool,
+    pub size: Option<u64>,
+    /// Target with symlinks and `..` resolved, when it exists
+    pub canonical_path: Option<String>,
+    /// Why the target couldn't be checked, e.g. an unreadable directory.
+    /// A plain missing file isn't an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LinkStatusOptions {
+    pub extraction: LinkExtractionOptions,
+    /// Fill `canonical_path`; costs one extra call per unique existing target
+    pub canonicalize: bool,
+}
+
+impl Default for LinkStatusOptions {
+    fn default() -> Self {
+        Self {
+            extraction: LinkExtractionOptions::default(),
+            canonicalize: true,
+        }
+    }
+}
+
+impl LinkStatusOptions {
+    pub fn with_extraction(mut self, extraction: LinkExtractionOptions) -> Self {
+        self.extraction = extraction;
+        self
+    }
+
+    pub fn with_canonicalize(mut self, canonicalize: bool) -> Self {
+        self.canonicalize = canonicalize;
+        self
+    }
+}
+
+impl LibraryLinkExtractor {
+    /// Attach a `LinkStatus` to every link, querying `fs` once per unique
+    /// `canonical_key`. Failures are recorded on the affected links only.
+    pub fn fill_status(
+        &self,
+        links: &mut [LibraryLink],
+        options: &LinkStatusOptions,
+        fs: &dyn AssetFs,
+    ) {
+        let mut cache: HashMap<String, LinkStatus> = HashMap::new();
+        for link in links {
+            if self.is_unresolvable(link) {
+                link.status = Some(LinkStatus {
+                    exists: false,
+                    size: None,
+                    canonical_path: None,
+                    error: Some("relative path with no blend file to resolve against".to_string()),
+                });
+                continue;
+            }
+            let target = link.absolute_path.as_deref().unwrap_or(&link.path);
+            let status = cache
+                .entry(link.canonical_key(PathPlatform::native()))
+                .or_insert_with(|| status_of(Path::new(target), options, fs));
+            link.status = Some(status.clone());
+        }
+    }
+}
+
+fn status_of(target: &Path, options: &LinkStatusOptions, fs: &dyn AssetFs) -> LinkStatus {
+    let missing = |error| LinkStatus {
+        exists: false,
+        size: None,
+        canonical_path: None,
+        error,
+    };
+    let metadata = match fs.metadata(target) {
+        Ok(stat) => stat,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return missing(None),
+        Err(e) => return missing(Some(e.to_string())),
+    };
+    let canonical_path = options
+        .canonicalize
+        .then(|| fs.canonicalize(target).ok())
+        .flatten()
+        .map(|path| path.to_string_lossy().into_owned());
+    LinkStatus {
+        exists: true,
+        size: Some(metadata.len),
+        canonical_path,
+        error: None,
+    }
+}
+
+impl BlendFile {
+    /// Get library links with each target's existence, size and canonical path
+    /// filled in, checking each unique target once
+    pub fn get_library_links_with_status(
+        &self,
+        options: &LinkStatusOptions,
+    ) -> Result<Vec<LibraryLink>> {
+        self.get_library_links_with_status_in(options, &LocalFs)
+    }
+
+    /// Like `get_library_links_with_status`, checking targets through `fs`
+    pub fn get_library_links_with_status_in(
+        &self,
+        options: &LinkStatusOptions,
+        fs: &dyn AssetFs,
+    ) -> Result<Vec<LibraryLink>> {
+        let extractor = self.link_extractor();
+        let mut links =
+            extractor.extract_links_with_options(&self.blocks, &self.dna, &options.extraction)?;
+        extractor.resolve_relative_paths_in(&mut links, fs)?;
+        extractor.fill_status(&mut links, options, fs);
+        Ok(links)
+    }
+}
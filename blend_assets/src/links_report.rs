@@ -0,0 +1,389 @@
+use crate::blend_file::BlendFile;
+use crate::flags::LinkFlags;
+use crate::library_link::{ExtractionStats, LibraryLink, Resolution};
+use serde::Serialize;
+
+/// JSON Schema for `LinksReportV1`
+pub const LINKS_REPORT_V1_SCHEMA: &str = include_str!("../schemas/links-report-v1.schema.json");
+
+/// JSON Schema for `LinksReportV2`
+pub const LINKS_REPORT_V2_SCHEMA: &str = include_str!("../schemas/links-report-v2.schema.json");
+
+/// JSON Schema for `LinksReportV3`, printed by `links --schema`
+pub const LINKS_REPORT_V3_SCHEMA: &str = include_str!("../schemas/links-report-v3.schema.json");
+
+/// Stable `links --format json-v1` output.
+///
+/// Fields are mapped explicitly from `LibraryLink` so the report keeps its
+/// shape as the internal type grows; any change here needs a new version.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinksReportV1 {
+    /// Always 1
+    pub schema_version: u32,
+    /// Blend file path as given
+    pub file: String,
+    /// Version that saved the file, e.g. "2.79"
+    pub blender_version: String,
+    pub links: Vec<LinkEntryV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkEntryV1 {
+    /// "Library", "Image", "Sound" or "MovieClip"
+    pub block_type: String,
+    pub block_name: Option<String>,
+    /// Path as stored in the file
+    pub path: String,
+    /// Path resolved against the blend file's directory
+    pub absolute_path: Option<String>,
+    pub is_relative: bool,
+}
+
+impl LinksReportV1 {
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    pub fn new(blend_file: &BlendFile, links: &[LibraryLink]) -> Self {
+        let version = blend_file.header.version;
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            file: blend_file.path.display().to_string(),
+            blender_version: format!("{}.{}", version / 100, version % 100),
+            links: links.iter().map(LinkEntryV1::from).collect(),
+        }
+    }
+}
+
+/// `links --format json-v2` output: v1 plus extraction fallback counts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinksReportV2 {
+    /// Always 2
+    pub schema_version: u32,
+    pub file: String,
+    pub blender_version: String,
+    /// Entries are unchanged from v1
+    pub links: Vec<LinkEntryV1>,
+    pub stats: ExtractionStats,
+}
+
+impl LinksReportV2 {
+    pub const SCHEMA_VERSION: u32 = 2;
+
+    pub fn new(blend_file: &BlendFile, links: &[LibraryLink], stats: ExtractionStats) -> Self {
+        let v1 = LinksReportV1::new(blend_file, links);
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            file: v1.file,
+            blender_version: v1.blender_version,
+            links: v1.links,
+            stats,
+        }
+    }
+}
+
+/// `links --format json-v3` output: v2 with datablock flags on each link
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinksReportV3 {
+    /// Always 3
+    pub schema_version: u32,
+    pub file: String,
+    pub blender_version: String,
+    pub links: Vec<LinkEntryV3>,
+    pub stats: ExtractionStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkEntryV3 {
+    pub block_type: String,
+    pub block_name: Option<String>,
+    pub path: String,
+    pub absolute_path: Option<String>,
+    pub is_relative: bool,
+    /// Null when the file's DNA doesn't describe the flags
+    pub flags: Option<LinkFlags>,
+}
+
+impl LinksReportV3 {
+    pub const SCHEMA_VERSION: u32 = 3;
+
+    pub fn new(blend_file: &BlendFile, links: &[LibraryLink], stats: ExtractionStats) -> Self {
+        let v2 = LinksReportV2::new(blend_file, links, stats);
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            file: v2.file,
+            blender_version: v2.blender_version,
+            links: links.iter().map(LinkEntryV3::from).collect(),
+            stats: v2.stats,
+        }
+    }
+}
+
+impl From<&LibraryLink> for LinkEntryV3 {
+    fn from(link: &LibraryLink) -> Self {
+        Self {
+            block_type: link.block_type.clone(),
+            block_name: link.block_name.clone(),
+            path: link.path.clone(),
+            absolute_path: link.absolute_path.clone(),
+            is_relative: link.is_relative,
+            flags: link.flags,
+        }
+    }
+}
+
+impl From<&LibraryLink> for LinkEntryV1 {
+    fn from(link: &LibraryLink) -> Self {
+        Self {
+            block_type: link.block_type.clone(),
+            block_name: link.block_name.clone(),
+            path: link.path.clone(),
+            absolute_path: link.absolute_path.clone(),
+            is_relative: link.is_relative,
+        }
+    }
+}
+
+/// How `LinksTable::render` fits the table to a terminal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TableLayout {
+    /// Widest a line may get; without one the Path column is as wide as the
+    /// longest path
+    pub max_width: Option<usize>,
+    /// Continue paths that don't fit on the following lines instead of
+    /// shortening them
+    pub wrap: bool,
+}
+
+impl TableLayout {
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+}
+
+/// Narrowest the Path column gets, however little room the rest leaves
+const MIN_PATH_WIDTH: usize = 16;
+
+/// `links --format table` output: one row per link, with Exists and the
+/// stat columns when the links carry that status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinksTable {
+    /// Header and width of each column; the Path column's width is decided
+    /// by `render`
+    pub columns: Vec<(&'static str, usize)>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl LinksTable {
+    /// Index of the Path column
+    pub const PATH: usize = 2;
+
+    /// Rows for `links`, with their resolved paths when `absolute` is set
+    pub fn new(links: &[LibraryLink], absolute: bool) -> Self {
+        let mut columns = vec![("Type", 15), ("Name", 30), ("Path", 0), ("Relative", 10)];
+        if links.iter().any(|link| link.status.is_some()) {
+            columns.push(("Exists", 7));
+        }
+        if links.iter().any(|link| link.asset_status.is_some()) {
+            columns.extend([("Size", 12), ("Modified", 25), ("Newer", 6)]);
+        }
+        let rows = links.iter().map(|link| row(link, absolute)).collect();
+        Self { columns, rows }
+    }
+
+    /// The header, a rule and every row, each line ending in a newline
+    pub fn render(&self, layout: &TableLayout) -> String {
+        let longest = self
+            .rows
+            .iter()
+            .map(|row| row[Self::PATH].chars().count())
+            .chain(["Path".len()])
+            .max()
+            .unwrap_or_default();
+        let fixed: usize = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != Self::PATH)
+            .map(|(_, (_, width))| width + 1)
+            .sum();
+        let path_width = match layout.max_width {
+            Some(max) => longest.min(max.saturating_sub(fixed).max(MIN_PATH_WIDTH)),
+            None => longest,
+        };
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, (_, width))| if i == Self::PATH { path_width } else { *width })
+            .collect();
+
+        let mut out = String::new();
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        push_line(&mut out, &header, &widths);
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        push_line(&mut out, &rule, &widths);
+        for row in &self.rows {
+            let path = &row[Self::PATH];
+            if !layout.wrap {
+                let mut row = row.clone();
+                row[Self::PATH] = truncate_middle(path, path_width);
+                push_line(&mut out, &row, &widths);
+                continue;
+            }
+            let chars: Vec<char> = path.chars().collect();
+            for (i, piece) in chars.chunks(path_width.max(1)).enumerate() {
+                let mut line = if i == 0 {
+                    row.clone()
+                } else {
+                    vec![String::new(); row.len()]
+                };
+                line[Self::PATH] = piece.iter().collect();
+                push_line(&mut out, &line, &widths);
+            }
+            if chars.is_empty() {
+                push_line(&mut out, row, &widths);
+            }
+        }
+        out
+    }
+}
+
+/// `cells` padded to `widths`, without trailing spaces
+fn push_line(out: &mut String, cells: &[String], widths: &[usize]) {
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect();
+    out.push_str(padded.join(" ").trim_end());
+    out.push('\n');
+}
+
+fn row(link: &LibraryLink, absolute: bool) -> Vec<String> {
+    let block_type = if link.is_packed {
+        format!("{} (packed)", link.block_type)
+    } else {
+        link.block_type.clone()
+    };
+    let path = match &link.absolute_path {
+        Some(absolute_path) if absolute => absolute_path.clone(),
+        _ => link.path.clone(),
+    };
+    let yes_no = |yes: bool| if yes { "Yes" } else { "No" }.to_string();
+    let mut row = vec![
+        block_type,
+        link.block_name.clone().unwrap_or_else(|| "N/A".to_string()),
+        path,
+        yes_no(link.is_relative),
+    ];
+    if let Some(status) = &link.status {
+        let exists = match (&status.error, status.exists) {
+            (Some(_), _) => "Error".to_string(),
+            (None, exists) => yes_no(exists),
+        };
+        row.push(exists);
+    }
+    if let Some(status) = &link.asset_status {
+        let size = match (&status.resolution, status.size) {
+            (Resolution::FoundWithDifferentCase { .. }, Some(size)) => format!("{size} (case)"),
+            (_, Some(size)) => size.to_string(),
+            (_, None) => "missing".to_string(),
+        };
+        let mtime = status
+            .mtime
+            .map(|mtime| mtime.to_rfc3339())
+            .unwrap_or_else(|| "N/A".to_string());
+        row.extend([size, mtime, yes_no(status.newer_than_blend)]);
+    }
+    row
+}
+
+/// `s` cut to at most `width` characters by putting `…` in place of its
+/// middle. The file name after the last `/` or `\\` stays whole when it
+/// fits; a longer name keeps its start and its extension.
+pub fn truncate_middle(s: &str, width: usize) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let len = chars.len();
+    if len <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let fits = |start: usize| len - start < width;
+    let name_start = chars
+        .iter()
+        .rposition(|&c| c == '/' || c == '\\')
+        .map_or(0, |separator| separator + 1);
+    let (head_start, tail_start) = if name_start > 0 && fits(name_start - 1) {
+        // Keeping the separator shows the cut was in the directories
+        (0, name_start - 1)
+    } else if fits(name_start) {
+        (0, name_start)
+    } else {
+        let extension = chars[name_start..]
+            .iter()
+            .rposition(|&c| c == '.')
+            .map(|dot| name_start + dot)
+            .filter(|&dot| dot > name_start && fits(dot));
+        (name_start, extension.unwrap_or(len))
+    };
+    let keep = width - 1 - (len - tail_start);
+    let mut out: String = chars[head_start..head_start + keep].iter().collect();
+    out.push('…');
+    out.extend(&chars[tail_start..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_middle_keeps_file_name() {
+        let path = "//textures/environment/outdoor/wood_planks.png";
+        assert_eq!(truncate_middle(path, 80), path);
+        assert_eq!(truncate_middle(path, path.len()), path);
+        assert_eq!(truncate_middle(path, 30), "//textures/en…/wood_planks.png");
+        assert_eq!(truncate_middle(path, 17), "…/wood_planks.png");
+        assert_eq!(truncate_middle(path, 16), "…wood_planks.png");
+        assert_eq!(
+            truncate_middle(r"C:\assets\rock.blend", 13),
+            r"C…\rock.blend"
+        );
+    }
+
+    #[test]
+    fn test_truncate_middle_long_file_name() {
+        // Too long for the name itself: its start and the extension stay
+        let path = "/lib/a_very_long_texture_name.png";
+        assert_eq!(truncate_middle(path, 12), "a_very_….png");
+        assert_eq!(truncate_middle("no_extension_at_all", 8), "no_exte…");
+        assert_eq!(truncate_middle("/lib/.hidden_settings_file", 8), ".hidden…");
+        assert_eq!(truncate_middle("name.an_extension_too_long", 8), "name.an…");
+        assert_eq!(truncate_middle("abc", 0), "");
+        assert_eq!(truncate_middle("abc", 1), "…");
+    }
+
+    #[test]
+    fn test_truncate_middle_counts_characters() {
+        let path = "/bibliothèque/matériaux/géologie/granit_rosé.png";
+        let cut = truncate_middle(path, 24);
+        assert_eq!(cut, "/biblio…/granit_rosé.png");
+        assert_eq!(cut.chars().count(), 24);
+        assert_eq!(
+            truncate_middle("/資料/テクスチャ/木目.png", 10),
+            "/資…/木目.png"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::convert::block_layout;
+use crate::dna::{Dna, DnaStruct};
+use crate::error::{FormatError, Result};
+use crate::library_usage::id_offset;
+use crate::naming::{reject, NamePolicy};
+use crate::pointers::PointerWalker;
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// What `merge_from` does with an ID whose name is already taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Keep the existing ID and don't copy the incoming one
+    #[default]
+    Skip,
+    /// Copy under the first free `name.001`, `name.002`, ...
+    Rename,
+    /// Replace the existing ID and its data. The incoming ID takes over the
+    /// old address, so users of the old ID now point at the new one.
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct MergeOptions {
+    /// ID codes to copy ("MA", "IM", ...); empty copies IDs of every type
+    pub codes: Vec<String>,
+    /// ID names to copy, without the code prefix; empty copies every name
+    pub names: Vec<String>,
+    pub collision: CollisionPolicy,
+    /// Rules every copied ID's final name must follow
+    pub name_policy: Option<NamePolicy>,
+}
+
+impl MergeOptions {
+    pub fn with_codes<S: Into<String>>(mut self, codes: impl IntoIterator<Item = S>) -> Self {
+        self.codes = codes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.names = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_collision(mut self, collision: CollisionPolicy) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// Hold copied names to `policy`, whose collision handling replaces
+    /// `collision`
+    pub fn with_name_policy(mut self, policy: NamePolicy) -> Self {
+        self.collision = policy.collision;
+        self.name_policy = Some(policy);
+        self
+    }
+}
+
+/// What `merge_from` copied. Names include the ID code prefix.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MergeReport {
+    /// IDs added to this file, under their final names
+    pub copied: Vec<String>,
+    pub skipped: Vec<String>,
+    /// (name in the other file, name given here)
+    pub renamed: Vec<(String, String)>,
+    pub overwritten: Vec<String>,
+    /// DATA blocks copied along with the IDs
+    pub data_blocks: usize,
+    /// Pointers into blocks that weren't copied, set to null
+    pub pointers_cleared: usize,
+}
+
+/// An ID selected from the other file, with the blocks that go with it
+struct Incoming {
+    name: String,
+    /// Index of the ID block, then its DATA blocks
+    blocks: Vec<usize>,
+    /// Index and address of the existing ID this one replaces
+    replaces: Option<(usize, u64)>,
+}
+
+impl BlendFile {
+    /// Copy ID blocks selected by `options` from `other`, along with the DATA
+    /// blocks they reference.
+    ///
+    /// Copied blocks get fresh addresses and their pointers are rewritten to
+    /// match; pointers to anything not copied (other IDs, the ID list
+    /// neighbours) are nulled. Every copied struct must have the same layout
+    /// in both files' DNA.
+    pub fn merge_from(&mut self, other: &BlendFile, options: &MergeOptions) -> Result<MergeReport> {
+        if self.dna.is_empty() || other.dna.is_empty() {
+            return Err(
+                FormatError::DnaError("merging requires both files' DNA".to_string()).into(),
+            );
+        }
+        if self.header.pointer_size != other.header.pointer_size
+            || self.header.endianness != other.header.endianness
+        {
+            return Err(FormatError::DnaError(
+                "can't merge files with different pointer size or endianness".to_string(),
+            )
+            .into());
+        }
+
+        let mut report = MergeReport::default();
+        let existing: HashMap<String, usize> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| Some((id_name(&self.dna, block)?, index)))
+            .collect();
+        let mut taken: HashSet<String> = existing.keys().cloned().collect();
+
+        let other_walker = PointerWalker::new(&other.dna, &other.header);
+        let mut seen = HashSet::new();
+        let mut incoming = Vec::new();
+        for (index, block) in other.blocks.iter().enumerate() {
+            let Some(name) = id_name(&other.dna, block) else {
+                continue;
+            };
+            if !options.selects(&name) {
+                continue;
+            }
+
+            let mut replaces = None;
+            let mut final_name = name.clone();
+            if taken.contains(&name) {
+                match options.collision {
+                    CollisionPolicy::Skip => {
+                        report.skipped.push(name);
+                        continue;
+                    }
+                    CollisionPolicy::Rename => {
+                        let max_len = name_capacity(&self.dna).saturating_sub(1);
+                        final_name = free_name(&name, max_len, &taken).ok_or_else(|| {
+                            FormatError::InvalidFormat(format!("no free name for {name}"))
+                        })?;
+                        report.renamed.push((name, final_name.clone()));
+                    }
+                    CollisionPolicy::Overwrite => {
+                        let index = existing[&name];
+                        replaces = Some((index, self.blocks[index].old_memory_address));
+                        report.overwritten.push(name);
+                    }
+                }
+            }
+
+            let blocks = data_closure(&other_walker, &other.dna, &other.blocks, index, &mut seen);
+            taken.insert(final_name.clone());
+            incoming.push(Incoming {
+                name: final_name,
+                blocks,
+                replaces,
+            });
+        }
+
+        if let Some(policy) = &options.name_policy {
+            let capacity = Some(name_capacity(&self.dna).saturating_sub(1));
+            reject(
+                incoming
+                    .iter()
+                    .flat_map(|item| policy.check_within(&item.name, capacity))
+                    .collect(),
+            )?;
+        }
+
+        // Map every copied struct onto this file's DNA before touching anything
+        let mut sdna_map: HashMap<u32, u32> = HashMap::new();
+        for &index in incoming.iter().flat_map(|i| &i.blocks) {
+            let block = &other.blocks[index];
+            if block_layout(&other.dna, block).is_none() {
+                continue;
+            }
+            if let Entry::Vacant(entry) = sdna_map.entry(block.sdna_index) {
+                entry.insert(matching_struct(&other.dna, &self.dna, block.sdna_index)?);
+            }
+        }
+
+        // Fresh addresses start past everything this file uses, including the
+        // blocks about to be dropped: an overwritten ID keeps its address, and
+        // pointers elsewhere may still hold theirs
+        let mut next_address = self
+            .blocks
+            .iter()
+            .map(|b| b.old_memory_address + b.data.len() as u64)
+            .max()
+            .unwrap_or(0);
+
+        // Drop the IDs being overwritten, and their data
+        let self_walker = PointerWalker::new(&self.dna, &self.header);
+        let mut removed = HashSet::new();
+        for (root, _) in incoming.iter().filter_map(|i| i.replaces) {
+            data_closure(&self_walker, &self.dna, &self.blocks, root, &mut removed);
+        }
+        let mut index = 0;
+        self.blocks.retain(|_| {
+            index += 1;
+            !removed.contains(&(index - 1))
+        });
+
+        let mut mapping: HashMap<u64, u64> = HashMap::new();
+        for item in &incoming {
+            for (position, &index) in item.blocks.iter().enumerate() {
+                let block = &other.blocks[index];
+                let address = match item.replaces {
+                    Some((_, address)) if position == 0 => address,
+                    _ => {
+                        next_address = next_address.next_multiple_of(16).max(16);
+                        let address = next_address;
+                        next_address += block.data.len().max(1) as u64;
+                        address
+                    }
+                };
+                mapping.insert(block.old_memory_address, address);
+            }
+        }
+
+        let mut copied = Vec::new();
+        let mut cleared = 0;
+        let mut map = |address: u64| {
+            let new = mapping.get(&address).copied();
+            if new.is_none() {
+                cleared += 1;
+            }
+            Some(new.unwrap_or(0))
+        };
+        for item in &incoming {
+            for (position, &index) in item.blocks.iter().enumerate() {
+                let mut block = other.blocks[index].clone();
+                block.old_memory_address = mapping[&block.old_memory_address];
+                if let Some(layout) = block_layout(&other.dna, &block) {
+                    other_walker.rewrite(&mut block, &layout.name, &mut map);
+                    block.sdna_index = sdna_map[&block.sdna_index];
+                }
+                if position == 0 {
+                    set_id_name(&self.dna, &mut block, &item.name);
+                } else {
+                    report.data_blocks += 1;
+                }
+                copied.push(block);
+            }
+            report.copied.push(item.name.clone());
+        }
+        report.pointers_cleared = cleared;
+
+        if !incoming.is_empty() {
+            self.blocks.extend(copied);
+            self.modified.mark_all();
+        }
+        Ok(report)
+    }
+}
+
+impl MergeOptions {
+    fn selects(&self, full_name: &str) -> bool {
+        let (code, name) = full_name.split_at_checked(2).unwrap_or((full_name, ""));
+        (self.codes.is_empty() || self.codes.iter().any(|c| c == code))
+            && (self.names.is_empty() || self.names.iter().any(|n| n == name))
+    }
+}
+
+/// `root` followed by every DATA block reachable from it through pointers,
+/// skipping blocks already in `seen`
+fn data_closure(
+    walker: &PointerWalker,
+    dna: &Dna,
+    blocks: &[Block],
+    root: usize,
+    seen: &mut HashSet<usize>,
+) -> Vec<usize> {
+    let by_address: HashMap<u64, usize> = blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| &b.code == b"DATA")
+        .map(|(i, b)| (b.old_memory_address, i))
+        .collect();
+
+    let mut out = vec![root];
+    seen.insert(root);
+    let mut next = 0;
+    while next < out.len() {
+        let block = &blocks[out[next]];
+        next += 1;
+        let Some(layout) = block_layout(dna, block) else {
+            continue;
+        };
+        walker.walk(block, &layout.name, &mut |_, address| {
+            if let Some(&index) = by_address.get(&address) {
+                if seen.insert(index) {
+                    out.push(index);
+                }
+            }
+        });
+    }
+    out
+}
+
+/// Index in `to` of the struct `from` has at `sdna_index`, provided the layouts match
+fn matching_struct(from: &Dna, to: &Dna, sdna_index: u32) -> Result<u32> {
+    let source = from
+        .struct_by_index(sdna_index as usize)
+        .ok_or_else(|| FormatError::DnaError(format!("no struct at SDNA index {sdna_index}")))?;
+    let position = to
+        .structs_by_index
+        .iter()
+        .position(|s| s.name == source.name)
+        .ok_or_else(|| {
+            FormatError::DnaError(format!(
+                "struct {} missing from the target DNA",
+                source.name
+            ))
+        })?;
+    if !same_layout(source, &to.structs_by_index[position]) {
+        return Err(FormatError::DnaError(format!(
+            "struct {} has a different layout in the target DNA",
+            source.name
+        ))
+        .into());
+    }
+    Ok(position as u32)
+}
+
+fn same_layout(a: &DnaStruct, b: &DnaStruct) -> bool {
+    a.size == b.size
+        && a.fields.len() == b.fields.len()
+        && a.fields.iter().zip(&b.fields).all(|(x, y)| {
+            x.name == y.name
+                && x.type_name == y.type_name
+                && x.offset == y.offset
+                && x.size == y.size
+        })
+}
+
+/// Byte range of `ID.name` within `block`, when it holds an ID
+fn name_range(dna: &Dna, block: &Block) -> Option<std::ops::Range<usize>> {
+    let layout = block_layout(dna, block)?;
+    let offset = id_offset(dna, &layout.name)?;
+    let name_field = dna.get_struct("ID")?.field("name")?;
+    let start = offset + name_field.offset?;
+    Some(start..start + name_field.size)
+}
+
+pub(crate) fn name_capacity(dna: &Dna) -> usize {
+    dna.get_struct("ID")
+        .and_then(|id| id.field("name"))
+        .map_or(0, |field| field.size)
+}
+
+pub(crate) fn id_name(dna: &Dna, block: &Block) -> Option<String> {
+    let raw = block.data.get(name_range(dna, block)?)?;
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    Some(String::from_utf8_lossy(&raw[..end]).into_owned()).filter(|name| name.len() > 2)
+}
+
+pub(crate) fn set_id_name(dna: &Dna, block: &mut Block, name: &str) {
+    let Some(range) = name_range(dna, block) else {
+        return;
+    };
+    let field = &mut block.data[range];
+    field.fill(0);
+    let name = truncate_at_char(name, field.len() - 1);
+    field[..name.len()].copy_from_slice(name.as_bytes());
+}
+
+/// First `name.NNN` not in `taken` that fits in `max_len` bytes, cutting
+/// `name` short to make room for the suffix
+pub(crate) fn free_name(name: &str, max_len: usize, taken: &HashSet<String>) -> Option<String> {
+    let base = truncate_at_char(name, max_len.checked_sub(".NNN".len())?);
+    (1..1000)
+        .map(|n| format!("{base}.{n:03}"))
+        .find(|candidate| !taken.contains(candidate))
+}
+
+/// The longest prefix of `text` within `max_len` bytes that ends on a char
+/// boundary
+fn truncate_at_char(text: &str, max_len: usize) -> &str {
+    let mut end = text.len().min(max_len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
@@ -0,0 +1,152 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::index::{fnv1a, FNV_OFFSET};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+/// Bookkeeping behind `BlendFile::modified_blocks`.
+///
+/// Blocks handed out by the `_mut` getters are fingerprinted first, so edits
+/// made through them are noticed without every setter reporting back; edits
+/// the file makes itself (remaps, conversions) mark blocks directly.
+#[derive(Debug, Clone, Default)]
+pub struct ModifiedBlocks {
+    marked: BTreeSet<usize>,
+    /// Hash of a block's data when it was first handed out mutably
+    snapshots: HashMap<usize, u64>,
+    /// The header changed, so every block is rewritten
+    whole_file: bool,
+    /// Set by `keep_originals`; survives `clear`
+    keep_originals: bool,
+    /// A block's data before its first change since open or the last save
+    originals: HashMap<usize, Vec<u8>>,
+}
+
+impl ModifiedBlocks {
+    pub(crate) fn watch(&mut self, index: usize, data: &[u8]) {
+        self.snapshots
+            .entry(index)
+            .or_insert_with(|| fnv1a(FNV_OFFSET, data));
+        // The block may be edited through the handed-out reference, and then
+        // it's too late to copy it
+        self.keep_original(index, || data.to_vec());
+    }
+
+    pub(crate) fn keeps_originals(&self) -> bool {
+        self.keep_originals
+    }
+
+    /// Store the data a block held before it first changed; later calls for
+    /// the same block are ignored
+    pub(crate) fn keep_original(&mut self, index: usize, data: impl FnOnce() -> Vec<u8>) {
+        if self.keep_originals {
+            self.originals.entry(index).or_insert_with(data);
+        }
+    }
+
+    /// Drop copies taken for blocks handed out mutably but left unchanged
+    pub(crate) fn drop_unchanged_originals(&mut self, blocks: &[Block]) {
+        if self.originals.is_empty() || self.whole_file {
+            return;
+        }
+        let (marked, snapshots) = (&self.marked, &self.snapshots);
+        self.originals.retain(|index, _| {
+            marked.contains(index)
+                || snapshots.get(index).is_none_or(|&hash| {
+                    blocks
+                        .get(*index)
+                        .is_none_or(|block| fnv1a(FNV_OFFSET, &block.data) != hash)
+                })
+        });
+    }
+
+    pub(crate) fn mark(&mut self, index: usize) {
+        self.marked.insert(index);
+    }
+
+    pub(crate) fn mark_all(&mut self) {
+        self.whole_file = true;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self {
+            keep_originals: self.keep_originals,
+            ..Self::default()
+        };
+    }
+
+    pub(crate) fn set_keep_originals(&mut self, keep: bool) {
+        self.keep_originals = keep;
+        if !keep {
+            self.originals.clear();
+        }
+    }
+}
+
+impl BlendFile {
+    /// Indices into `blocks` of every block changed since the file was opened
+    /// or last saved
+    pub fn modified_blocks(&self) -> BTreeSet<usize> {
+        let modified = &self.modified;
+        if modified.whole_file {
+            return (0..self.blocks.len()).collect();
+        }
+        let mut out = modified.marked.clone();
+        out.extend(modified.snapshots.iter().filter_map(|(&index, &hash)| {
+            let block = self.blocks.get(index)?;
+            (fnv1a(FNV_OFFSET, &block.data) != hash).then_some(index)
+        }));
+        out
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.modified_blocks().is_empty()
+    }
+
+    /// Record a change made by editing `blocks` directly, which the file can't observe
+    pub fn mark_modified(&mut self, index: usize) {
+        self.modified.mark(index);
+    }
+
+    /// Data block `index` held before its first change since the file was
+    /// opened or last saved. Only kept when the file was opened with
+    /// `keep_originals`; `None` for unchanged blocks.
+    ///
+    /// Edits made by writing to `blocks` directly happen before the file can
+    /// copy anything, so they have no original.
+    pub fn original_data(&self, index: usize) -> Option<&[u8]> {
+        let original = self.modified.originals.get(&index)?;
+        self.modified_blocks()
+            .contains(&index)
+            .then_some(original.as_slice())
+    }
+
+    /// Byte ranges where block `index` differs from its original, with a
+    /// length change reported as a range past the shorter of the two.
+    /// `None` when `original_data` is.
+    pub fn changed_ranges(&self, index: usize) -> Option<Vec<Range<usize>>> {
+        let original = self.original_data(index)?;
+        let current = &self.blocks.get(index)?.data;
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (offset, (a, b)) in original.iter().zip(current).enumerate() {
+            if a == b {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end == offset => last.end += 1,
+                _ => ranges.push(offset..offset + 1),
+            }
+        }
+        let (shorter, longer) = (
+            original.len().min(current.len()),
+            original.len().max(current.len()),
+        );
+        if shorter != longer {
+            match ranges.last_mut() {
+                Some(last) if last.end == shorter => last.end = longer,
+                _ => ranges.push(shorter..longer),
+            }
+        }
+        Some(ranges)
+    }
+}
@@ -0,0 +1,254 @@
+//! Rules for datablock names, checked before renames and merges change
+//! anything, and by `lint_names` for names already in a file. Names here are
+//! full ID names with their two-letter code ("OBhero"); length and charset
+//! apply to the part after the code, the name Blender shows.
+
+use crate::blend_file::BlendFile;
+use crate::error::{BlendFileError, FormatError, Result};
+use crate::merge::{free_name, id_name, name_capacity, set_id_name, CollisionPolicy};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Characters a name may use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameCharset {
+    #[default]
+    Any,
+    /// Printable ASCII, space included
+    Ascii,
+    /// ASCII letters, digits, `_`, and `.` for Blender's `.001` suffixes
+    Identifier,
+}
+
+impl NameCharset {
+    pub fn allows(self, c: char) -> bool {
+        match self {
+            NameCharset::Any => true,
+            NameCharset::Ascii => c == ' ' || c.is_ascii_graphic(),
+            NameCharset::Identifier => c.is_ascii_alphanumeric() || c == '_' || c == '.',
+        }
+    }
+}
+
+impl fmt::Display for NameCharset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NameCharset::Any => "any",
+            NameCharset::Ascii => "ascii",
+            NameCharset::Identifier => "identifier",
+        })
+    }
+}
+
+impl FromStr for NameCharset {
+    type Err = BlendFileError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "any" => Ok(NameCharset::Any),
+            "ascii" => Ok(NameCharset::Ascii),
+            "identifier" => Ok(NameCharset::Identifier),
+            _ => Err(FormatError::ParseError(format!(
+                "unknown name charset '{s}' (expected any, ascii or identifier)"
+            ))
+            .into()),
+        }
+    }
+}
+
+/// What names `rename_datablock`, `merge_from` (through
+/// `MergeOptions::with_name_policy`) and `lint_names` accept
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NamePolicy {
+    /// Longest name in bytes, not counting the ID code; the DNA's `ID.name`
+    /// size applies either way
+    pub max_len: Option<usize>,
+    pub charset: NameCharset,
+    /// What a rename or merge does when the name is taken. Renames never
+    /// remove another ID, so `Overwrite` rejects them like `Skip`.
+    pub collision: CollisionPolicy,
+}
+
+impl NamePolicy {
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    pub fn with_charset(mut self, charset: NameCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn with_collision(mut self, collision: CollisionPolicy) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// Every way `name` breaks the length and charset rules
+    pub fn check(&self, name: &str) -> Vec<NameViolation> {
+        self.check_within(name, None)
+    }
+
+    /// `check`, also holding the name to `capacity` bytes including the code
+    pub(crate) fn check_within(&self, name: &str, capacity: Option<usize>) -> Vec<NameViolation> {
+        let shown = name.get(2..).unwrap_or_default();
+        let violation = |problem| NameViolation {
+            name: name.to_string(),
+            problem,
+        };
+        let mut violations = Vec::new();
+        if shown.is_empty() {
+            violations.push(violation(NameProblem::Empty));
+        }
+        let max = [self.max_len, capacity.map(|c| c.saturating_sub(2))]
+            .into_iter()
+            .flatten()
+            .min();
+        if let Some(max) = max.filter(|&max| shown.len() > max) {
+            violations.push(violation(NameProblem::TooLong {
+                len: shown.len(),
+                max,
+            }));
+        }
+        let mut characters: Vec<char> = Vec::new();
+        for c in shown.chars().filter(|&c| !self.charset.allows(c)) {
+            if !characters.contains(&c) {
+                characters.push(c);
+            }
+        }
+        if !characters.is_empty() {
+            violations.push(violation(NameProblem::Charset {
+                charset: self.charset,
+                characters,
+            }));
+        }
+        violations
+    }
+}
+
+/// A name and one thing wrong with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NameViolation {
+    pub name: String,
+    pub problem: NameProblem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NameProblem {
+    /// Nothing after the ID code
+    Empty,
+    /// Bytes after the ID code, and the most allowed
+    TooLong { len: usize, max: usize },
+    /// Characters the charset doesn't allow, each listed once
+    Charset {
+        charset: NameCharset,
+        characters: Vec<char>,
+    },
+    /// Another ID already has the name
+    Taken,
+}
+
+impl fmt::Display for NameViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        match &self.problem {
+            NameProblem::Empty => write!(f, "empty name"),
+            NameProblem::TooLong { len, max } => {
+                write!(f, "{len} bytes, longer than {max}")
+            }
+            NameProblem::Charset {
+                charset,
+                characters,
+            } => {
+                let listed: Vec<String> = characters.iter().map(|c| format!("{c:?}")).collect();
+                write!(
+                    f,
+                    "{} not allowed by the {charset} charset",
+                    listed.join(", ")
+                )
+            }
+            NameProblem::Taken => write!(f, "already taken"),
+        }
+    }
+}
+
+/// `InvalidNames` carrying every violation, or `Ok` when there are none
+pub(crate) fn reject(violations: Vec<NameViolation>) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(BlendFileError::InvalidNames(violations))
+}
+
+impl BlendFile {
+    /// Rename the ID `name` to `new_name`, both with their ID code, checking
+    /// `new_name` against `policy` first. Returns the name given, which
+    /// differs from `new_name` when `CollisionPolicy::Rename` picked a free
+    /// `.001` suffix. Every violation is reported at once and nothing
+    /// changes unless there are none.
+    pub fn rename_datablock(
+        &mut self,
+        name: &str,
+        new_name: &str,
+        policy: &NamePolicy,
+    ) -> Result<String> {
+        let index = self
+            .blocks
+            .iter()
+            .position(|block| id_name(&self.dna, block).as_deref() == Some(name))
+            .ok_or_else(|| FormatError::InvalidFormat(format!("no ID named {name}")))?;
+        if new_name.get(..2) != name.get(..2) {
+            return Err(FormatError::InvalidFormat(format!(
+                "{new_name} doesn't keep the ID code of {name}"
+            ))
+            .into());
+        }
+
+        let taken: HashSet<String> = self
+            .blocks
+            .iter()
+            .filter_map(|block| id_name(&self.dna, block))
+            .filter(|other| other != name)
+            .collect();
+        let capacity = name_capacity(&self.dna).saturating_sub(1);
+        let mut violations = Vec::new();
+        let mut final_name = new_name.to_string();
+        if taken.contains(new_name) {
+            let free = match policy.collision {
+                CollisionPolicy::Rename => free_name(new_name, capacity, &taken),
+                CollisionPolicy::Skip | CollisionPolicy::Overwrite => None,
+            };
+            match free {
+                Some(free) => final_name = free,
+                None => violations.push(NameViolation {
+                    name: new_name.to_string(),
+                    problem: NameProblem::Taken,
+                }),
+            }
+        }
+        violations.extend(policy.check_within(&final_name, Some(capacity)));
+        reject(violations)?;
+
+        let block = &mut self.blocks[index];
+        self.modified.keep_original(index, || block.data.clone());
+        set_id_name(&self.dna, block, &final_name);
+        self.modified.mark(index);
+        Ok(final_name)
+    }
+
+    /// Names of IDs in the file that break `policy`'s length and charset
+    /// rules; nothing is changed
+    pub fn lint_names(&self, policy: &NamePolicy) -> Vec<NameViolation> {
+        self.blocks
+            .iter()
+            .filter_map(|block| id_name(&self.dna, block))
+            .flat_map(|name| policy.check(&name))
+            .collect()
+    }
+}
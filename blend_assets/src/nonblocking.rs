@@ -0,0 +1,71 @@
+//! Async wrappers for tokio services.
+//!
+//! The reader itself stays blocking; each call runs on tokio's blocking pool
+//! so parsing a large file never stalls the runtime's worker threads.
+//! Dropping a returned future before it completes cancels an open at the
+//! next block boundary instead of letting it run on unobserved.
+
+use crate::blend_file::{BlendFile, OpenOptions};
+use crate::cancel::Cancellation;
+use crate::error::{BlendFileError, Result};
+use crate::library_link::LibraryLink;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Cancels the blocking work when the future awaiting it is dropped
+struct CancelOnDrop(Cancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Run `f` on the blocking pool, re-raising its panics in the caller
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        // The runtime is shutting down
+        Err(_) => Err(BlendFileError::Cancelled),
+    }
+}
+
+impl BlendFile {
+    /// `open` on the blocking pool
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_options_async(path, OpenOptions::default()).await
+    }
+
+    /// `open_with_options` on the blocking pool. The open also stops when
+    /// `options.cancellation` is cancelled.
+    pub async fn open_with_options_async<P: AsRef<Path>>(
+        path: P,
+        mut options: OpenOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let cancellation = options.cancellation.get_or_insert_with(Cancellation::new);
+        let mut guard = CancelOnDrop(cancellation.clone());
+        let result = run_blocking(move || Self::open_with_options(path, &options)).await;
+        // Finished, so a later drop has nothing to cancel
+        guard.0 = Cancellation::new();
+        result
+    }
+
+    /// `get_library_links` on the blocking pool
+    pub async fn get_library_links_async(self: &Arc<Self>) -> Result<Vec<LibraryLink>> {
+        let blend_file = Arc::clone(self);
+        run_blocking(move || blend_file.get_library_links()).await
+    }
+
+    /// Links with the filesystem status of each target attached, as
+    /// `get_library_links_with_stat`, on the blocking pool
+    pub async fn trace_assets_async(self: &Arc<Self>) -> Result<Vec<LibraryLink>> {
+        let blend_file = Arc::clone(self);
+        run_blocking(move || blend_file.get_library_links_with_stat()).await
+    }
+}
@@ -0,0 +1,147 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::convert::block_layout;
+use crate::error::{FormatError, Result};
+use crate::library_usage::id_offset;
+use crate::pointers::PointerWalker;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// The datablock a DATA block belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnerRef {
+    /// ID code of the owning datablock ("ME", "IM", ...), or the block code
+    /// when the owner isn't an ID
+    pub code: String,
+    /// Owner name without the ID code prefix; empty when the owner isn't an ID
+    pub name: String,
+    /// Pointer field holding the DATA block's address, as a path within the
+    /// immediate owner's struct (e.g. `mvert` or `ldata.layers`)
+    pub field: String,
+}
+
+/// One row of `SizeStats`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockSize {
+    pub code: String,
+    pub address: u64,
+    pub size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<OwnerRef>,
+}
+
+/// Where a file's bytes go
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeStats {
+    /// Largest blocks first, DATA blocks attributed to their owners
+    pub largest: Vec<BlockSize>,
+    /// DATA blocks nothing points at, largest first
+    pub orphans: Vec<BlockSize>,
+}
+
+impl BlendFile {
+    /// Map each owned DATA block's address to the datablock whose pointer references it.
+    ///
+    /// Pointer fields of every DNA-typed block are scanned; the first pointer
+    /// found for an address wins. DATA blocks owned through other DATA blocks
+    /// (e.g. a custom data layer array) are attributed to the datablock at the
+    /// top of the chain, keeping the immediate pointer field.
+    pub fn data_ownership(&self) -> Result<HashMap<u64, OwnerRef>> {
+        if self.dna.is_empty() {
+            return Err(FormatError::DnaError(
+                "data ownership requires the file's DNA".to_string(),
+            )
+            .into());
+        }
+
+        let data_blocks: HashSet<u64> = self
+            .blocks
+            .iter()
+            .filter(|b| &b.code == b"DATA")
+            .map(|b| b.old_memory_address)
+            .collect();
+        let walker = PointerWalker::new(&self.dna, &self.header);
+
+        // DATA address -> (index of the owning block, pointer field)
+        let mut direct: HashMap<u64, (usize, String)> = HashMap::new();
+        for (index, block) in self.blocks.iter().enumerate() {
+            let Some(layout) = block_layout(&self.dna, block) else {
+                continue;
+            };
+            walker.walk(block, &layout.name, &mut |field, address| {
+                if address != block.old_memory_address && data_blocks.contains(&address) {
+                    direct
+                        .entry(address)
+                        .or_insert_with(|| (index, field.to_string()));
+                }
+            });
+        }
+
+        let mut ownership = HashMap::new();
+        for (&address, (owner_index, field)) in &direct {
+            // Climb DATA owners until an ID block; the bound guards against cycles
+            let mut top = *owner_index;
+            for _ in 0..direct.len() {
+                let owner = &self.blocks[top];
+                if &owner.code != b"DATA" {
+                    break;
+                }
+                match direct.get(&owner.old_memory_address) {
+                    Some((next, _)) => top = *next,
+                    None => break,
+                }
+            }
+
+            let (code, name) = self.id_name(&self.blocks[top]).unwrap_or_else(|| {
+                let code = self.blocks[top].get_type_name();
+                (code.trim_end_matches('\0').to_string(), String::new())
+            });
+            ownership.insert(
+                address,
+                OwnerRef {
+                    code,
+                    name,
+                    field: field.clone(),
+                },
+            );
+        }
+        Ok(ownership)
+    }
+
+    /// The `top` largest blocks with DATA owners attached, plus every orphaned DATA block
+    pub fn size_stats(&self, top: usize) -> Result<SizeStats> {
+        let ownership = self.data_ownership()?;
+        let row = |block: &Block| BlockSize {
+            code: block.get_type_name().trim_end_matches('\0').to_string(),
+            address: block.old_memory_address,
+            size: block.size,
+            owner: ownership.get(&block.old_memory_address).cloned(),
+        };
+
+        let mut by_size: Vec<&Block> = self.blocks.iter().collect();
+        by_size.sort_by_key(|b| std::cmp::Reverse(b.size));
+        let orphans = by_size
+            .iter()
+            .filter(|b| &b.code == b"DATA" && !ownership.contains_key(&b.old_memory_address))
+            .map(|b| row(b))
+            .collect();
+
+        Ok(SizeStats {
+            largest: by_size.iter().take(top).map(|b| row(b)).collect(),
+            orphans,
+        })
+    }
+
+    /// ID code and name of a block whose struct embeds an ID
+    fn id_name(&self, block: &Block) -> Option<(String, String)> {
+        let layout = block_layout(&self.dna, block)?;
+        let offset = id_offset(&self.dna, &layout.name)?;
+        let name_field = self.dna.get_struct("ID")?.field("name")?;
+        let start = offset + name_field.offset?;
+        let raw = block.data.get(start..start + name_field.size)?;
+        let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+        let full = String::from_utf8_lossy(&raw[..end]);
+        let (code, name) = full.split_at_checked(2).unwrap_or((&full, ""));
+        Some((code.to_string(), name.to_string()))
+    }
+}
@@ -0,0 +1,69 @@
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::convert::read_uint;
+use crate::error::{FormatError, Result};
+use crate::library_link::{is_packed_library, LinkExtractionOptions};
+use crate::scene::locate;
+use std::path::Path;
+
+impl BlendFile {
+    /// The .blend stored inside library `name`, which may be given with or
+    /// without its `LI` prefix.
+    ///
+    /// Follows `Library.packedfile` to the PackedFile block and its `data`
+    /// pointer to the block holding the file's bytes.
+    pub fn packed_library_data(&self, name: &str) -> Result<&[u8]> {
+        let extractor = self.link_extractor();
+        let options = LinkExtractionOptions::default().with_placeholders(true);
+        let mut library = None;
+        for block in self.get_library_blocks()? {
+            let block_name = extractor
+                .extract_block_link(block, &self.dna, &options)?
+                .and_then(|link| link.block_name);
+            if block_name.is_some_and(|n| n == name || n.get(2..) == Some(name)) {
+                library = Some(block);
+                break;
+            }
+        }
+        let library =
+            library.ok_or_else(|| FormatError::BlockError(format!("no library named {name}")))?;
+        if !is_packed_library(library, &self.dna) {
+            return Err(FormatError::BlockError(format!("library {name} is not packed")).into());
+        }
+
+        let packed_file = self.block_at(self.read_field(library, "Library", "packedfile")?)?;
+        let size = self.read_field(packed_file, "PackedFile", "size")? as usize;
+        let data = self.block_at(self.read_field(packed_file, "PackedFile", "data")?)?;
+        data.data.get(..size).ok_or_else(|| {
+            FormatError::BlockError(format!(
+                "packed data of library {name} holds {} of {size} bytes",
+                data.data.len()
+            ))
+            .into()
+        })
+    }
+
+    /// Write the .blend packed into library `name` to `out_path`
+    pub fn extract_packed_library<P: AsRef<Path>>(&self, name: &str, out_path: P) -> Result<()> {
+        std::fs::write(out_path, self.packed_library_data(name)?)?;
+        Ok(())
+    }
+
+    fn block_at(&self, address: u64) -> Result<&Block> {
+        self.blocks
+            .iter()
+            .find(|b| b.old_memory_address == address)
+            .ok_or_else(|| {
+                FormatError::BlockError(format!("no block at address {address:#x}")).into()
+            })
+    }
+
+    /// Read an integer or pointer field of the struct stored at the start of `block`
+    fn read_field(&self, block: &Block, struct_name: &str, field_name: &str) -> Result<u64> {
+        let (offset, field) = locate(&self.dna, struct_name, &[field_name])?;
+        let bytes = block.data.get(offset..offset + field.size).ok_or_else(|| {
+            FormatError::BlockError(format!("block shorter than {struct_name}.{field_name}"))
+        })?;
+        Ok(read_uint(bytes, self.header.endianness))
+    }
+}
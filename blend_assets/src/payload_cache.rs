@@ -0,0 +1,160 @@
+//! Decompressed block payloads kept beside a block index, so reopening a large
+//! file serves the chosen blocks (mesh `DATA`, say) without reading them from
+//! the blend again. Entries are named by a hash of their contents; the index
+//! records each block's hash and is only trusted while the file is unchanged.
+
+use crate::block::{Block, BlockCode};
+use crate::error::Result;
+use crate::index::{fnv1a, FNV_OFFSET};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Which payloads `BlendFile::write_index_with_cache` keeps, and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheOptions {
+    /// Block codes whose payloads are cached, zero-padded to four bytes
+    pub cache_payloads_for: Vec<BlockCode>,
+    /// Store entries zstd-compressed
+    pub compress: bool,
+    /// Total bytes of entries to keep; the least recently used go first
+    pub max_size: u64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            cache_payloads_for: Vec::new(),
+            compress: true,
+            max_size: 256 << 20,
+        }
+    }
+}
+
+impl CacheOptions {
+    /// Also cache blocks with `code` ("DATA", "ME", ...)
+    pub fn with_payloads_for(mut self, code: &[u8]) -> Self {
+        let mut padded = [0u8; 4];
+        let len = code.len().min(4);
+        padded[..len].copy_from_slice(&code[..len]);
+        self.cache_payloads_for.push(padded);
+        self
+    }
+
+    pub fn with_compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub(crate) fn caches(&self, block: &Block) -> bool {
+        self.cache_payloads_for.contains(&block.code)
+    }
+}
+
+/// A directory of payload entries, `<hash>-<size>.zst` or `.bin`
+pub(crate) struct PayloadCache<'a> {
+    dir: &'a Path,
+    compress: bool,
+}
+
+impl<'a> PayloadCache<'a> {
+    pub(crate) fn new(dir: &'a Path, compress: bool) -> Self {
+        Self { dir, compress }
+    }
+
+    fn entry_path(&self, hash: u64, size: usize, compressed: bool) -> PathBuf {
+        let extension = if compressed { "zst" } else { "bin" };
+        self.dir.join(format!("{hash:016x}-{size}.{extension}"))
+    }
+
+    /// Keep `data` under `hash`; an existing entry only counts as used
+    pub(crate) fn store(&self, hash: u64, data: &[u8]) -> Result<()> {
+        let path = self.entry_path(hash, data.len(), self.compress);
+        if path.exists() {
+            touch(&path);
+            return Ok(());
+        }
+        fs::create_dir_all(self.dir)?;
+        // Written aside and renamed, so readers never see half an entry
+        let mut temp = tempfile::NamedTempFile::new_in(self.dir)?;
+        if self.compress {
+            zstd::stream::copy_encode(data, &mut temp, 0)?;
+        } else {
+            temp.write_all(data)?;
+        }
+        temp.persist(&path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// The payload stored under `hash`, if an entry exists and its contents
+    /// still hash to it
+    pub(crate) fn load(&self, hash: u64, size: usize) -> Option<Vec<u8>> {
+        for compressed in [true, false] {
+            let path = self.entry_path(hash, size, compressed);
+            let Ok(bytes) = fs::read(&path) else {
+                continue;
+            };
+            let data = if compressed {
+                zstd::decode_all(bytes.as_slice()).ok()?
+            } else {
+                bytes
+            };
+            if data.len() == size && fnv1a(FNV_OFFSET, &data) == hash {
+                touch(&path);
+                return Some(data);
+            }
+        }
+        None
+    }
+
+    /// Remove the least recently used entries until the rest take at most
+    /// `max_size` bytes
+    pub(crate) fn evict(&self, max_size: u64) -> Result<()> {
+        let listing = match fs::read_dir(self.dir) {
+            Ok(listing) => listing,
+            // Nothing has been stored yet
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        for entry in listing {
+            let entry = entry?;
+            let path = entry.path();
+            let is_entry = matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("zst" | "bin")
+            );
+            if !is_entry {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+        entries.sort();
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in entries {
+            if total <= max_size {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Mark an entry as just used; failing only costs it its place in the LRU order
+fn touch(path: &Path) {
+    let _ = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
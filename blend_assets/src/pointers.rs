@@ -0,0 +1,149 @@
+use crate::block::Block;
+use crate::convert::read_uint;
+use crate::dna::Dna;
+use crate::header::{Endianness, Header};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Finds pointer fields in DNA-typed data: plain pointers, pointer arrays and
+/// pointers inside embedded structs (ListBase included)
+pub(crate) struct PointerWalker<'a> {
+    dna: &'a Dna,
+    pointer_size: usize,
+    pub(crate) endianness: Endianness,
+    /// Slots per struct name, built on first use
+    slots: RefCell<HashMap<String, Rc<[PointerSlot]>>>,
+}
+
+/// A pointer within one struct instance
+pub(crate) struct PointerSlot {
+    pub(crate) offset: usize,
+    /// Field path, e.g. `mvert` or `ldata.layers`
+    pub(crate) path: String,
+}
+
+impl<'a> PointerWalker<'a> {
+    pub(crate) fn new(dna: &'a Dna, header: &Header) -> Self {
+        Self {
+            dna,
+            pointer_size: header.pointer_size.bytes(),
+            endianness: header.endianness,
+            slots: RefCell::default(),
+        }
+    }
+
+    /// Every pointer in one instance of `struct_name`, in field order
+    pub(crate) fn slots(&self, struct_name: &str) -> Rc<[PointerSlot]> {
+        if let Some(slots) = self.slots.borrow().get(struct_name) {
+            return slots.clone();
+        }
+        let mut slots = Vec::new();
+        self.push_slots(struct_name, 0, "", &mut slots);
+        let slots: Rc<[PointerSlot]> = slots.into();
+        self.slots
+            .borrow_mut()
+            .insert(struct_name.to_string(), slots.clone());
+        slots
+    }
+
+    fn push_slots(&self, struct_name: &str, base: usize, prefix: &str, out: &mut Vec<PointerSlot>) {
+        let Some(layout) = self.dna.get_struct(struct_name) else {
+            return;
+        };
+        for field in &layout.fields {
+            // Nothing past a field of unknown size can be located
+            let Some(offset) = field.offset else {
+                break;
+            };
+            let path = format!("{prefix}{}", field.dna_name.name_only());
+            if field.dna_name.is_pointer() {
+                let count = field.size / self.pointer_size;
+                out.extend((0..count).map(|i| PointerSlot {
+                    offset: base + offset + i * self.pointer_size,
+                    path: path.clone(),
+                }));
+            } else if self.dna.get_struct(&field.type_name).is_some() {
+                let count = field.dna_name.array_size().max(1);
+                let element_size = (field.size / count).max(1);
+                let prefix = format!("{path}.");
+                for i in 0..count {
+                    let element = base + offset + i * element_size;
+                    self.push_slots(&field.type_name, element, &prefix, out);
+                }
+            }
+        }
+    }
+
+    /// Add every non-null pointer held by `block`'s struct instances to `out`
+    pub(crate) fn collect(&self, block: &Block, struct_name: &str, out: &mut HashSet<u64>) {
+        self.walk(block, struct_name, &mut |_, address| {
+            out.insert(address);
+        });
+    }
+
+    /// Call `visit` with the field path and value of every non-null pointer
+    /// held by `block`'s struct instances
+    pub(crate) fn walk(&self, block: &Block, struct_name: &str, visit: &mut dyn FnMut(&str, u64)) {
+        let Some(size) = self.dna.get_struct(struct_name).map(|s| s.size) else {
+            return;
+        };
+        let slots = self.slots(struct_name);
+        for instance in block.data.chunks_exact(size) {
+            for slot in slots.iter() {
+                let Some(bytes) = instance.get(slot.offset..slot.offset + self.pointer_size) else {
+                    continue;
+                };
+                let address = read_uint(bytes, self.endianness);
+                if address != 0 {
+                    visit(&slot.path, address);
+                }
+            }
+        }
+    }
+
+    /// Replace each non-null pointer in `block`'s struct instances for which
+    /// `map` returns a new value; returns how many pointers changed
+    pub(crate) fn rewrite(
+        &self,
+        block: &mut Block,
+        struct_name: &str,
+        map: &mut dyn FnMut(u64) -> Option<u64>,
+    ) -> usize {
+        let Some(size) = self.dna.get_struct(struct_name).map(|s| s.size) else {
+            return 0;
+        };
+        let slots = self.slots(struct_name);
+        let mut changed = 0;
+        for instance in block.data.chunks_exact_mut(size) {
+            for slot in slots.iter() {
+                let Some(bytes) = instance.get_mut(slot.offset..slot.offset + self.pointer_size)
+                else {
+                    continue;
+                };
+                let address = read_uint(bytes, self.endianness);
+                if address == 0 {
+                    continue;
+                }
+                if let Some(new) = map(address).filter(|&new| new != address) {
+                    write_uint_at(bytes, new, self.endianness);
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+}
+
+pub(crate) fn write_uint_at(bytes: &mut [u8], value: u64, endianness: Endianness) {
+    let le = value.to_le_bytes();
+    let size = bytes.len();
+    match endianness {
+        Endianness::Little => bytes.copy_from_slice(&le[..size]),
+        Endianness::Big => {
+            for (dst, src) in bytes.iter_mut().zip(le[..size].iter().rev()) {
+                *dst = *src;
+            }
+        }
+    }
+}
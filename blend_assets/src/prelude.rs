@@ -0,0 +1,24 @@
+//! The types most programs need, for a single glob import:
+//!
+//! ```
+//! use blend_assets::prelude::*;
+//!
+//! fn image_paths(path: &str) -> Result<Vec<String>> {
+//!     let blend_file = BlendFile::open(path)?;
+//!     let links: Vec<LibraryLink> = blend_file.get_library_links()?;
+//!     Ok(links.into_iter().map(|link| link.path).collect())
+//! }
+//! ```
+//!
+//! Opening is configured through [`OpenOptions`], or [`ReadWriteOptions`] for
+//! files opened to be modified and saved.
+
+pub use crate::blend_file::{
+    BlendFile, Compression, CompressionSettings, LockBehavior, OpenOptions, ReadWriteOptions,
+    SaveOptions, SaveOutcome, ValidationMode,
+};
+pub use crate::block::{Block, BlockCode};
+pub use crate::dna::{Dna, DnaField, DnaStruct};
+pub use crate::error::{BlendFileError, FormatError, Result};
+pub use crate::header::{Endianness, Header, PointerSize};
+pub use crate::library_link::{LibraryLink, LinkExtractionOptions};
@@ -0,0 +1,377 @@
+//! Filter expressions over block headers, as in
+//! `code == 'IM' && size > 1048576` or `name =~ 'hero_.*'`.
+//!
+//! A query compares a field with a literal; comparisons combine with `&&`,
+//! which binds tighter than `||`, and parentheses. Fields:
+//!
+//! - `code`: block code without padding, e.g. `IM` or `DATA`
+//! - `name`: ID name without its two-letter prefix; empty for other blocks
+//! - `struct`: DNA struct of the payload; empty for raw data
+//! - `size`, `count`, `sdna`: header values
+//!
+//! Numbers compare with `==`, `!=`, `<` and `>`, and are written in decimal
+//! or as `0x` hex. Strings compare with `==` and `!=`, or match a regex
+//! anywhere in the value with `=~`. String literals take single or double
+//! quotes; a backslash escapes the quote or another backslash and is kept
+//! before anything else, so `'\d+'` reaches the regex unchanged.
+
+use crate::blend_file::BlendFile;
+use crate::block::Block;
+use crate::block_code::raw_code;
+use crate::debug::id_name;
+use crate::dna::Dna;
+use crate::error::{BlendFileError, Result};
+use regex::Regex;
+
+/// A parsed query, reusable across blocks and files
+#[derive(Debug, Clone)]
+pub struct Query {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Number(NumberField, NumberOp, u64),
+    Text(TextField, TextTest),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Number(NumberField),
+    Text(TextField),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NumberField {
+    Size,
+    Count,
+    Sdna,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NumberOp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextField {
+    Code,
+    Name,
+    Struct,
+}
+
+#[derive(Debug, Clone)]
+enum TextTest {
+    Eq(String),
+    Ne(String),
+    Match(Regex),
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or()?;
+        let token = parser.peek();
+        if token.kind != TokenKind::End {
+            return Err(invalid(token.offset, "expected '&&', '||' or the end"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether `block` satisfies the query; `dna` supplies names and structs
+    pub fn matches(&self, dna: &Dna, block: &Block) -> bool {
+        self.expr.eval(dna, block)
+    }
+}
+
+impl Expr {
+    fn eval(&self, dna: &Dna, block: &Block) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(dna, block) || b.eval(dna, block),
+            Expr::And(a, b) => a.eval(dna, block) && b.eval(dna, block),
+            Expr::Number(field, op, value) => {
+                let actual = match field {
+                    NumberField::Size => block.size as u64,
+                    NumberField::Count => block.count as u64,
+                    NumberField::Sdna => block.sdna_index as u64,
+                };
+                match op {
+                    NumberOp::Eq => actual == *value,
+                    NumberOp::Ne => actual != *value,
+                    NumberOp::Lt => actual < *value,
+                    NumberOp::Gt => actual > *value,
+                }
+            }
+            Expr::Text(field, test) => {
+                let actual = match field {
+                    TextField::Code => raw_code(&block.code),
+                    TextField::Name => id_name(dna, block).unwrap_or_default(),
+                    TextField::Struct => struct_name(dna, block),
+                };
+                match test {
+                    TextTest::Eq(value) => actual == *value,
+                    TextTest::Ne(value) => actual != *value,
+                    TextTest::Match(regex) => regex.is_match(&actual),
+                }
+            }
+        }
+    }
+}
+
+/// DNA struct of `block`, empty for raw `DATA` and unknown indices
+fn struct_name(dna: &Dna, block: &Block) -> String {
+    block
+        .dna_struct(dna)
+        .map(|s| s.name.clone())
+        .unwrap_or_default()
+}
+
+fn invalid(offset: usize, message: impl Into<String>) -> BlendFileError {
+    BlendFileError::InvalidQuery {
+        offset,
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Str(String),
+    Number(u64),
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Match,
+    And,
+    Or,
+    Open,
+    Close,
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// Character offset of the token's first character
+    offset: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let offset = i;
+        let c = chars[i];
+        let pair = |second: char| chars.get(i + 1) == Some(&second);
+        let kind = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '<' => TokenKind::Lt,
+            '>' => TokenKind::Gt,
+            '=' if pair('=') => TokenKind::Eq,
+            '=' if pair('~') => TokenKind::Match,
+            '!' if pair('=') => TokenKind::Ne,
+            '&' if pair('&') => TokenKind::And,
+            '|' if pair('|') => TokenKind::Or,
+            '\'' | '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(invalid(offset, "unterminated string")),
+                        Some(&q) if q == c => break,
+                        Some('\\') if matches!(chars.get(i + 1), Some(&n) if n == c || n == '\\') =>
+                        {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&other) => {
+                            value.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    offset,
+                });
+                continue;
+            }
+            _ if c.is_ascii_digit() => {
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let text: String = chars[offset..i].iter().collect();
+                let parsed = match text.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => text.parse(),
+                };
+                let value =
+                    parsed.map_err(|_| invalid(offset, format!("invalid number '{text}'")))?;
+                tokens.push(Token {
+                    kind: TokenKind::Number(value),
+                    offset,
+                });
+                continue;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Ident(chars[offset..i].iter().collect()),
+                    offset,
+                });
+                continue;
+            }
+            _ => return Err(invalid(offset, format!("unexpected character '{c}'"))),
+        };
+        i += match kind {
+            TokenKind::Open | TokenKind::Close | TokenKind::Lt | TokenKind::Gt => 1,
+            _ => 2,
+        };
+        tokens.push(Token { kind, offset });
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        offset: chars.len(),
+    });
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if token.kind != TokenKind::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek().kind == TokenKind::Or {
+            self.advance();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        while self.peek().kind == TokenKind::And {
+            self.advance();
+            expr = Expr::And(Box::new(expr), Box::new(self.primary()?));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.advance();
+        let name = match token.kind {
+            TokenKind::Open => {
+                let expr = self.or()?;
+                let close = self.advance();
+                if close.kind != TokenKind::Close {
+                    return Err(invalid(close.offset, "expected ')'"));
+                }
+                return Ok(expr);
+            }
+            TokenKind::Ident(name) => name,
+            _ => return Err(invalid(token.offset, "expected a field or '('")),
+        };
+
+        let field = match name.as_str() {
+            "size" => Field::Number(NumberField::Size),
+            "count" => Field::Number(NumberField::Count),
+            "sdna" => Field::Number(NumberField::Sdna),
+            "code" => Field::Text(TextField::Code),
+            "name" => Field::Text(TextField::Name),
+            "struct" => Field::Text(TextField::Struct),
+            _ => {
+                return Err(invalid(
+                    token.offset,
+                    format!("unknown field '{name}' (code, name, struct, size, count, sdna)"),
+                ))
+            }
+        };
+        let op = self.advance();
+        if !matches!(
+            op.kind,
+            TokenKind::Eq | TokenKind::Ne | TokenKind::Lt | TokenKind::Gt | TokenKind::Match
+        ) {
+            return Err(invalid(op.offset, "expected '==', '!=', '<', '>' or '=~'"));
+        }
+        let value = self.advance();
+
+        let field = match field {
+            Field::Number(field) => {
+                let op = match op.kind {
+                    TokenKind::Eq => NumberOp::Eq,
+                    TokenKind::Ne => NumberOp::Ne,
+                    TokenKind::Lt => NumberOp::Lt,
+                    TokenKind::Gt => NumberOp::Gt,
+                    _ => return Err(invalid(op.offset, format!("'{name}' can't match a regex"))),
+                };
+                let TokenKind::Number(value) = value.kind else {
+                    return Err(invalid(
+                        value.offset,
+                        format!("'{name}' compares with a number"),
+                    ));
+                };
+                return Ok(Expr::Number(field, op, value));
+            }
+            Field::Text(field) => field,
+        };
+        let TokenKind::Str(text) = value.kind else {
+            return Err(invalid(
+                value.offset,
+                format!("'{name}' compares with a string"),
+            ));
+        };
+        let test = match op.kind {
+            TokenKind::Eq => TextTest::Eq(text),
+            TokenKind::Ne => TextTest::Ne(text),
+            TokenKind::Match => TextTest::Match(
+                Regex::new(&text)
+                    .map_err(|e| invalid(value.offset, format!("invalid regex: {e}")))?,
+            ),
+            _ => return Err(invalid(op.offset, format!("'{name}' can't be ordered"))),
+        };
+        Ok(Expr::Text(field, test))
+    }
+}
+
+impl BlendFile {
+    /// Blocks matching `expr`, in file order; see the `query` module for the syntax
+    pub fn query_blocks(&self, expr: &str) -> Result<Vec<&Block>> {
+        let query = Query::parse(expr)?;
+        Ok(self
+            .blocks
+            .iter()
+            .filter(|block| query.matches(&self.dna, block))
+            .collect())
+    }
+}
@@ -0,0 +1,136 @@
+use crate::blend_file::BlendFile;
+use crate::convert::block_layout;
+use crate::dna::OffsetTable;
+use crate::error::{FormatError, Result};
+use crate::library_link::filepath_location;
+use crate::pointers::PointerWalker;
+use crate::truncation::{fit_path, TruncationPolicy};
+use std::collections::{HashMap, HashSet};
+
+/// How `remap_addresses_with_options` treats pointers outside the mapping
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RemapOptions {
+    /// Null pointers that hold no block's address, e.g. into blocks that were
+    /// removed. Blender also leaves stale runtime pointers in some fields,
+    /// which this clears as well.
+    pub null_dangling: bool,
+}
+
+impl RemapOptions {
+    pub fn with_null_dangling(mut self, null_dangling: bool) -> Self {
+        self.null_dangling = null_dangling;
+        self
+    }
+}
+
+/// Result of `remap_link_path_with`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathRemap {
+    /// Blocks whose path was rewritten
+    pub blocks: usize,
+    /// One per truncated write
+    pub warnings: Vec<String>,
+}
+
+impl BlendFile {
+    /// Rewrite every pointer field holding an address in `mapping` to the mapped
+    /// address, returning how many fields changed.
+    ///
+    /// Walks all DNA-typed blocks, including pointer arrays and pointers inside
+    /// embedded structs and ListBases. Block addresses themselves are untouched.
+    pub fn remap_addresses(&mut self, mapping: &HashMap<u64, u64>) -> Result<usize> {
+        self.remap_addresses_with_options(mapping, &RemapOptions::default())
+    }
+
+    /// Like `remap_addresses`, optionally nulling dangling pointers
+    pub fn remap_addresses_with_options(
+        &mut self,
+        mapping: &HashMap<u64, u64>,
+        options: &RemapOptions,
+    ) -> Result<usize> {
+        if self.dna.is_empty() {
+            return Err(FormatError::DnaError(
+                "remapping addresses requires the file's DNA".to_string(),
+            )
+            .into());
+        }
+
+        let known: HashSet<u64> = self.blocks.iter().map(|b| b.old_memory_address).collect();
+        let walker = PointerWalker::new(&self.dna, &self.header);
+        let mut map = |address: u64| match mapping.get(&address) {
+            Some(&new) => Some(new),
+            None if options.null_dangling && !known.contains(&address) => Some(0),
+            None => None,
+        };
+
+        let mut changed = 0;
+        for (index, block) in self.blocks.iter_mut().enumerate() {
+            let Some(layout) = block_layout(&self.dna, block) else {
+                continue;
+            };
+            let original = self.modified.keeps_originals().then(|| block.data.clone());
+            let block_changed = walker.rewrite(block, &layout.name, &mut map);
+            if block_changed > 0 {
+                self.modified
+                    .keep_original(index, || original.unwrap_or_default());
+                self.modified.mark(index);
+                changed += block_changed;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Point every link stored as exactly `old` at `new`, returning how many
+    /// blocks changed. Fails without changing anything if `new` doesn't fit
+    /// a block's filepath field.
+    pub fn remap_link_path(&mut self, old: &str, new: &str) -> Result<usize> {
+        Ok(self
+            .remap_link_path_with(old, new, TruncationPolicy::Error)?
+            .blocks)
+    }
+
+    /// Like `remap_link_path`, applying `policy` where `new` doesn't fit.
+    /// Nothing is written if any block's write fails.
+    pub fn remap_link_path_with(
+        &mut self,
+        old: &str,
+        new: &str,
+        policy: TruncationPolicy,
+    ) -> Result<PathRemap> {
+        let mut targets = Vec::new();
+        let mut warnings = Vec::new();
+        let mut offsets = OffsetTable::new(&self.dna);
+        for (index, block) in self.blocks.iter().enumerate() {
+            let Some((offset, size)) = filepath_location(block, &mut offsets) else {
+                continue;
+            };
+            let Some(field) = block
+                .data
+                .get(offset..(offset + size).min(block.data.len()))
+            else {
+                continue;
+            };
+            let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+            if field[..end] != *old.as_bytes() {
+                continue;
+            }
+            let fitted = fit_path(new, field.len(), policy)?;
+            warnings.extend(fitted.warning.map(|w| format!("block {index}: {w}")));
+            targets.push((index, offset..offset + field.len(), fitted.value));
+        }
+
+        for (index, range, value) in &targets {
+            let data = &self.blocks[*index].data;
+            self.modified.keep_original(*index, || data.clone());
+            let field = &mut self.blocks[*index].data[range.clone()];
+            field.fill(0);
+            field[..value.len()].copy_from_slice(value.as_bytes());
+            self.modified.mark(*index);
+        }
+        Ok(PathRemap {
+            blocks: targets.len(),
+            warnings,
+        })
+    }
+}
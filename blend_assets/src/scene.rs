@@ -0,0 +1,274 @@
+use crate::blend_file::BlendFile;
+use crate::bytes::{read_f32_at, read_i16_at, read_i32_at};
+use crate::dna::{Dna, DnaField};
+use crate::error::{BlendFileError, FormatError, Result};
+use crate::header::Endianness;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Name, frame range, frame rate and units of a scene datablock. Settings
+/// the file's DNA has no field for are `None`, or `UnitSystem::Unknown`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceneInfo {
+    /// Scene name without the `SC` ID prefix
+    pub name: String,
+    pub frame_start: i32,
+    pub frame_end: i32,
+    /// Frames per second, `r.frs_sec / r.frs_sec_base`
+    pub fps: Option<f32>,
+    /// `r.frs_sec_base`, 1.001 for NTSC rates such as 24000/1001
+    pub fps_base: Option<f32>,
+    pub unit_system: UnitSystem,
+    /// `unit.scale_length`, the factor from Blender units to displayed ones
+    pub scale_length: Option<f32>,
+}
+
+/// Unit system of a scene, `Scene.unit.system`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    None,
+    Metric,
+    Imperial,
+    /// A value this reader does not know
+    Other(u8),
+    /// The DNA has no `Scene.unit.system`, as in files from before 2.5
+    Unknown,
+}
+
+impl UnitSystem {
+    fn from_raw(value: u8) -> Self {
+        match value {
+            0 => UnitSystem::None,
+            1 => UnitSystem::Metric,
+            2 => UnitSystem::Imperial,
+            other => UnitSystem::Other(other),
+        }
+    }
+}
+
+impl fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitSystem::None => write!(f, "none"),
+            UnitSystem::Metric => write!(f, "metric"),
+            UnitSystem::Imperial => write!(f, "imperial"),
+            UnitSystem::Other(value) => write!(f, "system {value}"),
+            UnitSystem::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for UnitSystem {
+    type Err = BlendFileError;
+
+    /// Case-insensitive, so Blender's `METRIC` works too
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(UnitSystem::None),
+            "metric" => Ok(UnitSystem::Metric),
+            "imperial" => Ok(UnitSystem::Imperial),
+            _ => Err(FormatError::ParseError(format!(
+                "unknown unit system '{s}' (expected none, metric or imperial)"
+            ))
+            .into()),
+        }
+    }
+}
+
+/// How far a frame rate or scale may be from the expected one, relative to
+/// it, so 23.976 matches 24000/1001 but not 24
+const CONFORM_TOLERANCE: f32 = 1e-4;
+
+/// The frame rate and units every scene should have; settings left `None`
+/// aren't checked
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ConformRules {
+    pub fps: Option<f32>,
+    pub unit_system: Option<UnitSystem>,
+    pub scale_length: Option<f32>,
+}
+
+impl ConformRules {
+    pub fn with_fps(mut self, fps: f32) -> Self {
+        self.fps = Some(fps);
+        self
+    }
+
+    pub fn with_unit_system(mut self, unit_system: UnitSystem) -> Self {
+        self.unit_system = Some(unit_system);
+        self
+    }
+
+    pub fn with_scale_length(mut self, scale_length: f32) -> Self {
+        self.scale_length = Some(scale_length);
+        self
+    }
+
+    /// The settings of `scene` that differ from these rules. A setting the
+    /// file doesn't store can't be confirmed, so it's reported as unknown.
+    pub fn check(&self, scene: &SceneInfo) -> Vec<ConformViolation> {
+        let mut violations = Vec::new();
+        let mut report = |setting, expected: String, actual: String| {
+            violations.push(ConformViolation {
+                scene: scene.name.clone(),
+                setting,
+                expected,
+                actual,
+            });
+        };
+        if let Some(fps) = self.fps {
+            if !scene.fps.is_some_and(|actual| close(actual, fps)) {
+                report("fps", number(fps), optional_number(scene.fps));
+            }
+        }
+        if let Some(system) = self.unit_system {
+            if scene.unit_system != system {
+                report("unit", system.to_string(), scene.unit_system.to_string());
+            }
+        }
+        if let Some(scale) = self.scale_length {
+            if !scene
+                .scale_length
+                .is_some_and(|actual| close(actual, scale))
+            {
+                report("scale", number(scale), optional_number(scene.scale_length));
+            }
+        }
+        violations
+    }
+}
+
+/// A scene setting that differs from `ConformRules`, with both values as
+/// printed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConformViolation {
+    /// Scene name without the `SC` ID prefix
+    pub scene: String,
+    /// `fps`, `unit` or `scale`
+    pub setting: &'static str,
+    pub expected: String,
+    /// `unknown` when the file doesn't store the setting
+    pub actual: String,
+}
+
+impl fmt::Display for ConformViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} is {}, expected {}",
+            self.scene, self.setting, self.actual, self.expected
+        )
+    }
+}
+
+fn close(actual: f32, expected: f32) -> bool {
+    (actual - expected).abs() <= CONFORM_TOLERANCE * expected.abs().max(1.0)
+}
+
+/// At most three decimals, without trailing zeros: `24`, `23.976`
+fn number(value: f32) -> String {
+    let text = format!("{value:.3}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn optional_number(value: Option<f32>) -> String {
+    value.map_or_else(|| "unknown".to_string(), number)
+}
+
+impl BlendFile {
+    /// Read every `SC` block through DNA. Files without DNA have no readable scenes.
+    pub fn scenes(&self) -> Result<Vec<SceneInfo>> {
+        if self.dna.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (name_offset, name_field) = locate(&self.dna, "Scene", &["id", "name"])?;
+        let frame_start = locate(&self.dna, "Scene", &["r", "sfra"])?;
+        let frame_end = locate(&self.dna, "Scene", &["r", "efra"])?;
+        // Missing from old files and minimal DNA, so these are optional
+        let optional = |path: &[&str]| {
+            locate(&self.dna, "Scene", path)
+                .ok()
+                .map(|(offset, _)| offset)
+        };
+        let frs_sec = optional(&["r", "frs_sec"]);
+        let frs_sec_base = optional(&["r", "frs_sec_base"]);
+        let unit_system = optional(&["unit", "system"]);
+        let scale_length = optional(&["unit", "scale_length"]);
+        let endianness = self.header.endianness;
+
+        let mut scenes = Vec::new();
+        for block in self.get_blocks_by_type(b"SC")? {
+            let data = &block.data;
+            let name_bytes = data
+                .get(name_offset..name_offset + name_field.size)
+                .ok_or_else(|| {
+                    FormatError::BlockError("scene block shorter than Scene.id.name".to_string())
+                })?;
+            let end = name_bytes
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(name_bytes.len());
+            let name = String::from_utf8_lossy(&name_bytes[..end]);
+
+            let frames = frs_sec
+                .map(|offset| read_i16_at(data, offset, endianness))
+                .transpose()?;
+            let fps_base = frs_sec_base
+                .map(|offset| read_f32_at(data, offset, endianness))
+                .transpose()?;
+            // Files from before frs_sec_base count whole frames
+            let fps = frames
+                .map(|frames| frames as f32 / fps_base.unwrap_or(1.0))
+                .filter(|fps| fps.is_finite());
+            let unit_system = match unit_system {
+                Some(offset) => UnitSystem::from_raw(*data.get(offset).ok_or_else(|| {
+                    FormatError::BlockError(
+                        "scene block shorter than Scene.unit.system".to_string(),
+                    )
+                })?),
+                None => UnitSystem::Unknown,
+            };
+
+            scenes.push(SceneInfo {
+                name: name.get(2..).unwrap_or_default().to_string(),
+                frame_start: read_i32(data, frame_start.0, endianness)?,
+                frame_end: read_i32(data, frame_end.0, endianness)?,
+                fps,
+                fps_base,
+                unit_system,
+                scale_length: scale_length
+                    .map(|offset| read_f32_at(data, offset, endianness))
+                    .transpose()?,
+            });
+        }
+        Ok(scenes)
+    }
+
+    /// Check every scene against `rules`; empty when all conform
+    pub fn check_conform(&self, rules: &ConformRules) -> Result<Vec<ConformViolation>> {
+        Ok(self
+            .scenes()?
+            .iter()
+            .flat_map(|scene| rules.check(scene))
+            .collect())
+    }
+}
+
+/// Absolute offset and field for a path of nested struct members, e.g. `Scene.r.sfra`
+pub(crate) fn locate<'a>(
+    dna: &'a Dna,
+    struct_name: &str,
+    path: &[&str],
+) -> Result<(usize, &'a DnaField)> {
+    let location = dna.field_offset(struct_name, &path.join("."))?;
+    Ok((location.offset, location.field))
+}
+
+pub(crate) fn read_i32(data: &[u8], offset: usize, endianness: Endianness) -> Result<i32> {
+    read_i32_at(data, offset, endianness)
+        .map_err(|_| FormatError::BlockError(format!("no int at offset {offset}")).into())
+}
@@ -0,0 +1,84 @@
+use crate::error::{BlendFileError, FormatError, Result};
+
+/// What path writes do when a value doesn't fit its fixed-size field
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TruncationPolicy {
+    /// Refuse the write
+    #[default]
+    Error,
+    /// Drop whole trailing components, leaving the longest ancestor
+    /// directory that fits, and record a warning; fails when not even the
+    /// first component fits
+    TruncateAtBoundary,
+    /// Replace the value with what the callback returns, e.g. a relative
+    /// form; fails if that doesn't fit either
+    Shorten(fn(&str) -> String),
+}
+
+/// A value made to fit a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FittedPath {
+    pub value: String,
+    /// Set when the value had to be cut
+    pub warning: Option<String>,
+}
+
+/// Fit `value` into a field of `field_size` bytes, leaving room for the null terminator
+pub fn fit_path(value: &str, field_size: usize, policy: TruncationPolicy) -> Result<FittedPath> {
+    let capacity = field_size.saturating_sub(1);
+    let fitted = |value: String| FittedPath {
+        value,
+        warning: None,
+    };
+    if value.len() <= capacity {
+        return Ok(fitted(value.to_string()));
+    }
+
+    match policy {
+        TruncationPolicy::Error => Err(too_long(value, capacity)),
+        TruncationPolicy::Shorten(shorten) => {
+            let shortened = shorten(value);
+            if shortened.len() > capacity {
+                return Err(too_long(&shortened, capacity));
+            }
+            Ok(fitted(shortened))
+        }
+        TruncationPolicy::TruncateAtBoundary => {
+            let value_out =
+                truncate_at_boundary(value, capacity).ok_or_else(|| too_long(value, capacity))?;
+            let warning = format!(
+                "path of {} bytes truncated to {} to fit {capacity}: {value_out}",
+                value.len(),
+                value_out.len()
+            );
+            tracing::warn!("{warning}");
+            Ok(FittedPath {
+                value: value_out,
+                warning: Some(warning),
+            })
+        }
+    }
+}
+
+fn too_long(value: &str, capacity: usize) -> BlendFileError {
+    FormatError::InvalidFormat(format!(
+        "path of {} bytes doesn't fit the {capacity} bytes available",
+        value.len()
+    ))
+    .into()
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+/// The longest `dir/...` prefix of `value` within `capacity` bytes that ends
+/// before a separator. Components are only ever dropped from the end: taking
+/// some out of the middle would name a different file.
+fn truncate_at_boundary(value: &str, capacity: usize) -> Option<String> {
+    value
+        .rmatch_indices(is_separator)
+        .map(|(i, _)| i)
+        .find(|&i| i > 0 && i <= capacity)
+        .map(|cut| value[..cut].to_string())
+}
@@ -0,0 +1,42 @@
+//! Summarize a blend file and check its linked assets, the way a
+//! pre-submit hook would.
+//!
+//! Usage: `cargo run --example check_file [FILE]`; without a file, a
+//! synthetic scene with one missing texture is generated. Exits with 1 when
+//! assets are missing from a file given on the command line.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use blend_file_reader::prelude::*;
+use common::SyntheticBlend;
+use std::process::ExitCode;
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let given = std::env::args_os().nth(1);
+    let path = match &given {
+        Some(path) => path.into(),
+        None => {
+            let path = dir.path().join("scene.blend");
+            let data = SyntheticBlend::new(300)
+                .image("IMgone", "//textures/gone.png")
+                .finish();
+            std::fs::write(&path, data)?;
+            path
+        }
+    };
+
+    let blend_file = BlendFile::open(&path)?;
+    let info = blend_file.info_with_asset_check();
+    print!("{info}");
+    let missing = info.missing_assets.unwrap_or(0);
+    println!("check: {}", if missing == 0 { "passed" } else { "failed" });
+
+    // The generated scene is broken on purpose; only real files fail the run
+    Ok(if missing > 0 && given.is_some() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,67 @@
+//! Compare save times for zstd at the default level against level 1 with a
+//! worker per core, the way Blender compresses its own saves.
+//!
+//! Usage: `cargo run --release --example compression_bench [SIZE_MB]`; a
+//! synthetic file of `SIZE_MB` megabytes (200 by default) is generated.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use blend_file_reader::prelude::*;
+use common::SyntheticBlend;
+use std::time::Instant;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let size_mb: usize = match std::env::args().nth(1) {
+        Some(arg) => arg.parse()?,
+        None => 200,
+    };
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("large.blend");
+
+    // Mesh-like data: low-entropy bytes from a running xorshift, so chunks
+    // compress but never repeat each other
+    let mut blend = SyntheticBlend::new(300).library("LIprops", "//props.blend");
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..size_mb {
+        let chunk: Vec<u8> = (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 58) as u8
+            })
+            .collect();
+        blend = blend.block(b"DATA", &chunk);
+    }
+    std::fs::write(&path, blend.finish())?;
+    println!("generated: {size_mb} MB");
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let runs = [
+        ("default", CompressionSettings::new(Compression::Zstd)),
+        (
+            "level 1, multithreaded",
+            CompressionSettings::new(Compression::Zstd)
+                .with_level(1)
+                .with_threads(threads),
+        ),
+    ];
+    for (label, settings) in runs {
+        let mut blend_file = BlendFile::open(&path)?;
+        let out = dir.path().join("out.blend");
+        let start = Instant::now();
+        blend_file
+            .save_as_with_options(&out, &SaveOptions::default().with_compression(settings))?;
+        let elapsed = start.elapsed();
+        let compressed = std::fs::metadata(&out)?.len();
+        println!(
+            "{label} (level {}, {} threads): {:.2?}, {:.1} MB",
+            settings.level,
+            settings.threads,
+            elapsed,
+            compressed as f64 / (1 << 20) as f64
+        );
+    }
+    Ok(())
+}
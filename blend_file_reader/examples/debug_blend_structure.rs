@@ -1,3 +1,4 @@
+use blend_file_reader::bytes::read_f32_at;
 use blend_file_reader::BlendFile;
 
 fn main() {
@@ -27,12 +28,10 @@ fn main() {
                 );
 
                 // Try to interpret as floats
-                if block.data.len() >= 12 {
-                    let mut floats = Vec::new();
-                    for chunk in block.data.chunks_exact(4).take(3) {
-                        let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
-                        floats.push(f32::from_le_bytes(bytes));
-                    }
+                let floats: Result<Vec<f32>, _> = (0..3)
+                    .map(|i| read_f32_at(&block.data, i * 4, blend_file.header.endianness))
+                    .collect();
+                if let Ok(floats) = floats {
                     println!("  First 3 floats: {floats:?}");
                 }
             }
@@ -1,4 +1,4 @@
-use blend_file_reader::BlendFile;
+use blend_file_reader::prelude::*;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Blend File Reader Demo ===");
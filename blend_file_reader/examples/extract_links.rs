@@ -0,0 +1,39 @@
+//! List the external files a blend file references.
+//!
+//! Usage: `cargo run --example extract_links [FILE]`; without a file, a small
+//! synthetic scene is generated in a temporary directory.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use blend_file_reader::prelude::*;
+use common::SyntheticBlend;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = match std::env::args_os().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = dir.path().join("scene.blend");
+            let data = SyntheticBlend::new(300)
+                .library("LIprops", "//props.blend")
+                .image("IMwood", "//textures/wood.png")
+                .image("IMsky", "/srv/hdri/sky.exr")
+                .finish();
+            std::fs::write(&path, data)?;
+            path
+        }
+    };
+
+    let blend_file = BlendFile::open(&path)?;
+    let (links, stats) =
+        blend_file.get_library_links_with_stats(&LinkExtractionOptions::default())?;
+    println!("links: {}", links.len());
+    for link in &links {
+        let resolved = link.absolute_path.as_deref().unwrap_or(&link.path);
+        println!("  {:<10} {} -> {resolved}", link.block_type, link.path);
+    }
+    println!("fallbacks: {}", stats.fallbacks());
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! Repoint a texture path and save the file in place.
+//!
+//! Usage: `cargo run --example remap_path [FILE OLD NEW]`; without arguments,
+//! a synthetic scene is generated and `//textures/wood.png` is moved to
+//! `//tex/wood.png`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use blend_file_reader::prelude::*;
+use common::SyntheticBlend;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, old, new) = match args.as_slice() {
+        [path, old, new] => (path.into(), old.clone(), new.clone()),
+        [] => {
+            let path = dir.path().join("scene.blend");
+            let data = SyntheticBlend::new(300)
+                .image("IMwood", "//textures/wood.png")
+                .image("IMstone", "//textures/stone.png")
+                .finish();
+            std::fs::write(&path, data)?;
+            (
+                path,
+                "//textures/wood.png".to_string(),
+                "//tex/wood.png".to_string(),
+            )
+        }
+        _ => return Err("usage: remap_path [FILE OLD NEW]".into()),
+    };
+
+    let mut blend_file = BlendFile::open_read_write(&path)?;
+    let changed = blend_file.remap_link_path(&old, &new)?;
+    println!(
+        "remapped: {changed} block(s), {:?}",
+        blend_file.modified_blocks()
+    );
+    match blend_file.save()? {
+        SaveOutcome::Saved => println!("saved: {}", path.display()),
+        SaveOutcome::NothingToSave => println!("unchanged: {}", path.display()),
+    }
+
+    // Read the file back to confirm the new path landed on disk
+    let reopened = BlendFile::open(&path)?;
+    for link in reopened.get_library_links()? {
+        println!("  {}", link.path);
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Trace which linked files exist and which library IDs the scene uses.
+//!
+//! Usage: `cargo run --example trace_links [FILE]`; without a file, a
+//! synthetic scene is generated next to one of its textures.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use blend_file_reader::link_status::LinkStatusOptions;
+use blend_file_reader::prelude::*;
+use common::SyntheticBlend;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = match std::env::args_os().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            std::fs::create_dir(dir.path().join("textures"))?;
+            std::fs::write(dir.path().join("textures/wood.png"), b"png")?;
+            let path = dir.path().join("scene.blend");
+            let data = SyntheticBlend::new(300)
+                .library("LIprops", "//props.blend")
+                .image("IMwood", "//textures/wood.png")
+                .image("IMgone", "//textures/gone.png")
+                .finish();
+            std::fs::write(&path, data)?;
+            path
+        }
+    };
+
+    let blend_file = BlendFile::open(&path)?;
+    let links = blend_file.get_library_links_with_status(&LinkStatusOptions::default())?;
+    let mut missing = 0;
+    for link in &links {
+        let exists = link.status.as_ref().is_some_and(|s| s.exists);
+        missing += usize::from(!exists);
+        let mark = if exists { "ok" } else { "missing" };
+        println!("{mark:<8} {}", link.path);
+    }
+    println!("missing: {missing} of {}", links.len());
+
+    // Per-library ID usage needs the file's DNA; report why when it's absent
+    match blend_file.library_usage() {
+        Ok(usage) => {
+            for library in usage {
+                println!("library {} ({} ID types)", library.path, library.ids.len());
+            }
+        }
+        Err(e) => println!("usage: {e}"),
+    }
+    Ok(())
+}
@@ -1,108 +0,0 @@
-use crate::error::Result;
-use crate::header::Header;
-use byteorder::ReadBytesExt;
-use std::collections::HashMap;
-use std::io::{Read, Seek};
-
-#[derive(Debug, Clone)]
-pub struct Dna {
-    pub structs: HashMap<String, DnaStruct>,
-    pub type_sizes: HashMap<String, usize>,
-}
-
-#[derive(Debug, Clone)]
-pub struct DnaStruct {
-    pub name: String,
-    pub fields: Vec<DnaField>,
-    pub size: usize,
-}
-
-#[derive(Debug, Clone)]
-pub struct DnaField {
-    pub name: String,
-    pub type_name: String,
-    pub offset: usize,
-    pub size: usize,
-}
-
-impl Dna {
-    pub fn from_reader<R: Read + Seek>(_reader: &mut R, _header: &Header) -> Result<Self> {
-        // For now, return an empty DNA structure
-        // This is a simplified implementation that doesn't parse the full DNA
-        // In a real implementation, we would scan for the DNA1 block and parse it
-
-        Ok(Dna {
-            structs: HashMap::new(),
-            type_sizes: HashMap::new(),
-        })
-    }
-
-    #[allow(dead_code)]
-    fn parse_dna_block<R: Read + Seek>(reader: &mut R, header: &Header) -> Result<Self> {
-        // Skip DNA1 identifier
-        reader.seek(std::io::SeekFrom::Current(4))?;
-
-        // Read DNA block size
-        let dna_size = match header.endianness {
-            crate::header::Endianness::Little => reader.read_u32::<byteorder::LittleEndian>()?,
-            crate::header::Endianness::Big => reader.read_u32::<byteorder::BigEndian>()?,
-        };
-
-        // Skip to DNA data
-        reader.seek(std::io::SeekFrom::Current(16))?;
-
-        // Read DNA data
-        let mut dna_data = vec![0u8; dna_size as usize];
-        reader.read_exact(&mut dna_data)?;
-
-        // Parse DNA structure
-        Self::parse_dna_data(&dna_data)
-    }
-
-    #[allow(dead_code)]
-    fn parse_dna_data(_data: &[u8]) -> Result<Self> {
-        // This is a simplified DNA parser
-        // In a real implementation, we would parse the full DNA structure
-
-        Ok(Dna {
-            structs: HashMap::new(),
-            type_sizes: HashMap::new(),
-        })
-    }
-
-    pub fn get_struct(&self, name: &str) -> Option<&DnaStruct> {
-        self.structs.get(name)
-    }
-
-    pub fn get_type_size(&self, type_name: &str) -> Option<usize> {
-        self.type_sizes.get(type_name).copied()
-    }
-
-    pub fn write_to_writer<W: std::io::Write>(&self, _writer: &mut W) -> Result<()> {
-        // For now, this is a no-op since we don't actually parse the DNA structure
-        // In a real implementation, we would write the DNA1 block back to the file
-        Ok(())
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_dna_creation() {
-        let dna = Dna {
-            structs: HashMap::new(),
-            type_sizes: HashMap::new(),
-        };
-
-        assert!(dna.structs.is_empty());
-        assert!(dna.type_sizes.is_empty());
-    }
-
-    #[test]
-    fn test_empty_dna_data() {
-        let result = Dna::parse_dna_data(&[]);
-        assert!(result.is_ok());
-    }
-}
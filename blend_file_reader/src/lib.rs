@@ -1,13 +1,5 @@
-pub mod blend_file;
-pub mod block;
-pub mod debug;
-pub mod dna;
-pub mod dna_io;
-pub mod dna_name;
-pub mod error;
-pub mod header;
-pub mod library_link;
+//! Re-exports blend-assets, which re-exports the blend-raw format modules, so
+//! `blend_file_reader::BlendFile` and `blend_file_reader::header::Header` keep
+//! working now that the code lives in those crates.
 
-pub use blend_file::BlendFile;
-pub use error::{BlendFileError, Result};
-pub use library_link::LibraryLink;
+pub use blend_assets::*;
@@ -1,6 +1,8 @@
 // Rust port of test_blendfile_dna.py
 // Tests for DNA name parsing, struct/field logic
 
+mod common;
+
 #[cfg(test)]
 mod tests {
     use blend_file_reader::dna_name::DnaName;
@@ -64,4 +66,1510 @@ mod tests {
         assert!(!n.is_method_pointer());
         assert_eq!(n.array_size(), 18);
     }
+
+    #[test]
+    fn test_pointer_to_pointers() {
+        let n = DnaName::parse("**mat").unwrap();
+        assert_eq!(n.name_only(), "mat");
+        assert!(n.is_pointer());
+        assert_eq!(n.array_size(), 1);
+    }
+
+    #[test]
+    fn test_pointer_array_2d() {
+        let n = DnaName::parse("*texpaintslot[2][4]").unwrap();
+        assert_eq!(n.name_only(), "texpaintslot");
+        assert!(n.is_pointer());
+        assert_eq!(n.dimensions(), vec![2, 4]);
+        assert_eq!(n.element_count(), 8);
+        assert_eq!(n.array_size(), 8);
+    }
+
+    #[test]
+    fn test_dimensions_of_scalars() {
+        let n = DnaName::parse("*marker").unwrap();
+        assert!(n.dimensions().is_empty());
+        assert_eq!(n.element_count(), 1);
+        assert!(DnaName::parse("(*delta_cache)()").is_ok());
+    }
+
+    #[test]
+    fn test_bit_field() {
+        let n = DnaName::parse("flag:1").unwrap();
+        assert_eq!(n.name_only(), "flag");
+        assert_eq!(n.bit_width(), Some(1));
+        assert_eq!(n.element_count(), 1);
+        assert_eq!(DnaName::new("flag").bit_width(), None);
+        assert!(DnaName::parse("flag:x").is_err());
+    }
+
+    #[test]
+    fn test_unbalanced_brackets() {
+        for name in ["verts[3", "verts3]", "verts[3]]", "verts[3][", "verts[x]"] {
+            assert!(DnaName::parse(name).is_err(), "{name}");
+        }
+        // The lossy constructor keeps working on the same input
+        let n = DnaName::new("verts[3][");
+        assert_eq!(n.name_only(), "verts");
+        assert_eq!(n.dimensions(), vec![3]);
+    }
+
+    #[test]
+    fn test_empty_name() {
+        assert!(DnaName::parse("").is_err());
+        assert!(DnaName::parse("*").is_err());
+        assert!(DnaName::parse("[3]").is_err());
+        let n = DnaName::new("");
+        assert_eq!(n.name_only(), "");
+        assert_eq!(n.element_count(), 1);
+    }
+}
+
+// Tests for dumping the DNA catalog as JSON
+#[cfg(test)]
+mod dna_json {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::BlendFile;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// ID { *next, char name[24] }, Object { ID id, float obmat[4][4] }
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["*next", "name[24]", "id", "obmat[4][4]"]),
+            types: strings(&["char", "float", "void", "ID", "Object"]),
+            tlens: vec![1, 4, 0, 32, 96],
+            structs: vec![(3, vec![(2, 0), (0, 1)]), (4, vec![(3, 2), (1, 3)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    #[test]
+    fn test_cli_dumps_catalog() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        std::fs::write(&path, SyntheticBlend::new(300).dna(&dna()).finish()).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("dna")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let blend_file = BlendFile::open(&path).unwrap();
+        assert_eq!(stdout.trim_end(), blend_file.dna.to_json().unwrap());
+
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        let obmat = &json["structs"][1]["fields"][1];
+        assert_eq!(obmat["full_name"], "obmat[4][4]");
+        assert_eq!(obmat["type"], "float");
+        assert_eq!((&obmat["offset"], &obmat["size"]), (&32.into(), &64.into()));
+    }
+}
+
+// Tests for diffing the DNA catalogs of files saved by different versions
+#[cfg(test)]
+mod dna_diff {
+    use crate::common::{strings, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::dna_diff::FieldChange;
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::BlendFile;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// As saved by 2.79: Object { ID id, float loc[3] }, Lamp { ID id }
+    fn dna_279() -> Dna {
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "loc[3]"]),
+            types: strings(&["char", "float", "ID", "Object", "Lamp"]),
+            tlens: vec![1, 4, 24, 36, 24],
+            structs: vec![
+                (2, vec![(0, 0)]),
+                (3, vec![(2, 1), (1, 2)]),
+                (4, vec![(2, 1)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    /// As saved by 4.0: Lamp is now Light, Object gained `*data` before
+    /// `loc`
+    fn dna_400() -> Dna {
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "loc[3]", "*data"]),
+            types: strings(&["char", "float", "void", "ID", "Object", "Light"]),
+            tlens: vec![1, 4, 0, 24, 44, 24],
+            structs: vec![
+                (3, vec![(0, 0)]),
+                (4, vec![(3, 1), (2, 3), (1, 2)]),
+                (5, vec![(3, 1)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn write_blend(dir: &Path, file_name: &str, version: u32, dna: &Dna) -> PathBuf {
+        let path = dir.join(file_name);
+        std::fs::write(&path, SyntheticBlend::new(version).dna(dna).finish()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_diff_across_versions() {
+        let dir = tempdir().unwrap();
+        let old = write_blend(dir.path(), "old.blend", 279, &dna_279());
+        let new = write_blend(dir.path(), "new.blend", 400, &dna_400());
+        let old = BlendFile::open(&old).unwrap();
+        let new = BlendFile::open(&new).unwrap();
+
+        let diff = old.dna.diff(&new.dna);
+        assert_eq!(diff.added, ["Light"]);
+        assert_eq!(diff.removed, ["Lamp"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].name, "Object");
+        assert_eq!(diff.changed[0].size, Some((36, 44)));
+        assert_eq!(
+            diff.changed[0].fields,
+            [
+                FieldChange::Moved {
+                    name: "loc".to_string(),
+                    old: Some(24),
+                    new: Some(32),
+                },
+                FieldChange::Added {
+                    name: "data".to_string(),
+                    declaration: "void *data".to_string(),
+                    offset: Some(24),
+                },
+            ]
+        );
+        assert!(new.dna.diff(&new.dna).is_empty());
+    }
+
+    #[test]
+    fn test_cli_diff() {
+        let dir = tempdir().unwrap();
+        let old = write_blend(dir.path(), "old.blend", 279, &dna_279());
+        let new = write_blend(dir.path(), "new.blend", 400, &dna_400());
+        let dna_diff = |args: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("dna")
+                .arg(&old)
+                .arg("--diff")
+                .arg(&new)
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{output:?}");
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        assert_eq!(
+            dna_diff(&[]),
+            "+ struct Light\n\
+             - struct Lamp\n\
+             ~ struct Object (size 36 -> 44)\n    \
+             ~ loc: offset 24 -> 32\n    \
+             + void *data at 24\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&dna_diff(&["--json"])).unwrap();
+        assert_eq!(json["added"], serde_json::json!(["Light"]));
+        let fields = &json["changed"][0]["fields"];
+        assert_eq!(fields[0]["change"], "moved");
+        assert_eq!(fields[1]["declaration"], "void *data");
+        assert_eq!(json["changed"][0]["size"], serde_json::json!([36, 44]));
+    }
+}
+
+// Tests for the raw SDNA tables and their text dump
+#[cfg(test)]
+mod raw_sdna {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::dna::RawSdna;
+    use blend_file_reader::header::{Endianness, PointerSize};
+    use blend_file_reader::BlendFile;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// A DNA1 payload laid out by hand as makesdna writes it, little-endian:
+    /// ID { char name[4] }, Object { ID id, void *next }
+    fn payload() -> Vec<u8> {
+        [
+            &b"SDNA"[..],
+            b"NAME",
+            &3u32.to_le_bytes(),
+            b"name[4]\0id\0*next\0",
+            b"\0\0\0",
+            b"TYPE",
+            &4u32.to_le_bytes(),
+            b"char\0void\0ID\0Object\0",
+            b"TLEN",
+            &[1, 0, 0, 0, 4, 0, 12, 0][..],
+            b"STRC",
+            &2u32.to_le_bytes(),
+            &[2, 0, 1, 0, 0, 0, 0, 0][..],
+            &[3, 0, 2, 0, 2, 0, 1, 0, 1, 0, 2, 0][..],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = payload();
+        let raw = RawSdna::from_payload(&payload, Endianness::Little).unwrap();
+        assert_eq!(raw.to_payload(Endianness::Little), payload);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::with_format(300, PointerSize::Bits64, Endianness::Little)
+            .block(b"DNA1", &payload)
+            .finish();
+        std::fs::write(&path, &data).unwrap();
+        let blend_file = BlendFile::open(&path).unwrap();
+        let dna = &blend_file.dna;
+        assert_eq!(dna.raw_names(), ["name[4]", "id", "*next"]);
+        assert_eq!(dna.raw_types(), ["char", "void", "ID", "Object"]);
+        assert_eq!(dna.raw_tlens(), [1, 0, 4, 12]);
+        assert_eq!(
+            dna.raw_structs(),
+            [(2, vec![(0, 0)]), (3, vec![(2, 1), (1, 2)])]
+        );
+        assert_eq!(dna.raw.to_payload(Endianness::Little), payload);
+
+        // Big-endian files store the same tables with swapped integers
+        let big = raw.to_payload(Endianness::Big);
+        assert_ne!(big, payload);
+        assert_eq!(RawSdna::from_payload(&big, Endianness::Big).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_cli_raw_dump() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(300).block(b"DNA1", &payload()).finish();
+        std::fs::write(&path, data).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("dna")
+            .arg(&path)
+            .arg("--raw")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "NAME 0 name[4]\n\
+             NAME 1 id\n\
+             NAME 2 *next\n\
+             TYPE 0 char\n\
+             TYPE 1 void\n\
+             TYPE 2 ID\n\
+             TYPE 3 Object\n\
+             TLEN 0 1\n\
+             TLEN 1 0\n\
+             TLEN 2 4\n\
+             TLEN 3 12\n\
+             STRC 0 ID (type 2, 1 fields)\n  \
+             char name[4] (type 0, name 0)\n\
+             STRC 1 Object (type 3, 2 fields)\n  \
+             ID id (type 2, name 1)\n  \
+             void *next (type 1, name 2)\n"
+        );
+    }
+}
+
+// Tests for DNA fingerprints and the release table behind `summary`/`info`
+#[cfg(test)]
+mod dna_release {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::blend_file::SummaryOrder;
+    use blend_file_reader::dna::{Dna, DnaFingerprint, RawSdna};
+    use blend_file_reader::dna_release::DnaReleases;
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// ID { name[24] }, Object { id, loc[3] }
+    fn raw() -> RawSdna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        RawSdna {
+            names: strings(&["name[24]", "id", "loc[3]"]),
+            types: strings(&["char", "float", "ID", "Object"]),
+            tlens: vec![1, 4, 24, 36],
+            structs: vec![(2, vec![(0, 0)]), (3, vec![(2, 1), (1, 2)])],
+        }
+    }
+
+    fn fixture() -> Vec<u8> {
+        let dna = Dna::from_raw(raw(), PointerSize::Bits64).unwrap();
+        SyntheticBlend::new(306)
+            .typed_block(b"OB", 1, 1, &[0u8; 36])
+            .dna(&dna)
+            .finish()
+    }
+
+    fn blend_file() -> BlendFile {
+        BlendFile::from_bytes(&fixture()).unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_survives_the_file() {
+        let dna = Dna::from_raw(raw(), PointerSize::Bits64).unwrap();
+        let blend_file = blend_file();
+        assert_eq!(blend_file.dna.fingerprint(), dna.fingerprint());
+
+        let stats = blend_file.dna.stats();
+        assert_eq!(
+            (stats.struct_count, stats.field_count, stats.max_struct_size),
+            (2, 3, 36)
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_injected_releases() {
+        let blend_file = blend_file();
+        let fingerprint = blend_file.dna.fingerprint();
+
+        let summary = blend_file.summary(SummaryOrder::Code);
+        let report = summary.dna.as_ref().unwrap();
+        assert_eq!(report.fingerprint, fingerprint.to_string());
+        assert_eq!(report.release, None);
+        assert!(summary.to_string().contains("unknown DNA variant"));
+
+        let mut releases = DnaReleases::builtin();
+        releases.insert(fingerprint, "3.6.5 (official)");
+        let report = blend_file.dna_report(&releases).unwrap();
+        assert_eq!(report.release.as_deref(), Some("3.6.5 (official)"));
+        assert_eq!(report.to_string(), "DNA matches Blender 3.6.5 (official)");
+
+        let without_dna = BlendFile::from_bytes(&SyntheticBlend::new(306).finish()).unwrap();
+        assert!(without_dna.dna_report(&releases).is_none());
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let releases = DnaReleases::parse(
+            "# comment\n\n00000000000000ff  4.1.0 (official)\n0000000000000100 4.1-fork\n",
+        )
+        .unwrap();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(
+            releases.lookup(DnaFingerprint(0xff)),
+            Some("4.1.0 (official)")
+        );
+        assert_eq!(releases.lookup(DnaFingerprint(0x100)), Some("4.1-fork"));
+        assert_eq!(releases.lookup(DnaFingerprint(1)), None);
+
+        for bad in ["00000000000000ff", "xyz 4.1.0"] {
+            let err = DnaReleases::parse(bad).unwrap_err();
+            assert!(
+                matches!(&err, BlendFileError::Format(FormatError::ParseError(m)) if m.starts_with("line 1")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cli_reads_release_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fork.blend");
+        std::fs::write(&path, fixture()).unwrap();
+        let table = dir.path().join("releases.txt");
+        std::fs::write(
+            &table,
+            format!("{} 3.6.5 (official)\n", blend_file().dna.fingerprint()),
+        )
+        .unwrap();
+
+        let run = |args: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .args(args)
+                .arg(&path)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{output:?}");
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        assert!(run(&["summary"]).contains("unknown DNA variant"));
+        let table = table.to_str().unwrap();
+        let summary = run(&["summary", "--dna-releases", table]);
+        assert!(
+            summary.contains("DNA matches Blender 3.6.5 (official)"),
+            "{summary}"
+        );
+        let info = run(&["info", "--json", "--dna-releases", table]);
+        assert!(info.contains("\"release\": \"3.6.5 (official)\""), "{info}");
+    }
+}
+
+// Tests for files holding more than one DNA1 block, as left by repair tools
+#[cfg(test)]
+mod duplicate_dna {
+    use crate::common::{strings, write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::BlendFile;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// The stale SDNA: ID { char name[24] }, Object { ID id }
+    fn first_dna() -> Dna {
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id"]),
+            types: strings(&["char", "ID", "Object"]),
+            tlens: vec![1, 24, 24],
+            structs: vec![(1, vec![(0, 0)]), (2, vec![(1, 1)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    /// The SDNA the blocks were written with: Object also has `loc[3]`
+    fn final_dna() -> Dna {
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "loc[3]"]),
+            types: strings(&["char", "float", "ID", "Object"]),
+            tlens: vec![1, 4, 24, 36],
+            structs: vec![(2, vec![(0, 0)]), (3, vec![(2, 1), (1, 2)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn object(name: &str, loc: [f32; 3]) -> Vec<u8> {
+        let mut data = vec![0u8; 36];
+        write_str(&mut data, 0, name);
+        for (i, value) in loc.iter().enumerate() {
+            data[24 + i * 4..28 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    fn write_repaired(dir: &Path) -> PathBuf {
+        let path = dir.join("repaired.blend");
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"OB", 1, 1, &object("OBhero", [1.0, 2.0, 3.0]))
+            .dna(&first_dna())
+            .typed_block(b"OB", 1, 1, &object("OBprop", [4.0, 5.0, 6.0]))
+            .library("LIkit", "//kit.blend")
+            .dna(&final_dna())
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_blocks_after_first_dna_are_read() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_repaired(dir.path())).unwrap();
+
+        let codes: Vec<String> = blend_file
+            .blocks
+            .iter()
+            .map(|block| block.get_type_name().trim_end_matches('\0').to_string())
+            .collect();
+        assert_eq!(codes, ["OB", "OB", "LI"]);
+        assert_eq!(blend_file.dna_blocks.len(), 2);
+        assert_eq!(blend_file.get_library_links().unwrap().len(), 1);
+
+        // Structs resolve through the last SDNA
+        let dna = &blend_file.dna;
+        assert!(dna.get_struct("Object").unwrap().field("loc").is_some());
+        assert_eq!(
+            blend_file.blocks[1].get_float_field(dna, "loc").unwrap(),
+            [4.0, 5.0, 6.0]
+        );
+        let offsets: Vec<String> = blend_file
+            .dna_blocks
+            .iter()
+            .map(|range| range.start.to_string())
+            .collect();
+        assert_eq!(
+            dna.warnings,
+            [format!(
+                "2 DNA1 blocks, at offsets {}; using the last",
+                offsets.join(", ")
+            )]
+        );
+        assert!(blend_file.layout_anomalies().is_empty());
+    }
+
+    #[test]
+    fn test_warnings_index_blocks_after_dna() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("repaired.blend");
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"OB", 1, 1, &object("OBhero", [1.0, 2.0, 3.0]))
+            .dna(&first_dna())
+            .block(b"DATA", &[])
+            .dna(&final_dna())
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        assert_eq!(blend_file.warnings.len(), 1);
+        let index = blend_file.warnings[0].index;
+        assert_eq!(index, 1);
+        assert_eq!(&blend_file.blocks[index].code, b"DATA");
+    }
+
+    #[test]
+    fn test_save_and_index_keep_final_dna() {
+        let dir = tempdir().unwrap();
+        let path = write_repaired(dir.path());
+        let blend_file = BlendFile::open(&path).unwrap();
+
+        let index = dir.path().join("repaired.rbatidx");
+        blend_file.write_index(&index).unwrap();
+        let indexed = BlendFile::open_with_index(&path, &index).unwrap();
+        assert_eq!(indexed.blocks.len(), 3);
+        assert_eq!(indexed.dna.get_struct("Object").unwrap().size, 36);
+
+        // Saving writes the DNA in use once
+        let saved = dir.path().join("saved.blend");
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        std::fs::write(&saved, out).unwrap();
+        let reopened = BlendFile::open(&saved).unwrap();
+        assert_eq!(reopened.blocks.len(), 3);
+        assert_eq!(reopened.dna_blocks.len(), 1);
+        assert!(reopened.dna.warnings.is_empty());
+        assert_eq!(reopened.dna.get_struct("Object").unwrap().size, 36);
+    }
+
+    #[test]
+    fn test_validate_reports_duplicate_dna() {
+        let dir = tempdir().unwrap();
+        let path = write_repaired(dir.path());
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("validate")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("2 DNA1 blocks, at offsets "), "{stdout}");
+        assert!(stdout.contains("1 issue(s)"), "{stdout}");
+    }
+}
+
+// Tests for checking block and struct sizes against the DNA
+#[cfg(test)]
+mod consistency {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::consistency::ConsistencyIssue;
+    use blend_file_reader::debug::decode_block;
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::{Endianness, PointerSize};
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// Pair { int a; int b } of 8 bytes, and Bad { int a } declared as 12
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["a", "b"]),
+            types: strings(&["int", "Pair", "Bad"]),
+            tlens: vec![4, 8, 12],
+            structs: vec![(1, vec![(0, 0), (0, 1)]), (2, vec![(0, 0)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn write(dir: &Path, data: Vec<u8>) -> PathBuf {
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn mismatched(dir: &Path) -> PathBuf {
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"PA", 0, 2, &[0; 16])
+            .typed_block(b"PA", 0, 2, &[0; 12])
+            .typed_block(b"DATA", 0, 1, &[0; 5])
+            .typed_block(b"PA", 7, 1, &[0; 8])
+            .typed_block(b"BA", 1, 1, &[0; 12])
+            .dna(&dna())
+            .finish();
+        write(dir, data)
+    }
+
+    #[test]
+    fn test_reports_block_and_struct_sizes() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(mismatched(dir.path())).unwrap();
+
+        let issues = blend_file.consistency_check(&blend_file.dna);
+        assert_eq!(
+            issues,
+            [
+                ConsistencyIssue::StructSize {
+                    sdna_index: 1,
+                    struct_name: "Bad".to_string(),
+                    declared: 12,
+                    fields: 4,
+                },
+                ConsistencyIssue::BlockSize {
+                    index: 1,
+                    code: "PA".to_string(),
+                    struct_name: "Pair".to_string(),
+                    size: 12,
+                    expected: 16,
+                },
+                ConsistencyIssue::UnknownStruct {
+                    index: 3,
+                    code: "PA".to_string(),
+                    sdna_index: 7,
+                },
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "block 1 (PA): 12 bytes, Pair x count needs 16"
+        );
+    }
+
+    #[test]
+    fn test_clean_files_have_no_issues() {
+        let mut blend_file = BlendFile::new(402, PointerSize::Bits32, Endianness::Big);
+        blend_file.add_library("lib", "//lib.blend").unwrap();
+        blend_file.add_scene("Scene").unwrap();
+        assert!(blend_file.consistency_check(&blend_file.dna).is_empty());
+
+        // Without DNA there's nothing to compare against
+        let dir = tempdir().unwrap();
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"PA", 0, 2, &[0; 12])
+            .finish();
+        let blend_file = BlendFile::open(write(dir.path(), data)).unwrap();
+        assert!(blend_file.consistency_check(&blend_file.dna).is_empty());
+        assert_eq!(blend_file.consistency_check(&dna()).len(), 2);
+    }
+
+    #[test]
+    fn test_cli_validate_deep() {
+        let dir = tempdir().unwrap();
+        let path = mismatched(dir.path());
+        let run = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("validate")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap()
+        };
+
+        // The block headers themselves are sane
+        let shallow = run(&[]);
+        assert!(shallow.status.success());
+        assert!(String::from_utf8_lossy(&shallow.stdout).ends_with("OK\n"));
+
+        let deep = run(&["--deep"]);
+        assert_eq!(deep.status.code(), Some(1));
+        let stdout = String::from_utf8_lossy(&deep.stdout);
+        assert!(
+            stdout.contains("struct Bad (SDNA 1): declared 12 bytes"),
+            "{stdout}"
+        );
+        assert!(
+            stdout.contains("block 3 (PA): no struct at SDNA index 7"),
+            "{stdout}"
+        );
+        assert!(stdout.contains("3 issue(s)"), "{stdout}");
+    }
+
+    #[test]
+    fn test_validate_dna_reports_blocks() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(mismatched(dir.path())).unwrap();
+
+        // The struct sizes aren't part of it, only blocks; DATA is skipped
+        let validation = blend_file.validate_dna();
+        assert_eq!(validation.blocks_checked, 4);
+        assert!(!validation.is_ok());
+        assert_eq!(
+            validation.mismatches,
+            blend_file.consistency_check(&blend_file.dna)[1..]
+        );
+
+        let json = serde_json::to_value(&validation).unwrap();
+        let mismatch = &json["mismatches"][0];
+        assert_eq!(mismatch["kind"], "block_size");
+        assert_eq!(
+            (&mismatch["index"], &mismatch["code"]),
+            (&1.into(), &"PA".into())
+        );
+        assert_eq!(
+            (&mismatch["expected"], &mismatch["size"]),
+            (&16.into(), &12.into())
+        );
+
+        let mut blend_file = BlendFile::new(402, PointerSize::Bits64, Endianness::Little);
+        blend_file.add_scene("Scene").unwrap();
+        let validation = blend_file.validate_dna();
+        assert!(validation.is_ok());
+        assert_eq!(validation.blocks_checked, blend_file.blocks.len());
+    }
+
+    #[test]
+    fn test_cli_dna_validate() {
+        let dir = tempdir().unwrap();
+        let path = mismatched(dir.path());
+        let run = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("dna")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap()
+        };
+
+        let output = run(&["--validate"]);
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout,
+            format!(
+                "block 1 (PA): 12 bytes, Pair x count needs 16\n\
+                 block 3 (PA): no struct at SDNA index 7\n\
+                 {}: 2 of 4 block(s) don't match the DNA\n",
+                path.display()
+            )
+        );
+
+        let output = run(&["--validate", "--json"]);
+        assert_eq!(output.status.code(), Some(1));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["blocks_checked"], 4);
+        assert_eq!(json["mismatches"][1]["kind"], "unknown_struct");
+
+        // --json needs a report to format
+        assert_eq!(run(&["--json"]).status.code(), Some(2));
+    }
+
+    /// Pair as in `dna`, plus Future { int a; Gizmo blob; int b } where
+    /// Gizmo is a type this reader has no size for
+    fn future_dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["a", "b", "blob"]),
+            types: strings(&["int", "Pair", "Future", "Gizmo"]),
+            tlens: vec![4, 8, 16, 0],
+            structs: vec![(1, vec![(0, 0), (0, 1)]), (2, vec![(0, 0), (3, 2), (0, 1)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn future(dir: &Path) -> PathBuf {
+        let data = SyntheticBlend::new(500)
+            .typed_block(b"PA", 0, 1, &[1, 0, 0, 0, 2, 0, 0, 0])
+            .typed_block(
+                b"FU",
+                1,
+                1,
+                &[3, 0, 0, 0, 9, 9, 9, 9, 9, 9, 9, 9, 4, 0, 0, 0],
+            )
+            .dna(&future_dna())
+            .finish();
+        write(dir, data)
+    }
+
+    #[test]
+    fn test_unknown_type_leaves_file_readable() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = BlendFile::open(future(dir.path())).unwrap();
+        assert_eq!(blend_file.dna.warnings.len(), 1);
+
+        let issues = blend_file.consistency_check(&blend_file.dna);
+        assert_eq!(
+            issues,
+            [ConsistencyIssue::IncompleteStruct {
+                sdna_index: 1,
+                struct_name: "Future".to_string(),
+                field: "blob".to_string(),
+                type_name: "Gizmo".to_string(),
+            }]
+        );
+
+        // Complete structs decode in full; incomplete ones up to the unknown field
+        let pair = decode_block(&blend_file.blocks[0], &blend_file.dna, &blend_file);
+        assert_eq!(pair.fields.len(), 2);
+        let fields = decode_block(&blend_file.blocks[1], &blend_file.dna, &blend_file).fields;
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["a", "blob"]);
+
+        // Rewriting every struct needs every field's offset
+        let err = blend_file
+            .convert_pointer_size(PointerSize::Bits32)
+            .unwrap_err();
+        assert!(
+            matches!(&err, BlendFileError::Format(FormatError::IncompleteStruct { struct_name, field })
+                if struct_name == "Future" && field == "blob"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_cli_validate_lists_incomplete_structs() {
+        let dir = tempdir().unwrap();
+        let path = future(dir.path());
+        for args in [&[][..], &["--deep"]] {
+            let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("validate")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(
+                stdout.contains("struct Future (SDNA 1): blob has unknown type Gizmo"),
+                "{stdout}"
+            );
+            assert!(stdout.contains("1 issue(s)"), "{stdout}");
+        }
+    }
+}
+
+// Tests for block inspection, decoding and the debug and show subcommands
+#[cfg(test)]
+mod debug {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::debug::{decode_block, DecodedValue};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Output};
+    use tempfile::tempdir;
+
+    /// ID { char name[24] }, Library { ID id; char filepath[64] },
+    /// Object { ID id; float loc[3]; int lay; Object *parent }
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "filepath[64]", "loc[3]", "lay", "*parent"]),
+            types: strings(&["char", "float", "int", "ID", "Library", "Object"]),
+            tlens: vec![1, 4, 4, 24, 88, 48],
+            structs: vec![
+                (3, vec![(0, 0)]),
+                (4, vec![(3, 1), (0, 2)]),
+                (5, vec![(3, 1), (1, 3), (2, 4), (5, 5)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let mut library = vec![0u8; 88];
+        write_str(&mut library, 0, "LIprops.blend");
+        write_str(&mut library, 24, "//libs/props.blend");
+
+        let mut object = vec![0u8; 48];
+        write_str(&mut object, 0, "OBCube");
+        for (i, value) in [1.5f32, -2.0, 0.25].iter().enumerate() {
+            object[24 + i * 4..28 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        object[36..40].copy_from_slice(&7i32.to_le_bytes());
+        object[40..48].copy_from_slice(&0x2000u64.to_le_bytes());
+
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"LI", 1, 1, &library)
+            .typed_block(b"OB", 2, 1, &object)
+            .block(b"DATA", &[1, 2, 3])
+            .dna(&dna())
+            .finish();
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn debug(path: &Path, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("debug")
+            .arg(path)
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_inspect_object_fields() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+
+        let inspection = blend_file.inspect_block(1).unwrap();
+        assert_eq!(inspection.code, "OB");
+        assert_eq!(inspection.struct_name.as_deref(), Some("Object"));
+        assert_eq!(inspection.strings[0].value, "OBCube");
+        let values: Vec<(&str, &str)> = inspection
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value.as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("id", "{ID}"),
+                ("loc[3]", "[1.5, -2, 0.25]"),
+                ("lay", "7"),
+                ("*parent", "0x2000"),
+            ]
+        );
+        assert_eq!(inspection.hexdump.len(), 3);
+        assert!(inspection.hexdump[0].starts_with("0000: 4f 42 43 75 62 65 00"));
+    }
+
+    #[test]
+    fn test_inspect_selection() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+
+        let raw = blend_file.inspect_blocks("DATA").unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw[0].index, 2);
+        assert!(raw[0].struct_name.is_none());
+        assert!(raw[0].fields.is_empty());
+
+        let err = blend_file.inspect_block(10).unwrap_err();
+        assert!(matches!(
+            err,
+            BlendFileError::Format(FormatError::BlockError(_))
+        ));
+    }
+
+    #[test]
+    fn test_cli_debug_library_and_object() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        // LI blocks by default
+        let output = debug(&path, &[]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("--- LI block 0 ---"), "{stdout}");
+        assert!(stdout.contains("\"//libs/props.blend\""), "{stdout}");
+        assert!(stdout.contains("filepath[64]"), "{stdout}");
+        assert!(!stdout.contains("OBCube"), "{stdout}");
+
+        let output = debug(&path, &["--code", "OB"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Struct: Object"), "{stdout}");
+        assert!(stdout.contains("\"OBCube\""), "{stdout}");
+        assert!(stdout.contains("[1.5, -2, 0.25]"), "{stdout}");
+    }
+
+    #[test]
+    fn test_cli_debug_json() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let output = debug(&path, &["--index", "1", "--json"]);
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let blocks = json.as_array().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["code"], "OB");
+        assert_eq!(blocks[0]["fields"][3]["name"], "*parent");
+
+        let output = debug(&path, &["--code", "LI", "--json"]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["strings"][1]["value"], "//libs/props.blend");
+
+        // --index and --code select differently, so they can't be combined
+        assert!(!debug(&path, &["--index", "1", "--code", "OB"])
+            .status
+            .success());
+    }
+
+    /// ID { char name[24] }, Mesh { ID id; int totvert },
+    /// Object { ID id; Mesh *data; Object *parent; float loc[3]; float mat[4][4]; SessionUID runtime }
+    /// where SessionUID is a type the DNA has no struct for
+    fn object_dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "name[24]",
+                "id",
+                "*data",
+                "*parent",
+                "loc[3]",
+                "mat[4][4]",
+                "totvert",
+                "runtime",
+            ]),
+            types: strings(&["char", "float", "int", "ID", "Mesh", "Object", "SessionUID"]),
+            tlens: vec![1, 4, 4, 24, 28, 120, 4],
+            structs: vec![
+                (3, vec![(0, 0)]),
+                (4, vec![(3, 1), (2, 6)]),
+                (5, vec![(3, 1), (4, 2), (5, 3), (1, 4), (1, 5), (6, 7)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    /// MECube at 0x1000 and OBCube at 0x2000 pointing at it
+    fn write_cube(dir: &Path) -> PathBuf {
+        let mut mesh = vec![0u8; 28];
+        write_str(&mut mesh, 0, "MECube");
+        mesh[24..28].copy_from_slice(&8i32.to_le_bytes());
+
+        let mut object = vec![0u8; 120];
+        write_str(&mut object, 0, "OBCube");
+        object[24..32].copy_from_slice(&0x1000u64.to_le_bytes());
+        object[32..40].copy_from_slice(&0x9000u64.to_le_bytes());
+        for (i, value) in [2.0f32, 3.0, 5.0].iter().enumerate() {
+            object[40 + i * 4..44 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        for i in 0..4 {
+            object[52 + i * 20..56 + i * 20].copy_from_slice(&1.0f32.to_le_bytes());
+        }
+        object[116..120].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+
+        let data = SyntheticBlend::new(402)
+            .typed_block(b"ME", 1, 1, &mesh)
+            .typed_block(b"OB", 2, 1, &object)
+            .block(b"DATA", &[1, 2, 3])
+            .dna(&object_dna())
+            .finish();
+        let path = dir.join("cube.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_decode_cube_object() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_cube(dir.path())).unwrap();
+
+        let index = blend_file.select_blocks(Some("OB"), Some("Cube"));
+        assert_eq!(index, [1]);
+        let decoded = decode_block(&blend_file.blocks[1], &blend_file.dna, &blend_file);
+        assert_eq!(decoded.struct_name.as_deref(), Some("Object"));
+        let value = |name: &str| {
+            let field = decoded.fields.iter().find(|f| f.name == name).unwrap();
+            field.value.to_string()
+        };
+        assert_eq!(value("loc[3]"), "[2.0, 3.0, 5.0]");
+        assert_eq!(value("*data"), "→ ME 'Cube' @0x1000");
+        assert_eq!(value("*parent"), "@0x9000 (no block)");
+        assert_eq!(value("runtime"), "<de ad be ef>");
+        // Multi-dimensional arrays are decoded flat
+        assert!(value("mat[4][4]").starts_with("[1.0, 0.0, 0.0, 0.0, 0.0, 1.0"));
+
+        let DecodedValue::Struct(id) = &decoded.fields[0].value else {
+            panic!("id should be expanded");
+        };
+        assert_eq!(
+            id.fields[0].value,
+            DecodedValue::String("OBCube".to_string())
+        );
+
+        let text = decoded.to_string();
+        assert!(
+            text.starts_with("Object\n  id: {ID}\n    name[24]: \"OBCube\"\n"),
+            "{text}"
+        );
+
+        // Blocks the DNA doesn't describe decode to their raw bytes
+        let raw = decode_block(&blend_file.blocks[2], &blend_file.dna, &blend_file);
+        assert!(raw.struct_name.is_none());
+        assert_eq!(
+            raw.fields[0].value,
+            DecodedValue::Opaque("01 02 03".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cli_show() {
+        let dir = tempdir().unwrap();
+        let path = write_cube(dir.path());
+        let show = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("show")
+                .arg("--file")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap()
+        };
+
+        let output = show(&["--code", "OB", "--name", "Cube"]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.starts_with("--- OB block 1 @0x2000 ---\nObject\n"),
+            "{stdout}"
+        );
+        assert!(stdout.contains("  loc[3]: [2.0, 3.0, 5.0]\n"), "{stdout}");
+        assert!(
+            stdout.contains("  *data: → ME 'Cube' @0x1000\n"),
+            "{stdout}"
+        );
+
+        let output = show(&["--name", "Cube", "--json"]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let blocks = json.as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        let data = &blocks[1]["decoded"]["fields"][1];
+        assert_eq!(data["value"]["kind"], "pointer");
+        assert_eq!(data["value"]["value"]["target"]["name"], "Cube");
+
+        assert!(!show(&["--code", "OB", "--name", "Sphere"]).status.success());
+        assert!(!show(&[]).status.success());
+    }
+}
+
+// Tests for reading ID, Image and Library flags across Blender versions
+#[cfg(test)]
+mod flags {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::flags::{IdFlags, ImageFlags, LibraryFlags, LinkFlags};
+    use blend_file_reader::header::{Endianness, PointerSize};
+    use blend_file_reader::library_link::LinkExtractionOptions;
+    use blend_file_reader::links_report::{LinksReportV3, LINKS_REPORT_V3_SCHEMA};
+    use blend_file_reader::BlendFile;
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    /// ID { char name[24]; short flag; short _pad }
+    /// Image { ID id; char filepath[64]; int flag }
+    /// Library { ID id; char filepath[64]; short tag; short _pad }
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["name[24]", "flag", "_pad", "id", "filepath[64]", "tag"]),
+            types: strings(&["char", "short", "int", "ID", "Image", "Library"]),
+            tlens: vec![1, 2, 4, 28, 96, 96],
+            structs: vec![
+                (3, vec![(0, 0), (1, 1), (1, 2)]),
+                (4, vec![(3, 3), (0, 4), (2, 1)]),
+                (5, vec![(3, 3), (0, 4), (1, 5), (1, 2)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn put(data: &mut [u8], offset: usize, value: u32, size: usize, endianness: Endianness) {
+        let bytes = match endianness {
+            Endianness::Little => value.to_le_bytes()[..size].to_vec(),
+            Endianness::Big => value.to_be_bytes()[4 - size..].to_vec(),
+        };
+        data[offset..offset + size].copy_from_slice(&bytes);
+    }
+
+    fn id_block(
+        name: &str,
+        path: &str,
+        id_flag: u32,
+        flag: u32,
+        flag_size: usize,
+        e: Endianness,
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; 96];
+        write_str(&mut data, 0, name);
+        put(&mut data, 24, id_flag, 2, e);
+        write_str(&mut data, 28, path);
+        put(&mut data, 92, flag, flag_size, e);
+        data
+    }
+
+    /// Flags of IMwood with `ID.flag = id_flag` and `Image.flag = image_flag`,
+    /// and of LIprops with `Library.tag = library_tag`
+    fn flags(
+        version: u32,
+        endianness: Endianness,
+        id_flag: u32,
+        image_flag: u32,
+        library_tag: u32,
+    ) -> (LinkFlags, LinkFlags) {
+        let data = SyntheticBlend::with_format(version, PointerSize::Bits64, endianness)
+            .typed_block(
+                b"IM",
+                1,
+                1,
+                &id_block("IMwood", "//wood.png", id_flag, image_flag, 4, endianness),
+            )
+            .typed_block(
+                b"LI",
+                2,
+                1,
+                &id_block("LIprops", "//props.blend", 0, library_tag, 2, endianness),
+            )
+            .dna(&dna())
+            .finish();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        let links = BlendFile::open(&path).unwrap().get_library_links().unwrap();
+        let of_type = |block_type: &str| {
+            let link = links.iter().find(|l| l.block_type == block_type).unwrap();
+            link.flags.unwrap()
+        };
+        (of_type("Image"), of_type("Library"))
+    }
+
+    const FAKE_USER: u32 = 1 << 9;
+
+    #[test]
+    fn test_flags_of_a_current_file() {
+        let image = (1 << 0) | (1 << 12) | (1 << 14);
+        let (flags, library) = flags(402, Endianness::Little, FAKE_USER | (1 << 1), image, 1);
+
+        // Bit 1 has been a runtime tag since 2.77
+        assert_eq!(flags.id, IdFlags::FAKE_USER);
+        assert_eq!(
+            flags.image,
+            Some(ImageFlags::HIGH_BITDEPTH | ImageFlags::USE_VIEWS)
+        );
+        assert_eq!(flags.library, None);
+
+        assert_eq!(library.id, IdFlags::empty());
+        assert_eq!(library.image, None);
+        assert_eq!(library.library, Some(LibraryFlags::RESYNC_REQUIRED));
+    }
+
+    #[test]
+    fn test_flags_of_an_old_file() {
+        let image = (1 << 0) | (1 << 12) | (1 << 14);
+        let (flags, library) = flags(
+            276,
+            Endianness::Big,
+            FAKE_USER | (1 << 1) | (1 << 10),
+            image,
+            1,
+        );
+
+        assert_eq!(flags.id, IdFlags::FAKE_USER | IdFlags::INDIRECT);
+        // Bit 0 meant IMA_FIELDS, bit 12 IMA_IGNORE_ALPHA
+        assert_eq!(
+            flags.image,
+            Some(ImageFlags::IGNORE_ALPHA | ImageFlags::USE_VIEWS)
+        );
+        assert_eq!(library.library, Some(LibraryFlags::empty()));
+    }
+
+    #[test]
+    fn test_from_raw_version_table() {
+        assert_eq!(IdFlags::from_raw(1 << 11, 279), IdFlags::empty());
+        assert_eq!(IdFlags::from_raw(1 << 11, 280), IdFlags::INDIRECT_WEAK_LINK);
+        assert_eq!(IdFlags::from_raw(1 << 12, 283), IdFlags::empty());
+        assert_eq!(
+            IdFlags::from_raw(1 << 12, 290),
+            IdFlags::EMBEDDED_DATA_LIB_OVERRIDE
+        );
+        assert_eq!(
+            IdFlags::from_raw(1 | 2, 276),
+            IdFlags::EXTERN | IdFlags::INDIRECT
+        );
+        assert_eq!(
+            ImageFlags::from_raw(1 << 15 | 1 << 16, 279),
+            ImageFlags::IS_STEREO | ImageFlags::IS_MULTIVIEW
+        );
+        assert_eq!(
+            ImageFlags::from_raw(1 << 15 | 1 << 16, 280),
+            ImageFlags::empty()
+        );
+        assert_eq!(LibraryFlags::from_raw(1, 293), LibraryFlags::empty());
+    }
+
+    #[test]
+    fn test_no_flags_without_dna() {
+        let data = SyntheticBlend::new(402)
+            .image("IMwood", "//wood.png")
+            .finish();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        let links = BlendFile::open(&path).unwrap().get_library_links().unwrap();
+        assert_eq!(links[0].flags, None);
+    }
+
+    #[test]
+    fn test_flags_in_v3_report() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(402)
+            .typed_block(
+                b"IM",
+                1,
+                1,
+                &id_block(
+                    "IMwood",
+                    "//wood.png",
+                    FAKE_USER,
+                    1 << 14,
+                    4,
+                    Endianness::Little,
+                ),
+            )
+            .dna(&dna())
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        let blend_file = BlendFile::open(&path).unwrap();
+        let (links, stats) = blend_file
+            .get_library_links_with_stats(&LinkExtractionOptions::default())
+            .unwrap();
+
+        let report = serde_json::to_value(LinksReportV3::new(&blend_file, &links, stats)).unwrap();
+        let schema: Value = serde_json::from_str(LINKS_REPORT_V3_SCHEMA).unwrap();
+        assert!(
+            jsonschema::validator_for(&schema)
+                .unwrap()
+                .is_valid(&report),
+            "{report:#}"
+        );
+        assert_eq!(report["schema_version"], 3);
+        let flags = &report["links"][0]["flags"];
+        assert_eq!(flags["id"], "FAKE_USER");
+        assert_eq!(flags["image"], "USE_VIEWS");
+        assert_eq!(flags["library"], Value::Null);
+
+        // Flags the schema doesn't know are rejected
+        let mut drifted = report.clone();
+        drifted["links"][0]["flags"]["id"] = Value::from("FAKE_USER | MISSING");
+        assert!(!jsonschema::validator_for(&schema)
+            .unwrap()
+            .is_valid(&drifted));
+    }
+}
+
+// Tests for the block code registry and the codes subcommand
+#[cfg(test)]
+mod block_code {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::blend_file::SummaryOrder;
+    use blend_file_reader::block_code::{block_code_registry, display_name, lookup, raw_code};
+    use blend_file_reader::BlendFile;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_known_codes() {
+        let brush = lookup(b"BR\0\0", 402).unwrap();
+        assert_eq!((brush.name, brush.struct_name), ("Brush", Some("Brush")));
+        assert_eq!(
+            lookup(b"WM\0\0", 300).unwrap().struct_name,
+            Some("wmWindowManager")
+        );
+        assert_eq!(
+            lookup(b"GLOB", 300).unwrap().struct_name,
+            Some("FileGlobal")
+        );
+        assert_eq!(lookup(b"DNA1", 300).unwrap().struct_name, None);
+
+        // TEST starts with the texture code but is the thumbnail block
+        assert_eq!(display_name(b"TEST", 402), "Thumbnail");
+        assert_eq!(display_name(b"TE\0\0", 402), "Texture");
+    }
+
+    #[test]
+    fn test_version_dependent_codes() {
+        assert_eq!(lookup(b"GR\0\0", 279).unwrap().struct_name, Some("Group"));
+        assert_eq!(
+            lookup(b"GR\0\0", 280).unwrap().struct_name,
+            Some("Collection")
+        );
+        assert_eq!(display_name(b"LA\0\0", 249), "Lamp");
+        assert_eq!(display_name(b"LA\0\0", 405), "Light");
+        assert!(lookup(b"IP\0\0", 249).is_some());
+        assert!(lookup(b"IP\0\0", 250).is_none());
+
+        // Every code has exactly one meaning in any version
+        for version in [249, 279, 280, 290, 300, 402, 405] {
+            for info in block_code_registry() {
+                let same: Vec<_> = block_code_registry()
+                    .iter()
+                    .filter(|other| other.code == info.code && other.applies_to(version))
+                    .collect();
+                assert!(same.len() <= 1, "{} in {version}", info.code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_codes_pass_through() {
+        assert!(lookup(b"ZZ\0\0", 402).is_none());
+        assert_eq!(display_name(b"ZZ\0\0", 402), "ZZ");
+        assert_eq!(raw_code(b"Q\x01\xff\0"), "Q\\x01\\xff");
+        assert_eq!(display_name(b"\0\0\0\0", 402), "");
+        assert!(lookup(b"G", 402).is_none());
+    }
+
+    #[test]
+    fn test_summary_and_blocks_show_names() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(279)
+            .block(b"GR", &[0; 8])
+            .block(b"ZZ", &[0; 8])
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let summary = BlendFile::open(&path).unwrap().summary(SummaryOrder::Code);
+        let names: Vec<(&str, &str)> = summary
+            .block_types
+            .iter()
+            .map(|t| (t.code.as_str(), t.name.as_str()))
+            .collect();
+        assert_eq!(names, [("GR", "Group"), ("ZZ", "ZZ")]);
+        let text = summary.to_string();
+        assert!(text.contains("  GR (Group): 1 (8 bytes)"), "{text}");
+        assert!(text.contains("  ZZ: 1 (8 bytes)"), "{text}");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("blocks")
+            .arg(&path)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.lines().any(|l| l.starts_with("GR       Group ")),
+            "{stdout}"
+        );
+    }
+
+    #[test]
+    fn test_cli_codes() {
+        let run = |args: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("codes")
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let all = run(&[]);
+        assert!(all.contains("Group"), "{all}");
+        assert!(all.contains("Collection"), "{all}");
+
+        let old = run(&["--for-version", "279"]);
+        assert!(old.lines().any(|l| l.starts_with("GR     Group")), "{old}");
+        assert!(!old.contains("Collection"), "{old}");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&run(&["--json", "--for-version", "402"])).unwrap();
+        let gr = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["code"] == "GR")
+            .unwrap();
+        assert_eq!(gr["struct_name"], "Collection");
+    }
 }
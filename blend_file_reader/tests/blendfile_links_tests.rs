@@ -0,0 +1,1938 @@
+// Tests for library link extraction, resolution, status and reporting
+
+mod common;
+
+// Tests for per-link asset status (size, mtime) used by `links --stat`
+#[cfg(test)]
+mod asset_status {
+    use crate::common::image_link;
+    use blend_file_reader::library_link::LibraryLinkExtractor;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    fn set_mtime(path: &std::path::Path, mtime: SystemTime) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(mtime).unwrap();
+    }
+
+    #[test]
+    fn test_stat_empty_texture() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+        fs::create_dir(dir.path().join("textures")).unwrap();
+        fs::write(dir.path().join("textures/empty.png"), b"").unwrap();
+        fs::write(dir.path().join("textures/wood.png"), b"not empty").unwrap();
+
+        let mut links = vec![
+            image_link("//textures/empty.png"),
+            image_link("//textures/wood.png"),
+            image_link("//textures/missing.png"),
+        ];
+        let extractor = LibraryLinkExtractor::new(&blend_path);
+        extractor.resolve_relative_paths(&mut links).unwrap();
+        extractor.stat_links(&mut links).unwrap();
+
+        let empty = links[0].asset_status.as_ref().unwrap();
+        assert!(empty.exists);
+        assert_eq!(empty.size, Some(0));
+        assert!(empty.is_empty());
+        assert!(empty.mtime.is_some());
+
+        let wood = links[1].asset_status.as_ref().unwrap();
+        assert!(wood.exists);
+        assert_eq!(wood.size, Some(9));
+        assert!(!wood.is_empty());
+
+        let missing = links[2].asset_status.as_ref().unwrap();
+        assert!(!missing.exists);
+        assert_eq!(missing.size, None);
+        assert_eq!(missing.mtime, None);
+        assert!(!missing.is_empty());
+        assert!(!missing.newer_than_blend);
+    }
+
+    #[test]
+    fn test_stat_newer_and_older_than_blend() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+        fs::write(dir.path().join("old.exr"), b"old").unwrap();
+        fs::write(dir.path().join("new.exr"), b"new").unwrap();
+
+        let now = SystemTime::now();
+        set_mtime(&blend_path, now - Duration::from_secs(3600));
+        set_mtime(&dir.path().join("old.exr"), now - Duration::from_secs(7200));
+        set_mtime(&dir.path().join("new.exr"), now);
+
+        let mut links = vec![image_link("//old.exr"), image_link("//new.exr")];
+        let extractor = LibraryLinkExtractor::new(&blend_path);
+        extractor.resolve_relative_paths(&mut links).unwrap();
+        extractor.stat_links(&mut links).unwrap();
+
+        assert!(!links[0].asset_status.as_ref().unwrap().newer_than_blend);
+        assert!(links[1].asset_status.as_ref().unwrap().newer_than_blend);
+    }
+
+    #[test]
+    fn test_stat_json_uses_rfc3339() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+        fs::write(dir.path().join("wood.png"), b"wood").unwrap();
+
+        let mut links = vec![image_link("//wood.png")];
+        let extractor = LibraryLinkExtractor::new(&blend_path);
+        extractor.resolve_relative_paths(&mut links).unwrap();
+        extractor.stat_links(&mut links).unwrap();
+
+        let json = serde_json::to_value(&links[0]).unwrap();
+        let mtime = json["asset_status"]["mtime"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(mtime).is_ok());
+
+        // Links without a status keep the original JSON shape
+        let plain = serde_json::to_value(image_link("//wood.png")).unwrap();
+        assert!(plain.get("asset_status").is_none());
+    }
+}
+
+// Tests for streaming library link extraction with early termination
+#[cfg(test)]
+mod link_streaming {
+    use crate::common::{shifted_library_blend, CountingReader, SyntheticBlend};
+    use blend_file_reader::library_link::{
+        extract_links_streaming, extract_links_streaming_from_reader,
+        extract_links_streaming_from_unseekable_reader, LinkExtractionOptions,
+    };
+    use blend_file_reader::BlendFile;
+    use std::io::Cursor;
+    use std::ops::ControlFlow;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn sample_blend() -> Vec<u8> {
+        let mut blend = SyntheticBlend::new(300)
+            .library("lib.blend", "//libs/characters.blend")
+            .image("wood", "//textures/wood.png");
+        // Plenty of filler after the first links so early exit is observable
+        for _ in 0..64 {
+            blend = blend.block(b"DATA", &[0u8; 4096]);
+        }
+        blend.image("stone", "/abs/textures/stone.png").finish()
+    }
+
+    #[test]
+    fn test_break_after_first_link_stops_reading() {
+        let data = sample_blend();
+        let count = Arc::new(AtomicU64::new(0));
+        let reader = CountingReader {
+            inner: Cursor::new(data.clone()),
+            count: count.clone(),
+        };
+
+        let mut seen = Vec::new();
+        let stats = extract_links_streaming_from_reader(
+            reader,
+            Path::new("/project/scene.blend"),
+            |link| {
+                seen.push(link);
+                ControlFlow::Break(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].path, "//libs/characters.blend");
+        assert_eq!(
+            seen[0].absolute_path.as_deref(),
+            Some("/project/libs/characters.blend")
+        );
+        assert!(stats.stopped_early);
+        assert_eq!(stats.links_found, 1);
+        assert_eq!(stats.blocks_scanned, 1);
+        assert!(count.load(Ordering::SeqCst) < data.len() as u64 / 4);
+        assert!(stats.bytes_read <= count.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_full_stream_matches_get_library_links() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        std::fs::write(&blend_path, sample_blend()).unwrap();
+
+        let mut streamed = Vec::new();
+        let stats = extract_links_streaming(&blend_path, |link| {
+            streamed.push(link);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        let blend_file = BlendFile::open(&blend_path).unwrap();
+        let mut expected = blend_file.get_library_links().unwrap();
+
+        assert!(!stats.stopped_early);
+        assert_eq!(stats.links_found, 3);
+        assert_eq!(stats.blocks_scanned, blend_file.blocks.len());
+
+        // Streaming yields links in file order; the eager API groups them by type
+        let key = |l: &blend_file_reader::LibraryLink| (l.block_type.clone(), l.path.clone());
+        streamed.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_stream_reads_fields_through_dna() {
+        let dir = tempdir().unwrap();
+        let data = shifted_library_blend("//libs/kit.blend");
+        let plain = dir.path().join("scene.blend");
+        std::fs::write(&plain, &data).unwrap();
+        let compressed = dir.path().join("compressed.blend");
+        std::fs::write(&compressed, zstd::encode_all(&data[..], 1).unwrap()).unwrap();
+
+        let expected = BlendFile::open(&plain)
+            .unwrap()
+            .get_library_links()
+            .unwrap();
+        assert_eq!(expected.len(), 1);
+        assert_eq!(expected[0].path, "//libs/kit.blend");
+        for path in [&plain, &compressed] {
+            let mut streamed = Vec::new();
+            extract_links_streaming(path, |link| {
+                streamed.push(link);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+            assert_eq!(streamed.len(), 1, "{}", path.display());
+            assert_eq!(streamed[0].path, expected[0].path);
+            assert_eq!(streamed[0].block_name, expected[0].block_name);
+        }
+
+        // Without a way back to the DNA, strict mode refuses to guess
+        let strict = LinkExtractionOptions::default().with_strict(true);
+        let result =
+            extract_links_streaming_from_unseekable_reader(&data[..], &plain, &strict, |_| {
+                ControlFlow::Continue(())
+            });
+        assert!(result.is_err());
+    }
+}
+
+// Tests for the placeholder path policy of library link extraction
+#[cfg(test)]
+mod placeholder_link {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::library_link::{is_placeholder_path, LinkExtractionOptions};
+    use blend_file_reader::BlendFile;
+    use tempfile::tempdir;
+
+    const PLACEHOLDERS: &[&str] = &["", " ", "   ", "//", " // ", "Untitled", "//Untitled"];
+
+    fn open_with_images(paths: &[&str]) -> (tempfile::TempDir, BlendFile) {
+        let dir = tempdir().unwrap();
+        let mut blend = SyntheticBlend::new(300);
+        for (i, path) in paths.iter().enumerate() {
+            blend = blend.image(&format!("image_{i}"), path);
+        }
+        let blend_path = dir.path().join("scene.blend");
+        std::fs::write(&blend_path, blend.finish()).unwrap();
+        let blend_file = BlendFile::open(&blend_path).unwrap();
+        (dir, blend_file)
+    }
+
+    #[test]
+    fn test_placeholder_forms() {
+        for path in PLACEHOLDERS {
+            assert!(
+                is_placeholder_path(path),
+                "{path:?} should be a placeholder"
+            );
+        }
+        assert!(!is_placeholder_path("//textures/wood.png"));
+        assert!(!is_placeholder_path("//Untitled.png"));
+    }
+
+    #[test]
+    fn test_placeholders_excluded_by_default() {
+        for path in PLACEHOLDERS {
+            let (_dir, blend_file) = open_with_images(&[path]);
+            let links = blend_file.get_library_links().unwrap();
+            assert!(links.is_empty(), "{path:?} produced {links:?}");
+        }
+    }
+
+    #[test]
+    fn test_placeholders_included_with_flag() {
+        let options = LinkExtractionOptions::default().with_placeholders(true);
+        for path in PLACEHOLDERS {
+            let (_dir, blend_file) = open_with_images(&[path]);
+            let links = blend_file.get_library_links_with_options(&options).unwrap();
+            assert_eq!(links.len(), 1, "{path:?} was not reported");
+            assert!(links[0].is_placeholder);
+            assert_eq!(links[0].path, *path);
+        }
+    }
+
+    #[test]
+    fn test_real_paths_are_not_flagged() {
+        let (_dir, blend_file) = open_with_images(&["//textures/wood.png", "//"]);
+        let options = LinkExtractionOptions::default().with_placeholders(true);
+
+        let links = blend_file.get_library_links_with_options(&options).unwrap();
+        assert_eq!(links.len(), 2);
+        assert!(!links[0].is_placeholder);
+        assert!(links[1].is_placeholder);
+
+        let links = blend_file.get_library_links().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].path, "//textures/wood.png");
+    }
+}
+
+// Tests for the exit-early "does this file reference X?" query
+#[cfg(test)]
+mod references_path {
+    use crate::common::{shifted_library_blend, CountingReader, SyntheticBlend};
+    use blend_file_reader::library_link::{references_path_from_reader, PathMatch};
+    use blend_file_reader::BlendFile;
+    use std::io::Cursor;
+    use std::path::Path;
+    use std::process::Command;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn sample_blend() -> Vec<u8> {
+        let mut blend = SyntheticBlend::new(300)
+            .library("LIrig", "//rigs/old_rig.blend")
+            .image("IMwood", "//textures/wood.png");
+        for _ in 0..64 {
+            blend = blend.block(b"DATA", &[0u8; 4096]);
+        }
+        blend.image("IMstone", "/abs/textures/stone.png").finish()
+    }
+
+    fn query(data: &[u8], query: &str, mode: PathMatch) -> (bool, u64) {
+        let count = Arc::new(AtomicU64::new(0));
+        let reader = CountingReader {
+            inner: Cursor::new(data.to_vec()),
+            count: count.clone(),
+        };
+        let (found, _) =
+            references_path_from_reader(reader, Path::new("/project/shot_010.blend"), query, mode)
+                .unwrap();
+        (found, count.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn test_first_match_stops_reading() {
+        let data = sample_blend();
+
+        let (found, read) = query(&data, "old_rig.blend", PathMatch::Suffix);
+        assert!(found);
+        assert!(
+            read < data.len() as u64 / 4,
+            "read {read} of {}",
+            data.len()
+        );
+
+        // A miss has to look at every block, after the header pass for the DNA
+        let (found, read) = query(&data, "new_rig.blend", PathMatch::Suffix);
+        assert!(!found);
+        assert!(read > data.len() as u64);
+    }
+
+    #[test]
+    fn test_match_modes() {
+        let data = sample_blend();
+        let cases = [
+            ("//rigs/old_rig.blend", PathMatch::Exact, true),
+            ("/project/rigs/old_rig.blend", PathMatch::Exact, true),
+            ("old_rig.blend", PathMatch::Exact, false),
+            ("old_rig.blend", PathMatch::Suffix, true),
+            ("rigs/old_rig.blend", PathMatch::Suffix, true),
+            ("rig.blend", PathMatch::Suffix, false),
+            ("rig.blend", PathMatch::Substring, true),
+            ("/abs/textures", PathMatch::Substring, true),
+            ("stone.jpg", PathMatch::Substring, false),
+        ];
+        for (path, mode, expected) in cases {
+            assert_eq!(query(&data, path, mode).0, expected, "{path} {mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_blend_file_references_path() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot_010.blend");
+        std::fs::write(&path, sample_blend()).unwrap();
+        let blend_file = BlendFile::open(&path).unwrap();
+
+        assert!(blend_file
+            .references_path("wood.png", PathMatch::Suffix)
+            .unwrap());
+        let resolved = dir.path().join("textures/wood.png");
+        assert!(blend_file
+            .references_path(resolved.to_str().unwrap(), PathMatch::Exact)
+            .unwrap());
+        assert!(!blend_file
+            .references_path("wood.jpg", PathMatch::Substring)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_references_path_agrees_with_links() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot_010.blend");
+        std::fs::write(&path, shifted_library_blend("//rigs/old_rig.blend")).unwrap();
+        let blend_file = BlendFile::open(&path).unwrap();
+
+        let links = blend_file.get_library_links().unwrap();
+        assert_eq!(links[0].path, "//rigs/old_rig.blend");
+        assert!(blend_file
+            .references_path("old_rig.blend", PathMatch::Suffix)
+            .unwrap());
+        assert!(blend_file_reader::library_link::file_references_path(
+            &path,
+            "old_rig.blend",
+            PathMatch::Suffix
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_cli_exit_codes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot_010.blend");
+        std::fs::write(&path, sample_blend()).unwrap();
+        let grep = |file: &Path, query: &str| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .args(["grep-path", "--file"])
+                .arg(file)
+                .args(["--path", query, "--match", "suffix"])
+                .status()
+                .unwrap()
+                .code()
+        };
+
+        assert_eq!(grep(&path, "old_rig.blend"), Some(0));
+        assert_eq!(grep(&path, "new_rig.blend"), Some(1));
+        assert_eq!(
+            grep(&dir.path().join("missing.blend"), "old_rig.blend"),
+            Some(2)
+        );
+    }
+}
+
+// Tests for link extraction with inline existence status
+#[cfg(test)]
+mod link_status {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::asset_fs::{AssetFs, AssetMetadata};
+    use blend_file_reader::library_link::{LibraryLink, LibraryLinkExtractor};
+    use blend_file_reader::link_status::LinkStatusOptions;
+    use blend_file_reader::BlendFile;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    /// Serves sizes from a map, denies anything under `/locked` and counts calls
+    #[derive(Default)]
+    struct CountingFs {
+        files: HashMap<PathBuf, u64>,
+        stats: RefCell<Vec<PathBuf>>,
+    }
+
+    impl AssetFs for CountingFs {
+        fn metadata(&self, path: &Path) -> io::Result<AssetMetadata> {
+            self.stats.borrow_mut().push(path.to_path_buf());
+            if path.starts_with("/locked") {
+                return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+            }
+            match self.files.get(path) {
+                Some(&len) => Ok(AssetMetadata {
+                    len,
+                    modified: None,
+                }),
+                None => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            Ok(Path::new("/real").join(path.strip_prefix("/").unwrap()))
+        }
+
+        fn read(&self, _path: &Path) -> io::Result<Vec<u8>> {
+            unreachable!("status checks don't read files")
+        }
+
+        fn read_dir(&self, _dir: &Path) -> io::Result<Vec<PathBuf>> {
+            unreachable!("status checks don't list directories")
+        }
+    }
+
+    #[test]
+    fn test_duplicate_paths_stat_once() {
+        let fs = CountingFs {
+            files: HashMap::from([(PathBuf::from("/project/wood.png"), 42)]),
+            ..Default::default()
+        };
+        let mut links = vec![
+            LibraryLink::new("//wood.png", "Image"),
+            LibraryLink::new("/project/wood.png", "Image"),
+            LibraryLink::new("//wood.png", "Image"),
+            LibraryLink::new("//gone.png", "Image"),
+        ];
+        let extractor = LibraryLinkExtractor::new("/project/scene.blend");
+        extractor.resolve_relative_paths(&mut links).unwrap();
+        extractor.fill_status(&mut links, &LinkStatusOptions::default(), &fs);
+
+        assert_eq!(
+            *fs.stats.borrow(),
+            [
+                PathBuf::from("/project/wood.png"),
+                PathBuf::from("/project/gone.png")
+            ]
+        );
+        for link in &links[..3] {
+            let status = link.status.as_ref().unwrap();
+            assert!(status.exists);
+            assert_eq!(status.size, Some(42));
+            assert_eq!(
+                status.canonical_path.as_deref(),
+                Some("/real/project/wood.png")
+            );
+        }
+        let gone = links[3].status.as_ref().unwrap();
+        assert!(!gone.exists);
+        assert_eq!(gone.error, None);
+    }
+
+    #[test]
+    fn test_unreadable_directory_fails_only_its_links() {
+        let fs = CountingFs {
+            files: HashMap::from([(PathBuf::from("/project/wood.png"), 1)]),
+            ..Default::default()
+        };
+        let mut links = vec![
+            LibraryLink::new("/locked/stone.png", "Image"),
+            LibraryLink::new("/project/wood.png", "Image"),
+        ];
+        let options = LinkStatusOptions::default().with_canonicalize(false);
+        LibraryLinkExtractor::new("/project/scene.blend").fill_status(&mut links, &options, &fs);
+
+        let locked = links[0].status.as_ref().unwrap();
+        assert!(!locked.exists);
+        assert!(locked
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("ermission denied"));
+        let wood = links[1].status.as_ref().unwrap();
+        assert!(wood.exists);
+        assert_eq!(wood.canonical_path, None);
+    }
+
+    #[test]
+    fn test_blend_file_links_with_status() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("wood.png"), b"wood").unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .image("IMwood", "//wood.png")
+            .image("IMgone", "//gone.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        let links = blend_file
+            .get_library_links_with_status(&LinkStatusOptions::default())
+            .unwrap();
+        let wood = links[0].status.as_ref().unwrap();
+        assert!(wood.exists);
+        assert_eq!(wood.size, Some(4));
+        let real = dir.path().join("wood.png").canonicalize().unwrap();
+        assert_eq!(
+            wood.canonical_path.as_deref(),
+            Some(&*real.to_string_lossy())
+        );
+        assert!(!links[1].status.as_ref().unwrap().exists);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_denied_on_disk() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("stone.png"), b"stone").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Root ignores directory permissions, leaving nothing to test
+        let denied = std::fs::metadata(locked.join("stone.png")).is_err();
+
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .image("IMstone", "//locked/stone.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        let links = BlendFile::open(&path)
+            .unwrap()
+            .get_library_links_with_status(&LinkStatusOptions::default());
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let status = links.unwrap()[0].status.clone().unwrap();
+        if denied {
+            assert!(!status.exists);
+            assert!(status.error.is_some());
+        } else {
+            assert!(status.exists);
+        }
+    }
+}
+
+// Tests for counting DNA fallbacks during link extraction
+#[cfg(test)]
+mod extraction_stats {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::library_link::{
+        ExtractionStats, LibraryLinkExtractor, LinkExtractionOptions,
+    };
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn open(dir: &std::path::Path, data: Vec<u8>) -> BlendFile {
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        BlendFile::open(&path).unwrap()
+    }
+
+    fn stats(blend_file: &BlendFile) -> ExtractionStats {
+        blend_file
+            .get_library_links_with_stats(&LinkExtractionOptions::default())
+            .unwrap()
+            .1
+    }
+
+    /// ID { char name[24] }, Image { ID id, char _pad[8], char filepath[64] }:
+    /// name at 0 and filepath at 32, away from the fixed offset 104
+    fn image_dna(with_filepath: bool) -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let filepath = if with_filepath {
+            "filepath[64]"
+        } else {
+            "path[64]"
+        };
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "_pad[8]", filepath]),
+            types: strings(&["char", "ID", "Image"]),
+            tlens: vec![1, 24, 96],
+            structs: vec![(1, vec![(0, 0)]), (2, vec![(1, 1), (0, 2), (0, 3)])],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn dna_image() -> Vec<u8> {
+        let mut payload = vec![0u8; 96];
+        write_str(&mut payload, 0, "IMgrain");
+        write_str(&mut payload, 32, "//grain.png");
+        payload
+    }
+
+    #[test]
+    fn test_no_dna_uses_version_table() {
+        let dir = tempdir().unwrap();
+        let data = SyntheticBlend::new(300)
+            .image("IMwood", "//wood.png")
+            .finish();
+        let blend_file = open(dir.path(), data);
+
+        let stats = stats(&blend_file);
+        assert_eq!(stats.dna_missing, 2);
+        assert_eq!(stats.version_table, 2);
+        assert_eq!(stats.field_missing + stats.raw_heuristic, 0);
+        assert_eq!(stats.fallbacks(), 2);
+    }
+
+    #[test]
+    fn test_old_or_unknown_version_is_raw_heuristic() {
+        let dir = tempdir().unwrap();
+        let data = SyntheticBlend::new(279)
+            .image("IMwood", "//wood.png")
+            .finish();
+        let blend_file = open(dir.path(), data);
+        assert_eq!(stats(&blend_file).raw_heuristic, 2);
+
+        // An extractor that isn't told the version can't vouch for the offsets
+        let (links, stats) = LibraryLinkExtractor::new(&blend_file.path)
+            .extract_links_with_stats(
+                &blend_file.blocks,
+                &blend_file.dna,
+                &LinkExtractionOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(links[0].path, "//wood.png");
+        assert_eq!(stats.raw_heuristic, 2);
+        assert_eq!(stats.version_table, 0);
+    }
+
+    #[test]
+    fn test_strict_rejects_raw_heuristic() {
+        let dir = tempdir().unwrap();
+        let data = SyntheticBlend::new(279)
+            .image("IMwood", "//wood.png")
+            .finish();
+        let blend_file = open(dir.path(), data);
+
+        let strict = LinkExtractionOptions::default().with_strict(true);
+        let err = blend_file
+            .get_library_links_with_options(&strict)
+            .unwrap_err();
+        assert!(
+            matches!(err, BlendFileError::Format(FormatError::DnaError(ref msg)) if msg.contains("Image.filepath"))
+        );
+
+        // Version-table offsets are accepted
+        let data = SyntheticBlend::new(300)
+            .image("IMwood", "//wood.png")
+            .finish();
+        let blend_file = open(dir.path(), data);
+        assert_eq!(
+            blend_file
+                .get_library_links_with_options(&strict)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_dna_fields_need_no_fallback() {
+        let dir = tempdir().unwrap();
+        let data = SyntheticBlend::new(279)
+            .typed_block(b"IM", 1, 1, &dna_image())
+            .dna(&image_dna(true))
+            .finish();
+        let blend_file = open(dir.path(), data);
+
+        let strict = LinkExtractionOptions::default().with_strict(true);
+        let (links, stats) = blend_file.get_library_links_with_stats(&strict).unwrap();
+        assert_eq!(stats, ExtractionStats::default());
+        assert_eq!(links[0].path, "//grain.png");
+        assert_eq!(links[0].block_name.as_deref(), Some("IMgrain"));
+    }
+
+    #[test]
+    fn test_missing_dna_field_falls_back() {
+        let dir = tempdir().unwrap();
+        let mut payload = dna_image();
+        payload.resize(1200, 0);
+        write_str(&mut payload, 104, "//fixed.png");
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"IM", 1, 1, &payload)
+            .dna(&image_dna(false))
+            .finish();
+        let blend_file = open(dir.path(), data);
+
+        let (links, stats) = blend_file
+            .get_library_links_with_stats(&LinkExtractionOptions::default())
+            .unwrap();
+        // filepath isn't in the DNA; the name still is
+        assert_eq!(stats.field_missing, 1);
+        assert_eq!(stats.version_table, 1);
+        assert_eq!(stats.dna_missing, 0);
+        assert_eq!(links[0].path, "//fixed.png");
+        assert_eq!(links[0].block_name.as_deref(), Some("IMgrain"));
+    }
+
+    #[test]
+    fn test_cli_strict() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("old.blend");
+        let data = SyntheticBlend::new(279)
+            .image("IMwood", "//wood.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let run = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("links")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        let lenient = run(&["--format", "json"]);
+        assert!(lenient.status.success());
+        // One summary warning for the whole file
+        let stderr = String::from_utf8_lossy(&lenient.stderr);
+        assert_eq!(stderr.matches("WARN").count(), 1, "{stderr}");
+        assert!(stderr.contains("raw_heuristic=2"), "{stderr}");
+
+        let strict = run(&["--strict"]);
+        assert!(!strict.status.success());
+        assert!(String::from_utf8_lossy(&strict.stderr).contains("guessed offsets"));
+    }
+}
+
+// Tests for canonical link keys and grouping differently spelled duplicates
+#[cfg(test)]
+mod duplicate_links {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::library_link::{
+        canonical_path, duplicate_links, LibraryLink, PathPlatform,
+    };
+    use blend_file_reader::BlendFile;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn paths(group: &[&LibraryLink]) -> Vec<String> {
+        group.iter().map(|link| link.path.clone()).collect()
+    }
+
+    #[test]
+    fn test_dot_segments() {
+        let key = |path| canonical_path(path, PathPlatform::Posix);
+        assert_eq!(key("//./textures/wood.jpg"), "//textures/wood.jpg");
+        assert_eq!(key("//textures/old/../wood.jpg"), "//textures/wood.jpg");
+        assert_eq!(key("//textures//wood.jpg"), "//textures/wood.jpg");
+        assert_eq!(key("/srv/../../wood.jpg"), "/wood.jpg");
+        assert_eq!(key("../textures/./wood.jpg"), "../textures/wood.jpg");
+        assert_eq!(key("//../shared/wood.jpg"), "//shared/wood.jpg");
+    }
+
+    #[test]
+    fn test_case_follows_platform() {
+        let link = LibraryLink::new("//Textures/Wood.JPG", "Image");
+        assert_eq!(
+            link.canonical_key(PathPlatform::Posix),
+            "//Textures/Wood.JPG"
+        );
+        assert_eq!(
+            link.canonical_key(PathPlatform::Windows),
+            "//textures/wood.jpg"
+        );
+        assert_eq!(
+            link.canonical_key(PathPlatform::MacOs),
+            "//textures/wood.jpg"
+        );
+    }
+
+    #[test]
+    fn test_backslashes() {
+        for platform in [PathPlatform::Posix, PathPlatform::Windows] {
+            assert_eq!(
+                canonical_path("//textures\\wood.jpg", platform),
+                "//textures/wood.jpg"
+            );
+        }
+        assert_eq!(
+            canonical_path("C:\\Assets\\.\\wood.jpg", PathPlatform::Windows),
+            "c:/assets/wood.jpg"
+        );
+    }
+
+    #[test]
+    fn test_grouping() {
+        let links: Vec<_> = [
+            "//textures/wood.jpg",
+            "//textures/stone.jpg",
+            "//./textures/wood.jpg",
+            "//textures/stone.jpg",
+            "//Textures\\Wood.jpg",
+        ]
+        .into_iter()
+        .map(|path| LibraryLink::new(path, "Image"))
+        .collect();
+
+        let posix = duplicate_links(&links, PathPlatform::Posix);
+        assert_eq!(posix.len(), 1);
+        assert_eq!(
+            paths(&posix[0]),
+            ["//textures/wood.jpg", "//./textures/wood.jpg"]
+        );
+
+        // Identical spellings alone are not reported
+        let windows = duplicate_links(&links, PathPlatform::Windows);
+        assert_eq!(windows.len(), 1);
+        assert_eq!(
+            paths(&windows[0]),
+            [
+                "//textures/wood.jpg",
+                "//./textures/wood.jpg",
+                "//Textures\\Wood.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_duplicates_and_cli() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .image("IMwood", "//textures/wood.jpg")
+            .image("IMwood.001", "//./textures/wood.jpg")
+            .image("IMstone", "//textures/stone.jpg")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let groups = BlendFile::open(&path).unwrap().duplicate_links().unwrap();
+        assert_eq!(groups.len(), 1);
+        let names: Vec<_> = groups[0]
+            .iter()
+            .map(|link| link.block_name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["IMwood", "IMwood.001"]);
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .args(["links", "--show-duplicates", "--format", "json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let key = json[0]["key"].as_str().unwrap();
+        assert!(key.ends_with("/textures/wood.jpg"), "{key}");
+        assert!(!key.contains("/./"), "{key}");
+        assert_eq!(json[0]["links"][1]["path"], "//./textures/wood.jpg");
+    }
+}
+
+// Tests for block codes that never yield library links
+#[cfg(test)]
+mod non_link_codes {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::library_link::{
+        extract_links_streaming, is_non_link_code, LibraryLinkExtractor, LinkExtractionOptions,
+    };
+    use blend_file_reader::BlendFile;
+    use std::ops::ControlFlow;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    const RECENT: &str = "/home/user/old.blend";
+
+    /// A UI block with a recent-file path at both fallback `filepath` offsets
+    fn recent_files_payload() -> Vec<u8> {
+        let mut payload = vec![0u8; 1200];
+        write_str(&mut payload, 104, RECENT);
+        write_str(&mut payload, 144, RECENT);
+        payload
+    }
+
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let data = SyntheticBlend::new(300)
+            .block(b"WM\0\0", &recent_files_payload())
+            .block(b"SR\0\0", &recent_files_payload())
+            .block(b"WS\0\0", &recent_files_payload())
+            .block(b"USER", &recent_files_payload())
+            .library("LIold", RECENT)
+            .finish();
+        let path = dir.join("ui.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_ui_blocks_yield_no_links() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let blend_file = BlendFile::open(&path).unwrap();
+
+        let links = blend_file.get_library_links().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].path, RECENT);
+        assert_eq!(links[0].block_type, "Library");
+
+        let mut streamed = Vec::new();
+        extract_links_streaming(&path, |link| {
+            streamed.push(link.path);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(streamed, [RECENT]);
+    }
+
+    #[test]
+    fn test_single_block_extraction_skips_ui_blocks() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let blend_file = BlendFile::open(&path).unwrap();
+        let extractor = LibraryLinkExtractor::new(&path);
+        let options = LinkExtractionOptions::default().with_placeholders(true);
+
+        let denied: Vec<_> = blend_file
+            .blocks
+            .iter()
+            .filter(|block| is_non_link_code(&block.code))
+            .collect();
+        assert_eq!(denied.len(), 4);
+        for block in denied {
+            let link = extractor
+                .extract_block_link(block, &blend_file.dna, &options)
+                .unwrap();
+            assert_eq!(link, None, "{:?}", block.code);
+        }
+        assert!(!is_non_link_code(b"LI\0\0"));
+    }
+}
+
+// Tests for the versioned links JSON report and its schema
+#[cfg(test)]
+mod links_report {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::library_link::LinkExtractionOptions;
+    use blend_file_reader::links_report::{
+        LinksReportV1, LinksReportV2, LINKS_REPORT_V1_SCHEMA, LINKS_REPORT_V2_SCHEMA,
+        LINKS_REPORT_V3_SCHEMA,
+    };
+    use blend_file_reader::BlendFile;
+    use serde_json::Value;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn schema() -> Value {
+        serde_json::from_str(LINKS_REPORT_V1_SCHEMA).unwrap()
+    }
+
+    fn schema_v2() -> Value {
+        serde_json::from_str(LINKS_REPORT_V2_SCHEMA).unwrap()
+    }
+
+    fn schema_v3() -> Value {
+        serde_json::from_str(LINKS_REPORT_V3_SCHEMA).unwrap()
+    }
+
+    fn write_fixture(dir: &std::path::Path) -> std::path::PathBuf {
+        let path = dir.join("shot.blend");
+        let data = SyntheticBlend::new(279)
+            .library("LIprops", "//props.blend")
+            .image("IMwood", "/textures/wood.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn run_links(args: &[&str]) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("links")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_report_matches_schema() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+        let links = blend_file.get_library_links().unwrap();
+        let report = serde_json::to_value(LinksReportV1::new(&blend_file, &links)).unwrap();
+
+        let validator = jsonschema::validator_for(&schema()).unwrap();
+        assert!(validator.is_valid(&report), "{report:#}");
+
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["blender_version"], "2.79");
+        assert_eq!(report["links"][0]["block_type"], "Library");
+        assert_eq!(report["links"][0]["path"], "//props.blend");
+        assert_eq!(report["links"][1]["is_relative"], false);
+    }
+
+    #[test]
+    fn test_schema_rejects_drift() {
+        let validator = jsonschema::validator_for(&schema()).unwrap();
+        let report = serde_json::json!({
+            "schema_version": 1,
+            "file": "shot.blend",
+            "blender_version": "4.2",
+            "links": [{
+                "block_type": "Image",
+                "block_name": null,
+                "path": "//wood.png",
+                "absolute_path": null,
+                "is_relative": true,
+                "asset_status": {"exists": true}
+            }]
+        });
+        // New internal fields must not leak into v1
+        assert!(!validator.is_valid(&report));
+    }
+
+    #[test]
+    fn test_cli_json_is_latest_version() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let path = path.to_str().unwrap();
+
+        let json: Value = serde_json::from_str(&run_links(&[path, "--format", "json"])).unwrap();
+        let v3: Value = serde_json::from_str(&run_links(&[path, "--format", "json-v3"])).unwrap();
+        assert_eq!(json, v3);
+        // --stat data stays out of the stable report
+        let stat: Value =
+            serde_json::from_str(&run_links(&[path, "--format", "json", "--stat"])).unwrap();
+        let validator = jsonschema::validator_for(&schema_v3()).unwrap();
+        assert!(validator.is_valid(&json), "{json:#}");
+        assert!(validator.is_valid(&stat));
+
+        // v1 and v2 keep their shape
+        let v2: Value = serde_json::from_str(&run_links(&[path, "--format", "json-v2"])).unwrap();
+        assert!(jsonschema::validator_for(&schema_v2())
+            .unwrap()
+            .is_valid(&v2));
+        let v1: Value = serde_json::from_str(&run_links(&[path, "--format", "json-v1"])).unwrap();
+        assert!(jsonschema::validator_for(&schema()).unwrap().is_valid(&v1));
+        assert_eq!(v1["links"], v2["links"]);
+        // Without DNA no flags can be read
+        assert_eq!(json["links"][0]["flags"], Value::Null);
+
+        let printed: Value = serde_json::from_str(&run_links(&["--schema"])).unwrap();
+        assert_eq!(printed, schema_v3());
+    }
+
+    #[test]
+    fn test_v2_report_includes_stats() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+        let (links, stats) = blend_file
+            .get_library_links_with_stats(&LinkExtractionOptions::default())
+            .unwrap();
+        let report = serde_json::to_value(LinksReportV2::new(&blend_file, &links, stats)).unwrap();
+
+        let validator = jsonschema::validator_for(&schema_v2()).unwrap();
+        assert!(validator.is_valid(&report), "{report:#}");
+        assert_eq!(report["schema_version"], 2);
+        // 2.79 without DNA: path and name of both links are guessed
+        assert_eq!(report["stats"]["dna_missing"], 4);
+        assert_eq!(report["stats"]["raw_heuristic"], 4);
+    }
+}
+
+// Tests for fitting the links table to a width and for --paths-only
+#[cfg(test)]
+mod links_table {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::links_report::{LinksTable, TableLayout};
+    use blend_file_reader::BlendFile;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    const LONG_PATH: &str =
+        "/mnt/projects/feature/assets/environment/forest/textures/bark_diffuse.png";
+
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let path = dir.join("shot.blend");
+        let data = SyntheticBlend::new(279)
+            .library("LIprops", "//props.blend")
+            .image("IMbark", LONG_PATH)
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn run_links(path: &Path, args: &[&str]) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .arg("links")
+            .arg(path)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_paths_only() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let stdout = run_links(&path, &["--paths-only"]);
+        let props = dir.path().join("props.blend");
+        assert_eq!(
+            stdout,
+            format!("{}\n{LONG_PATH}\n", props.display()),
+            "no header, one resolved path per line"
+        );
+    }
+
+    #[test]
+    fn test_table_fits_max_width() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        // Piped output isn't a terminal, so auto leaves paths whole
+        let stdout = run_links(&path, &[]);
+        assert!(stdout.contains(LONG_PATH), "{stdout}");
+
+        let stdout = run_links(&path, &["--max-width", "100"]);
+        let lines: Vec<&str> = stdout.lines().skip(1).collect();
+        assert!(
+            lines.iter().all(|line| line.chars().count() <= 100),
+            "{stdout}"
+        );
+        assert!(
+            lines[3].contains("/mnt/projects/feature/as…/bark_diffuse.png"),
+            "{stdout}"
+        );
+
+        let stdout = run_links(&path, &["--max-width", "100", "--wrap"]);
+        let lines: Vec<&str> = stdout.lines().skip(1).collect();
+        assert_eq!(lines.len(), 5, "{stdout}");
+        let continued = lines[4].trim_start();
+        assert_eq!(
+            lines[3].split_whitespace().nth(2).unwrap().to_string() + continued,
+            LONG_PATH
+        );
+    }
+
+    #[test]
+    fn test_render_without_limit() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+        let links = blend_file.get_library_links().unwrap();
+        let table = LinksTable::new(&links, false);
+        assert_eq!(table.columns.len(), 4);
+
+        let rendered = table.render(&TableLayout::default());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["Type", "Name", "Path", "Relative"]
+        );
+        // The Path column is as wide as the longest path
+        let path_at = lines[0].find("Path").unwrap();
+        assert_eq!(&lines[3][path_at..path_at + LONG_PATH.len()], LONG_PATH);
+        assert!(lines[3].ends_with("No"));
+        assert!(lines.iter().all(|line| !line.ends_with(' ')));
+    }
+}
+
+// Tests for listing the IDs a file links from its libraries
+#[cfg(test)]
+mod library_usage {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::library_usage::LinkedId;
+    use blend_file_reader::BlendFile;
+    use tempfile::tempdir;
+
+    /// ID { *next, *prev, *newid, *lib, name[66], pad[46] } (144 bytes),
+    /// Library { id, filepath[1024] }, Object { id, *data }, Mesh { id }
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "*next",
+                "*prev",
+                "*newid",
+                "*lib",
+                "name[66]",
+                "pad[46]",
+                "id",
+                "filepath[1024]",
+                "*data",
+            ]),
+            types: strings(&["char", "void", "ID", "Library", "Object", "Mesh"]),
+            tlens: vec![1, 0, 144, 1168, 152, 144],
+            structs: vec![
+                (2, vec![(2, 0), (2, 1), (2, 2), (3, 3), (0, 4), (0, 5)]),
+                (3, vec![(2, 6), (0, 7)]),
+                (4, vec![(2, 6), (1, 8)]),
+                (5, vec![(2, 6)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn id(name: &str, lib: u64) -> Vec<u8> {
+        let mut payload = vec![0u8; 144];
+        payload[24..32].copy_from_slice(&lib.to_le_bytes());
+        write_str(&mut payload, 32, name);
+        payload
+    }
+
+    fn library(filepath: &str) -> Vec<u8> {
+        let mut payload = id("LIcube.blend", 0);
+        payload.resize(1168, 0);
+        write_str(&mut payload, 144, filepath);
+        payload
+    }
+
+    fn object(name: &str, data: u64) -> Vec<u8> {
+        let mut payload = id(name, 0);
+        payload.extend_from_slice(&data.to_le_bytes());
+        payload
+    }
+
+    /// A local object uses the linked Cube mesh; the linked Cube object itself isn't used
+    fn write_linked_cube(dir: &std::path::Path) -> std::path::PathBuf {
+        let builder = SyntheticBlend::new(300);
+        let lib = builder.next_address();
+        let builder = builder.typed_block(b"LI", 1, 1, &library("//lib/cube.blend"));
+        let builder = builder.typed_block(b"ID", 0, 1, &id("OBCube", lib));
+        let linked_mesh = builder.next_address();
+        let data = builder
+            .typed_block(b"ID", 0, 1, &id("MECube", lib))
+            .typed_block(b"OB", 2, 1, &object("OBLocal", linked_mesh))
+            .typed_block(b"ME", 3, 1, &id("MELocal", 0))
+            .dna(&dna())
+            .finish();
+
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_linked_ids_grouped_by_library() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_linked_cube(dir.path())).unwrap();
+        let usage = blend_file.library_usage().unwrap();
+
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].path, "//lib/cube.blend");
+        assert_eq!(usage[0].ids.keys().collect::<Vec<_>>(), vec!["ME", "OB"]);
+        assert_eq!(
+            usage[0].ids["ME"],
+            vec![LinkedId {
+                name: "Cube".to_string(),
+                unreferenced: false,
+            }]
+        );
+        assert_eq!(
+            usage[0].ids["OB"],
+            vec![LinkedId {
+                name: "Cube".to_string(),
+                unreferenced: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_no_libraries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.blend");
+        std::fs::write(
+            &path,
+            SyntheticBlend::new(300)
+                .image("IMwood", "//wood.png")
+                .finish(),
+        )
+        .unwrap();
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        assert!(blend_file.library_usage().unwrap().is_empty());
+    }
+}
+
+// Tests for finding library links that lead back to the file they start from
+#[cfg(test)]
+mod dependency_cycle {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::dependencies::{dependency_cycles, DependencyCycle};
+    use blend_file_reader::BlendFile;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn write_blend(dir: &Path, name: &str, libraries: &[&str]) -> PathBuf {
+        let mut blend = SyntheticBlend::new(300);
+        for (i, library) in libraries.iter().enumerate() {
+            blend = blend.library(&format!("LIlib{i}"), library);
+        }
+        let path = dir.join(name);
+        std::fs::write(&path, blend.finish()).unwrap();
+        path.canonicalize().unwrap()
+    }
+
+    /// shot.blend -> set.blend <-> props.blend, props.blend -> props.blend,
+    /// plus a missing library that ends its chain
+    fn write_fixtures(dir: &Path) -> (PathBuf, PathBuf, PathBuf) {
+        let shot = write_blend(dir, "shot.blend", &["//set.blend", "//gone.blend"]);
+        let set = write_blend(dir, "set.blend", &["//props.blend"]);
+        let props = write_blend(
+            dir,
+            "props.blend",
+            &["//set.blend", "//props.blend", "//set.blend"],
+        );
+        (shot, set, props)
+    }
+
+    #[test]
+    fn test_cycles_reported_once() {
+        let dir = tempdir().unwrap();
+        let (shot, set, props) = write_fixtures(dir.path());
+
+        let cycles = dependency_cycles(&shot).unwrap();
+        assert_eq!(
+            cycles,
+            vec![vec![set.clone(), props.clone()], vec![props.clone()]]
+        );
+
+        // Starting inside the loop finds the same two cycles
+        let cycles = dependency_cycles(&props).unwrap();
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.contains(&vec![props.clone(), set.clone()]));
+        assert!(cycles.contains(&vec![props.clone()]));
+
+        assert_eq!(
+            DependencyCycle(vec![set.clone(), props.clone()]).to_string(),
+            format!(
+                "{} -> {} -> {}",
+                set.display(),
+                props.display(),
+                set.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_reference_without_recursion() {
+        let dir = tempdir().unwrap();
+        let (shot, _, props) = write_fixtures(dir.path());
+
+        let links = BlendFile::open(&props).unwrap().self_references().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].path, "//props.blend");
+        assert!(BlendFile::open(&shot)
+            .unwrap()
+            .self_references()
+            .unwrap()
+            .is_empty());
+
+        // Files parsed from memory have no path to compare against
+        let data = std::fs::read(&props).unwrap();
+        assert!(BlendFile::from_bytes(&data)
+            .unwrap()
+            .self_references()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_acyclic_chain() {
+        let dir = tempdir().unwrap();
+        write_blend(dir.path(), "props.blend", &[]);
+        let shot = write_blend(dir.path(), "shot.blend", &["//props.blend"]);
+        assert!(dependency_cycles(&shot).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_cycles() {
+        let dir = tempdir().unwrap();
+        let (shot, set, props) = write_fixtures(dir.path());
+        let validate = |path: &Path, recursive: bool| {
+            let mut command = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"));
+            command.arg("validate").arg(path);
+            if recursive {
+                command.arg("--recursive");
+            }
+            command.output().unwrap()
+        };
+
+        let output = validate(&shot, false);
+        assert!(output.status.success(), "{output:?}");
+
+        let output = validate(&shot, true);
+        assert!(!output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let chain = format!(
+            "circular library dependency: {} -> {} -> {}",
+            set.display(),
+            props.display(),
+            set.display()
+        );
+        assert!(stdout.contains(&chain), "{stdout}");
+        assert_eq!(stdout.matches("circular library dependency").count(), 2);
+
+        let output = validate(&props, false);
+        assert!(!output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains("library links its own file: //props.blend"),
+            "{stdout}"
+        );
+    }
+}
+
+// Tests for symlink and case-insensitive asset resolution
+#[cfg(test)]
+mod asset_resolution {
+    use crate::common::image_link;
+    use blend_file_reader::library_link::{
+        LibraryLink, LibraryLinkExtractor, Resolution, ResolveOptions,
+    };
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn resolve(blend_path: &std::path::Path, options: ResolveOptions, links: &mut [LibraryLink]) {
+        let extractor = LibraryLinkExtractor::new(blend_path).with_resolve_options(options);
+        extractor.resolve_relative_paths(links).unwrap();
+        extractor.stat_links(links).unwrap();
+    }
+
+    #[test]
+    fn test_case_insensitive_fallback() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+        fs::create_dir(dir.path().join("textures")).unwrap();
+        fs::write(dir.path().join("textures/Wood.JPG"), b"jpeg").unwrap();
+
+        let mut links = vec![
+            image_link("//textures/wood.jpg"),
+            image_link("//textures/oak.jpg"),
+        ];
+        let options = ResolveOptions::default().with_case_insensitive_fallback(true);
+        resolve(&blend_path, options, &mut links);
+
+        let wood = links[0].asset_status.as_ref().unwrap();
+        assert!(wood.exists);
+        assert_eq!(wood.size, Some(4));
+        assert_eq!(
+            wood.resolution,
+            Resolution::FoundWithDifferentCase {
+                actual: dir
+                    .path()
+                    .join("textures/Wood.JPG")
+                    .to_string_lossy()
+                    .into_owned(),
+            }
+        );
+        assert_eq!(
+            links[1].asset_status.as_ref().unwrap().resolution,
+            Resolution::Missing
+        );
+    }
+
+    #[test]
+    fn test_wrong_case_is_missing_without_fallback() {
+        let dir = tempdir().unwrap();
+        let blend_path = dir.path().join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+        fs::write(dir.path().join("Wood.JPG"), b"jpeg").unwrap();
+
+        let mut links = vec![image_link("//wood.jpg")];
+        resolve(&blend_path, ResolveOptions::default(), &mut links);
+
+        let status = links[0].asset_status.as_ref().unwrap();
+        // Case-insensitive filesystems find the file directly
+        if status.exists {
+            assert_eq!(status.resolution, Resolution::Found);
+        } else {
+            assert_eq!(status.resolution, Resolution::Missing);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_reports_real_path() {
+        let dir = tempdir().unwrap();
+        let real_root = dir.path().join("storage/project");
+        fs::create_dir_all(real_root.join("textures")).unwrap();
+        fs::write(real_root.join("textures/wood.png"), b"png").unwrap();
+        let mounted = dir.path().join("project");
+        std::os::unix::fs::symlink(&real_root, &mounted).unwrap();
+
+        let blend_path = mounted.join("scene.blend");
+        fs::write(&blend_path, b"BLENDER-v300").unwrap();
+
+        let mut plain = vec![image_link("//textures/wood.png")];
+        resolve(&blend_path, ResolveOptions::default(), &mut plain);
+        let plain_path = plain[0].absolute_path.clone().unwrap();
+        assert!(plain_path.starts_with(&*mounted.to_string_lossy()));
+
+        let mut followed = vec![
+            image_link("//textures/wood.png"),
+            image_link("//missing.png"),
+        ];
+        let options = ResolveOptions::default().with_follow_symlinks(true);
+        resolve(&blend_path, options, &mut followed);
+
+        let real = real_root.canonicalize().unwrap();
+        assert_eq!(
+            followed[0].absolute_path.as_deref(),
+            Some(&*real.join("textures/wood.png").to_string_lossy())
+        );
+        assert_eq!(
+            followed[0].asset_status.as_ref().unwrap().resolution,
+            Resolution::Found
+        );
+        // Missing targets still resolve against the real blend directory
+        assert_eq!(
+            followed[1].absolute_path.as_deref(),
+            Some(&*real.join("missing.png").to_string_lossy())
+        );
+    }
+}
+
+// Tests for asset checks routed through the AssetFs abstraction
+#[cfg(test)]
+mod asset_fs {
+    use crate::common::image_link;
+    use blend_file_reader::asset_fs::{AssetFs, AssetMetadata, MemFs};
+    use blend_file_reader::library_link::{
+        LibraryLink, LibraryLinkExtractor, Resolution, ResolveOptions,
+    };
+    use blend_file_reader::link_status::LinkStatusOptions;
+    use std::cell::RefCell;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn stat(fs: &dyn AssetFs, options: ResolveOptions, links: &mut [LibraryLink]) {
+        let extractor =
+            LibraryLinkExtractor::new("/project/scene.blend").with_resolve_options(options);
+        extractor.resolve_relative_paths_in(links, fs).unwrap();
+        extractor.stat_links_in(links, fs).unwrap();
+    }
+
+    /// Records every call before forwarding it to a `MemFs`
+    struct CountingFs {
+        inner: MemFs,
+        calls: RefCell<Vec<(&'static str, PathBuf)>>,
+    }
+
+    impl CountingFs {
+        fn new(inner: MemFs) -> Self {
+            Self {
+                inner,
+                calls: RefCell::default(),
+            }
+        }
+
+        fn record(&self, call: &'static str, path: &Path) {
+            self.calls.borrow_mut().push((call, path.to_path_buf()));
+        }
+    }
+
+    impl AssetFs for CountingFs {
+        fn metadata(&self, path: &Path) -> io::Result<AssetMetadata> {
+            self.record("metadata", path);
+            self.inner.metadata(path)
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.record("read", path);
+            self.inner.read(path)
+        }
+
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            self.record("canonicalize", path);
+            self.inner.canonicalize(path)
+        }
+
+        fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+            self.record("read_dir", dir);
+            self.inner.read_dir(dir)
+        }
+    }
+
+    #[test]
+    fn test_stat_empty_texture() {
+        let fs = MemFs::new()
+            .with_file("/project/scene.blend", "BLENDER-v300")
+            .with_file("/project/textures/empty.png", "")
+            .with_file("/project/textures/wood.png", "not empty");
+        let mut links = vec![
+            image_link("//textures/empty.png"),
+            image_link("//textures/wood.png"),
+            image_link("//textures/missing.png"),
+        ];
+        stat(&fs, ResolveOptions::default(), &mut links);
+
+        let empty = links[0].asset_status.as_ref().unwrap();
+        assert!(empty.exists);
+        assert!(empty.is_empty());
+        let wood = links[1].asset_status.as_ref().unwrap();
+        assert_eq!(wood.size, Some(9));
+        let missing = links[2].asset_status.as_ref().unwrap();
+        assert!(!missing.exists);
+        assert_eq!(missing.size, None);
+    }
+
+    #[test]
+    fn test_stat_newer_and_older_than_blend() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut fs = MemFs::new()
+            .with_file("/project/scene.blend", "BLENDER-v300")
+            .with_file("/project/old.exr", "old")
+            .with_file("/project/new.exr", "new");
+        fs.set_modified("/project/scene.blend", now - Duration::from_secs(3600));
+        fs.set_modified("/project/old.exr", now - Duration::from_secs(7200));
+        fs.set_modified("/project/new.exr", now);
+
+        let mut links = vec![image_link("//old.exr"), image_link("//new.exr")];
+        stat(&fs, ResolveOptions::default(), &mut links);
+
+        let old = links[0].asset_status.as_ref().unwrap();
+        assert!(!old.newer_than_blend);
+        assert_eq!(old.mtime.unwrap().timestamp(), 1_700_000_000 - 7200);
+        assert!(links[1].asset_status.as_ref().unwrap().newer_than_blend);
+    }
+
+    #[test]
+    fn test_case_insensitive_fallback() {
+        let fs = MemFs::new().with_file("/project/textures/Wood.JPG", "jpeg");
+        let mut links = vec![
+            image_link("//textures/wood.jpg"),
+            image_link("//textures/oak.jpg"),
+        ];
+        let options = ResolveOptions::default().with_case_insensitive_fallback(true);
+        stat(&fs, options, &mut links);
+
+        let wood = links[0].asset_status.as_ref().unwrap();
+        assert_eq!(wood.size, Some(4));
+        assert_eq!(
+            wood.resolution,
+            Resolution::FoundWithDifferentCase {
+                actual: "/project/textures/Wood.JPG".to_string(),
+            }
+        );
+        assert_eq!(
+            links[1].asset_status.as_ref().unwrap().resolution,
+            Resolution::Missing
+        );
+    }
+
+    #[test]
+    fn test_follow_symlinks_normalizes_through_fs() {
+        let fs = MemFs::new().with_file("/project/textures/wood.png", "wood");
+        let mut links = vec![image_link("//textures/../textures/wood.png")];
+        let options = ResolveOptions::default().with_follow_symlinks(true);
+        stat(&fs, options, &mut links);
+
+        assert_eq!(
+            links[0].absolute_path.as_deref(),
+            Some("/project/textures/wood.png")
+        );
+        assert_eq!(
+            links[0].asset_status.as_ref().unwrap().resolution,
+            Resolution::Found
+        );
+    }
+
+    #[test]
+    fn test_stat_call_pattern() {
+        let fs = CountingFs::new(
+            MemFs::new()
+                .with_file("/project/scene.blend", "BLENDER-v300")
+                .with_file("/project/tex/wood.png", "wood")
+                .with_file("/project/tex/rock.png", "rock"),
+        );
+        let mut links = vec![
+            image_link("//tex/wood.png"),
+            image_link("//tex/wood.png"),
+            image_link("//tex/ROCK.png"),
+            image_link("//tex/gone.png"),
+        ];
+        let options = ResolveOptions::default().with_case_insensitive_fallback(true);
+        stat(&fs, options, &mut links);
+
+        let calls = fs.calls.borrow();
+        let calls: Vec<(&str, &str)> = calls
+            .iter()
+            .map(|(call, path)| (*call, path.to_str().unwrap()))
+            .collect();
+        // One metadata call per unique target; directories are only listed
+        // for targets that miss, and nothing is read
+        assert_eq!(
+            calls,
+            [
+                ("metadata", "/project/scene.blend"),
+                ("metadata", "/project/tex/wood.png"),
+                ("metadata", "/project/tex/ROCK.png"),
+                ("read_dir", "/project/tex"),
+                ("metadata", "/project/tex/rock.png"),
+                ("metadata", "/project/tex/gone.png"),
+                ("read_dir", "/project/tex"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_status_through_memfs() {
+        let fs = CountingFs::new(MemFs::new().with_file("/project/wood.png", "wood"));
+        let mut links = vec![image_link("//wood.png"), image_link("//wood.png")];
+        let extractor = LibraryLinkExtractor::new("/project/scene.blend");
+        extractor
+            .resolve_relative_paths_in(&mut links, &fs)
+            .unwrap();
+        extractor.fill_status(&mut links, &LinkStatusOptions::default(), &fs);
+
+        let status = links[1].status.as_ref().unwrap();
+        assert!(status.exists);
+        assert_eq!(status.size, Some(4));
+        assert_eq!(status.canonical_path.as_deref(), Some("/project/wood.png"));
+        assert_eq!(fs.calls.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_memfs_basics() {
+        let fs = MemFs::new()
+            .with_file("/a/b/one.png", "1")
+            .with_file("/a/b/c/two.png", "22")
+            .with_file("/a/three.png", "333");
+
+        assert!(fs.exists(Path::new("/a/./b/one.png")).unwrap());
+        assert!(!fs.exists(Path::new("/a/b/none.png")).unwrap());
+        assert_eq!(
+            fs.read_dir(Path::new("/a/b")).unwrap(),
+            [PathBuf::from("/a/b/c"), PathBuf::from("/a/b/one.png")]
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("/missing")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            fs.canonicalize(Path::new("/a/b/c/../../three.png"))
+                .unwrap(),
+            PathBuf::from("/a/three.png")
+        );
+
+        let mut out = Vec::new();
+        assert_eq!(
+            fs.copy_in(Path::new("/a/b/c/two.png"), &mut out).unwrap(),
+            2
+        );
+        assert_eq!(out, b"22");
+    }
+}
+
+// Tests for libraries packed into the blend file
+#[cfg(test)]
+mod packed_library {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::BlendFile;
+    use tempfile::tempdir;
+
+    /// Library { _pad0[144], filepath[1024], *packedfile }, PackedFile { size, seek, *data },
+    /// ID { *lib, name[66] }
+    fn library_dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "_pad0[144]",
+                "filepath[1024]",
+                "*packedfile",
+                "size",
+                "seek",
+                "*data",
+                "*lib",
+                "name[66]",
+            ]),
+            types: strings(&["char", "int", "void", "Library", "PackedFile", "ID"]),
+            tlens: vec![1, 4, 0, 1176, 16, 74],
+            structs: vec![
+                (3, vec![(0, 0), (0, 1), (4, 2)]),
+                (4, vec![(1, 3), (1, 4), (2, 5)]),
+                (5, vec![(2, 6), (0, 7)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn library(name: &str, filepath: &str, packedfile: u64) -> Vec<u8> {
+        let mut payload = vec![0u8; 1176];
+        write_str(&mut payload, 32, name);
+        write_str(&mut payload, 144, filepath);
+        payload[1168..1176].copy_from_slice(&packedfile.to_le_bytes());
+        payload
+    }
+
+    fn write_fixture(dir: &std::path::Path) -> (std::path::PathBuf, Vec<u8>) {
+        let inner = SyntheticBlend::new(300)
+            .image("IMwood", "//textures/wood.png")
+            .finish();
+
+        let blend = SyntheticBlend::new(300);
+        let packed_address = blend.next_address() + 0x2000;
+        let data_address = packed_address + 0x1000;
+        let mut packed_file = vec![0u8; 16];
+        packed_file[..4].copy_from_slice(&(inner.len() as i32).to_le_bytes());
+        packed_file[8..].copy_from_slice(&data_address.to_le_bytes());
+
+        let data = blend
+            .typed_block(
+                b"LI",
+                0,
+                1,
+                &library("LIprops", "//props.blend", packed_address),
+            )
+            .typed_block(b"LI", 0, 1, &library("LIset", "//missing_set.blend", 0))
+            .typed_block(b"DATA", 1, 1, &packed_file)
+            .block(b"DATA", &inner)
+            .dna(&library_dna())
+            .finish();
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        (path, inner)
+    }
+
+    #[test]
+    fn test_packed_library_is_flagged() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path()).0).unwrap();
+
+        let links = blend_file.get_library_links().unwrap();
+        let packed: Vec<_> = links
+            .iter()
+            .map(|l| (l.path.as_str(), l.is_packed))
+            .collect();
+        assert_eq!(
+            packed,
+            vec![("//props.blend", true), ("//missing_set.blend", false)]
+        );
+
+        let usage = blend_file.library_usage().unwrap();
+        assert!(usage[0].is_packed);
+        assert!(!usage[1].is_packed);
+
+        // Neither target exists on disk, but only the unpacked one is missing
+        assert_eq!(blend_file.info_with_asset_check().missing_assets, Some(1));
+    }
+
+    #[test]
+    fn test_extract_packed_library() {
+        let dir = tempdir().unwrap();
+        let (path, inner) = write_fixture(dir.path());
+        let blend_file = BlendFile::open(path).unwrap();
+
+        let out = dir.path().join("props.blend");
+        blend_file.extract_packed_library("props", &out).unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), inner);
+
+        let extracted = BlendFile::open(&out).unwrap();
+        let links = extracted.get_library_links().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].path, "//textures/wood.png");
+    }
+
+    #[test]
+    fn test_extract_unpacked_library_fails() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path()).0).unwrap();
+        let out = dir.path().join("set.blend");
+
+        let err = blend_file
+            .extract_packed_library("LIset", &out)
+            .unwrap_err();
+        assert!(err.to_string().contains("not packed"), "{err}");
+        assert!(blend_file.extract_packed_library("nope", &out).is_err());
+        assert!(!out.exists());
+    }
+}
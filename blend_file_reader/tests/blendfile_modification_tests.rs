@@ -1,22 +1,45 @@
-use blend_file_reader::*;
-use std::fs;
+mod common;
 
+// Tests against real .blend files made by `cargo xtask gen-fixtures`.
+//
+// Ignored by default since the fixtures aren't committed; run them with
+// `cargo test --test blendfile_modification_tests fixture_tier -- --ignored`
+// after generating.
 #[cfg(test)]
-mod tests {
-    use super::*;
+mod fixture_tier {
+    use blend_file_reader::BlendFile;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// A scratch copy of the fixture `name`, or `None` (with a note) when it
+    /// hasn't been generated
+    fn fixture_copy(name: &str) -> Option<(TempDir, PathBuf)> {
+        let source = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/test_blend_files")
+            .join(name);
+        if !source.exists() {
+            eprintln!(
+                "skipping: {} missing, run `cargo xtask gen-fixtures`",
+                source.display()
+            );
+            return None;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::copy(&source, &path).expect("Failed to copy test file");
+        Some((dir, path))
+    }
 
     #[test]
+    #[ignore = "needs fixtures from `cargo xtask gen-fixtures`"]
     fn test_change_path() {
-        // Create a temporary copy of the test file
-        let test_file = "tests/test_blend_files/linked_cube.blend";
-        let temp_file = "tests/test_blend_files/temp_linked_cube.blend";
-
-        // Copy the original file to temp location
-        fs::copy(test_file, temp_file).expect("Failed to copy test file");
+        let Some((_dir, temp_file)) = fixture_copy("linked_cube.blend") else {
+            return;
+        };
 
         // Open the blend file in read+write mode
         let mut blend_file =
-            BlendFile::open_read_write(temp_file).expect("Failed to open blend file");
+            BlendFile::open_read_write(&temp_file).expect("Failed to open blend file");
 
         // Find all library link blocks
         let mut library_blocks = blend_file
@@ -35,7 +58,7 @@ mod tests {
         blend_file.save().expect("Failed to save blend file");
 
         // Reopen the file and verify the change
-        let reopened_file = BlendFile::open(temp_file).expect("Failed to reopen blend file");
+        let reopened_file = BlendFile::open(&temp_file).expect("Failed to reopen blend file");
         let library_blocks = reopened_file
             .get_blocks_by_type(b"LI")
             .expect("Failed to get library blocks");
@@ -46,30 +69,21 @@ mod tests {
                 .expect("Failed to get library path");
             assert_eq!(path, "//new_library_path.blend");
         }
-
-        // Clean up
-        fs::remove_file(temp_file).expect("Failed to remove temp file");
     }
 
     #[test]
+    #[ignore = "needs fixtures from `cargo xtask gen-fixtures`"]
     fn test_block_hash() {
-        // Create a temporary copy of the test file
-        let test_file = "tests/test_blend_files/linked_cube.blend";
-        let temp_file = "tests/test_blend_files/temp_linked_cube_hash.blend";
-
-        // Copy the original file to temp location
-        fs::copy(test_file, temp_file).expect("Failed to copy test file");
+        let Some((_dir, temp_file)) = fixture_copy("linked_cube.blend") else {
+            return;
+        };
 
         // Open the blend file in read+write mode
         let mut blend_file =
-            BlendFile::open_read_write(temp_file).expect("Failed to open blend file");
+            BlendFile::open_read_write(&temp_file).expect("Failed to open blend file");
 
         // Get the original hash
-        let original_hash = blend_file
-            .blocks
-            .iter()
-            .map(|b| b.code)
-            .collect::<Vec<_>>();
+        let original_hash = blend_file.blocks.iter().map(|b| b.code).collect::<Vec<_>>();
 
         // Find a library block and modify it
         let mut library_blocks = blend_file
@@ -86,7 +100,7 @@ mod tests {
         blend_file.save().expect("Failed to save blend file");
 
         // Reopen and verify the change
-        let reopened_file = BlendFile::open(temp_file).expect("Failed to reopen blend file");
+        let reopened_file = BlendFile::open(&temp_file).expect("Failed to reopen blend file");
         let new_hash = reopened_file
             .blocks
             .iter()
@@ -106,25 +120,21 @@ mod tests {
                 .expect("Failed to get library path");
             assert_eq!(path, "//modified_path.blend");
         }
-
-        // Clean up
-        fs::remove_file(temp_file).expect("Failed to remove temp file");
     }
 
     #[test]
+    #[ignore = "needs fixtures from `cargo xtask gen-fixtures`"]
     fn test_modify_object_location() {
-        // Create a temporary copy of the test file
-        let test_file = "tests/test_blend_files/linked_cube.blend";
-        let temp_file = "tests/test_blend_files/temp_object_location.blend";
-
-        // Copy the original file to temp location
-        fs::copy(test_file, temp_file).expect("Failed to copy test file");
+        let Some((_dir, temp_file)) = fixture_copy("linked_cube.blend") else {
+            return;
+        };
 
         // Open the blend file in read+write mode
         let mut blend_file =
-            BlendFile::open_read_write(temp_file).expect("Failed to open blend file");
+            BlendFile::open_read_write(&temp_file).expect("Failed to open blend file");
 
         // Find an object block
+        let dna = blend_file.dna.clone();
         let mut object_blocks = blend_file
             .get_blocks_by_type_mut(b"OB")
             .expect("Failed to get object blocks");
@@ -133,7 +143,7 @@ mod tests {
         if let Some(block) = object_blocks.first_mut() {
             let new_location = [10.0, 20.0, 30.0];
             block
-                .set_float_array_field("loc", &new_location)
+                .set_float_field(&dna, "loc", &new_location)
                 .expect("Failed to set object location");
         }
 
@@ -141,34 +151,29 @@ mod tests {
         blend_file.save().expect("Failed to save blend file");
 
         // Reopen and verify the change
-        let reopened_file = BlendFile::open(temp_file).expect("Failed to reopen blend file");
+        let reopened_file = BlendFile::open(&temp_file).expect("Failed to reopen blend file");
         let object_blocks = reopened_file
             .get_blocks_by_type(b"OB")
             .expect("Failed to get object blocks");
 
         if let Some(block) = object_blocks.first() {
             let location = block
-                .get_float_array_field("loc", 3)
+                .get_float_field(&reopened_file.dna, "loc")
                 .expect("Failed to get object location");
             assert_eq!(location, [10.0, 20.0, 30.0]);
         }
-
-        // Clean up
-        fs::remove_file(temp_file).expect("Failed to remove temp file");
     }
 
     #[test]
+    #[ignore = "needs fixtures from `cargo xtask gen-fixtures`"]
     fn test_compressed_file_modification() {
-        // Create a temporary copy of the compressed test file
-        let test_file = "tests/test_blend_files/linked_cube_compressed.blend";
-        let temp_file = "tests/test_blend_files/temp_compressed.blend";
-
-        // Copy the original file to temp location
-        fs::copy(test_file, temp_file).expect("Failed to copy test file");
+        let Some((_dir, temp_file)) = fixture_copy("linked_cube_compressed.blend") else {
+            return;
+        };
 
         // Open the blend file in read+write mode
         let mut blend_file =
-            BlendFile::open_read_write(temp_file).expect("Failed to open compressed blend file");
+            BlendFile::open_read_write(&temp_file).expect("Failed to open compressed blend file");
 
         // Find a library block and modify it
         let mut library_blocks = blend_file
@@ -188,7 +193,7 @@ mod tests {
 
         // Reopen and verify the change
         let reopened_file =
-            BlendFile::open(temp_file).expect("Failed to reopen compressed blend file");
+            BlendFile::open(&temp_file).expect("Failed to reopen compressed blend file");
         let library_blocks = reopened_file
             .get_blocks_by_type(b"LI")
             .expect("Failed to get library blocks");
@@ -199,8 +204,2094 @@ mod tests {
                 .expect("Failed to get library path");
             assert_eq!(path, "//compressed_modified.blend");
         }
+    }
+}
+
+// Tests for tracking which blocks changed since open
+#[cfg(test)]
+mod modified_blocks {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::blend_file::{OpenOptions, ReadWriteOptions, SaveOutcome};
+    use blend_file_reader::header::Endianness;
+    use blend_file_reader::BlendFile;
+    use std::collections::{BTreeSet, HashMap};
+    use tempfile::tempdir;
+
+    fn write_fixture(dir: &std::path::Path) -> std::path::PathBuf {
+        let path = dir.join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .library("LIprops", "//props.blend")
+            .image("IMwood", "//wood.png")
+            .image("IMstone", "//stone.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_edit_marks_exactly_that_block() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        assert!(!blend_file.is_dirty());
+
+        let mut images = blend_file.get_blocks_by_type_mut(b"IM").unwrap();
+        images[1].data[104..115].copy_from_slice(b"//rock.png\0");
+        assert!(blend_file.is_dirty());
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([2]));
+
+        assert_eq!(blend_file.save().unwrap(), SaveOutcome::Saved);
+        assert!(!blend_file.is_dirty());
+        assert!(blend_file.modified_blocks().is_empty());
+
+        let reopened = BlendFile::open(&path).unwrap();
+        let paths: Vec<_> = reopened
+            .get_library_links()
+            .unwrap()
+            .into_iter()
+            .map(|l| l.path)
+            .collect();
+        assert_eq!(paths, ["//props.blend", "//wood.png", "//rock.png"]);
+    }
+
+    #[test]
+    fn test_mutable_access_alone_is_not_a_change() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let before = std::fs::metadata(&path).unwrap().modified().unwrap();
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+
+        let mut libraries = blend_file.get_library_blocks_mut().unwrap();
+        let original = libraries[0].data[144];
+        libraries[0].data[144] = b'X';
+        libraries[0].data[144] = original;
+        assert!(!blend_file.is_dirty());
+
+        assert_eq!(blend_file.save().unwrap(), SaveOutcome::NothingToSave);
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_file_level_edits_are_tracked() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+
+        blend_file.mark_modified(1);
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([1]));
+
+        // Without DNA there's nothing to remap, but the conversion error leaves it clean
+        assert!(blend_file.remap_addresses(&HashMap::new()).is_err());
+        assert!(blend_file.convert_endianness(Endianness::Big).is_err());
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_original_data_is_from_before_the_first_edit() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let untouched = BlendFile::open(&path).unwrap();
+        let options = ReadWriteOptions::default().with_keep_originals(true);
+        let mut blend_file = BlendFile::open_read_write_with_options(&path, options).unwrap();
+
+        blend_file.get_blocks_by_type_mut(b"IM").unwrap()[1].data[104..115]
+            .copy_from_slice(b"//rock.png\0");
+        blend_file.get_blocks_by_type_mut(b"IM").unwrap()[1].data[104..115]
+            .copy_from_slice(b"//sand.png\0");
+
+        let original = blend_file.original_data(2).unwrap();
+        assert_eq!(original, untouched.blocks[2].data);
+        assert_eq!(&blend_file.blocks[2].data[104..115], b"//sand.png\0");
+        assert_eq!(blend_file.changed_ranges(2).unwrap(), vec![107..115]);
+
+        // Handed out but not changed, or never handed out
+        assert_eq!(blend_file.original_data(1), None);
+        assert_eq!(blend_file.original_data(0), None);
+        assert_eq!(blend_file.changed_ranges(0), None);
+
+        // Saving makes the saved data the new baseline
+        blend_file.save().unwrap();
+        assert_eq!(blend_file.original_data(2), None);
+    }
+
+    #[test]
+    fn test_originals_of_remapped_blocks() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let untouched = BlendFile::open(&path).unwrap();
+        let options = OpenOptions::default().with_keep_originals(true);
+        let mut blend_file = BlendFile::open_with_options(&path, &options).unwrap();
+
+        assert_eq!(
+            blend_file
+                .remap_link_path("//wood.png", "//textures/wood.png")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            blend_file.original_data(1),
+            Some(&untouched.blocks[1].data[..])
+        );
+        assert_eq!(blend_file.original_data(2), None);
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_originals_are_off_by_default() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+
+        blend_file.get_blocks_by_type_mut(b"IM").unwrap()[1].data[104] = b'X';
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([2]));
+        assert_eq!(blend_file.original_data(2), None);
+        assert_eq!(blend_file.changed_ranges(2), None);
+    }
+}
+
+// Tests for rewriting pointer fields through BlendFile::remap_addresses
+#[cfg(test)]
+mod address_remap {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::remap::RemapOptions;
+    use blend_file_reader::BlendFile;
+    use std::collections::{BTreeSet, HashMap};
+    use tempfile::tempdir;
+
+    const A: u64 = 0x1000;
+    const B: u64 = 0x2000;
+    const C: u64 = 0x3000;
+    const PAIR: u64 = 0x4000;
+    const NEW_C: u64 = 0x5000;
+    const RAW: u64 = 0x6000;
+    const DANGLING: u64 = 0xdead0;
+
+    /// ListBase { *first, *last },
+    /// Thing { *next, *prev, ListBase children, *targets[2], int value }
+    fn thing_dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "*first",
+                "*last",
+                "*next",
+                "*prev",
+                "children",
+                "*targets[2]",
+                "value",
+            ]),
+            types: strings(&["char", "int", "void", "ListBase", "Thing"]),
+            tlens: vec![1, 4, 0, 16, 52],
+            structs: vec![
+                (3, vec![(2, 0), (2, 1)]),
+                (4, vec![(4, 2), (4, 3), (3, 4), (4, 5), (1, 6)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    /// next, prev, children.first, children.last, targets[0], targets[1]
+    fn thing(pointers: [u64; 6], value: i32) -> Vec<u8> {
+        let mut data: Vec<u8> = pointers.iter().flat_map(|p| p.to_le_bytes()).collect();
+        data.extend_from_slice(&value.to_le_bytes());
+        data
+    }
+
+    fn write_fixture(dir: &std::path::Path) -> std::path::PathBuf {
+        let mut pair = thing([0, 0, 0, 0, 0, C], 4);
+        pair.extend(thing([0, 0, 0, 0, C, 0], 5));
+        let data = SyntheticBlend::new(300)
+            .typed_block(b"TH", 1, 1, &thing([B, DANGLING, C, C, C, B], 1))
+            .typed_block(b"TH", 1, 1, &thing([0, A, 0, 0, C, 0], 2))
+            .typed_block(b"TH", 1, 1, &thing([0; 6], 3))
+            .typed_block(b"TH", 1, 2, &pair)
+            .typed_block(b"TH", 1, 1, &thing([0; 6], 3))
+            .block(b"DATA", &C.to_le_bytes())
+            .dna(&thing_dna())
+            .finish();
+        let path = dir.join("things.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Pointer values of every Thing instance, keyed by (block address, instance)
+    fn pointers(blend_file: &BlendFile) -> HashMap<(u64, usize), [u64; 6]> {
+        let mut out = HashMap::new();
+        for block in blend_file.get_blocks_by_type(b"TH").unwrap() {
+            for (i, instance) in block.data.chunks_exact(52).enumerate() {
+                let mut values = [0u64; 6];
+                for (j, value) in values.iter_mut().enumerate() {
+                    *value = u64::from_le_bytes(instance[j * 8..j * 8 + 8].try_into().unwrap());
+                }
+                out.insert((block.old_memory_address, i), values);
+            }
+        }
+        out
+    }
+
+    fn value_at(blend_file: &BlendFile, address: u64) -> i32 {
+        let block = blend_file
+            .blocks
+            .iter()
+            .find(|b| b.old_memory_address == address)
+            .unwrap();
+        i32::from_le_bytes(block.data[48..52].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_remap_moves_every_reference() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+
+        let changed = blend_file
+            .remap_addresses(&HashMap::from([(C, NEW_C)]))
+            .unwrap();
+        // children.first, children.last and targets[0] of A, targets[0] of B,
+        // one target in each PAIR instance
+        assert_eq!(changed, 6);
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([0, 1, 3]));
+
+        let after = pointers(&blend_file);
+        assert_eq!(after[&(A, 0)], [B, DANGLING, NEW_C, NEW_C, NEW_C, B]);
+        assert_eq!(after[&(B, 0)], [0, A, 0, 0, NEW_C, 0]);
+        assert_eq!(after[&(PAIR, 0)], [0, 0, 0, 0, 0, NEW_C]);
+        assert_eq!(after[&(PAIR, 1)], [0, 0, 0, 0, NEW_C, 0]);
+        assert!(after.values().flatten().all(|&p| p != C));
+        for &pointer in &after[&(A, 0)][2..5] {
+            assert_eq!(value_at(&blend_file, pointer), 3);
+        }
+
+        // Raw DATA holding the same bytes isn't a pointer field
+        let raw = blend_file
+            .blocks
+            .iter()
+            .find(|b| b.old_memory_address == RAW)
+            .unwrap();
+        assert_eq!(raw.data, C.to_le_bytes());
+    }
+
+    #[test]
+    fn test_remap_survives_save() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+        blend_file
+            .remap_addresses(&HashMap::from([(B, A)]))
+            .unwrap();
+
+        let saved = dir.path().join("saved.blend");
+        let mut out = std::fs::File::create(&saved).unwrap();
+        blend_file.write_to_writer(&mut out).unwrap();
+        drop(out);
+
+        let reopened = BlendFile::open(&saved).unwrap();
+        assert_eq!(pointers(&reopened)[&(A, 0)], [A, DANGLING, C, C, C, A]);
+    }
+
+    #[test]
+    fn test_null_dangling_is_optional() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let mut kept = BlendFile::open(&path).unwrap();
+        assert_eq!(kept.remap_addresses(&HashMap::new()).unwrap(), 0);
+        assert_eq!(pointers(&kept)[&(A, 0)][1], DANGLING);
+
+        let mut nulled = BlendFile::open(&path).unwrap();
+        let options = RemapOptions::default().with_null_dangling(true);
+        // Mapping C away to a removed address leaves it dangling too, but explicit
+        // mappings win over nulling
+        let changed = nulled
+            .remap_addresses_with_options(&HashMap::from([(C, 0xbeef0)]), &options)
+            .unwrap();
+        assert_eq!(changed, 7);
+        assert_eq!(
+            pointers(&nulled)[&(A, 0)],
+            [B, 0, 0xbeef0, 0xbeef0, 0xbeef0, B]
+        );
+    }
+}
+
+// Tests for copying datablocks between files with BlendFile::merge_from
+#[cfg(test)]
+mod merge {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::merge::{CollisionPolicy, MergeOptions};
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    const ID_SIZE: usize = 40;
+    const MATERIAL_SIZE: usize = 60;
+
+    /// ID { *next, *prev, char name[24] },
+    /// Material { ID id, float *colors, Image *image, float r },
+    /// Image { ID id }
+    fn material_dna(r_name: &str) -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "*next", "*prev", "name[24]", "id", "*colors", "*image", r_name,
+            ]),
+            types: strings(&["char", "float", "ID", "Material", "Image"]),
+            tlens: vec![1, 4, ID_SIZE as u16, MATERIAL_SIZE as u16, ID_SIZE as u16],
+            structs: vec![
+                (2, vec![(2, 0), (2, 1), (0, 2)]),
+                (3, vec![(2, 3), (1, 4), (4, 5), (1, 6)]),
+                (4, vec![(2, 3)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn id(name: &str, next: u64) -> Vec<u8> {
+        let mut data = vec![0u8; ID_SIZE];
+        data[..8].copy_from_slice(&next.to_le_bytes());
+        write_str(&mut data, 16, name);
+        data
+    }
+
+    fn material(name: &str, next: u64, colors: u64, image: u64, r: f32) -> Vec<u8> {
+        let mut data = id(name, next);
+        data.extend_from_slice(&colors.to_le_bytes());
+        data.extend_from_slice(&image.to_le_bytes());
+        data.extend_from_slice(&r.to_le_bytes());
+        data
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn pointer(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn name(data: &[u8]) -> String {
+        let raw = &data[16..40];
+        let end = raw.iter().position(|&b| b == 0).unwrap();
+        String::from_utf8_lossy(&raw[..end]).into_owned()
+    }
+
+    /// Kit file: MAWood -> colors DATA and IMGrain, plus MABark
+    fn write_source(dir: &Path) -> PathBuf {
+        let base = SyntheticBlend::new(300);
+        let wood = base.next_address();
+        let (colors, grain, bark) = (wood + 0x1000, wood + 0x2000, wood + 0x3000);
+        let data = base
+            .typed_block(b"MA", 1, 1, &material("MAWood", bark, colors, grain, 0.5))
+            .block(b"DATA", &floats(&[0.1, 0.2, 0.3]))
+            .typed_block(b"IM", 2, 1, &id("IMGrain", 0))
+            .typed_block(b"MA", 1, 1, &material("MABark", 0, 0, 0, 0.25))
+            .dna(&material_dna("r"))
+            .finish();
+        let path = dir.join("kit.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Target file: its own MAWood with colors, and IMStone
+    fn write_target(dir: &Path, dna: &Dna) -> PathBuf {
+        let base = SyntheticBlend::new(300);
+        let wood = base.next_address();
+        let colors = wood + 0x1000;
+        let data = base
+            .typed_block(b"MA", 1, 1, &material("MAWood", 0, colors, 0, 0.9))
+            .block(b"DATA", &floats(&[0.7, 0.8]))
+            .typed_block(b"IM", 2, 1, &id("IMStone", 0))
+            .dna(dna)
+            .finish();
+        let path = dir.join("target.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Every pointer field in MA blocks is null or holds a block's address
+    fn assert_pointers_valid(blend_file: &BlendFile) {
+        let addresses: HashSet<u64> = blend_file
+            .blocks
+            .iter()
+            .map(|b| b.old_memory_address)
+            .collect();
+        assert_eq!(
+            addresses.len(),
+            blend_file.blocks.len(),
+            "addresses are unique"
+        );
+        for block in blend_file.get_blocks_by_type(b"MA").unwrap() {
+            for offset in [0, 8, 40, 48] {
+                let value = pointer(&block.data, offset);
+                assert!(
+                    value == 0 || addresses.contains(&value),
+                    "dangling {value:#x}"
+                );
+            }
+        }
+    }
+
+    fn materials(blend_file: &BlendFile) -> Vec<String> {
+        let blocks = blend_file.get_blocks_by_type(b"MA").unwrap();
+        blocks.iter().map(|b| name(&b.data)).collect()
+    }
+
+    fn block_at(blend_file: &BlendFile, address: u64) -> &blend_file_reader::block::Block {
+        blend_file
+            .blocks
+            .iter()
+            .find(|b| b.old_memory_address == address)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_merge_material_with_rename() {
+        let dir = tempdir().unwrap();
+        let source = BlendFile::open(write_source(dir.path())).unwrap();
+        let mut target = BlendFile::open(write_target(dir.path(), &material_dna("r"))).unwrap();
+
+        let options = MergeOptions::default()
+            .with_codes(["MA"])
+            .with_names(["Wood"])
+            .with_collision(CollisionPolicy::Rename);
+        let report = target.merge_from(&source, &options).unwrap();
+
+        assert_eq!(report.copied, ["MAWood.001"]);
+        assert_eq!(
+            report.renamed,
+            [("MAWood".to_string(), "MAWood.001".to_string())]
+        );
+        assert_eq!(report.data_blocks, 1);
+        // ID.next to MABark and the unselected image
+        assert_eq!(report.pointers_cleared, 2);
+        assert_eq!(materials(&target), ["MAWood", "MAWood.001"]);
+        assert!(target.is_dirty());
+        assert_pointers_valid(&target);
+
+        let merged = target.get_blocks_by_type(b"MA").unwrap()[1].data.clone();
+        assert_eq!(pointer(&merged, 0), 0);
+        assert_eq!(pointer(&merged, 48), 0);
+        let colors = block_at(&target, pointer(&merged, 40));
+        assert_eq!(colors.data, floats(&[0.1, 0.2, 0.3]));
+
+        // The merged file round-trips through save and open
+        let saved = dir.path().join("merged.blend");
+        target
+            .write_to_writer(&mut std::fs::File::create(&saved).unwrap())
+            .unwrap();
+        let reopened = BlendFile::open(&saved).unwrap();
+        assert_eq!(materials(&reopened), ["MAWood", "MAWood.001"]);
+        assert_eq!(reopened.blocks.len(), target.blocks.len());
+        assert_pointers_valid(&reopened);
+    }
+
+    #[test]
+    fn test_merge_remaps_between_copied_ids() {
+        let dir = tempdir().unwrap();
+        let source = BlendFile::open(write_source(dir.path())).unwrap();
+        let mut target = BlendFile::open(write_target(dir.path(), &material_dna("r"))).unwrap();
+
+        let options = MergeOptions::default().with_collision(CollisionPolicy::Rename);
+        let report = target.merge_from(&source, &options).unwrap();
+
+        assert_eq!(report.copied, ["MAWood.001", "IMGrain", "MABark"]);
+        assert_eq!(materials(&target), ["MAWood", "MAWood.001", "MABark"]);
+        assert_pointers_valid(&target);
+
+        let merged = target.get_blocks_by_type(b"MA").unwrap()[1].data.clone();
+        let image = block_at(&target, pointer(&merged, 48));
+        assert_eq!(name(&image.data), "IMGrain");
+        let bark = block_at(&target, pointer(&merged, 0));
+        assert_eq!(name(&bark.data), "MABark");
+    }
+
+    #[test]
+    fn test_merge_skip_keeps_existing() {
+        let dir = tempdir().unwrap();
+        let source = BlendFile::open(write_source(dir.path())).unwrap();
+        let mut target = BlendFile::open(write_target(dir.path(), &material_dna("r"))).unwrap();
+        let before = target.blocks.len();
+
+        let options = MergeOptions::default().with_names(["Wood"]);
+        let report = target.merge_from(&source, &options).unwrap();
+
+        assert_eq!(report.skipped, ["MAWood"]);
+        assert!(report.copied.is_empty());
+        assert_eq!(target.blocks.len(), before);
+        assert!(!target.is_dirty());
+    }
+
+    #[test]
+    fn test_merge_overwrite_replaces_in_place() {
+        let dir = tempdir().unwrap();
+        let source = BlendFile::open(write_source(dir.path())).unwrap();
+        let mut target = BlendFile::open(write_target(dir.path(), &material_dna("r"))).unwrap();
+        let old_address = target.get_blocks_by_type(b"MA").unwrap()[0].old_memory_address;
+
+        let options = MergeOptions::default()
+            .with_codes(["MA"])
+            .with_names(["Wood"])
+            .with_collision(CollisionPolicy::Overwrite);
+        let report = target.merge_from(&source, &options).unwrap();
+
+        assert_eq!(report.overwritten, ["MAWood"]);
+        let blocks = target.get_blocks_by_type(b"MA").unwrap();
+        assert_eq!(blocks.len(), 1);
+        let wood = blocks[0];
+        assert_eq!(wood.old_memory_address, old_address);
+        assert_eq!(
+            f32::from_le_bytes(wood.data[56..60].try_into().unwrap()),
+            0.5
+        );
+        // The old colors block went with the old material
+        let data = target.get_blocks_by_type(b"DATA").unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].data, floats(&[0.1, 0.2, 0.3]));
+        assert_pointers_valid(&target);
+    }
+
+    #[test]
+    fn test_merge_rename_near_the_name_limit() {
+        let dir = tempdir().unwrap();
+        // 23 bytes, all the name field holds; the é straddles byte 19
+        let long = format!("MA{}\u{e9}yyy", "x".repeat(16));
+        let write = |file: &str| {
+            let data = SyntheticBlend::new(300)
+                .typed_block(b"MA", 1, 1, &material(&long, 0, 0, 0, 0.5))
+                .dna(&material_dna("r"))
+                .finish();
+            let path = dir.path().join(file);
+            std::fs::write(&path, data).unwrap();
+            BlendFile::open(path).unwrap()
+        };
+        let source = write("kit.blend");
+        let mut target = write("target.blend");
+
+        let options = MergeOptions::default().with_collision(CollisionPolicy::Rename);
+        let report = target.merge_from(&source, &options).unwrap();
+
+        // The base loses the é whole rather than half of it
+        let renamed = format!("MA{}.001", "x".repeat(16));
+        assert_eq!(report.renamed, [(long.clone(), renamed.clone())]);
+        assert_eq!(materials(&target), [long, renamed]);
+    }
+
+    #[test]
+    fn test_merge_overwrite_allocates_past_dropped_blocks() {
+        let dir = tempdir().unwrap();
+
+        // The replacement colors are big enough to run into the old MAWood
+        let base = SyntheticBlend::new(300);
+        let colors = base.next_address() + 0x1000;
+        let source = base
+            .typed_block(b"MA", 1, 1, &material("MAWood", 0, colors, 0, 0.5))
+            .block(b"DATA", &floats(&[0.5; 2048]))
+            .dna(&material_dna("r"))
+            .finish();
+        let source_path = dir.path().join("kit.blend");
+        std::fs::write(&source_path, source).unwrap();
+        let source = BlendFile::open(source_path).unwrap();
+
+        let base = SyntheticBlend::new(300).block(b"DATA", &floats(&[1.0]));
+        let wood = base.next_address();
+        let target = base
+            .typed_block(b"MA", 1, 1, &material("MAWood", 0, wood + 0x1000, 0, 0.9))
+            .block(b"DATA", &floats(&[0.7, 0.8]))
+            .dna(&material_dna("r"))
+            .finish();
+        let target_path = dir.path().join("target.blend");
+        std::fs::write(&target_path, target).unwrap();
+        let mut target = BlendFile::open(target_path).unwrap();
+
+        let options = MergeOptions::default().with_collision(CollisionPolicy::Overwrite);
+        target.merge_from(&source, &options).unwrap();
+        assert_pointers_valid(&target);
+
+        let mut ranges: Vec<(u64, u64)> = target
+            .blocks
+            .iter()
+            .map(|b| {
+                (
+                    b.old_memory_address,
+                    b.old_memory_address + b.data.len() as u64,
+                )
+            })
+            .collect();
+        ranges.sort();
+        assert!(
+            ranges.windows(2).all(|pair| pair[0].1 <= pair[1].0),
+            "{ranges:x?}"
+        );
+        let wood = target.get_blocks_by_type(b"MA").unwrap()[0];
+        assert_eq!(block_at(&target, pointer(&wood.data, 40)).data.len(), 8192);
+    }
+
+    #[test]
+    fn test_merge_rejects_incompatible_dna() {
+        let dir = tempdir().unwrap();
+        let source = BlendFile::open(write_source(dir.path())).unwrap();
+        let mut target =
+            BlendFile::open(write_target(dir.path(), &material_dna("roughness"))).unwrap();
+        let before = target.blocks.len();
+
+        let options = MergeOptions::default().with_codes(["MA"]);
+        let err = target.merge_from(&source, &options).unwrap_err();
+        assert!(
+            matches!(err, BlendFileError::Format(FormatError::DnaError(ref msg)) if msg.contains("Material"))
+        );
+        assert_eq!(target.blocks.len(), before);
+
+        // Types whose layout matches can still be copied
+        let options = MergeOptions::default().with_codes(["IM"]);
+        assert_eq!(
+            target.merge_from(&source, &options).unwrap().copied,
+            ["IMGrain"]
+        );
+    }
+}
+
+// Tests for NamePolicy checks on renames, merges and validate
+#[cfg(test)]
+mod naming {
+    use crate::common::{id_dna, write_str, SyntheticBlend};
+
+    use blend_file_reader::merge::{CollisionPolicy, MergeOptions};
+    use blend_file_reader::naming::{NameCharset, NamePolicy, NameProblem, NameViolation};
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn id(name: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 24];
+        write_str(&mut data, 0, name);
+        data
+    }
+
+    fn write_blend(dir: &Path, file_name: &str, names: &[&str]) -> PathBuf {
+        let mut blend = SyntheticBlend::new(300);
+        for name in names {
+            blend = blend.typed_block(b"OB", 1, 1, &id(name));
+        }
+        let path = dir.join(file_name);
+        std::fs::write(&path, blend.dna(&id_dna()).finish()).unwrap();
+        path
+    }
+
+    fn names(blend_file: &BlendFile) -> Vec<String> {
+        blend_file
+            .lint_names(&NamePolicy::default().with_max_len(0))
+            .into_iter()
+            .map(|violation| violation.name)
+            .collect()
+    }
+
+    fn violations(err: BlendFileError) -> Vec<NameViolation> {
+        match err {
+            BlendFileError::InvalidNames(violations) => violations,
+            other => panic!("{other:?}"),
+        }
+    }
+
+    fn invalid_names(err: BlendFileError) -> Vec<String> {
+        violations(err).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_rename_charset() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path(), "shot.blend", &["OBhero", "OBprop"]);
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        let ascii = NamePolicy::default().with_charset(NameCharset::Ascii);
+
+        let err = blend_file
+            .rename_datablock("OBhero", "OBhéros", &ascii)
+            .unwrap_err();
+        assert_eq!(
+            invalid_names(err),
+            ["OBhéros: 'é' not allowed by the ascii charset"]
+        );
+        assert!(!blend_file.is_dirty());
+
+        let renamed = blend_file
+            .rename_datablock("OBhero", "OBhéros", &NamePolicy::default())
+            .unwrap();
+        assert_eq!(renamed, "OBhéros");
+        assert_eq!(names(&blend_file), ["OBhéros", "OBprop"]);
+        assert!(blend_file.is_dirty());
+
+        let identifier = NamePolicy::default().with_charset(NameCharset::Identifier);
+        assert!(blend_file
+            .rename_datablock("OBprop", "OBprop_v2.001", &identifier)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rename_reports_every_violation() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path(), "shot.blend", &["OBhero", "OBsöme long name"]);
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        let policy = NamePolicy::default()
+            .with_charset(NameCharset::Identifier)
+            .with_max_len(8);
+
+        let err = blend_file
+            .rename_datablock("OBhero", "OBsöme long name", &policy)
+            .unwrap_err();
+        assert_eq!(
+            invalid_names(err),
+            [
+                "OBsöme long name: already taken",
+                "OBsöme long name: 15 bytes, longer than 8",
+                "OBsöme long name: 'ö', ' ' not allowed by the identifier charset",
+            ]
+        );
+
+        // The DNA's name size holds without a max_len of its own
+        let err = blend_file
+            .rename_datablock(
+                "OBhero",
+                "OBa_name_that_fills_24_bytes",
+                &NamePolicy::default(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            violations(err),
+            [NameViolation {
+                name: "OBa_name_that_fills_24_bytes".to_string(),
+                problem: NameProblem::TooLong { len: 26, max: 21 },
+            }]
+        );
+        assert!(!blend_file.is_dirty());
+    }
+
+    #[test]
+    fn test_rename_collisions() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path(), "shot.blend", &["OBhero", "OBprop"]);
+        let mut blend_file = BlendFile::open(&path).unwrap();
+
+        let overwrite = NamePolicy::default().with_collision(CollisionPolicy::Overwrite);
+        assert!(blend_file
+            .rename_datablock("OBhero", "OBprop", &overwrite)
+            .is_err());
+
+        let rename = NamePolicy::default().with_collision(CollisionPolicy::Rename);
+        let renamed = blend_file
+            .rename_datablock("OBhero", "OBprop", &rename)
+            .unwrap();
+        assert_eq!(renamed, "OBprop.001");
+
+        // Renaming to its own name is no collision
+        assert!(blend_file
+            .rename_datablock("OBprop", "OBprop", &NamePolicy::default())
+            .is_ok());
+        assert!(matches!(
+            blend_file.rename_datablock("OBprop", "MAprop", &NamePolicy::default()),
+            Err(BlendFileError::Format(FormatError::InvalidFormat(_)))
+        ));
+    }
+
+    #[test]
+    fn test_merge_checks_names_before_copying() {
+        let dir = tempdir().unwrap();
+        let target = write_blend(dir.path(), "shot.blend", &["OBhero"]);
+        let source = write_blend(dir.path(), "kit.blend", &["OBhero", "OBcafé", "OBrock"]);
+        let mut blend_file = BlendFile::open(&target).unwrap();
+        let kit = BlendFile::open(&source).unwrap();
+        let before = blend_file.blocks.len();
+
+        let policy = NamePolicy::default()
+            .with_charset(NameCharset::Identifier)
+            .with_collision(CollisionPolicy::Rename);
+        let options = MergeOptions::default().with_name_policy(policy.clone());
+        assert_eq!(options.collision, CollisionPolicy::Rename);
+        let err = blend_file.merge_from(&kit, &options).unwrap_err();
+        assert_eq!(
+            invalid_names(err),
+            ["OBcafé: 'é' not allowed by the identifier charset"]
+        );
+        assert_eq!(blend_file.blocks.len(), before);
+        assert!(!blend_file.is_dirty());
+
+        let options = MergeOptions::default()
+            .with_names(["hero", "rock"])
+            .with_name_policy(policy);
+        let report = blend_file.merge_from(&kit, &options).unwrap();
+        assert_eq!(report.copied, ["OBhero.001", "OBrock"]);
+    }
+
+    #[test]
+    fn test_lint_names() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(
+            dir.path(),
+            "shot.blend",
+            &["OBhero", "OBcafé", "OBtab\tname"],
+        );
+        let blend_file = BlendFile::open(&path).unwrap();
+
+        let violations =
+            blend_file.lint_names(&NamePolicy::default().with_charset(NameCharset::Ascii));
+        let problems: Vec<(&str, &NameProblem)> = violations
+            .iter()
+            .map(|violation| (violation.name.as_str(), &violation.problem))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (
+                    "OBcafé",
+                    &NameProblem::Charset {
+                        charset: NameCharset::Ascii,
+                        characters: vec!['é'],
+                    }
+                ),
+                (
+                    "OBtab\tname",
+                    &NameProblem::Charset {
+                        charset: NameCharset::Ascii,
+                        characters: vec!['\t'],
+                    }
+                ),
+            ]
+        );
+        assert!(blend_file.lint_names(&NamePolicy::default()).is_empty());
+
+        let validate = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+                .arg("validate")
+                .arg(&path)
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        assert!(validate(&[]).status.success());
+        let output = validate(&["--name-charset", "ascii", "--max-name-len", "4"]);
+        assert!(!output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains("OBcafé: 'é' not allowed by the ascii charset"),
+            "{stdout}"
+        );
+        assert!(
+            stdout.contains("OBtab\tname: 8 bytes, longer than 4"),
+            "{stdout}"
+        );
+        // café is 5 bytes
+        assert!(stdout.contains("4 issue(s)"), "{stdout}");
+    }
+}
+
+// Tests for fitting overlong paths into fixed-size filepath fields
+#[cfg(test)]
+mod truncation {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::truncation::{fit_path, TruncationPolicy};
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use tempfile::tempdir;
+
+    /// Library filepath field: offset 144, 1024 bytes
+    const FIELD: std::ops::Range<usize> = 144..144 + 1024;
+
+    /// 1100 bytes: `/` + 21 directories of 50 bytes each + a 49-byte filename
+    fn long_path() -> String {
+        let dirs: String = (0..21)
+            .map(|i| format!("/{i:02}{}", "d".repeat(47)))
+            .collect();
+        let path = format!("/{dirs}/{}rig.blend", "r".repeat(39));
+        assert_eq!(path.len(), 1100);
+        path
+    }
+
+    fn open(dir: &std::path::Path) -> BlendFile {
+        let path = dir.join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .library("LIrig", "//rig.blend")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        BlendFile::open_read_write(&path).unwrap()
+    }
+
+    fn stored(blend_file: &BlendFile) -> &[u8] {
+        &blend_file.blocks[0].data[FIELD]
+    }
+
+    #[test]
+    fn test_error_policy_refuses() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = open(dir.path());
+
+        let err = blend_file
+            .remap_link_path_with("//rig.blend", &long_path(), TruncationPolicy::Error)
+            .unwrap_err();
+        assert!(
+            matches!(err, BlendFileError::Format(FormatError::InvalidFormat(ref msg)) if msg.contains("1100 bytes"))
+        );
+        assert!(stored(&blend_file).starts_with(b"//rig.blend\0"));
+        assert!(!blend_file.is_dirty());
+    }
+
+    #[test]
+    fn test_truncate_at_boundary_keeps_leading_directories() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = open(dir.path());
+        let path = long_path();
+
+        let remap = blend_file
+            .remap_link_path_with("//rig.blend", &path, TruncationPolicy::TruncateAtBoundary)
+            .unwrap();
+        assert_eq!(remap.blocks, 1);
+        assert_eq!(remap.warnings.len(), 1);
+        assert!(
+            remap.warnings[0].contains("1100 bytes"),
+            "{:?}",
+            remap.warnings
+        );
+
+        let field = stored(&blend_file);
+        let end = field.iter().position(|&b| b == 0).unwrap();
+        let value = std::str::from_utf8(&field[..end]).unwrap();
+        assert!(field[end..].iter().all(|&b| b == 0));
+        // Whole directories from the front, nothing from the middle: the
+        // deepest ancestor directory that fits
+        assert_eq!(value, &path[..1 + 50 * 20]);
+        assert_eq!(path.as_bytes()[value.len()], b'/');
+    }
+
+    #[test]
+    fn test_shorten_policy_uses_callback() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = open(dir.path());
+
+        fn relativize(path: &str) -> String {
+            format!("//{}", &path[path.rfind('/').unwrap() + 1..])
+        }
+        let remap = blend_file
+            .remap_link_path_with(
+                "//rig.blend",
+                &long_path(),
+                TruncationPolicy::Shorten(relativize),
+            )
+            .unwrap();
+        assert!(remap.warnings.is_empty());
+        let expected = relativize(&long_path());
+        assert_eq!(&stored(&blend_file)[..expected.len()], expected.as_bytes());
+        assert_eq!(stored(&blend_file)[expected.len()], 0);
+
+        // A callback that doesn't shorten enough is still an error
+        fn identity(path: &str) -> String {
+            path.to_string()
+        }
+        assert!(fit_path(&long_path(), 1024, TruncationPolicy::Shorten(identity)).is_err());
+    }
+
+    #[test]
+    fn test_truncation_cuts_only_between_components() {
+        let fitted = fit_path("ab/éééé", 8, TruncationPolicy::TruncateAtBoundary).unwrap();
+        assert_eq!(fitted.value, "ab");
+        assert!(fitted.warning.is_some());
+
+        // No separator to cut at, so nothing shorter names the same place
+        let value = "é".repeat(20);
+        assert!(fit_path(&value, 8, TruncationPolicy::TruncateAtBoundary).is_err());
+
+        let fitted = fit_path("short", 8, TruncationPolicy::Error).unwrap();
+        assert_eq!(fitted.value, "short");
+        assert_eq!(fitted.warning, None);
+    }
+}
+
+// Tests for compressing files on save
+#[cfg(test)]
+mod compression_settings {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::blend_file::{
+        Compression, CompressionSettings, SaveOptions, SaveOutcome,
+    };
+    use blend_file_reader::{BlendFile, BlendFileError};
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn write_fixture(dir: &Path, data_size: usize) -> PathBuf {
+        let path = dir.join("shot.blend");
+        let payload: Vec<u8> = (0..data_size).map(|i| (i * 7 % 251) as u8).collect();
+        let data = SyntheticBlend::new(300)
+            .library("LIprops", "//props.blend")
+            .image("IMwood", "//wood.png")
+            .block(b"DATA", &payload)
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn save_as(path: &Path, out: &Path, compression: CompressionSettings) {
+        let mut blend_file = BlendFile::open(path).unwrap();
+        let options = SaveOptions::default().with_compression(compression);
+        blend_file.save_as_with_options(out, &options).unwrap();
+    }
+
+    /// Window size in bytes from the header of the zstd frame at the start of `data`
+    fn zstd_window_size(data: &[u8]) -> u64 {
+        assert_eq!(data[..4], [0x28, 0xB5, 0x2F, 0xFD]);
+        let descriptor = data[4];
+        assert_eq!(descriptor & 0x20, 0, "single-segment frames have no window");
+        let window = data[5];
+        let base = 1u64 << (10 + (window >> 3));
+        base + (base / 8) * u64::from(window & 0x07)
+    }
+
+    #[test]
+    fn test_saved_files_reopen() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 4096);
+        let original = BlendFile::open(&path).unwrap();
+
+        for settings in [
+            CompressionSettings::default(),
+            CompressionSettings::new(Compression::Zstd),
+            CompressionSettings::new(Compression::Zstd)
+                .with_level(19)
+                .with_threads(4),
+            CompressionSettings::new(Compression::Gzip).with_level(9),
+            CompressionSettings::new(Compression::Zlib).with_level(1),
+        ] {
+            let out = dir.path().join("out.blend");
+            save_as(&path, &out, settings);
+
+            let reopened = BlendFile::open(&out).unwrap();
+            assert_eq!(reopened.compression(), settings.kind, "{settings:?}");
+            assert_eq!(reopened.blocks.len(), original.blocks.len());
+            assert_eq!(
+                reopened.get_library_links().unwrap().len(),
+                original.get_library_links().unwrap().len()
+            );
+            let data = |b: &BlendFile| b.blocks.last().unwrap().data.clone();
+            assert_eq!(data(&reopened), data(&original));
+        }
+    }
+
+    #[test]
+    fn test_save_compresses_in_place() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 4096);
+
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        let mut images = blend_file.get_blocks_by_type_mut(b"IM").unwrap();
+        images[0].data[104..115].copy_from_slice(b"//rock.png\0");
+        let options = SaveOptions::default().with_compression("zstd:3:2".parse().unwrap());
+        assert_eq!(
+            blend_file.save_with_options(&options).unwrap(),
+            SaveOutcome::Saved
+        );
+        drop(blend_file);
+
+        let reopened = BlendFile::open(&path).unwrap();
+        assert_eq!(reopened.compression(), Compression::Zstd);
+        let links = reopened.get_library_links().unwrap();
+        assert!(links.iter().any(|l| l.path == "//rock.png"));
+    }
+
+    #[test]
+    fn test_legacy_gzip_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 4096);
+        let original = BlendFile::open(&path).unwrap().get_library_links().unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        let out = dir.path().join("legacy.blend");
+        blend_file
+            .save_as_with_compression(&out, Compression::Gzip)
+            .unwrap();
+
+        // zlib's header at level 1: no flags, zero mtime, XFL fastest, unix
+        let data = std::fs::read(&out).unwrap();
+        assert_eq!(data[..10], [0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0x04, 0x03]);
+        assert_eq!(
+            Compression::from_magic(data[..4].try_into().unwrap()),
+            Compression::Gzip
+        );
+
+        let reopened = BlendFile::open(&out).unwrap();
+        assert_eq!(reopened.compression(), Compression::Gzip);
+        let paths = |links: &[blend_file_reader::LibraryLink]| {
+            links.iter().map(|l| l.path.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(&reopened.get_library_links().unwrap()),
+            paths(&original)
+        );
+    }
+
+    #[test]
+    fn test_level_sets_zstd_window() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 4 << 20);
+
+        let window = |level: i32| {
+            let out = dir.path().join(format!("level{level}.blend"));
+            save_as(
+                &path,
+                &out,
+                CompressionSettings::new(Compression::Zstd).with_level(level),
+            );
+            zstd_window_size(&std::fs::read(out).unwrap())
+        };
+        assert!(window(1) < window(19));
+        assert!(window(3) <= window(19));
+    }
+
+    #[test]
+    fn test_threads_keep_output_readable() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 4 << 20);
+        let original = std::fs::read(&path).unwrap();
+
+        for threads in [0, 1, 8] {
+            let out = dir.path().join(format!("threads{threads}.blend"));
+            save_as(
+                &path,
+                &out,
+                CompressionSettings::new(Compression::Zstd).with_threads(threads),
+            );
+            let compressed = std::fs::read(&out).unwrap();
+            assert_eq!(zstd::decode_all(&compressed[..]).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_settings_are_rejected() {
+        let zstd = CompressionSettings::new(Compression::Zstd);
+        let gzip = CompressionSettings::new(Compression::Gzip);
+        for settings in [
+            zstd.with_level(*zstd::compression_level_range().end() + 1),
+            zstd.with_threads(100_000),
+            gzip.with_level(10),
+            gzip.with_level(-1),
+            gzip.with_threads(2),
+            CompressionSettings::new(Compression::Zlib).with_level(12),
+        ] {
+            let err = settings.validate().unwrap_err();
+            assert!(
+                matches!(err, BlendFileError::InvalidCompression(_)),
+                "{settings:?}"
+            );
+        }
+        assert!(zstd.with_level(-5).validate().is_ok());
+        assert!(gzip.with_level(0).validate().is_ok());
+
+        // Nothing is written when the settings are invalid
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), 64);
+        let before = std::fs::read(&path).unwrap();
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file.get_blocks_by_type_mut(b"IM").unwrap()[0].data[104] = b'/';
+        let options = SaveOptions::default().with_compression(gzip.with_level(42));
+        let err = blend_file.save_with_options(&options).unwrap_err();
+        assert!(err.to_string().contains("outside 0..=9"), "{err}");
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| s.parse::<CompressionSettings>();
+
+        let settings = parse("zstd:3:8").unwrap();
+        assert_eq!(
+            (settings.kind, settings.level, settings.threads),
+            (Compression::Zstd, 3, 8)
+        );
+        let settings = parse("gzip:9").unwrap();
+        assert_eq!((settings.kind, settings.level), (Compression::Gzip, 9));
+        assert_eq!(parse("zstd").unwrap().level, 3);
+        assert_eq!(parse("ZLIB").unwrap().level, 6);
+        assert_eq!(parse("none").unwrap(), CompressionSettings::default());
+
+        for bad in [
+            "lz4",
+            "zstd:fast",
+            "zstd:3:-1",
+            "zstd:3:8:1",
+            "gzip:9:2",
+            "",
+        ] {
+            let err = parse(bad).unwrap_err();
+            assert!(
+                matches!(err, BlendFileError::InvalidCompression(_)),
+                "{bad}"
+            );
+        }
+        assert!(parse("lz4")
+            .unwrap_err()
+            .to_string()
+            .contains("expected none, zstd, gzip or zlib"));
+    }
+}
+
+// Tests for keeping permissions, group and modification time across saves
+#[cfg(test)]
+#[cfg(unix)]
+mod save_metadata {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::blend_file::{SaveOptions, SaveOutcome, SaveReport};
+    use blend_file_reader::BlendFile;
+    use std::fs::{self, File};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    /// 2001-09-09, well clear of anything a save could stamp
+    fn old_mtime() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000)
+    }
+
+    /// A fixture with mode `mode` and an old modification time
+    fn write_fixture(dir: &Path, name: &str, mode: u32) -> PathBuf {
+        let path = dir.join(name);
+        let data = SyntheticBlend::new(300)
+            .image("IMwood", "//wood.png")
+            .finish();
+        fs::write(&path, data).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old_mtime())
+            .unwrap();
+        path
+    }
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    fn mtime(path: &Path) -> SystemTime {
+        fs::metadata(path).unwrap().modified().unwrap()
+    }
+
+    fn edit_and_save(path: &Path, options: &SaveOptions) -> SaveReport {
+        let mut blend_file = BlendFile::open_read_write(path).unwrap();
+        let mut images = blend_file.get_blocks_by_type_mut(b"IM").unwrap();
+        images[0].data[104..115].copy_from_slice(b"//rock.png\0");
+        blend_file.save_with_report(options).unwrap()
+    }
+
+    #[test]
+    fn test_save_keeps_mode_and_mtime_when_asked() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), "shot.blend", 0o640);
+
+        let report = edit_and_save(&path, &SaveOptions::default().with_preserve_mtime(true));
+        assert_eq!(report.outcome, SaveOutcome::Saved);
+        assert_eq!(report.permissions, Some(true));
+        assert_eq!(report.group, Some(true));
+        assert_eq!(report.mtime, Some(true));
+        assert_eq!(mode(&path), 0o640);
+        assert_eq!(mtime(&path), old_mtime());
+
+        let links = BlendFile::open(&path).unwrap().get_library_links().unwrap();
+        assert_eq!(links[0].path, "//rock.png");
+    }
+
+    #[test]
+    fn test_save_bumps_mtime_by_default() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), "shot.blend", 0o640);
+
+        let report = edit_and_save(&path, &SaveOptions::default());
+        assert_eq!(report.permissions, Some(true));
+        assert_eq!(report.mtime, None);
+        assert_eq!(mode(&path), 0o640);
+        assert!(mtime(&path) > old_mtime());
+    }
+
+    #[test]
+    fn test_nothing_to_save_reports_nothing() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), "shot.blend", 0o640);
+
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        let report = blend_file
+            .save_with_report(&SaveOptions::default().with_preserve_mtime(true))
+            .unwrap();
+        assert_eq!(report.outcome, SaveOutcome::NothingToSave);
+        assert_eq!((report.permissions, report.mtime), (None, None));
+        assert_eq!(mtime(&path), old_mtime());
+    }
+
+    #[test]
+    fn test_save_replaces_the_file_whole() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path(), "shot.blend", 0o640);
+        let before = fs::read(&path).unwrap();
+        let mut reader = File::open(&path).unwrap();
+
+        edit_and_save(&path, &SaveOptions::default());
+
+        // A reader of the old file keeps seeing it, and no temp file is left
+        let mut seen = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut seen).unwrap();
+        assert_eq!(seen, before);
+        assert_ne!(fs::read(&path).unwrap(), before);
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["shot.blend"]);
+    }
+
+    #[test]
+    fn test_save_as_through_symlink_keeps_the_link() {
+        let dir = tempdir().unwrap();
+        let source = write_fixture(dir.path(), "shot.blend", 0o644);
+        let target = write_fixture(dir.path(), "v002.blend", 0o640);
+        let link = dir.path().join("latest.blend");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut blend_file = BlendFile::open(&source).unwrap();
+        let report = blend_file
+            .save_as_with_report(&link, &SaveOptions::default())
+            .unwrap();
+        assert_eq!(report.permissions, Some(true));
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(mode(&target), 0o640);
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&source).unwrap());
+    }
+
+    #[test]
+    fn test_save_as_over_existing_file() {
+        let dir = tempdir().unwrap();
+        let source = write_fixture(dir.path(), "shot.blend", 0o644);
+        let target = write_fixture(dir.path(), "publish.blend", 0o604);
+        let options = SaveOptions::default().with_preserve_mtime(true);
+
+        let mut blend_file = BlendFile::open(&source).unwrap();
+        let report = blend_file.save_as_with_report(&target, &options).unwrap();
+        assert_eq!(report.permissions, Some(true));
+        assert_eq!(report.mtime, Some(true));
+        assert_eq!(mode(&target), 0o604);
+        assert_eq!(mtime(&target), old_mtime());
+
+        // A new file has nothing to keep
+        let fresh = dir.path().join("fresh.blend");
+        let report = blend_file.save_as_with_report(&fresh, &options).unwrap();
+        assert_eq!(report.outcome, SaveOutcome::Saved);
+        assert_eq!(
+            (report.permissions, report.group, report.mtime),
+            (None, None, None)
+        );
+    }
+}
+
+// Tests for converting blend files between 32-bit and 64-bit pointer layouts
+#[cfg(test)]
+mod pointer_size_conversion {
+    use crate::common::{mesh_with_materials, write_str, SyntheticBlend};
+    use blend_file_reader::blend_file::ValidationMode;
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::{Endianness, PointerSize};
+    use blend_file_reader::BlendFile;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// ID { *next, *prev, name[24] }, Library { id, filepath[64], *parent },
+    /// Object { id, loc[3], *data } with 32-bit type lengths
+    fn raw_sdna_32() -> RawSdna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        RawSdna {
+            names: strings(&[
+                "*next",
+                "*prev",
+                "name[24]",
+                "id",
+                "filepath[64]",
+                "*parent",
+                "loc[3]",
+                "*data",
+            ]),
+            types: strings(&["char", "float", "void", "ID", "Library", "Object"]),
+            tlens: vec![1, 4, 0, 32, 100, 48],
+            structs: vec![
+                (3, vec![(2, 0), (2, 1), (0, 2)]),
+                (4, vec![(3, 3), (0, 4), (4, 5)]),
+                (5, vec![(3, 3), (1, 6), (2, 7)]),
+            ],
+        }
+    }
+
+    fn sdna_32() -> Dna {
+        Dna::from_raw(raw_sdna_32(), PointerSize::Bits32).unwrap()
+    }
+
+    fn library_32() -> Vec<u8> {
+        let mut payload = vec![0u8; 100];
+        payload[0..4].copy_from_slice(&0x5000u32.to_le_bytes());
+        write_str(&mut payload, 8, "LIlib.blend");
+        write_str(&mut payload, 32, "//libs/lib.blend");
+        payload[96..100].copy_from_slice(&0x2000u32.to_le_bytes());
+        payload
+    }
+
+    fn object_32() -> Vec<u8> {
+        let mut payload = vec![0u8; 48];
+        write_str(&mut payload, 8, "OBCube");
+        for (i, value) in [2.0f32, 3.0, 5.0].iter().enumerate() {
+            payload[32 + i * 4..36 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        payload[44..48].copy_from_slice(&0x3000u32.to_le_bytes());
+        payload
+    }
+
+    fn write_32bit_file(path: &Path) {
+        let data = SyntheticBlend::with_format(279, PointerSize::Bits32, Endianness::Little)
+            .typed_block(b"LI", 1, 1, &library_32())
+            .typed_block(b"OB", 2, 1, &object_32())
+            .block(b"DATA", &[1, 2, 3, 4, 5, 6, 7])
+            .dna(&sdna_32())
+            .finish();
+        std::fs::write(path, data).unwrap();
+    }
+
+    fn field_bytes<'a>(dna: &Dna, block: &'a [u8], struct_name: &str, field: &str) -> &'a [u8] {
+        let field = dna
+            .get_struct(struct_name)
+            .unwrap()
+            .fields
+            .iter()
+            .find(|f| f.name == field)
+            .unwrap();
+        let offset = field.offset.unwrap();
+        &block[offset..offset + field.size]
+    }
+
+    #[test]
+    fn test_offsets_follow_file_pointer_size() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_32bit_file(&path);
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        let dna = &blend_file.dna;
+        assert_eq!(dna.pointer_size, 4);
+        // Fields after pointers sit 4 bytes on per pointer, not 8
+        assert_eq!(dna.field_offset("Object", "id.name").unwrap().offset, 8);
+        assert_eq!(dna.field_offset("Object", "data").unwrap().offset, 44);
+        assert_eq!(dna.field_offset("Library", "parent").unwrap().offset, 96);
+        assert_eq!(
+            blend_file.blocks[1].get_float_field(dna, "loc").unwrap(),
+            [2.0, 3.0, 5.0]
+        );
+
+        // Pointer arrays take one pointer width per element
+        let raw = RawSdna {
+            names: vec!["*mat[3]".to_string(), "totcol".to_string()],
+            types: vec![
+                "short".to_string(),
+                "Material".to_string(),
+                "Mesh".to_string(),
+            ],
+            tlens: vec![2, 0, 14],
+            structs: vec![(2, vec![(1, 0), (0, 1)])],
+        };
+        let dna = Dna::from_raw(raw.clone(), PointerSize::Bits32).unwrap();
+        let mat = dna.field_offset("Mesh", "mat").unwrap();
+        assert_eq!((mat.offset, mat.field.size), (0, 12));
+        assert_eq!(dna.field_offset("Mesh", "totcol").unwrap().offset, 12);
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        assert_eq!(dna.field_offset("Mesh", "totcol").unwrap().offset, 24);
+    }
+
+    #[test]
+    fn test_32_to_64_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_32bit_file(&path);
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        assert_eq!(blend_file.header.pointer_size, PointerSize::Bits32);
+        assert_eq!(blend_file.dna.get_struct("Object").unwrap().size, 48);
+
+        let report = blend_file
+            .convert_pointer_size(PointerSize::Bits64)
+            .unwrap();
+        assert_eq!(report.converted_blocks, 2);
+        // ID next/prev twice plus Library.parent and Object.data
+        assert_eq!(report.pointers_converted, 6);
+        // The raw DATA block carries no DNA layout
+        assert_eq!(report.raw_blocks, vec![2]);
+
+        let converted = dir.path().join("converted.blend");
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        std::fs::write(&converted, out).unwrap();
+
+        let reopened = BlendFile::open(&converted).unwrap();
+        assert_eq!(reopened.header.pointer_size, PointerSize::Bits64);
+        let dna = &reopened.dna;
+        assert_eq!(dna.get_struct("ID").unwrap().size, 40);
+        assert_eq!(dna.get_struct("Library").unwrap().size, 112);
+        assert_eq!(dna.get_struct("Object").unwrap().size, 60);
+
+        let li = &reopened.get_library_blocks().unwrap()[0].data;
+        assert_eq!(li.len(), 112);
+        assert_eq!(
+            &field_bytes(dna, li, "Library", "filepath")[..16],
+            b"//libs/lib.blend"
+        );
+        assert_eq!(
+            field_bytes(dna, li, "Library", "parent"),
+            0x2000u64.to_le_bytes()
+        );
+        assert_eq!(&li[..8], 0x5000u64.to_le_bytes());
+        assert_eq!(&li[16..27], b"LIlib.blend");
+
+        let ob = &reopened.get_blocks_by_type(b"OB").unwrap()[0].data;
+        let loc: Vec<f32> = field_bytes(dna, ob, "Object", "loc")
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        assert_eq!(loc, vec![2.0, 3.0, 5.0]);
+        assert_eq!(
+            field_bytes(dna, ob, "Object", "data"),
+            0x3000u64.to_le_bytes()
+        );
+
+        let data = &reopened.get_blocks_by_type(b"DATA").unwrap()[0].data;
+        assert_eq!(data, &vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_round_trip_back_to_32() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_32bit_file(&path);
+        let original = std::fs::read(&path).unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        blend_file
+            .convert_pointer_size(PointerSize::Bits64)
+            .unwrap();
+        blend_file
+            .convert_pointer_size(PointerSize::Bits32)
+            .unwrap();
+
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        assert_eq!(out, original);
+    }
+
+    #[test]
+    fn test_narrowing_rejects_wide_addresses() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_32bit_file(&path);
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        blend_file
+            .convert_pointer_size(PointerSize::Bits64)
+            .unwrap();
+        blend_file.blocks[0].old_memory_address = 0x1_0000_0000;
+
+        let result = blend_file.convert_pointer_size(PointerSize::Bits32);
+        assert!(result.is_err());
+        // A failed conversion leaves the file untouched
+        assert_eq!(blend_file.header.pointer_size, PointerSize::Bits64);
+    }
+
+    #[test]
+    fn test_conversion_requires_dna() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("no_dna.blend");
+        std::fs::write(
+            &path,
+            SyntheticBlend::new(300).block(b"OB", &[0; 16]).finish(),
+        )
+        .unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        assert!(blend_file.dna.is_empty());
+        assert!(blend_file
+            .convert_pointer_size(PointerSize::Bits32)
+            .is_err());
+    }
+
+    #[test]
+    fn test_struct_shorter_than_its_fields() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("short.blend");
+        // Object's fields need 48 bytes but its TLEN says 40
+        let mut raw = raw_sdna_32();
+        raw.tlens[5] = 40;
+        let dna = Dna::from_raw(raw, PointerSize::Bits32).unwrap();
+        let data = SyntheticBlend::with_format(279, PointerSize::Bits32, Endianness::Little)
+            .typed_block(b"OB", 2, 1, &object_32()[..40])
+            .dna(&dna)
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let mut blend_file =
+            BlendFile::open_with_validation(&path, ValidationMode::Permissive).unwrap();
+        let err = blend_file
+            .convert_pointer_size(PointerSize::Bits64)
+            .unwrap_err();
+        assert!(err.to_string().contains("Object.loc"), "{err}");
+        assert_eq!(blend_file.header.pointer_size, PointerSize::Bits32);
+    }
+
+    #[test]
+    fn test_pointer_array_blocks_are_converted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("materials.blend");
+        let data = mesh_with_materials(PointerSize::Bits32, Endianness::Little, &[0x5000, 0x6000]);
+        std::fs::write(&path, data).unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        let report = blend_file
+            .convert_pointer_size(PointerSize::Bits64)
+            .unwrap();
+        // Mesh.mat, then both slots of the array it points at
+        assert_eq!(report.converted_blocks, 2);
+        assert_eq!(report.pointers_converted, 3);
+        assert!(report.raw_blocks.is_empty());
+
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        let converted = dir.path().join("converted.blend");
+        std::fs::write(&converted, out).unwrap();
+        let reopened = BlendFile::open(&converted).unwrap();
+        let array = &reopened.blocks[1];
+        assert_eq!(array.size, 16);
+        assert_eq!(
+            u64::from_le_bytes(array.data[0..8].try_into().unwrap()),
+            0x5000
+        );
+        assert_eq!(
+            u64::from_le_bytes(array.data[8..16].try_into().unwrap()),
+            0x6000
+        );
+    }
+}
+
+// Tests for converting legacy big-endian blend files to little-endian
+#[cfg(test)]
+mod endianness_conversion {
+    use crate::common::{mesh_with_materials, write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::header::{Endianness, PointerSize};
+    use blend_file_reader::BlendFile;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// ID { *next, name[8] }, Thing { id, count, scale, flags[2], size, label[6] }
+    fn sdna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&[
+                "*next", "name[8]", "id", "count", "scale", "flags[2]", "size", "label[6]",
+            ]),
+            types: strings(&["char", "short", "int", "float", "double", "ID", "Thing"]),
+            tlens: vec![1, 2, 4, 4, 8, 16, 42],
+            structs: vec![
+                (5, vec![(0, 0), (0, 1)]),
+                (6, vec![(5, 2), (2, 3), (3, 4), (1, 5), (4, 6), (0, 7)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn thing_big_endian() -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&0x1234_5678u64.to_be_bytes());
+        payload.extend_from_slice(b"THCrate\0");
+        payload.extend_from_slice(&(-42i32).to_be_bytes());
+        payload.extend_from_slice(&1.5f32.to_be_bytes());
+        payload.extend_from_slice(&7i16.to_be_bytes());
+        payload.extend_from_slice(&(-3i16).to_be_bytes());
+        payload.extend_from_slice(&0.25f64.to_be_bytes());
+        let mut label = [0u8; 6];
+        write_str(&mut label, 0, "box");
+        payload.extend_from_slice(&label);
+        payload
+    }
+
+    fn write_big_endian_file(path: &Path) {
+        let data = SyntheticBlend::with_format(279, PointerSize::Bits64, Endianness::Big)
+            .typed_block(b"TH", 1, 1, &thing_big_endian())
+            .block(b"DATA", &[1, 2, 3, 4])
+            .dna(&sdna())
+            .finish();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_big_endian_sdna() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_big_endian_file(&path);
+
+        // The DNA1 payload really is big-endian: 8 names, counted high byte first
+        let data = std::fs::read(&path).unwrap();
+        let sdna = data.windows(8).position(|w| w == b"SDNANAME").unwrap();
+        assert_eq!(data[sdna + 8..sdna + 12], [0, 0, 0, 8]);
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        let dna = &blend_file.dna;
+        assert_eq!(dna.raw_tlens(), [1, 2, 4, 4, 8, 16, 42]);
+        let thing = dna.get_struct("Thing").unwrap();
+        assert_eq!(thing.size, 42);
+        let offsets: Vec<(&str, Option<usize>)> = thing
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                ("id", Some(0)),
+                ("count", Some(16)),
+                ("scale", Some(20)),
+                ("flags", Some(24)),
+                ("size", Some(28)),
+                ("label", Some(36)),
+            ]
+        );
+        assert!(dna.check_struct_sizes().is_ok());
+        assert_eq!(blend_file.blocks[0].sdna_index, 1);
+
+        // DNA-aware reads follow the file's byte order
+        assert_eq!(dna.endianness, Endianness::Big);
+        assert_eq!(
+            blend_file.blocks[0].get_float_field(dna, "scale").unwrap(),
+            [1.5]
+        );
+    }
+
+    #[test]
+    fn test_big_to_little_preserves_values() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_big_endian_file(&path);
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        assert_eq!(blend_file.header.endianness, Endianness::Big);
+        let address = blend_file.blocks[0].old_memory_address;
+        let report = blend_file.convert_endianness(Endianness::Little).unwrap();
+        assert_eq!(report.converted_blocks, 1);
+        // Nothing points at the raw DATA block, so its layout is unknown
+        assert_eq!(report.raw_blocks, vec![1]);
+        let dna = &blend_file.dna;
+        assert_eq!(
+            blend_file.blocks[0].get_float_field(dna, "scale").unwrap(),
+            [1.5]
+        );
+
+        let converted = dir.path().join("converted.blend");
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        std::fs::write(&converted, out).unwrap();
+
+        let reopened = BlendFile::open(&converted).unwrap();
+        assert_eq!(reopened.header.endianness, Endianness::Little);
+        assert_eq!(reopened.dna.get_struct("Thing").unwrap().size, 42);
+
+        let block = &reopened.blocks[0];
+        assert_eq!(block.old_memory_address, address);
+        let data = &block.data;
+        assert_eq!(
+            u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            0x1234_5678
+        );
+        assert_eq!(&data[8..16], b"THCrate\0");
+        assert_eq!(i32::from_le_bytes(data[16..20].try_into().unwrap()), -42);
+        assert_eq!(f32::from_le_bytes(data[20..24].try_into().unwrap()), 1.5);
+        assert_eq!(i16::from_le_bytes(data[24..26].try_into().unwrap()), 7);
+        assert_eq!(i16::from_le_bytes(data[26..28].try_into().unwrap()), -3);
+        assert_eq!(f64::from_le_bytes(data[28..36].try_into().unwrap()), 0.25);
+        assert_eq!(&data[36..42], b"box\0\0\0");
+
+        // Raw DATA has no layout to swap by
+        let raw = &reopened.get_blocks_by_type(b"DATA").unwrap()[0].data;
+        assert_eq!(raw, &vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_round_trip_back_to_big() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.blend");
+        write_big_endian_file(&path);
+        let original = std::fs::read(&path).unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        blend_file.convert_endianness(Endianness::Little).unwrap();
+        blend_file.convert_endianness(Endianness::Big).unwrap();
+
+        let mut out = Vec::new();
+        blend_file.write_to_writer(&mut out).unwrap();
+        assert_eq!(out, original);
+    }
+
+    #[test]
+    fn test_conversion_requires_dna() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("no_dna.blend");
+        let data = SyntheticBlend::with_format(279, PointerSize::Bits64, Endianness::Big)
+            .block(b"OB", &[0; 16])
+            .finish();
+        std::fs::write(&path, data).unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        assert!(blend_file.convert_endianness(Endianness::Little).is_err());
+        assert_eq!(blend_file.header.endianness, Endianness::Big);
+    }
+
+    #[test]
+    fn test_pointer_array_blocks_are_swapped() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("materials.blend");
+        let data = mesh_with_materials(PointerSize::Bits64, Endianness::Big, &[0x5000, 0x6000]);
+        std::fs::write(&path, data).unwrap();
+
+        let mut blend_file = BlendFile::open(&path).unwrap();
+        let report = blend_file.convert_endianness(Endianness::Little).unwrap();
+        assert_eq!(report.converted_blocks, 2);
+        assert!(report.raw_blocks.is_empty());
+        let array = &blend_file.blocks[1].data;
+        assert_eq!(u64::from_le_bytes(array[0..8].try_into().unwrap()), 0x5000);
+        assert_eq!(u64::from_le_bytes(array[8..16].try_into().unwrap()), 0x6000);
+    }
+}
+
+// Tests for the audit log saves append to the RBAT block
+#[cfg(test)]
+mod audit {
+    use crate::common::{id_dna, write_str, SyntheticBlend};
+    use blend_file_reader::audit::{AUDIT_CODE, DEFAULT_AUDIT_TOOL};
+    use blend_file_reader::blend_file::{ReadWriteOptions, SaveOptions};
+
+    use blend_file_reader::naming::NamePolicy;
+    use blend_file_reader::BlendFile;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn write_blend(dir: &Path) -> PathBuf {
+        let mut blend = SyntheticBlend::new(300);
+        for name in ["OBhero", "OBprop"] {
+            let mut data = vec![0u8; 24];
+            write_str(&mut data, 0, name);
+            blend = blend.typed_block(b"OB", 1, 1, &data);
+        }
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, blend.dna(&id_dna()).finish()).unwrap();
+        path
+    }
+
+    fn run(args: &[&str], path: &Path) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .args(args)
+            .arg(path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_log_accumulates_across_sessions() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path());
+        let audit = SaveOptions::default().with_audit(true);
+
+        // With the originals kept, the change is traced to the field
+        let options = ReadWriteOptions::default().with_keep_originals(true);
+        let mut blend_file = BlendFile::open_read_write_with_options(&path, options).unwrap();
+        assert!(blend_file.audit_log().unwrap().is_empty());
+        blend_file
+            .rename_datablock("OBhero", "OBvillain", &NamePolicy::default())
+            .unwrap();
+        blend_file.save_with_options(&audit).unwrap();
+
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file
+            .rename_datablock("OBprop", "OBrock", &NamePolicy::default())
+            .unwrap();
+        blend_file
+            .save_with_options(&audit.clone().with_audit_tool("publish 2.1"))
+            .unwrap();
+
+        let log = BlendFile::open(&path).unwrap().audit_log().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].tool, DEFAULT_AUDIT_TOOL);
+        assert_eq!(log[0].changes.len(), 1);
+        let change = &log[0].changes[0];
+        assert_eq!(
+            (change.block.as_str(), change.field.as_deref()),
+            ("OBvillain", Some("id.name"))
+        );
+        assert!(change.before.is_some());
+        assert_ne!(change.before.as_ref(), Some(&change.after));
+
+        // Without them the whole block is recorded, with no before hash
+        assert_eq!(log[1].tool, "publish 2.1");
+        let change = &log[1].changes[0];
+        assert_eq!(
+            (
+                change.block.as_str(),
+                change.field.as_deref(),
+                change.before.as_deref()
+            ),
+            ("OBrock", None, None)
+        );
+        assert!(log[0].timestamp <= log[1].timestamp);
+    }
+
+    #[test]
+    fn test_log_survives_unaudited_saves() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path());
+
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file
+            .rename_datablock("OBhero", "OBvillain", &NamePolicy::default())
+            .unwrap();
+        blend_file
+            .save_with_options(&SaveOptions::default().with_audit(true))
+            .unwrap();
+
+        // A tool that doesn't audit still writes back the block it doesn't
+        // know, as Blender-style readers skip it
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file
+            .rename_datablock("OBprop", "OBrock", &NamePolicy::default())
+            .unwrap();
+        blend_file.save().unwrap();
+
+        let blend_file = BlendFile::open(&path).unwrap();
+        let audit_blocks = blend_file
+            .blocks
+            .iter()
+            .filter(|block| block.code == AUDIT_CODE)
+            .count();
+        assert_eq!(audit_blocks, 1);
+        let log = blend_file.audit_log().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].changes[0].block, "OBvillain");
+
+        // Nothing to save records nothing
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file
+            .save_with_options(&SaveOptions::default().with_audit(true))
+            .unwrap();
+        assert_eq!(
+            BlendFile::open(&path).unwrap().audit_log().unwrap().len(),
+            1
+        );
+
+        let stdout = run(&["validate"], &path);
+        assert!(stdout.ends_with(": OK\n"), "{stdout}");
+        let stdout = run(&["validate", "--deep"], &path);
+        assert!(stdout.ends_with(": OK\n"), "{stdout}");
+    }
+
+    #[test]
+    fn test_cli_prints_history() {
+        let dir = tempdir().unwrap();
+        let path = write_blend(dir.path());
+        let stdout = run(&["audit-log"], &path);
+        assert!(stdout.starts_with("No audit log in "), "{stdout}");
+
+        let mut blend_file = BlendFile::open_read_write(&path).unwrap();
+        blend_file
+            .rename_datablock("OBhero", "OBvillain", &NamePolicy::default())
+            .unwrap();
+        blend_file
+            .save_with_options(&SaveOptions::default().with_audit(true))
+            .unwrap();
+
+        let stdout = run(&["audit-log"], &path);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 2, "{stdout}");
+        assert!(
+            lines[0].ends_with(&format!(" {DEFAULT_AUDIT_TOOL}, 1 change(s)")),
+            "{stdout}"
+        );
+        assert!(lines[1].starts_with("  OBvillain: ? -> "), "{stdout}");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&run(&["audit-log", "--json"], &path)).unwrap();
+        assert_eq!(json[0]["changes"][0]["block"], "OBvillain");
+    }
+}
+
+// Tests for rewriting stored link paths with BlendFile::remap_link_path
+#[cfg(test)]
+mod link_path_remap {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::{BlendFile, BlendFileError, FormatError};
+    use std::collections::BTreeSet;
+    use tempfile::tempdir;
+
+    fn open(dir: &std::path::Path) -> BlendFile {
+        let path = dir.join("shot.blend");
+        let data = SyntheticBlend::new(300)
+            .library("LIprops", "//props.blend")
+            .image("IMwood", "//textures/wood.png")
+            .image("IMwood2", "//textures/wood.png")
+            .image("IMstone", "//textures/stone.png")
+            .finish();
+        std::fs::write(&path, data).unwrap();
+        BlendFile::open_read_write(&path).unwrap()
+    }
+
+    fn paths(blend_file: &BlendFile) -> Vec<String> {
+        let links = blend_file.get_library_links().unwrap();
+        links.into_iter().map(|l| l.path).collect()
+    }
+
+    #[test]
+    fn test_remap_link_path_rewrites_exact_matches() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = open(dir.path());
+
+        let changed = blend_file
+            .remap_link_path("//textures/wood.png", "//tex/wood.png")
+            .unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(blend_file.modified_blocks(), BTreeSet::from([1, 2]));
+        assert_eq!(
+            paths(&blend_file),
+            [
+                "//props.blend",
+                "//tex/wood.png",
+                "//tex/wood.png",
+                "//textures/stone.png"
+            ]
+        );
+
+        // A prefix of a stored path isn't a match
+        assert_eq!(blend_file.remap_link_path("//textures", "//t").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_remap_link_path_too_long_changes_nothing() {
+        let dir = tempdir().unwrap();
+        let mut blend_file = open(dir.path());
 
-        // Clean up
-        fs::remove_file(temp_file).expect("Failed to remove temp file");
+        let long = format!("//{}", "x".repeat(2000));
+        let err = blend_file
+            .remap_link_path("//textures/wood.png", &long)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BlendFileError::Format(FormatError::InvalidFormat(_))
+        ));
+        assert!(!blend_file.is_dirty());
+        assert_eq!(paths(&blend_file)[1], "//textures/wood.png");
     }
 }
@@ -0,0 +1,210 @@
+// Synthetic .blend builders and other fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use blend_file_reader::dna::{Dna, RawSdna};
+use blend_file_reader::header::{Endianness, Header, PointerSize};
+use blend_file_reader::library_link::LibraryLink;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Builds a blend file block by block, little-endian with 64-bit pointers unless
+/// created through `with_format`
+pub struct SyntheticBlend {
+    data: Vec<u8>,
+    header: Header,
+    next_address: u64,
+}
+
+impl SyntheticBlend {
+    pub fn new(version: u32) -> Self {
+        Self::with_format(version, PointerSize::Bits64, Endianness::Little)
+    }
+
+    pub fn with_format(version: u32, pointer_size: PointerSize, endianness: Endianness) -> Self {
+        let header = Header {
+            magic: *b"BLENDER",
+            pointer_size,
+            endianness,
+            version,
+        };
+        let mut data = Vec::new();
+        header.write_to_writer(&mut data).unwrap();
+        Self {
+            data,
+            header,
+            next_address: 0x1000,
+        }
+    }
+
+    pub fn block(self, code: &[u8], payload: &[u8]) -> Self {
+        self.typed_block(code, 0, 1, payload)
+    }
+
+    /// Old memory address the next block will be written with
+    pub fn next_address(&self) -> u64 {
+        self.next_address
+    }
+
+    /// Block whose payload holds `count` instances of DNA struct `sdna_index`
+    pub fn typed_block(mut self, code: &[u8], sdna_index: u32, count: u32, payload: &[u8]) -> Self {
+        let mut full_code = [0u8; 4];
+        full_code[..code.len()].copy_from_slice(code);
+        self.data.extend_from_slice(&full_code);
+        self.push_u32(payload.len() as u32);
+        let address = self.next_address;
+        self.push_uint(address, self.header.pointer_size.bytes());
+        self.push_u32(sdna_index);
+        self.push_u32(count);
+        self.data.extend_from_slice(payload);
+        self.next_address += 0x1000;
+        self
+    }
+
+    /// Library block with `filepath` at offset 144 and `name` at offset 32
+    pub fn library(self, name: &str, filepath: &str) -> Self {
+        let mut payload = vec![0u8; 1200];
+        write_str(&mut payload, 32, name);
+        write_str(&mut payload, 144, filepath);
+        self.block(b"LI", &payload)
+    }
+
+    /// Image block with `name` at offset 0 and `filepath` at offset 104
+    pub fn image(self, name: &str, filepath: &str) -> Self {
+        let mut payload = vec![0u8; 1200];
+        write_str(&mut payload, 0, name);
+        write_str(&mut payload, 104, filepath);
+        self.block(b"IM", &payload)
+    }
+
+    /// Append the DNA1 block describing `dna`
+    pub fn dna(mut self, dna: &Dna) -> Self {
+        dna.write_to_writer(&mut self.data, &self.header).unwrap();
+        self
+    }
+
+    /// Append the ENDB terminator, zeroed like Blender writes it
+    pub fn finish(mut self) -> Vec<u8> {
+        self.data.extend_from_slice(b"ENDB");
+        self.push_u32(0);
+        self.push_uint(0, self.header.pointer_size.bytes());
+        self.push_u32(0);
+        self.push_u32(0);
+        self.data
+    }
+
+    fn push_u32(&mut self, value: u32) {
+        self.push_uint(value as u64, 4);
+    }
+
+    fn push_uint(&mut self, value: u64, size: usize) {
+        let bytes = value.to_le_bytes();
+        match self.header.endianness {
+            Endianness::Little => self.data.extend_from_slice(&bytes[..size]),
+            Endianness::Big => self.data.extend(bytes[..size].iter().rev()),
+        }
+    }
+}
+
+pub fn write_str(payload: &mut [u8], offset: usize, value: &str) {
+    payload[offset..offset + value.len()].copy_from_slice(value.as_bytes());
+    payload[offset + value.len()] = 0;
+}
+
+/// One library linking `filepath`, laid out by its DNA as Library { ID id;
+/// char filepath[1024] } with a 66-byte ID, so `filepath` is at 66 rather
+/// than the fallback offset of 144
+pub fn shifted_library_blend(filepath: &str) -> Vec<u8> {
+    let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+    let raw = RawSdna {
+        names: strings(&["name[66]", "id", "filepath[1024]"]),
+        types: strings(&["char", "ID", "Library"]),
+        tlens: vec![1, 66, 1090],
+        structs: vec![(1, vec![(0, 0)]), (2, vec![(1, 1), (0, 2)])],
+    };
+    let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+    let mut payload = vec![0u8; 1090];
+    write_str(&mut payload, 0, "LIkit");
+    write_str(&mut payload, 66, filepath);
+    SyntheticBlend::new(300)
+        .typed_block(b"LI", 1, 1, &payload)
+        .dna(&dna)
+        .finish()
+}
+
+/// Mesh { Material **mat; short totcol; short pad } pointing at a raw DATA
+/// block of `materials`, the way Blender stores material slots
+pub fn mesh_with_materials(
+    pointer_size: PointerSize,
+    endianness: Endianness,
+    materials: &[u64],
+) -> Vec<u8> {
+    let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+    let width = pointer_size.bytes();
+    let raw = RawSdna {
+        names: strings(&["**mat", "totcol", "pad"]),
+        types: strings(&["short", "Material", "Mesh"]),
+        tlens: vec![2, 0, (width + 4) as u16],
+        structs: vec![(2, vec![(1, 0), (0, 1), (0, 2)])],
+    };
+    let dna = Dna::from_raw(raw, pointer_size).unwrap();
+    let uint = |value: u64, size: usize| -> Vec<u8> {
+        let bytes = value.to_le_bytes()[..size].to_vec();
+        match endianness {
+            Endianness::Little => bytes,
+            Endianness::Big => bytes.into_iter().rev().collect(),
+        }
+    };
+
+    let blend = SyntheticBlend::with_format(279, pointer_size, endianness);
+    let array_address = blend.next_address() + 0x1000;
+    let mut mesh = uint(array_address, width);
+    mesh.extend(uint(materials.len() as u64, 2));
+    mesh.extend([0, 0]);
+    let array: Vec<u8> = materials.iter().flat_map(|&m| uint(m, width)).collect();
+    blend
+        .typed_block(b"ME", 0, 1, &mesh)
+        .block(b"DATA", &array)
+        .dna(&dna)
+        .finish()
+}
+
+/// ID { char name[24] }, Object { ID id }
+pub fn id_dna() -> Dna {
+    let raw = RawSdna {
+        names: strings(&["name[24]", "id"]),
+        types: strings(&["char", "ID", "Object"]),
+        tlens: vec![1, 24, 24],
+        structs: vec![(1, vec![(0, 0)]), (2, vec![(1, 1)])],
+    };
+    Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+}
+
+pub fn strings(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| s.to_string()).collect()
+}
+
+pub fn image_link(path: &str) -> LibraryLink {
+    LibraryLink::new(path, "Image")
+}
+
+/// Reader that adds the bytes it reads to `count`, for checking how far a
+/// streaming read got
+pub struct CountingReader<R> {
+    pub inner: R,
+    pub count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::SeqCst);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
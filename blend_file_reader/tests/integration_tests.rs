@@ -1,3 +1,5 @@
+mod common;
+
 use blend_file_reader::BlendFile;
 use std::fs;
 
@@ -54,8 +56,14 @@ fn test_library_link_extraction() {
         dna: blend_file_reader::dna::Dna {
             structs: std::collections::HashMap::new(),
             type_sizes: std::collections::HashMap::new(),
+            ..Default::default()
         },
         blocks: vec![],
+        dna_blocks: Vec::new(),
+        warnings: Vec::new(),
+        lock_warnings: Vec::new(),
+        incomplete: None,
+        modified: Default::default(),
         mmap: None,
         file: None,
     };
@@ -80,6 +88,7 @@ fn test_block_filtering() {
         dna: blend_file_reader::dna::Dna {
             structs: std::collections::HashMap::new(),
             type_sizes: std::collections::HashMap::new(),
+            ..Default::default()
         },
         blocks: vec![
             Block {
@@ -110,6 +119,11 @@ fn test_block_filtering() {
                 data: vec![0; 150],
             },
         ],
+        dna_blocks: Vec::new(),
+        warnings: Vec::new(),
+        lock_warnings: Vec::new(),
+        incomplete: None,
+        modified: Default::default(),
         mmap: None,
         file: None,
     };
@@ -149,21 +163,11 @@ fn test_path_resolution() {
     let extractor = LibraryLinkExtractor::new("/home/user/project/scene.blend");
 
     let mut links = vec![
-        LibraryLink {
-            path: "textures/wood.jpg".to_string(),
-            absolute_path: None,
-            block_type: "Image".to_string(),
-            block_name: None,
-            is_relative: true,
-        },
-        LibraryLink {
-            path: "/absolute/path/texture.jpg".to_string(),
-            absolute_path: None,
-            block_type: "Image".to_string(),
-            block_name: None,
-            is_relative: false,
-        },
+        LibraryLink::new("textures/wood.jpg", "Image"),
+        LibraryLink::new("/absolute/path/texture.jpg", "Image"),
     ];
+    assert!(links[0].is_relative);
+    assert!(!links[1].is_relative);
 
     extractor.resolve_relative_paths(&mut links).unwrap();
 
@@ -178,11 +182,373 @@ fn test_path_resolution() {
 fn test_cli_functionality() {
     use std::process::Command;
 
-    // Test that the CLI binary can be built
+    // Test that the CLI binary can be built, with the features this test was
+    // built with so later CLI tests still find their subcommands
+    let features = [
+        (cfg!(feature = "sqlite-export"), "sqlite-export"),
+        (cfg!(feature = "async"), "async"),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, name)| *name)
+    .collect::<Vec<_>>()
+    .join(",");
     let status = Command::new("cargo")
-        .args(["build", "--bin", "blend-file-reader"])
+        .args([
+            "build",
+            "--bin",
+            "blend-file-reader",
+            "--features",
+            &features,
+        ])
         .status()
         .unwrap();
 
     assert!(status.success());
 }
+
+// Runs the workflow examples end to end so they can't rot.
+//
+// `cargo test` builds every example before running tests; they generate
+// their own fixtures when started without arguments.
+#[cfg(test)]
+mod examples {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    /// target/<profile>/examples/<name>, next to this test's deps directory
+    fn example(name: &str) -> PathBuf {
+        let exe = std::env::current_exe().unwrap();
+        let profile_dir = exe.parent().and_then(|deps| deps.parent()).unwrap();
+        let path = profile_dir
+            .join("examples")
+            .join(name)
+            .with_extension(std::env::consts::EXE_EXTENSION);
+        assert!(
+            path.exists(),
+            "{} is missing; build it with `cargo build --examples`",
+            path.display()
+        );
+        path
+    }
+
+    fn run(name: &str) -> String {
+        let output = Command::new(example(name)).output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert!(
+            output.status.success(),
+            "{name} failed: {stdout}{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        stdout
+    }
+
+    #[test]
+    fn test_extract_links_example() {
+        let stdout = run("extract_links");
+        assert!(stdout.contains("links: 3"), "{stdout}");
+        assert!(stdout.contains("//props.blend"), "{stdout}");
+    }
+
+    #[test]
+    fn test_remap_path_example() {
+        let stdout = run("remap_path");
+        assert!(stdout.contains("remapped: 1 block(s)"), "{stdout}");
+        assert!(stdout.contains("saved:"), "{stdout}");
+        assert!(stdout.contains("  //tex/wood.png"), "{stdout}");
+        assert!(!stdout.contains("//textures/wood.png"), "{stdout}");
+    }
+
+    #[test]
+    fn test_trace_links_example() {
+        let stdout = run("trace_links");
+        assert!(stdout.contains("ok       //textures/wood.png"), "{stdout}");
+        assert!(stdout.contains("missing: 2 of 3"), "{stdout}");
+    }
+
+    #[test]
+    fn test_check_file_example() {
+        let stdout = run("check_file");
+        assert!(stdout.contains("Missing Assets: 1"), "{stdout}");
+        assert!(stdout.contains("check: failed"), "{stdout}");
+    }
+}
+
+// Tests for reading blend data from memory and the CLI's `-` (stdin) file argument
+#[cfg(test)]
+mod stdin {
+    use crate::common::SyntheticBlend;
+    use blend_file_reader::BlendFile;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Output, Stdio};
+    use tempfile::tempdir;
+
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let data = SyntheticBlend::new(300)
+            .library("LIprops", "//props.blend")
+            .image("IMwood", "//textures/wood.png")
+            .image("IMsky", "/srv/hdri/sky.exr")
+            .block(b"DATA", &[1u8; 64])
+            .finish();
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn run(args: &[&str], stdin: Option<&[u8]>) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut pipe = child.stdin.take().unwrap();
+        pipe.write_all(stdin.unwrap_or_default()).unwrap();
+        drop(pipe);
+        child.wait_with_output().unwrap()
+    }
+
+    /// Drop what depends on where the file came from: its path and resolved link paths
+    fn without_origin(mut value: serde_json::Value) -> serde_json::Value {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("file");
+            object.remove("path");
+        }
+        strip_absolute(&mut value);
+        value
+    }
+
+    fn strip_absolute(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                object.remove("absolute_path");
+                object.values_mut().for_each(strip_absolute);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip_absolute),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let data = std::fs::read(&path).unwrap();
+        let opened = BlendFile::open(&path).unwrap();
+
+        for bytes in [data.clone(), zstd::encode_all(&data[..], 3).unwrap()] {
+            let blend_file = BlendFile::from_bytes(&bytes).unwrap();
+            assert_eq!(blend_file.path, PathBuf::new());
+            assert_eq!(blend_file.blocks.len(), opened.blocks.len());
+
+            // Relative links have nothing to resolve against
+            let links = blend_file.get_library_links().unwrap();
+            let paths: Vec<_> = links.iter().map(|l| l.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                ["//props.blend", "//textures/wood.png", "/srv/hdri/sky.exr"]
+            );
+            assert!(links.iter().all(|l| l.absolute_path.is_none()));
+
+            let stat = blend_file.get_library_links_with_stat().unwrap();
+            assert!(stat
+                .iter()
+                .all(|l| !l.asset_status.as_ref().unwrap().exists));
+        }
+        assert!(BlendFile::from_bytes(b"BLENDER").is_err());
+    }
+
+    #[test]
+    fn test_cli_stdin_matches_path() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let file = path.to_str().unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let compressed = zstd::encode_all(&data[..], 3).unwrap();
+
+        for input in [&data, &compressed] {
+            let compare_json = |args: &[&str]| {
+                let from_path = run(&[args, &[file]].concat(), None);
+                let from_stdin = run(&[args, &["-f", "-"]].concat(), Some(input));
+                assert!(from_stdin.status.success(), "{args:?}: {from_stdin:?}");
+                let json = |output: &Output| {
+                    without_origin(serde_json::from_slice(&output.stdout).unwrap())
+                };
+                assert_eq!(json(&from_stdin), json(&from_path), "{args:?}");
+            };
+            compare_json(&["links", "--format", "json"]);
+            compare_json(&["summary", "--format", "json"]);
+            compare_json(&["info", "--json"]);
+
+            // Text output differs only in the line naming the file
+            let body = |output: Output| {
+                let stdout = String::from_utf8(output.stdout).unwrap();
+                stdout.lines().skip(1).collect::<Vec<_>>().join("\n")
+            };
+            assert_eq!(
+                body(run(&["blocks", "-"], Some(input))),
+                body(run(&["blocks", file], None))
+            );
+
+            let validate = run(&["validate", "-"], Some(input));
+            assert_eq!(String::from_utf8(validate.stdout).unwrap(), "-: OK\n");
+
+            let grep = |query| run(&["grep-path", "-", "--path", query], Some(input));
+            assert!(grep("//props.blend").status.success());
+            assert_eq!(grep("//other.blend").status.code(), Some(1));
+        }
+    }
+
+    #[test]
+    fn test_cli_stdin_garbage() {
+        let output = run(&["links", "-"], Some(b"not a blend file"));
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains("Error"));
+    }
+}
+
+// Tests for exporting blocks, links and pointers to SQLite
+#[cfg(test)]
+#[cfg(feature = "sqlite-export")]
+mod sqlite_export {
+    use crate::common::{write_str, SyntheticBlend};
+    use blend_file_reader::dna::{Dna, RawSdna};
+    use blend_file_reader::export::to_sqlite;
+    use blend_file_reader::header::PointerSize;
+    use blend_file_reader::BlendFile;
+    use rusqlite::Connection;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    /// ID { char name[24] }, Image { ID id; char filepath[64] },
+    /// Material { ID id; Image *image }
+    fn dna() -> Dna {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        let raw = RawSdna {
+            names: strings(&["name[24]", "id", "filepath[64]", "*image"]),
+            types: strings(&["char", "ID", "Image", "Material"]),
+            tlens: vec![1, 24, 88, 32],
+            structs: vec![
+                (1, vec![(0, 0)]),
+                (2, vec![(1, 1), (0, 2)]),
+                (3, vec![(1, 1), (2, 3)]),
+            ],
+        };
+        Dna::from_raw(raw, PointerSize::Bits64).unwrap()
+    }
+
+    fn image(name: &str, path: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 88];
+        write_str(&mut data, 0, name);
+        write_str(&mut data, 24, path);
+        data
+    }
+
+    fn material(name: &str, image: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+        write_str(&mut data, 0, name);
+        data[24..32].copy_from_slice(&image.to_le_bytes());
+        data
+    }
+
+    /// IMwood at 0x1000, IMstone at 0x2000, and three materials
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let data = SyntheticBlend::new(402)
+            .typed_block(b"IM", 1, 1, &image("IMwood", "//textures/wood.png"))
+            .typed_block(b"IM", 1, 1, &image("IMstone", "/abs/stone.png"))
+            .typed_block(b"MA", 2, 1, &material("MAFloor", 0x1000))
+            .typed_block(b"MA", 2, 1, &material("MATable", 0x1000))
+            .typed_block(b"MA", 2, 1, &material("MAWall", 0x2000))
+            .dna(&dna())
+            .finish();
+        let path = dir.join("shot.blend");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn count(db: &Connection, table: &str) -> usize {
+        db.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_matches_parsed_file() {
+        let dir = tempdir().unwrap();
+        let blend_file = BlendFile::open(write_fixture(dir.path())).unwrap();
+        let db_path = dir.path().join("shot.sqlite");
+        to_sqlite(&blend_file, &db_path).unwrap();
+
+        let db = Connection::open(&db_path).unwrap();
+        assert_eq!(count(&db, "blocks"), blend_file.blocks.len());
+        assert_eq!(
+            count(&db, "links"),
+            blend_file.get_library_links().unwrap().len()
+        );
+        assert_eq!(count(&db, "pointers"), 3);
+
+        // Which materials reference image "wood"?
+        let mut query = db
+            .prepare(
+                "SELECT src.name FROM pointers p
+                 JOIN blocks src ON src.id = p.src_block
+                 JOIN blocks dst ON dst.id = p.dst_block
+                 WHERE src.code = 'MA' AND dst.name = 'wood' ORDER BY src.name",
+            )
+            .unwrap();
+        let materials: Vec<String> = query
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(materials, ["Floor", "Table"]);
+
+        let (struct_name, address): (String, i64) = db
+            .query_row(
+                "SELECT struct_name, address FROM blocks WHERE name = 'stone'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((struct_name.as_str(), address), ("Image", 0x2000));
+
+        let (abs_path, relative): (String, bool) = db
+            .query_row(
+                "SELECT abs_path, relative FROM links WHERE path = '//textures/wood.png'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(relative);
+        assert!(abs_path.ends_with("textures/wood.png"), "{abs_path}");
+    }
+
+    #[test]
+    fn test_cli_export_sqlite() {
+        let dir = tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let out = dir.path().join("shot.sqlite");
+        std::fs::write(&out, b"stale").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_blend-file-reader"))
+            .args(["export-sqlite", "--file"])
+            .arg(&path)
+            .arg("--out")
+            .arg(&out)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+
+        // The stale file is replaced, and nothing else is left in the directory
+        let db = Connection::open(&out).unwrap();
+        assert_eq!(
+            count(&db, "blocks"),
+            BlendFile::open(&path).unwrap().blocks.len()
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,670 @@
+use crate::bytes::read_f32_at;
+use crate::dna::{Dna, DnaField, DnaStruct};
+use crate::error::{FormatError, Result};
+use crate::header::{Endianness, Header};
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::fmt;
+use std::io::{Read, Seek};
+
+/// Inconsistent block header values found while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockAnomaly {
+    /// A non-ENDB block with no payload
+    ZeroSize,
+    /// A block holding zero instances
+    ZeroCount,
+    /// More instances than payload bytes, so each would be under a byte
+    CountExceedsSize { count: u32, size: u32 },
+}
+
+impl fmt::Display for BlockAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockAnomaly::ZeroSize => write!(f, "size is 0"),
+            BlockAnomaly::ZeroCount => write!(f, "count is 0"),
+            BlockAnomaly::CountExceedsSize { count, size } => {
+                write!(f, "count {count} exceeds size {size}")
+            }
+        }
+    }
+}
+
+/// A block's type code, zero-padded to four bytes: `*b"OB\0\0"`, `*b"DATA"`
+pub type BlockCode = [u8; 4];
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub code: BlockCode,
+    pub size: u32,
+    pub old_memory_address: u64,
+    pub sdna_index: u32,
+    pub count: u32,
+    /// Where the payload starts in the uncompressed file; 0 for blocks not
+    /// read from one
+    pub data_offset: u64,
+    pub data: Vec<u8>,
+}
+
+impl Block {
+    pub fn from_reader<R: Read + Seek>(reader: &mut R, header: &Header) -> Result<Option<Self>> {
+        let mut block = match Self::header_from_reader(reader, header)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        block.data_offset = reader.stream_position()?;
+
+        // Read the actual block data
+        let mut data = vec![0u8; block.size as usize];
+        reader.read_exact(&mut data)?;
+        block.data = data;
+
+        Ok(Some(block))
+    }
+
+    /// Bytes taken by a block header: code, size, old address, SDNA index and count
+    pub fn header_size(header: &Header) -> usize {
+        16 + header.pointer_size.bytes()
+    }
+
+    /// Read only the block header, leaving `data` empty so callers can decide
+    /// whether the payload is worth loading. `DNA1` and `ENDB` are read like
+    /// any other block; `None` means the file ended.
+    pub fn header_from_reader<R: Read>(reader: &mut R, header: &Header) -> Result<Option<Self>> {
+        let mut code = [0u8; 4];
+        match reader.read_exact(&mut code) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        // Read size
+        let size = match header.endianness {
+            Endianness::Little => reader.read_u32::<LittleEndian>()?,
+            Endianness::Big => reader.read_u32::<byteorder::BigEndian>()?,
+        };
+
+        // Read old memory address
+        let old_memory_address = match header.pointer_size {
+            crate::header::PointerSize::Bits32 => {
+                let addr = match header.endianness {
+                    Endianness::Little => reader.read_u32::<LittleEndian>()?,
+                    Endianness::Big => reader.read_u32::<byteorder::BigEndian>()?,
+                };
+                addr as u64
+            }
+            crate::header::PointerSize::Bits64 => match header.endianness {
+                Endianness::Little => reader.read_u64::<LittleEndian>()?,
+                Endianness::Big => reader.read_u64::<byteorder::BigEndian>()?,
+            },
+        };
+
+        // Read SDNA index
+        let sdna_index = match header.endianness {
+            Endianness::Little => reader.read_u32::<LittleEndian>()?,
+            Endianness::Big => reader.read_u32::<byteorder::BigEndian>()?,
+        };
+
+        // Read count
+        let count = match header.endianness {
+            Endianness::Little => reader.read_u32::<LittleEndian>()?,
+            Endianness::Big => reader.read_u32::<byteorder::BigEndian>()?,
+        };
+
+        Ok(Some(Block {
+            code,
+            size,
+            old_memory_address,
+            sdna_index,
+            count,
+            data_offset: 0,
+            data: Vec::new(),
+        }))
+    }
+
+    /// Header values no sane writer produces; such blocks have no usable instances
+    pub fn anomaly(&self) -> Option<BlockAnomaly> {
+        if &self.code == b"ENDB" {
+            return None;
+        }
+        if self.size == 0 {
+            Some(BlockAnomaly::ZeroSize)
+        } else if self.count == 0 {
+            Some(BlockAnomaly::ZeroCount)
+        } else if self.count > self.size {
+            Some(BlockAnomaly::CountExceedsSize {
+                count: self.count,
+                size: self.size,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn is_library(&self) -> bool {
+        &self.code[..2] == b"LI"
+    }
+
+    pub fn is_image(&self) -> bool {
+        &self.code[..2] == b"IM"
+    }
+
+    pub fn is_sound(&self) -> bool {
+        &self.code[..2] == b"SO"
+    }
+
+    pub fn is_movie_clip(&self) -> bool {
+        &self.code[..2] == b"MC"
+    }
+
+    pub fn get_type_name(&self) -> String {
+        String::from_utf8_lossy(&self.code).into_owned()
+    }
+
+    /// The DNA struct at this block's SDNA index. `None` for unknown indices
+    /// and for raw `DATA` blocks and `RBAT` audit logs, whose index 0
+    /// describes nothing.
+    pub fn dna_struct<'a>(&self, dna: &'a Dna) -> Option<&'a DnaStruct> {
+        if matches!(&self.code, b"DATA" | b"RBAT") && self.sdna_index == 0 {
+            return None;
+        }
+        dna.struct_by_index(self.sdna_index as usize)
+    }
+
+    pub fn get_string_field(&self, _field_name: &str) -> Result<String> {
+        // This is a simplified implementation - in a real scenario, you'd use DNA info
+        // For now, we'll search for null-terminated strings in the data
+        let null_pos = self
+            .data
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.data.len());
+        let string_data = &self.data[..null_pos];
+        Ok(String::from_utf8_lossy(string_data).into_owned())
+    }
+
+    pub fn set_string_field(&mut self, _field_name: &str, value: &str) -> Result<()> {
+        // This is a simplified implementation - in a real scenario, you'd use DNA info
+        let bytes = value.as_bytes();
+        // Never cut a multi-byte character in half
+        let mut len = bytes.len().min(self.data.len());
+        while !value.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        // Copy the string bytes
+        self.data[..len].copy_from_slice(&bytes[..len]);
+
+        // Null-terminate if there's space
+        if len < self.data.len() {
+            self.data[len] = 0;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_float_array_field(&self, _field_name: &str, count: usize) -> Result<Vec<f32>> {
+        // This is a simplified implementation - in a real scenario, you'd use DNA info
+        self.get_float_array_at(0, count, Endianness::Little)
+    }
+
+    pub fn set_float_array_field(&mut self, _field_name: &str, values: &[f32]) -> Result<()> {
+        // This is a simplified implementation - in a real scenario, you'd use DNA info
+        self.set_float_array_at(0, values, Endianness::Little)
+    }
+
+    /// Read `count` floats in `endianness` starting at byte `offset`, failing
+    /// if any of them lies outside the payload
+    pub fn get_float_array_at(
+        &self,
+        offset: usize,
+        count: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<f32>> {
+        self.checked_range(offset, count.saturating_mul(4))?;
+        (0..count)
+            .map(|i| read_f32_at(&self.data, offset + i * 4, endianness))
+            .collect()
+    }
+
+    /// Write `values` as floats in `endianness` starting at byte `offset`;
+    /// nothing is written unless all of them fit
+    pub fn set_float_array_at(
+        &mut self,
+        offset: usize,
+        values: &[f32],
+        endianness: Endianness,
+    ) -> Result<()> {
+        let range = self.checked_range(offset, values.len().saturating_mul(4))?;
+        for (chunk, value) in self.data[range].chunks_exact_mut(4).zip(values) {
+            match endianness {
+                Endianness::Little => chunk.copy_from_slice(&value.to_le_bytes()),
+                Endianness::Big => chunk.copy_from_slice(&value.to_be_bytes()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a float field, with offset and element count taken from the DNA
+    /// struct this block holds and byte order from the DNA's file
+    pub fn get_float_field(&self, dna: &Dna, field_name: &str) -> Result<Vec<f32>> {
+        let (offset, field) = self.float_field(dna, field_name)?;
+        self.get_float_array_at(offset, field.dna_name.element_count(), dna.endianness)
+    }
+
+    /// A float field split into rows along its last dimension, e.g. the four
+    /// `[x, y]` corners of `float pattern_corners[4][2]`. Scalars and 1D
+    /// arrays are a single row.
+    pub fn get_float_rows(&self, dna: &Dna, field_name: &str) -> Result<Vec<Vec<f32>>> {
+        let (offset, field) = self.float_field(dna, field_name)?;
+        let values = self.get_float_array_at(offset, field.element_count(), dna.endianness)?;
+        let width = field.dimensions().last().copied().unwrap_or(1).max(1);
+        Ok(values.chunks(width).map(<[f32]>::to_vec).collect())
+    }
+
+    /// Overwrite a float field located through DNA; `values` must fill it exactly
+    pub fn set_float_field(&mut self, dna: &Dna, field_name: &str, values: &[f32]) -> Result<()> {
+        let (offset, field) = self.float_field(dna, field_name)?;
+        let count = field.dna_name.element_count();
+        if values.len() != count {
+            return Err(FormatError::BlockError(format!(
+                "field {field_name} holds {count} floats, got {}",
+                values.len()
+            )));
+        }
+        self.set_float_array_at(offset, values, dna.endianness)
+    }
+
+    /// A float field of this block's struct, with its offset
+    fn float_field<'a>(&self, dna: &'a Dna, field_name: &str) -> Result<(usize, &'a DnaField)> {
+        let layout = dna
+            .struct_by_index(self.sdna_index as usize)
+            .ok_or_else(|| {
+                FormatError::DnaError(format!("no DNA struct at index {}", self.sdna_index))
+            })?;
+        let field = layout.field(field_name).ok_or_else(|| {
+            FormatError::DnaError(format!("{} has no field {field_name}", layout.name))
+        })?;
+        if field.type_name != "float" || field.dna_name.is_pointer() {
+            return Err(FormatError::DnaError(format!(
+                "{}.{field_name} is not a float field",
+                layout.name
+            )));
+        }
+        Ok((layout.offset_of(field)?, field))
+    }
+
+    fn checked_range(&self, offset: usize, len: usize) -> Result<std::ops::Range<usize>> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.data.len() => Ok(offset..end),
+            _ => Err(FormatError::BlockError(format!(
+                "bytes {offset}..{} out of range for {} block of {} bytes",
+                offset.saturating_add(len),
+                self.get_type_name().trim_end_matches('\0'),
+                self.data.len()
+            ))),
+        }
+    }
+
+    pub fn write_to_writer<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        header: &crate::header::Header,
+    ) -> Result<()> {
+        use byteorder::{LittleEndian, WriteBytesExt};
+
+        // Write block code
+        writer.write_all(&self.code)?;
+
+        // Write size
+        match header.endianness {
+            Endianness::Little => writer.write_u32::<LittleEndian>(self.size)?,
+            Endianness::Big => writer.write_u32::<byteorder::BigEndian>(self.size)?,
+        }
+
+        // Write old memory address
+        match header.pointer_size {
+            crate::header::PointerSize::Bits32 => {
+                let addr = self.old_memory_address as u32;
+                match header.endianness {
+                    Endianness::Little => writer.write_u32::<LittleEndian>(addr)?,
+                    Endianness::Big => writer.write_u32::<byteorder::BigEndian>(addr)?,
+                }
+            }
+            crate::header::PointerSize::Bits64 => match header.endianness {
+                Endianness::Little => writer.write_u64::<LittleEndian>(self.old_memory_address)?,
+                Endianness::Big => {
+                    writer.write_u64::<byteorder::BigEndian>(self.old_memory_address)?
+                }
+            },
+        }
+
+        // Write SDNA index
+        match header.endianness {
+            Endianness::Little => writer.write_u32::<LittleEndian>(self.sdna_index)?,
+            Endianness::Big => writer.write_u32::<byteorder::BigEndian>(self.sdna_index)?,
+        }
+
+        // Write count
+        match header.endianness {
+            Endianness::Little => writer.write_u32::<LittleEndian>(self.count)?,
+            Endianness::Big => writer.write_u32::<byteorder::BigEndian>(self.count)?,
+        }
+
+        // Write data
+        writer.write_all(&self.data)?;
+
+        Ok(())
+    }
+}
+
+pub struct BlockIterator<'a, R: Read + Seek> {
+    reader: &'a mut R,
+    header: &'a Header,
+    finished: bool,
+}
+
+impl<'a, R: Read + Seek> BlockIterator<'a, R> {
+    pub fn new(reader: &'a mut R, header: &'a Header) -> Self {
+        BlockIterator {
+            reader,
+            header,
+            finished: false,
+        }
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for BlockIterator<'a, R> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match Block::from_reader(self.reader, self.header) {
+            Ok(Some(block)) => {
+                // Only ENDB ends the blocks; a DNA1 can sit before others
+                self.finished = &block.code == b"ENDB";
+                Some(Ok(block))
+            }
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_block_creation() {
+        let mut data = vec![
+            b'L', b'I', b'\0', b'\0', // code
+            100, 0, 0, 0, // size (little-endian)
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // address (64-bit)
+            0, 0, 0, 0, // sdna_index
+            1, 0, 0, 0, // count
+        ];
+        data.extend(vec![0u8; 100]); // Add 100 bytes of data
+
+        let header = crate::header::Header {
+            magic: *b"BLENDER",
+            pointer_size: crate::header::PointerSize::Bits64,
+            endianness: Endianness::Little,
+            version: 279,
+        };
+
+        let mut cursor = Cursor::new(data);
+        let block = Block::from_reader(&mut cursor, &header).unwrap().unwrap();
+
+        assert_eq!(block.code, *b"LI\0\0");
+        assert_eq!(block.size, 100);
+        assert_eq!(block.old_memory_address, 0x10);
+        assert_eq!(block.sdna_index, 0);
+        assert_eq!(block.count, 1);
+        assert_eq!(block.data.len(), 100);
+    }
+
+    #[test]
+    fn test_block_type_checking() {
+        let block = Block {
+            code: *b"LI\0\0",
+            size: 100,
+            old_memory_address: 0x1000,
+            sdna_index: 0,
+            count: 1,
+            data_offset: 100,
+            data: vec![0; 100],
+        };
+
+        assert!(block.is_library());
+        assert!(!block.is_image());
+        assert!(!block.is_sound());
+        assert!(!block.is_movie_clip());
+
+        let block = Block {
+            code: *b"IM\0\0",
+            size: 200,
+            old_memory_address: 0x2000,
+            sdna_index: 1,
+            count: 1,
+            data_offset: 200,
+            data: vec![0; 200],
+        };
+
+        assert!(!block.is_library());
+        assert!(block.is_image());
+        assert!(!block.is_sound());
+        assert!(!block.is_movie_clip());
+    }
+
+    fn object_block(len: usize) -> Block {
+        Block {
+            code: *b"OB\0\0",
+            size: len as u32,
+            old_memory_address: 0x3000,
+            sdna_index: 0,
+            count: 1,
+            data_offset: len as u64,
+            data: vec![0; len],
+        }
+    }
+
+    #[test]
+    fn test_float_array_out_of_range() {
+        let mut block = object_block(8);
+
+        let err = block.get_float_array_field("loc", 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Block error: bytes 0..12 out of range for OB block of 8 bytes"
+        );
+        assert!(block
+            .set_float_array_field("loc", &[1.0, 2.0, 3.0])
+            .is_err());
+        // A failed write leaves the payload untouched
+        assert_eq!(block.data, vec![0; 8]);
+        assert!(block
+            .get_float_array_at(usize::MAX, 1, Endianness::Little)
+            .is_err());
+        assert!(block
+            .get_float_array_at(0, usize::MAX, Endianness::Little)
+            .is_err());
+
+        // Too short for even one float
+        assert!(object_block(3).get_float_array_field("loc", 1).is_err());
+        assert!(object_block(3)
+            .get_float_array_at(0, 0, Endianness::Little)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_float_array_at_boundary() {
+        let mut block = object_block(12);
+
+        block
+            .set_float_array_at(4, &[2.5, -1.0], Endianness::Little)
+            .unwrap();
+        assert_eq!(
+            block.get_float_array_at(4, 2, Endianness::Little).unwrap(),
+            vec![2.5, -1.0]
+        );
+        assert_eq!(
+            block.get_float_array_field("loc", 3).unwrap(),
+            vec![0.0, 2.5, -1.0]
+        );
+        assert!(block.get_float_array_at(8, 2, Endianness::Little).is_err());
+        assert!(block
+            .get_float_array_at(12, 0, Endianness::Little)
+            .unwrap()
+            .is_empty());
+        // The last float starts at exactly len - 4
+        assert_eq!(
+            block.get_float_array_at(8, 1, Endianness::Little).unwrap(),
+            vec![-1.0]
+        );
+        assert!(block.get_float_array_at(9, 1, Endianness::Little).is_err());
+    }
+
+    #[test]
+    fn test_float_field_through_dna() {
+        let raw = crate::dna::RawSdna {
+            names: vec!["flag".to_string(), "loc[3]".to_string()],
+            types: vec!["int".to_string(), "float".to_string(), "Object".to_string()],
+            tlens: vec![4, 4, 16],
+            structs: vec![(2, vec![(0, 0), (1, 1)])],
+        };
+        let dna = Dna::from_raw(raw, crate::header::PointerSize::Bits64).unwrap();
+
+        let mut block = object_block(16);
+        block
+            .set_float_field(&dna, "loc", &[1.0, 2.0, 3.0])
+            .unwrap();
+        assert_eq!(
+            block.get_float_field(&dna, "loc").unwrap(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(&block.data[..4], &[0; 4]);
+
+        assert!(block.set_float_field(&dna, "loc", &[1.0]).is_err());
+        assert!(block.get_float_field(&dna, "flag").is_err());
+        assert!(block.get_float_field(&dna, "rot").is_err());
+
+        // DNA says 16 bytes, but the payload is short
+        let short = object_block(12);
+        assert!(short.get_float_field(&dna, "loc").is_err());
+    }
+
+    #[test]
+    fn test_float_field_big_endian_round_trip() {
+        let raw = crate::dna::RawSdna {
+            names: vec!["flag".to_string(), "loc[3]".to_string()],
+            types: vec!["int".to_string(), "float".to_string(), "Object".to_string()],
+            tlens: vec![4, 4, 16],
+            structs: vec![(2, vec![(0, 0), (1, 1)])],
+        };
+        let mut dna = Dna::from_raw(raw, crate::header::PointerSize::Bits64).unwrap();
+        dna.endianness = Endianness::Big;
+
+        let mut block = object_block(16);
+        block
+            .set_float_field(&dna, "loc", &[1.0, -2.5, 3.0])
+            .unwrap();
+        assert_eq!(&block.data[4..8], &1.0f32.to_be_bytes());
+        assert_eq!(
+            block.get_float_field(&dna, "loc").unwrap(),
+            vec![1.0, -2.5, 3.0]
+        );
+        assert_eq!(
+            block.get_float_rows(&dna, "loc").unwrap(),
+            [[1.0, -2.5, 3.0]]
+        );
+        assert_eq!(
+            block.get_float_array_at(8, 1, Endianness::Big).unwrap(),
+            vec![-2.5]
+        );
+        assert_ne!(
+            block.get_float_array_at(8, 1, Endianness::Little).unwrap(),
+            vec![-2.5]
+        );
+    }
+
+    #[test]
+    fn test_float_rows_follow_shape() {
+        let raw = crate::dna::RawSdna {
+            names: vec![
+                "flag".to_string(),
+                "loc[3]".to_string(),
+                "pattern_corners[4][2]".to_string(),
+            ],
+            types: vec!["int".to_string(), "float".to_string(), "Object".to_string()],
+            tlens: vec![4, 4, 48],
+            structs: vec![(2, vec![(1, 0), (1, 1), (1, 2)])],
+        };
+        let dna = Dna::from_raw(raw, crate::header::PointerSize::Bits64).unwrap();
+
+        let mut block = object_block(48);
+        let corners: Vec<f32> = (0..8).map(|i| i as f32).collect();
+        block
+            .set_float_field(&dna, "pattern_corners", &corners)
+            .unwrap();
+        block.set_float_field(&dna, "flag", &[0.5]).unwrap();
+        assert_eq!(
+            block.get_float_rows(&dna, "pattern_corners").unwrap(),
+            [[0.0, 1.0], [2.0, 3.0], [4.0, 5.0], [6.0, 7.0]]
+        );
+        assert_eq!(block.get_float_rows(&dna, "loc").unwrap(), [[0.0; 3]]);
+        assert_eq!(block.get_float_rows(&dna, "flag").unwrap(), [[0.5]]);
+    }
+
+    #[test]
+    fn test_dna_struct_by_sdna_index() {
+        let raw = crate::dna::RawSdna {
+            names: vec!["*next".to_string(), "flag".to_string()],
+            types: vec!["int".to_string(), "Link".to_string(), "Object".to_string()],
+            tlens: vec![4, 8, 4],
+            structs: vec![(1, vec![(1, 0)]), (2, vec![(0, 1)])],
+        };
+        let dna = Dna::from_raw(raw, crate::header::PointerSize::Bits64).unwrap();
+
+        let mut block = object_block(4);
+        block.sdna_index = 1;
+        assert_eq!(block.dna_struct(&dna).unwrap().name, "Object");
+        block.sdna_index = 0;
+        assert_eq!(block.dna_struct(&dna).unwrap().name, "Link");
+        block.sdna_index = 2;
+        assert!(block.dna_struct(&dna).is_none());
+
+        // Raw DATA at index 0 isn't a list of Links
+        block.code = *b"DATA";
+        block.sdna_index = 0;
+        assert!(block.dna_struct(&dna).is_none());
+    }
+
+    #[test]
+    fn test_set_string_field_keeps_utf8_whole() {
+        let mut block = Block {
+            code: *b"IM\0\0",
+            size: 4,
+            old_memory_address: 0x1000,
+            sdna_index: 0,
+            count: 1,
+            data_offset: 0,
+            data: vec![0xff; 4],
+        };
+        // "aéé" is 5 bytes; cutting at 4 would split the second é
+        block.set_string_field("name", "aéé").unwrap();
+        assert_eq!(&block.data[..3], "aé".as_bytes());
+        assert_eq!(block.data[3], 0);
+    }
+}
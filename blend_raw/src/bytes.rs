@@ -0,0 +1,103 @@
+//! Bounds-checked reads of numbers at byte offsets in block payloads.
+//!
+//! Each `read_*_at` copies the value's bytes out before decoding, so the
+//! data needs no particular alignment, and fails instead of panicking when
+//! the value would run past the end of `data`.
+
+use crate::error::{FormatError, Result};
+use crate::header::Endianness;
+
+/// The `N` bytes at `offset`
+fn array_at<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    offset
+        .checked_add(N)
+        .and_then(|end| data.get(offset..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            FormatError::BlockError(format!(
+                "bytes {offset}..{} out of range for {} bytes of data",
+                offset.saturating_add(N),
+                data.len()
+            ))
+        })
+}
+
+macro_rules! read_at {
+    ($name:ident, $ty:ty) => {
+        #[doc = concat!("The `", stringify!($ty), "` stored at `offset`")]
+        pub fn $name(data: &[u8], offset: usize, endianness: Endianness) -> Result<$ty> {
+            let bytes = array_at(data, offset)?;
+            Ok(match endianness {
+                Endianness::Little => <$ty>::from_le_bytes(bytes),
+                Endianness::Big => <$ty>::from_be_bytes(bytes),
+            })
+        }
+    };
+}
+
+read_at!(read_i16_at, i16);
+read_at!(read_u16_at, u16);
+read_at!(read_i32_at, i32);
+read_at!(read_u32_at, u32);
+read_at!(read_i64_at, i64);
+read_at!(read_u64_at, u64);
+read_at!(read_f32_at, f32);
+read_at!(read_f64_at, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_f32_bounds() {
+        let mut data = vec![0u8; 10];
+        data[6..10].copy_from_slice(&1.5f32.to_le_bytes());
+
+        // Exactly len - 4 is the last place a float fits
+        assert_eq!(read_f32_at(&data, 6, Endianness::Little).unwrap(), 1.5);
+        assert!(read_f32_at(&data, 7, Endianness::Little).is_err());
+        assert!(read_f32_at(&data, 10, Endianness::Little).is_err());
+        assert!(read_f32_at(&data, usize::MAX, Endianness::Little).is_err());
+        assert!(read_f32_at(&[], 0, Endianness::Little).is_err());
+
+        // A 3-byte block holds no float at all
+        let err = read_f32_at(&[1, 2, 3], 0, Endianness::Little).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Block error: bytes 0..4 out of range for 3 bytes of data"
+        );
+    }
+
+    #[test]
+    fn test_unaligned_and_big_endian() {
+        let mut data = vec![0u8; 16];
+        data[1..5].copy_from_slice(&(-2.25f32).to_be_bytes());
+        data[5..13].copy_from_slice(&0.125f64.to_le_bytes());
+        data[13..15].copy_from_slice(&(-7i16).to_le_bytes());
+
+        assert_eq!(read_f32_at(&data, 1, Endianness::Big).unwrap(), -2.25);
+        assert_eq!(read_f64_at(&data, 5, Endianness::Little).unwrap(), 0.125);
+        assert_eq!(read_i16_at(&data, 13, Endianness::Little).unwrap(), -7);
+        assert!(read_f64_at(&data, 9, Endianness::Little).is_err());
+        assert!(read_u16_at(&data, 15, Endianness::Little).is_err());
+    }
+
+    #[test]
+    fn test_integers() {
+        let data = [0xff, 0xff, 0xff, 0xfe, 0, 0, 0, 0];
+        assert_eq!(read_i32_at(&data, 0, Endianness::Big).unwrap(), -2);
+        assert_eq!(
+            read_u32_at(&data, 0, Endianness::Little).unwrap(),
+            0xfeff_ffff
+        );
+        assert_eq!(
+            read_u64_at(&data, 0, Endianness::Big).unwrap(),
+            0xffff_fffe_0000_0000
+        );
+        assert_eq!(
+            read_i64_at(&data, 0, Endianness::Little).unwrap(),
+            0xfeff_ffff
+        );
+        assert!(read_u64_at(&data, 1, Endianness::Little).is_err());
+    }
+}
@@ -0,0 +1,1494 @@
+use crate::block::Block;
+use crate::dna_name::DnaName;
+use crate::error::{FormatError, Result};
+use crate::header::{Endianness, Header, PointerSize};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+/// A struct whose fields don't add up to the size TLEN declares for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SizeMismatch {
+    /// Position of the struct in the STRC table
+    pub sdna_index: usize,
+    pub struct_name: String,
+    /// Size from TLEN
+    pub expected: usize,
+    /// Sum of the field sizes
+    pub actual: usize,
+}
+
+impl std::fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "struct {}: fields add up to {} bytes but TLEN declares {}",
+            self.struct_name, self.actual, self.expected
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dna {
+    pub structs: HashMap<String, DnaStruct>,
+    pub type_sizes: HashMap<String, usize>,
+    /// Structs in STRC order, so a block's `sdna_index` indexes straight into it
+    pub structs_by_index: Vec<DnaStruct>,
+    /// Pointer width in bytes the offsets were computed for (0 when no DNA was parsed)
+    pub pointer_size: usize,
+    /// Byte order of the file the DNA was read from, and so of the values in
+    /// the blocks it describes; little-endian for a catalog built with `from_raw`
+    pub endianness: Endianness,
+    pub raw: RawSdna,
+    /// Malformed field names found while building the catalog; they're kept,
+    /// read leniently
+    pub warnings: Vec<String>,
+}
+
+/// The SDNA tables exactly as stored in a DNA1 block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawSdna {
+    pub names: Vec<String>,
+    pub types: Vec<String>,
+    pub tlens: Vec<u16>,
+    /// (struct type index, [(field type index, field name index)])
+    pub structs: Vec<(u16, Vec<(u16, u16)>)>,
+}
+
+/// Identifies the exact DNA layout a file was written with; shown as 16 hex digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DnaFingerprint(pub u64);
+
+impl std::fmt::Display for DnaFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl std::str::FromStr for DnaFingerprint {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        u64::from_str_radix(s, 16)
+            .map(DnaFingerprint)
+            .map_err(|_| FormatError::ParseError(format!("invalid DNA fingerprint '{s}'")))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnaStats {
+    pub struct_count: usize,
+    /// Fields summed over all structs
+    pub field_count: usize,
+    /// Largest struct size in bytes, for the pointer size the DNA was read with
+    pub max_struct_size: usize,
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[derive(Debug, Clone)]
+pub struct DnaStruct {
+    pub name: String,
+    pub fields: Vec<DnaField>,
+    pub size: usize,
+    /// A field's type has no known size, so the fields after it have no offset
+    pub incomplete: bool,
+}
+
+impl DnaStruct {
+    /// Look up a field by its bare name (no pointer stars or array dimensions)
+    pub fn field(&self, name: &str) -> Option<&DnaField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Offset of `field`, or `IncompleteStruct` when it follows a field of unknown size
+    pub fn offset_of(&self, field: &DnaField) -> Result<usize> {
+        field
+            .offset
+            .ok_or_else(|| self.incomplete_error(&field.name))
+    }
+
+    /// The field of unknown size that stopped the layout, in an incomplete struct
+    pub fn unsized_field(&self) -> Option<&DnaField> {
+        // Layout stops right after it, so it is the last field with an offset
+        self.incomplete
+            .then(|| self.fields.iter().rev().find(|f| f.offset.is_some()))
+            .flatten()
+    }
+
+    /// `IncompleteStruct` naming the unsized field, for reads that need the whole struct
+    pub fn check_complete(&self) -> Result<()> {
+        match self.unsized_field() {
+            Some(field) => Err(self.incomplete_error(&field.name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Bytes the fields take up, laid out in order; only meaningful for
+    /// complete structs
+    pub fn fields_size(&self) -> usize {
+        self.fields.iter().map(|field| field.size).sum()
+    }
+
+    /// Every field with embedded structs expanded into their members, in
+    /// layout order, as dotted paths (`id.name`) with offsets from the start
+    /// of this struct. Pointers and arrays of structs stay single fields, so
+    /// each path also works with `Dna::field_offset`. Fields past one of
+    /// unknown size are left out, having no offset.
+    pub fn flattened_fields<'a>(&'a self, dna: &'a Dna) -> Vec<FlatField<'a>> {
+        let mut out = Vec::new();
+        self.flatten_into(dna, "", 0, &mut out);
+        out
+    }
+
+    fn flatten_into<'a>(
+        &'a self,
+        dna: &'a Dna,
+        prefix: &str,
+        base: usize,
+        out: &mut Vec<FlatField<'a>>,
+    ) {
+        for field in &self.fields {
+            let Some(offset) = field.offset else { break };
+            let path = format!("{prefix}{}", field.name);
+            match dna.get_struct(&field.type_name) {
+                Some(inner) if !field.is_pointer() && field.element_count() == 1 => {
+                    inner.flatten_into(dna, &format!("{path}."), base + offset, out);
+                }
+                _ => out.push(FlatField {
+                    path,
+                    offset: base + offset,
+                    field,
+                }),
+            }
+        }
+    }
+
+    fn incomplete_error(&self, field: &str) -> FormatError {
+        FormatError::IncompleteStruct {
+            struct_name: self.name.clone(),
+            field: field.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DnaField {
+    pub name: String,
+    pub type_name: String,
+    /// Byte offset in the struct; `None` past a field of unknown size
+    pub offset: Option<usize>,
+    pub size: usize,
+    pub dna_name: DnaName,
+}
+
+impl DnaField {
+    /// Whether the field holds a pointer (or an array of them) rather than the type itself
+    pub fn is_pointer(&self) -> bool {
+        self.dna_name.is_pointer()
+    }
+
+    pub fn is_method_pointer(&self) -> bool {
+        self.dna_name.is_method_pointer()
+    }
+
+    /// Array dimensions in declaration order; empty for scalars
+    pub fn dimensions(&self) -> Vec<usize> {
+        self.dna_name.dimensions()
+    }
+
+    /// Number of elements (or pointers) the field holds, 1 for scalars
+    pub fn element_count(&self) -> usize {
+        self.dna_name.element_count()
+    }
+
+    /// Bytes of one element: `size` split over `element_count`
+    pub fn element_size(&self) -> usize {
+        self.size / self.element_count().max(1)
+    }
+}
+
+/// Where a dotted field path such as `id.name` ends up in a struct
+#[derive(Debug, Clone, Copy)]
+pub struct FieldLocation<'a> {
+    /// Bytes from the start of the outer struct
+    pub offset: usize,
+    /// The last field on the path, with its size and type
+    pub field: &'a DnaField,
+}
+
+/// A field reached through embedded structs, from `DnaStruct::flattened_fields`
+#[derive(Debug, Clone)]
+pub struct FlatField<'a> {
+    /// Dotted path from the outer struct, e.g. `id.name`
+    pub path: String,
+    /// Bytes from the start of the outer struct
+    pub offset: usize,
+    pub field: &'a DnaField,
+}
+
+/// `Dna::field_offset` results kept by struct and path, for reading the same
+/// fields from many blocks without walking the struct definitions each time
+#[derive(Debug, Clone)]
+pub struct OffsetTable<'a> {
+    dna: &'a Dna,
+    /// By struct, then path; `None` for paths the DNA can't locate
+    locations: HashMap<String, HashMap<String, Option<FieldLocation<'a>>>>,
+    walks: usize,
+}
+
+impl<'a> OffsetTable<'a> {
+    pub fn new(dna: &'a Dna) -> Self {
+        Self {
+            dna,
+            locations: HashMap::new(),
+            walks: 0,
+        }
+    }
+
+    pub fn dna(&self) -> &'a Dna {
+        self.dna
+    }
+
+    /// `Dna::field_offset`, walking the structs only the first time a struct
+    /// and path are asked for. Failed lookups are remembered as `None`.
+    pub fn field_offset(&mut self, struct_name: &str, path: &str) -> Option<FieldLocation<'a>> {
+        if let Some(location) = self
+            .locations
+            .get(struct_name)
+            .and_then(|paths| paths.get(path))
+        {
+            return *location;
+        }
+        self.walks += 1;
+        let location = self.dna.field_offset(struct_name, path).ok();
+        self.locations
+            .entry(struct_name.to_string())
+            .or_default()
+            .insert(path.to_string(), location);
+        location
+    }
+
+    /// Lookups that walked the struct definitions, one per struct and path
+    pub fn walks(&self) -> usize {
+        self.walks
+    }
+}
+
+impl Dna {
+    /// Locate the DNA1 block and parse it. Files without one yield an empty catalog.
+    /// Repaired files can hold several; like Blender, the last one before
+    /// ENDB is used, and a warning lists where they all start.
+    ///
+    /// Only seeks forward from the end of the file header, so a decompressing
+    /// stream can be read as well as a file.
+    pub fn from_reader<R: Read + Seek>(reader: &mut R, header: &Header) -> Result<Self> {
+        let mut position = reader.seek(SeekFrom::Start(Header::SIZE as u64))?;
+
+        let mut dna_offsets = Vec::new();
+        let mut payload = None;
+        while let Some(block) = Block::header_from_reader(reader, header)? {
+            match &block.code {
+                b"ENDB" => break,
+                b"DNA1" => {
+                    dna_offsets.push(position);
+                    let mut data = vec![0u8; block.size as usize];
+                    reader.read_exact(&mut data)?;
+                    payload = Some(data);
+                    position = reader.stream_position()?;
+                }
+                _ => position = reader.seek(SeekFrom::Current(block.size as i64))?,
+            }
+        }
+
+        let Some(payload) = payload else {
+            return Ok(Dna::default());
+        };
+        let mut dna = Self::parse_dna_data(&payload, header)?;
+        dna.warnings.extend(duplicate_dna_warning(&dna_offsets));
+        Ok(dna)
+    }
+
+    /// Parse the DNA1 block whose code starts at the reader's position
+    pub fn parse_dna_block<R: Read + Seek>(reader: &mut R, header: &Header) -> Result<Self> {
+        // Skip DNA1 identifier
+        reader.seek(SeekFrom::Current(4))?;
+
+        // Read DNA block size
+        let dna_size = match header.endianness {
+            Endianness::Little => reader.read_u32::<LittleEndian>()?,
+            Endianness::Big => reader.read_u32::<BigEndian>()?,
+        };
+
+        // Skip old address, SDNA index and count to reach the DNA data
+        reader.seek(SeekFrom::Current(header.pointer_size.bytes() as i64 + 8))?;
+
+        // Read DNA data
+        let mut dna_data = vec![0u8; dna_size as usize];
+        reader.read_exact(&mut dna_data)?;
+
+        Self::parse_dna_data(&dna_data, header)
+    }
+
+    fn parse_dna_data(data: &[u8], header: &Header) -> Result<Self> {
+        if data.is_empty() {
+            return Ok(Dna::default());
+        }
+        let mut dna = Self::from_raw(
+            RawSdna::from_payload(data, header.endianness)?,
+            header.pointer_size,
+        )?;
+        dna.endianness = header.endianness;
+        Ok(dna)
+    }
+
+    /// Build the catalog from SDNA tables, laying out fields for `pointer_size`
+    pub fn from_raw(raw: RawSdna, pointer_size: PointerSize) -> Result<Self> {
+        if raw.tlens.len() != raw.types.len() {
+            return Err(FormatError::DnaError(format!(
+                "{} types but {} type lengths",
+                raw.types.len(),
+                raw.tlens.len()
+            )));
+        }
+
+        let type_sizes: HashMap<String, usize> = raw
+            .types
+            .iter()
+            .zip(&raw.tlens)
+            .map(|(name, &len)| (name.clone(), len as usize))
+            .collect();
+
+        let mut structs_by_index = Vec::with_capacity(raw.structs.len());
+        let mut warnings = Vec::new();
+        for (type_index, fields) in &raw.structs {
+            let name = raw.type_name(*type_index)?;
+            // `None` once a field of unknown size has been laid out
+            let mut offset = Some(0);
+            let mut dna_fields = Vec::with_capacity(fields.len());
+
+            for &(field_type, field_name) in fields {
+                let full_name = raw.name(field_name)?;
+                let dna_name = DnaName::parse(full_name).unwrap_or_else(|e| {
+                    warnings.push(format!("{name}: {e}"));
+                    DnaName::new(full_name)
+                });
+                let type_name = match raw.type_name(field_type) {
+                    Ok(type_name) => type_name.to_string(),
+                    Err(_) => format!("<type {field_type}>"),
+                };
+                let element_size = if dna_name.is_pointer() {
+                    Some(pointer_size.bytes())
+                } else {
+                    raw.known_size(field_type)
+                };
+                if element_size.is_none() && offset.is_some() {
+                    warnings.push(format!(
+                        "{name}: {} has unknown type {type_name}; later fields have no offset",
+                        dna_name.name_only()
+                    ));
+                }
+                let size = element_size.unwrap_or(0) * dna_name.array_size();
+
+                dna_fields.push(DnaField {
+                    name: dna_name.name_only(),
+                    type_name,
+                    offset,
+                    size,
+                    dna_name,
+                });
+                offset = offset.zip(element_size).map(|(offset, _)| offset + size);
+            }
+
+            structs_by_index.push(DnaStruct {
+                name: name.to_string(),
+                fields: dna_fields,
+                size: raw.tlens[*type_index as usize] as usize,
+                incomplete: offset.is_none(),
+            });
+        }
+
+        let structs = structs_by_index
+            .iter()
+            .map(|s| (s.name.clone(), s.clone()))
+            .collect();
+
+        Ok(Dna {
+            structs,
+            type_sizes,
+            structs_by_index,
+            pointer_size: pointer_size.bytes(),
+            endianness: Endianness::Little,
+            raw,
+            warnings,
+        })
+    }
+
+    /// `DnaError` for the first mismatch `validate` finds, or `Ok` when it
+    /// finds none
+    pub fn check_struct_sizes(&self) -> Result<()> {
+        match self.validate().into_iter().next() {
+            Some(mismatch) => Err(FormatError::DnaError(mismatch.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Every struct, in SDNA order, whose fields don't add up to the size
+    /// TLEN declares. Incomplete structs can't be checked and are left out.
+    pub fn validate(&self) -> Vec<SizeMismatch> {
+        self.structs_by_index
+            .iter()
+            .enumerate()
+            .filter(|(_, layout)| !layout.incomplete)
+            .filter_map(|(sdna_index, layout)| {
+                let actual = layout.fields_size();
+                (actual != layout.size).then(|| SizeMismatch {
+                    sdna_index,
+                    struct_name: layout.name.clone(),
+                    expected: layout.size,
+                    actual,
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve `path`, field names separated by dots, from `struct_name`
+    /// through embedded structs, summing their offsets. Pointers can't be
+    /// followed: what they point to isn't stored in the struct.
+    pub fn field_offset(&self, struct_name: &str, path: &str) -> Result<FieldLocation<'_>> {
+        let error =
+            |message: String| FormatError::DnaError(format!("{struct_name}.{path}: {message}"));
+        let mut layout = self
+            .get_struct(struct_name)
+            .ok_or_else(|| FormatError::DnaError(format!("struct {struct_name} not in DNA")))?;
+        let mut offset = 0;
+        let mut segments = path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let field = layout
+                .field(segment)
+                .ok_or_else(|| error(format!("{} has no field '{segment}'", layout.name)))?;
+            offset += layout.offset_of(field)?;
+            if segments.peek().is_none() {
+                return Ok(FieldLocation { offset, field });
+            }
+            if field.dna_name.is_pointer() {
+                return Err(error(format!(
+                    "{segment} is a pointer and can't be followed"
+                )));
+            }
+            layout = self.get_struct(&field.type_name).ok_or_else(|| {
+                error(format!("{segment} is a {}, not a struct", field.type_name))
+            })?;
+        }
+        unreachable!("split yields at least one segment")
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<&DnaStruct> {
+        self.structs.get(name)
+    }
+
+    /// Look up a struct by its position in the STRC table, as referenced by `Block::sdna_index`
+    pub fn struct_by_index(&self, index: usize) -> Option<&DnaStruct> {
+        self.structs_by_index.get(index)
+    }
+
+    pub fn get_type_size(&self, type_name: &str) -> Option<usize> {
+        self.type_sizes.get(type_name).copied()
+    }
+
+    /// SDNA name table in file order, with pointer stars and array dimensions
+    pub fn raw_names(&self) -> &[String] {
+        &self.raw.names
+    }
+
+    /// SDNA type table in file order; `raw_tlens` shares its indices
+    pub fn raw_types(&self) -> &[String] {
+        &self.raw.types
+    }
+
+    pub fn raw_tlens(&self) -> &[u16] {
+        &self.raw.tlens
+    }
+
+    /// STRC entries in file order: (struct type index, [(field type index, field name index)])
+    pub fn raw_structs(&self) -> &[(u16, Vec<(u16, u16)>)] {
+        &self.raw.structs
+    }
+
+    /// True when no SDNA has been parsed or attached
+    pub fn is_empty(&self) -> bool {
+        self.structs_by_index.is_empty()
+    }
+
+    /// Hash of the raw name, type, length and struct tables in file order.
+    /// Builds that changed no DNA share a fingerprint; a patched build that
+    /// added or reordered a field does not.
+    pub fn fingerprint(&self) -> DnaFingerprint {
+        let raw = &self.raw;
+        let mut hash = FNV_OFFSET;
+        let mut feed = |bytes: &[u8]| hash = fnv1a(hash, bytes);
+        feed(&(raw.names.len() as u32).to_le_bytes());
+        for name in &raw.names {
+            feed(name.as_bytes());
+            feed(&[0]);
+        }
+        feed(&(raw.types.len() as u32).to_le_bytes());
+        for type_name in &raw.types {
+            feed(type_name.as_bytes());
+            feed(&[0]);
+        }
+        feed(&(raw.tlens.len() as u32).to_le_bytes());
+        for tlen in &raw.tlens {
+            feed(&tlen.to_le_bytes());
+        }
+        feed(&(raw.structs.len() as u32).to_le_bytes());
+        for (type_index, fields) in &raw.structs {
+            feed(&type_index.to_le_bytes());
+            feed(&(fields.len() as u16).to_le_bytes());
+            for (field_type, field_name) in fields {
+                feed(&field_type.to_le_bytes());
+                feed(&field_name.to_le_bytes());
+            }
+        }
+        DnaFingerprint(hash)
+    }
+
+    /// Struct and field counts and the largest struct size
+    pub fn stats(&self) -> DnaStats {
+        DnaStats {
+            struct_count: self.structs_by_index.len(),
+            field_count: self.structs_by_index.iter().map(|s| s.fields.len()).sum(),
+            max_struct_size: self
+                .structs_by_index
+                .iter()
+                .map(|s| s.size)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Recompute struct sizes (TLEN) and field offsets for another pointer size
+    pub fn with_pointer_size(&self, pointer_size: PointerSize) -> Result<Dna> {
+        let mut raw = self.raw.clone();
+        let struct_of_type: HashMap<u16, usize> = raw
+            .structs
+            .iter()
+            .enumerate()
+            .map(|(index, (type_index, _))| (*type_index, index))
+            .collect();
+        let mut sizes: HashMap<u16, usize> = HashMap::new();
+        for index in 0..raw.structs.len() {
+            Self::relaid_struct_size(&raw, &struct_of_type, index, pointer_size, &mut sizes, 0)?;
+        }
+        for (type_index, size) in sizes {
+            raw.tlens[type_index as usize] = u16::try_from(size).map_err(|_| {
+                FormatError::DnaError(format!(
+                    "struct {} grows to {size} bytes",
+                    raw.types[type_index as usize]
+                ))
+            })?;
+        }
+        let mut dna = Self::from_raw(raw, pointer_size)?;
+        dna.endianness = self.endianness;
+        Ok(dna)
+    }
+
+    fn relaid_struct_size(
+        raw: &RawSdna,
+        struct_of_type: &HashMap<u16, usize>,
+        index: usize,
+        pointer_size: PointerSize,
+        sizes: &mut HashMap<u16, usize>,
+        depth: usize,
+    ) -> Result<usize> {
+        let (type_index, fields) = &raw.structs[index];
+        if let Some(&size) = sizes.get(type_index) {
+            return Ok(size);
+        }
+        if depth > raw.structs.len() {
+            return Err(FormatError::DnaError(format!(
+                "struct {} contains itself",
+                raw.types[*type_index as usize]
+            )));
+        }
+
+        let mut size = 0;
+        for &(field_type, field_name) in fields {
+            let dna_name = DnaName::new(raw.name(field_name)?);
+            let element_size = if dna_name.is_pointer() {
+                pointer_size.bytes()
+            } else if let Some(&nested) = struct_of_type.get(&field_type) {
+                Self::relaid_struct_size(
+                    raw,
+                    struct_of_type,
+                    nested,
+                    pointer_size,
+                    sizes,
+                    depth + 1,
+                )?
+            } else if let Some(known) = raw.known_size(field_type) {
+                known
+            } else {
+                // Without the field's size the struct can't be relaid; keep its TLEN
+                size = raw.tlens[*type_index as usize] as usize;
+                break;
+            };
+            size += element_size * dna_name.array_size();
+        }
+
+        sizes.insert(*type_index, size);
+        Ok(size)
+    }
+
+    /// Write the catalog back as a complete DNA1 block
+    pub fn write_to_writer<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        header: &Header,
+    ) -> Result<()> {
+        if self.raw.types.is_empty() {
+            // Nothing was parsed, so there is no DNA1 block to reproduce
+            return Ok(());
+        }
+
+        let payload = self.raw.to_payload(header.endianness);
+
+        let block = Block {
+            code: *b"DNA1",
+            size: payload.len() as u32,
+            old_memory_address: 0,
+            sdna_index: 0,
+            count: 1,
+            data_offset: 0,
+            data: payload,
+        };
+        block.write_to_writer(writer, header)
+    }
+}
+
+impl RawSdna {
+    /// Read the tables from a DNA1 block's payload
+    pub fn from_payload(data: &[u8], endianness: Endianness) -> Result<Self> {
+        match endianness {
+            Endianness::Little => Self::parse::<LittleEndian>(data),
+            Endianness::Big => Self::parse::<BigEndian>(data),
+        }
+    }
+
+    /// The DNA1 payload holding these tables; for tables read from a file it
+    /// matches the stored payload byte for byte
+    pub fn to_payload(&self, endianness: Endianness) -> Vec<u8> {
+        match endianness {
+            Endianness::Little => self.to_bytes::<LittleEndian>(),
+            Endianness::Big => self.to_bytes::<BigEndian>(),
+        }
+    }
+
+    fn parse<E: ByteOrder>(data: &[u8]) -> Result<Self> {
+        let mut cursor = std::io::Cursor::new(data);
+
+        expect_tag(&mut cursor, b"SDNA")?;
+        expect_tag(&mut cursor, b"NAME")?;
+        let names = read_strings::<E>(&mut cursor)?;
+
+        align4(&mut cursor)?;
+        expect_tag(&mut cursor, b"TYPE")?;
+        let types = read_strings::<E>(&mut cursor)?;
+
+        align4(&mut cursor)?;
+        expect_tag(&mut cursor, b"TLEN")?;
+        let mut tlens = Vec::with_capacity(types.len());
+        for _ in 0..types.len() {
+            tlens.push(cursor.read_u16::<E>()?);
+        }
+
+        align4(&mut cursor)?;
+        expect_tag(&mut cursor, b"STRC")?;
+        let struct_count = cursor.read_u32::<E>()?;
+        let mut structs = Vec::new();
+        for _ in 0..struct_count {
+            let type_index = cursor.read_u16::<E>()?;
+            let field_count = cursor.read_u16::<E>()?;
+            let mut fields = Vec::with_capacity(field_count as usize);
+            for _ in 0..field_count {
+                let field_type = cursor.read_u16::<E>()?;
+                let field_name = cursor.read_u16::<E>()?;
+                fields.push((field_type, field_name));
+            }
+            structs.push((type_index, fields));
+        }
+
+        Ok(RawSdna {
+            names,
+            types,
+            tlens,
+            structs,
+        })
+    }
+
+    fn to_bytes<E: ByteOrder>(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"SDNA");
+
+        for (tag, strings) in [(b"NAME", &self.names), (b"TYPE", &self.types)] {
+            out.extend_from_slice(tag);
+            out.write_u32::<E>(strings.len() as u32).unwrap();
+            for s in strings {
+                out.extend_from_slice(s.as_bytes());
+                out.push(0);
+            }
+            pad4(&mut out);
+        }
+
+        out.extend_from_slice(b"TLEN");
+        for &len in &self.tlens {
+            out.write_u16::<E>(len).unwrap();
+        }
+        pad4(&mut out);
+
+        out.extend_from_slice(b"STRC");
+        out.write_u32::<E>(self.structs.len() as u32).unwrap();
+        for (type_index, fields) in &self.structs {
+            out.write_u16::<E>(*type_index).unwrap();
+            out.write_u16::<E>(fields.len() as u16).unwrap();
+            for &(field_type, field_name) in fields {
+                out.write_u16::<E>(field_type).unwrap();
+                out.write_u16::<E>(field_name).unwrap();
+            }
+        }
+
+        out
+    }
+
+    fn type_name(&self, index: u16) -> Result<&str> {
+        self.types
+            .get(index as usize)
+            .map(String::as_str)
+            .ok_or_else(|| FormatError::DnaError(format!("type index {index} out of range")))
+    }
+
+    /// Size of a non-pointer field of type `index`; `None` for types missing
+    /// from the table and sizeless ones, which only `void` pointers may use
+    fn known_size(&self, index: u16) -> Option<usize> {
+        let len = *self.tlens.get(index as usize)?;
+        (len > 0).then_some(len as usize)
+    }
+
+    fn name(&self, index: u16) -> Result<&str> {
+        self.names
+            .get(index as usize)
+            .map(String::as_str)
+            .ok_or_else(|| FormatError::DnaError(format!("name index {index} out of range")))
+    }
+}
+
+/// One line per table entry with its index, in file order, so dumps from two
+/// files (or from makesdna) diff line by line:
+///
+/// ```text
+/// NAME 0 *next
+/// TYPE 3 ID
+/// TLEN 3 40
+/// STRC 0 ID (type 3, 3 fields)
+///   void *next (type 2, name 0)
+/// ```
+impl std::fmt::Display for RawSdna {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let type_name = |index: u16| self.types.get(index as usize).map_or("?", String::as_str);
+        let name = |index: u16| self.names.get(index as usize).map_or("?", String::as_str);
+        for (index, name) in self.names.iter().enumerate() {
+            writeln!(f, "NAME {index} {name}")?;
+        }
+        for (index, type_name) in self.types.iter().enumerate() {
+            writeln!(f, "TYPE {index} {type_name}")?;
+        }
+        for (index, len) in self.tlens.iter().enumerate() {
+            writeln!(f, "TLEN {index} {len}")?;
+        }
+        for (index, (type_index, fields)) in self.structs.iter().enumerate() {
+            writeln!(
+                f,
+                "STRC {index} {} (type {type_index}, {} fields)",
+                type_name(*type_index),
+                fields.len()
+            )?;
+            for &(field_type, field_name) in fields {
+                writeln!(
+                    f,
+                    "  {} {} (type {field_type}, name {field_name})",
+                    type_name(field_type),
+                    name(field_name)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The warning for a file whose DNA1 blocks start at `offsets`, if there is
+/// more than one
+pub fn duplicate_dna_warning(offsets: &[u64]) -> Option<String> {
+    if offsets.len() < 2 {
+        return None;
+    }
+    let listed: Vec<String> = offsets.iter().map(u64::to_string).collect();
+    Some(format!(
+        "{} DNA1 blocks, at offsets {}; using the last",
+        offsets.len(),
+        listed.join(", ")
+    ))
+}
+
+fn expect_tag<R: Read>(reader: &mut R, tag: &[u8; 4]) -> Result<()> {
+    let mut found = [0u8; 4];
+    reader.read_exact(&mut found)?;
+    if &found != tag {
+        return Err(FormatError::DnaError(format!(
+            "expected {} section, found {:?}",
+            String::from_utf8_lossy(tag),
+            String::from_utf8_lossy(&found)
+        )));
+    }
+    Ok(())
+}
+
+fn read_strings<E: ByteOrder>(cursor: &mut std::io::Cursor<&[u8]>) -> Result<Vec<String>> {
+    let count = cursor.read_u32::<E>()?;
+    let mut strings = Vec::new();
+    for _ in 0..count {
+        let mut bytes = Vec::new();
+        loop {
+            match cursor.read_u8()? {
+                0 => break,
+                b => bytes.push(b),
+            }
+        }
+        strings.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Ok(strings)
+}
+
+fn align4(cursor: &mut std::io::Cursor<&[u8]>) -> Result<()> {
+    let position = cursor.position();
+    cursor.set_position((position + 3) & !3);
+    Ok(())
+}
+
+fn pad4(out: &mut Vec<u8>) {
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Dna {
+    /// The catalog as JSON, structs sorted by name, for tools outside Rust
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| FormatError::IoError(format!("serializing DNA: {e}")))
+    }
+}
+
+/// Structs sorted by name and type sizes by type, so two files' catalogs
+/// diff cleanly
+#[cfg(feature = "serde")]
+impl serde::Serialize for Dna {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut structs: Vec<&DnaStruct> = self.structs_by_index.iter().collect();
+        structs.sort_by(|a, b| a.name.cmp(&b.name));
+        let type_sizes: std::collections::BTreeMap<&String, &usize> =
+            self.type_sizes.iter().collect();
+
+        let mut out = serializer.serialize_struct("Dna", 3)?;
+        out.serialize_field("pointer_size", &self.pointer_size)?;
+        out.serialize_field("structs", &structs)?;
+        out.serialize_field("type_sizes", &type_sizes)?;
+        out.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DnaStruct {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut out = serializer.serialize_struct("DnaStruct", 4)?;
+        out.serialize_field("name", &self.name)?;
+        out.serialize_field("size", &self.size)?;
+        out.serialize_field("incomplete", &self.incomplete)?;
+        out.serialize_field("fields", &self.fields)?;
+        out.end()
+    }
+}
+
+/// `type` is the element type and `size` the whole field's; `full_name`
+/// keeps the pointer stars and array dimensions, and `shape` lists the
+/// dimensions
+#[cfg(feature = "serde")]
+impl serde::Serialize for DnaField {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut out = serializer.serialize_struct("DnaField", 6)?;
+        out.serialize_field("name", &self.name)?;
+        out.serialize_field("full_name", &self.dna_name.name_full)?;
+        out.serialize_field("type", &self.type_name)?;
+        out.serialize_field("offset", &self.offset)?;
+        out.serialize_field("size", &self.size)?;
+        out.serialize_field("shape", &self.dimensions())?;
+        out.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(pointer_size: PointerSize, endianness: Endianness) -> Header {
+        Header {
+            magic: *b"BLENDER",
+            pointer_size,
+            endianness,
+            version: 300,
+        }
+    }
+
+    fn sample_raw() -> RawSdna {
+        RawSdna {
+            names: vec![
+                "*next".to_string(),
+                "*prev".to_string(),
+                "name[24]".to_string(),
+                "id".to_string(),
+                "loc[3]".to_string(),
+                "*data".to_string(),
+            ],
+            types: vec![
+                "char".to_string(),
+                "float".to_string(),
+                "void".to_string(),
+                "ID".to_string(),
+                "Object".to_string(),
+            ],
+            // 64-bit sizes: ID = 8 + 8 + 24, Object = ID + 12 + 8
+            tlens: vec![1, 4, 0, 40, 60],
+            structs: vec![
+                (3, vec![(2, 0), (2, 1), (0, 2)]),
+                (4, vec![(3, 3), (1, 4), (2, 5)]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_dna_creation() {
+        let dna = Dna {
+            structs: HashMap::new(),
+            type_sizes: HashMap::new(),
+            ..Default::default()
+        };
+
+        assert!(dna.structs.is_empty());
+        assert!(dna.type_sizes.is_empty());
+    }
+
+    #[test]
+    fn test_empty_dna_data() {
+        let result = Dna::parse_dna_data(&[], &header(PointerSize::Bits64, Endianness::Little));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_sdna_round_trip() {
+        let raw = sample_raw();
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let header = header(PointerSize::Bits64, endianness);
+            let bytes = match endianness {
+                Endianness::Little => raw.to_bytes::<LittleEndian>(),
+                Endianness::Big => raw.to_bytes::<BigEndian>(),
+            };
+            let dna = Dna::parse_dna_data(&bytes, &header).unwrap();
+            assert_eq!(dna.raw, raw);
+        }
+    }
+
+    #[test]
+    fn test_raw_dump() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        assert_eq!(dna.raw_names()[4], "loc[3]");
+        assert_eq!(dna.raw_types()[3], "ID");
+        assert_eq!(dna.raw_tlens()[3], 40);
+        assert_eq!(dna.raw_structs()[1].0, 4);
+
+        let dump = dna.raw.to_string();
+        assert!(dump.starts_with("NAME 0 *next\nNAME 1 *prev\n"), "{dump}");
+        assert!(dump.contains("TYPE 4 Object\n"), "{dump}");
+        assert!(dump.contains("TLEN 4 60\n"), "{dump}");
+        assert!(
+            dump.ends_with(
+                "STRC 1 Object (type 4, 3 fields)\n  \
+                 ID id (type 3, name 3)\n  \
+                 float loc[3] (type 1, name 4)\n  \
+                 void *data (type 2, name 5)\n"
+            ),
+            "{dump}"
+        );
+    }
+
+    #[test]
+    fn test_field_layout() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let object = dna.struct_by_index(1).unwrap();
+        assert_eq!(object.name, "Object");
+        assert_eq!(object.size, 60);
+
+        let offsets: Vec<(&str, Option<usize>, usize)> = object
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset, f.size))
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                ("id", Some(0), 40),
+                ("loc", Some(40), 12),
+                ("data", Some(52), 8)
+            ]
+        );
+        assert!(object.fields[2].dna_name.is_pointer());
+        assert_eq!(dna.get_type_size("ID"), Some(40));
+    }
+
+    #[test]
+    fn test_fields_keep_parsed_names() {
+        // Object { Material **mat, MTex *mtex[18], float pattern_corners[4][2] }
+        let raw = RawSdna {
+            names: vec![
+                "**mat".to_string(),
+                "*mtex[18]".to_string(),
+                "pattern_corners[4][2]".to_string(),
+            ],
+            types: vec![
+                "float".to_string(),
+                "Material".to_string(),
+                "MTex".to_string(),
+                "Object".to_string(),
+            ],
+            tlens: vec![4, 0, 0, 184],
+            structs: vec![(3, vec![(1, 0), (2, 1), (0, 2)])],
+        };
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        let object = dna.get_struct("Object").unwrap();
+
+        let mat = object.field("mat").unwrap();
+        assert!(mat.is_pointer() && !mat.is_method_pointer());
+        assert_eq!((mat.element_count(), mat.size), (1, 8));
+        assert_eq!(mat.dna_name.name_full, "**mat");
+
+        let mtex = object.field("mtex").unwrap();
+        assert!(mtex.is_pointer());
+        assert_eq!(mtex.dimensions(), [18]);
+        assert_eq!((mtex.element_size(), mtex.size), (8, 144));
+
+        let corners = object.field("pattern_corners").unwrap();
+        assert!(!corners.is_pointer());
+        assert_eq!(corners.dimensions(), [4, 2]);
+        assert_eq!((corners.element_count(), corners.element_size()), (8, 4));
+    }
+
+    #[test]
+    fn test_field_shape() {
+        // Object { float loc[3]; int flag; MTex *mtex[18]; float pattern_corners[4][2];
+        //          float parentinv[4][4][1] }
+        let raw = RawSdna {
+            names: vec![
+                "loc[3]".to_string(),
+                "flag".to_string(),
+                "*mtex[18]".to_string(),
+                "pattern_corners[4][2]".to_string(),
+                "parentinv[4][4][1]".to_string(),
+            ],
+            types: vec![
+                "float".to_string(),
+                "int".to_string(),
+                "MTex".to_string(),
+                "Object".to_string(),
+            ],
+            tlens: vec![4, 4, 0, 184],
+            structs: vec![(3, vec![(0, 0), (1, 1), (2, 2), (0, 3), (0, 4)])],
+        };
+        let dna = Dna::from_raw(raw, PointerSize::Bits32).unwrap();
+        let object = dna.get_struct("Object").unwrap();
+        let shape = |name: &str| {
+            let field = object.field(name).unwrap();
+            (field.dimensions(), field.element_size(), field.size)
+        };
+
+        assert_eq!(shape("flag"), (vec![], 4, 4));
+        assert_eq!(shape("loc"), (vec![3], 4, 12));
+        assert_eq!(shape("pattern_corners"), (vec![4, 2], 4, 32));
+        assert_eq!(shape("parentinv"), (vec![4, 4, 1], 4, 64));
+        // Pointer arrays count pointers, of this file's pointer size
+        assert_eq!(shape("mtex"), (vec![18], 4, 72));
+    }
+
+    #[test]
+    fn test_struct_sizes_follow_pointer_size() {
+        assert!(Dna::from_raw(sample_raw(), PointerSize::Bits64)
+            .unwrap()
+            .check_struct_sizes()
+            .is_ok());
+
+        // 64-bit TLENs read with 4-byte pointers: ID is 4 + 4 + 24
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits32).unwrap();
+        let object = dna.get_struct("Object").unwrap();
+        assert_eq!(object.field("data").unwrap().offset, Some(52));
+        let err = dna.check_struct_sizes().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DNA error: struct ID: fields add up to 32 bytes but TLEN declares 40"
+        );
+
+        let mut raw = sample_raw();
+        raw.tlens[3] = 32;
+        raw.tlens[4] = 48;
+        let dna = Dna::from_raw(raw, PointerSize::Bits32).unwrap();
+        assert!(dna.check_struct_sizes().is_ok());
+        assert_eq!(
+            dna.get_struct("Object")
+                .unwrap()
+                .field("data")
+                .unwrap()
+                .offset,
+            Some(44)
+        );
+
+        // Incomplete structs have no layout to check
+        let dna = Dna::from_raw(future_raw(6), PointerSize::Bits64).unwrap();
+        assert!(dna.check_struct_sizes().is_ok());
+    }
+
+    #[test]
+    fn test_validate_catches_corrupted_tlen() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        assert_eq!(dna.get_type_size("ID"), Some(40));
+        assert_eq!(dna.type_sizes.len(), 5);
+        assert!(dna.validate().is_empty());
+
+        // A float of 8 bytes stretches Object's loc[3] past its declared size
+        let mut raw = sample_raw();
+        raw.tlens[1] = 8;
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        assert_eq!(
+            dna.validate(),
+            [SizeMismatch {
+                sdna_index: 1,
+                struct_name: "Object".to_string(),
+                expected: 60,
+                actual: 72,
+            }]
+        );
+
+        // Every mismatch is listed, not only the first
+        let mut raw = sample_raw();
+        raw.tlens[3] = 48;
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        let listed: Vec<String> = dna.validate().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            listed,
+            [
+                "struct ID: fields add up to 40 bytes but TLEN declares 48",
+                "struct Object: fields add up to 68 bytes but TLEN declares 60",
+            ]
+        );
+        // A strict check fails on the first of them
+        let err = dna.check_struct_sizes().unwrap_err();
+        assert_eq!(err.to_string(), format!("DNA error: {}", listed[0]));
+    }
+
+    #[test]
+    fn test_flattened_fields() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let object = dna.get_struct("Object").unwrap();
+        let fields = object.flattened_fields(&dna);
+        let flat: Vec<(&str, usize, usize)> = fields
+            .iter()
+            .map(|f| (f.path.as_str(), f.offset, f.field.size))
+            .collect();
+        assert_eq!(
+            flat,
+            [
+                ("id.next", 0, 8),
+                ("id.prev", 8, 8),
+                ("id.name", 16, 24),
+                ("loc", 40, 12),
+                ("data", 52, 8),
+            ]
+        );
+        for f in &fields {
+            assert_eq!(
+                dna.field_offset("Object", &f.path).unwrap().offset,
+                f.offset
+            );
+        }
+
+        // A pointer to a struct isn't followed
+        let mut raw = sample_raw();
+        raw.structs[1].1[2] = (3, 5);
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        let object = dna.get_struct("Object").unwrap();
+        let last = object.flattened_fields(&dna).pop().unwrap();
+        assert_eq!(
+            (last.path.as_str(), last.field.type_name.as_str()),
+            ("data", "ID")
+        );
+    }
+
+    #[test]
+    fn test_offset_table_walks_once() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let mut offsets = OffsetTable::new(&dna);
+        for _ in 0..3 {
+            let name = offsets.field_offset("Object", "id.name").unwrap();
+            assert_eq!((name.offset, name.field.size), (16, 24));
+            assert!(offsets.field_offset("Object", "id.missing").is_none());
+        }
+        assert_eq!(offsets.field_offset("ID", "name").unwrap().offset, 16);
+        assert_eq!(offsets.walks(), 3);
+    }
+
+    #[test]
+    fn test_dotted_field_offset() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let name = dna.field_offset("Object", "id.name").unwrap();
+        assert_eq!((name.offset, name.field.size), (16, 24));
+        assert_eq!(name.field.type_name, "char");
+        assert_eq!(dna.field_offset("Object", "loc").unwrap().offset, 40);
+        assert_eq!(dna.field_offset("ID", "name").unwrap().offset, 16);
+
+        let error =
+            |struct_name, path| dna.field_offset(struct_name, path).unwrap_err().to_string();
+        assert_eq!(
+            error("Object", "id.nmae"),
+            "DNA error: Object.id.nmae: ID has no field 'nmae'"
+        );
+        assert_eq!(
+            error("Object", "id.next.name"),
+            "DNA error: Object.id.next.name: next is a pointer and can't be followed"
+        );
+        assert_eq!(
+            error("Object", "loc.x"),
+            "DNA error: Object.loc.x: loc is a float, not a struct"
+        );
+        assert_eq!(
+            error("Object", ""),
+            "DNA error: Object.: Object has no field ''"
+        );
+        assert_eq!(
+            error("Mesh", "id.name"),
+            "DNA error: struct Mesh not in DNA"
+        );
+
+        // Paths past a field of unknown size have no offset
+        let dna = Dna::from_raw(future_raw(6), PointerSize::Bits64).unwrap();
+        assert!(matches!(
+            dna.field_offset("Future", "loc"),
+            Err(FormatError::IncompleteStruct { .. })
+        ));
+        assert_eq!(dna.field_offset("Future", "id.name").unwrap().offset, 16);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_catalog() {
+        let mut raw = sample_raw();
+        // Listed after Object in STRC order, first by name
+        raw.types.push("Camera".to_string());
+        raw.tlens.push(12);
+        raw.structs.push((5, vec![(1, 4)]));
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&dna.to_json().unwrap()).unwrap();
+        assert_eq!(json["pointer_size"], 8);
+        let names: Vec<&str> = json["structs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Camera", "ID", "Object"]);
+
+        let loc = &json["structs"][2]["fields"][1];
+        assert_eq!(
+            *loc,
+            serde_json::json!({
+                "name": "loc",
+                "full_name": "loc[3]",
+                "type": "float",
+                "offset": 40,
+                "size": 12,
+                "shape": [3],
+            })
+        );
+        assert_eq!(json["structs"][2]["fields"][2]["full_name"], "*data");
+        assert_eq!(json["type_sizes"]["Object"], 60);
+
+        // Same catalog, same bytes
+        let again = Dna::from_raw(dna.raw.clone(), PointerSize::Bits64).unwrap();
+        assert_eq!(again.to_json().unwrap(), dna.to_json().unwrap());
+    }
+
+    #[test]
+    fn test_malformed_names_become_warnings() {
+        let mut raw = sample_raw();
+        raw.names.push("broken[2".to_string());
+        let broken = raw.names.len() as u16 - 1;
+        raw.structs[0].1.push((0, broken));
+
+        let dna = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        assert_eq!(dna.warnings.len(), 1);
+        assert!(dna.warnings[0].contains("broken[2"), "{}", dna.warnings[0]);
+        assert!(dna.struct_by_index(0).unwrap().field("broken").is_some());
+    }
+
+    /// `sample_raw` plus `Future { id, blob, loc[3] }`, where `blob`'s type is
+    /// `Gizmo` (no size) or an index past the type table
+    fn future_raw(blob_type: u16) -> RawSdna {
+        let mut raw = sample_raw();
+        raw.names.push("blob".to_string());
+        raw.types
+            .extend(["Future".to_string(), "Gizmo".to_string()]);
+        raw.tlens.extend([100, 0]);
+        raw.structs.push((5, vec![(3, 3), (blob_type, 6), (1, 4)]));
+        raw
+    }
+
+    #[test]
+    fn test_unknown_types_mark_struct_incomplete() {
+        for blob_type in [6, 99] {
+            let dna = Dna::from_raw(future_raw(blob_type), PointerSize::Bits64).unwrap();
+
+            // Structs without unknown types are unaffected
+            let object = dna.get_struct("Object").unwrap();
+            assert!(!object.incomplete);
+            assert!(object.check_complete().is_ok());
+            assert_eq!(object.offset_of(object.field("data").unwrap()).unwrap(), 52);
+
+            let future = dna.get_struct("Future").unwrap();
+            assert!(future.incomplete);
+            assert_eq!(future.size, 100);
+            let offsets: Vec<Option<usize>> = future.fields.iter().map(|f| f.offset).collect();
+            assert_eq!(offsets, [Some(0), Some(40), None]);
+            assert_eq!(future.fields[1].size, 0);
+            assert_eq!(future.unsized_field().unwrap().name, "blob");
+            assert_eq!(future.offset_of(future.field("id").unwrap()).unwrap(), 0);
+
+            let err = future.offset_of(future.field("loc").unwrap()).unwrap_err();
+            assert!(
+                matches!(&err, FormatError::IncompleteStruct { struct_name, field }
+                    if struct_name == "Future" && field == "loc"),
+                "{err}"
+            );
+            assert!(matches!(
+                future.check_complete(),
+                Err(FormatError::IncompleteStruct { field, .. }) if field == "blob"
+            ));
+            assert_eq!(dna.warnings.len(), 1);
+            assert!(
+                dna.warnings[0].contains("Future: blob"),
+                "{}",
+                dna.warnings[0]
+            );
+        }
+        let dna = Dna::from_raw(future_raw(99), PointerSize::Bits64).unwrap();
+        assert_eq!(
+            dna.get_struct("Future").unwrap().fields[1].type_name,
+            "<type 99>"
+        );
+    }
+
+    #[test]
+    fn test_with_pointer_size_keeps_incomplete_struct_size() {
+        let dna = Dna::from_raw(future_raw(99), PointerSize::Bits64).unwrap();
+        let narrow = dna.with_pointer_size(PointerSize::Bits32).unwrap();
+        assert_eq!(narrow.get_struct("Object").unwrap().size, 48);
+        assert_eq!(narrow.get_struct("Future").unwrap().size, 100);
+        assert!(narrow.get_struct("Future").unwrap().incomplete);
+    }
+
+    #[test]
+    fn test_with_pointer_size() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let narrow = dna.with_pointer_size(PointerSize::Bits32).unwrap();
+
+        assert_eq!(narrow.get_struct("ID").unwrap().size, 32);
+        let object = narrow.get_struct("Object").unwrap();
+        assert_eq!(object.size, 48);
+        assert_eq!(object.fields[1].offset, Some(32));
+        assert_eq!(object.fields[2].offset, Some(44));
+        assert_eq!(object.fields[2].size, 4);
+        assert_eq!(narrow.raw.tlens, vec![1, 4, 0, 32, 48]);
+
+        let wide = narrow.with_pointer_size(PointerSize::Bits64).unwrap();
+        assert_eq!(wide.raw, dna.raw);
+    }
+
+    #[test]
+    fn test_fingerprint_stable_across_parses() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        let fingerprint = dna.fingerprint();
+        assert_eq!(fingerprint.to_string().len(), 16);
+        assert_eq!(
+            fingerprint.to_string().parse::<DnaFingerprint>().unwrap(),
+            fingerprint
+        );
+
+        // Byte order and catalog hashing don't enter into it
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let header = header(PointerSize::Bits64, endianness);
+            let bytes = match endianness {
+                Endianness::Little => dna.raw.to_bytes::<LittleEndian>(),
+                Endianness::Big => dna.raw.to_bytes::<BigEndian>(),
+            };
+            let parsed = Dna::parse_dna_data(&bytes, &header).unwrap();
+            assert_eq!(parsed.fingerprint(), fingerprint);
+        }
+
+        // Swapping two fields is a different layout
+        let mut raw = sample_raw();
+        raw.structs[1].1.swap(1, 2);
+        let swapped = Dna::from_raw(raw, PointerSize::Bits64).unwrap();
+        assert_ne!(swapped.fingerprint(), fingerprint);
+        assert_ne!(Dna::default().fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_stats() {
+        let dna = Dna::from_raw(sample_raw(), PointerSize::Bits64).unwrap();
+        assert_eq!(
+            dna.stats(),
+            DnaStats {
+                struct_count: 2,
+                field_count: 6,
+                max_struct_size: 60,
+            }
+        );
+        assert_eq!(Dna::default().stats().max_struct_size, 0);
+    }
+}
@@ -0,0 +1,265 @@
+//! How struct layouts differ between two DNA catalogs, e.g. files saved by
+//! two Blender versions.
+
+use crate::dna::{Dna, DnaField, DnaStruct};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Structs only in one catalog, and the changes to structs in both. Names
+/// are sorted, so the same two catalogs always give the same diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DnaDiff {
+    /// Structs only in the newer catalog
+    pub added: Vec<String>,
+    /// Structs only in the older catalog
+    pub removed: Vec<String>,
+    pub changed: Vec<StructDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StructDiff {
+    pub name: String,
+    /// Declared size before and after, when it changed
+    pub size: Option<(usize, usize)>,
+    /// Removed, retyped and moved fields in the older order, then added ones
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "change", rename_all = "snake_case"))]
+pub enum FieldChange {
+    Added {
+        name: String,
+        /// Declaration such as `float loc[3]`
+        declaration: String,
+        offset: Option<usize>,
+    },
+    Removed {
+        name: String,
+        declaration: String,
+    },
+    /// The type, pointer-ness or array dimensions changed
+    Retyped {
+        name: String,
+        old: String,
+        new: String,
+    },
+    Moved {
+        name: String,
+        old: Option<usize>,
+        new: Option<usize>,
+    },
+}
+
+impl DnaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Dna {
+    /// What changed from this catalog to `other`
+    pub fn diff(&self, other: &Dna) -> DnaDiff {
+        let old: BTreeSet<&String> = self.structs.keys().collect();
+        let new: BTreeSet<&String> = other.structs.keys().collect();
+        DnaDiff {
+            added: new.difference(&old).map(|name| name.to_string()).collect(),
+            removed: old.difference(&new).map(|name| name.to_string()).collect(),
+            changed: old
+                .intersection(&new)
+                .filter_map(|name| diff_struct(&self.structs[*name], &other.structs[*name]))
+                .collect(),
+        }
+    }
+}
+
+fn diff_struct(old: &DnaStruct, new: &DnaStruct) -> Option<StructDiff> {
+    let mut fields = Vec::new();
+    for field in &old.fields {
+        let Some(now) = new.field(&field.name) else {
+            fields.push(FieldChange::Removed {
+                name: field.name.clone(),
+                declaration: declaration(field),
+            });
+            continue;
+        };
+        let (before, after) = (declaration(field), declaration(now));
+        if before != after {
+            fields.push(FieldChange::Retyped {
+                name: field.name.clone(),
+                old: before,
+                new: after,
+            });
+        }
+        if field.offset != now.offset {
+            fields.push(FieldChange::Moved {
+                name: field.name.clone(),
+                old: field.offset,
+                new: now.offset,
+            });
+        }
+    }
+    for field in new.fields.iter().filter(|f| old.field(&f.name).is_none()) {
+        fields.push(FieldChange::Added {
+            name: field.name.clone(),
+            declaration: declaration(field),
+            offset: field.offset,
+        });
+    }
+
+    let size = (old.size != new.size).then_some((old.size, new.size));
+    (size.is_some() || !fields.is_empty()).then(|| StructDiff {
+        name: old.name.clone(),
+        size,
+        fields,
+    })
+}
+
+fn declaration(field: &DnaField) -> String {
+    format!("{} {}", field.type_name, field.dna_name.name_full)
+}
+
+fn offset(offset: &Option<usize>) -> String {
+    offset.map_or_else(|| "?".to_string(), |offset| offset.to_string())
+}
+
+impl fmt::Display for DnaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added {
+            writeln!(f, "+ struct {name}")?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- struct {name}")?;
+        }
+        for change in &self.changed {
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StructDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~ struct {}", self.name)?;
+        if let Some((old, new)) = self.size {
+            write!(f, " (size {old} -> {new})")?;
+        }
+        writeln!(f)?;
+        for field in &self.fields {
+            writeln!(f, "    {field}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Added {
+                declaration,
+                offset: at,
+                ..
+            } => write!(f, "+ {declaration} at {}", offset(at)),
+            FieldChange::Removed { declaration, .. } => write!(f, "- {declaration}"),
+            FieldChange::Retyped { name, old, new } => write!(f, "~ {name}: {old} -> {new}"),
+            FieldChange::Moved { name, old, new } => {
+                write!(f, "~ {name}: offset {} -> {}", offset(old), offset(new))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dna::RawSdna;
+    use crate::header::PointerSize;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// ID { *next, name[24] }, Object { ID id, float loc[3], int flag },
+    /// Lamp { ID id }
+    fn old_raw() -> RawSdna {
+        RawSdna {
+            names: strings(&["*next", "name[24]", "id", "loc[3]", "flag"]),
+            types: strings(&["char", "float", "int", "void", "ID", "Object", "Lamp"]),
+            tlens: vec![1, 4, 4, 0, 32, 48, 32],
+            structs: vec![
+                (4, vec![(3, 0), (0, 1)]),
+                (5, vec![(4, 2), (1, 3), (2, 4)]),
+                (6, vec![(4, 2)]),
+            ],
+        }
+    }
+
+    /// Lamp became Light, Object.loc became double, `flag` moved behind a
+    /// new `*data`
+    fn new_raw() -> RawSdna {
+        RawSdna {
+            names: strings(&["*next", "name[24]", "id", "loc[3]", "flag", "*data"]),
+            types: strings(&["char", "double", "int", "void", "ID", "Object", "Light"]),
+            tlens: vec![1, 8, 4, 0, 32, 72, 32],
+            structs: vec![
+                (4, vec![(3, 0), (0, 1)]),
+                (5, vec![(4, 2), (1, 3), (3, 5), (2, 4)]),
+                (6, vec![(4, 2)]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Dna::from_raw(old_raw(), PointerSize::Bits64).unwrap();
+        let new = Dna::from_raw(new_raw(), PointerSize::Bits64).unwrap();
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.added, ["Light"]);
+        assert_eq!(diff.removed, ["Lamp"]);
+        assert_eq!(diff.changed.len(), 1);
+        let object = &diff.changed[0];
+        assert_eq!(object.size, Some((48, 72)));
+        assert_eq!(
+            object.fields,
+            [
+                FieldChange::Retyped {
+                    name: "loc".to_string(),
+                    old: "float loc[3]".to_string(),
+                    new: "double loc[3]".to_string(),
+                },
+                FieldChange::Moved {
+                    name: "flag".to_string(),
+                    old: Some(44),
+                    new: Some(64),
+                },
+                FieldChange::Added {
+                    name: "data".to_string(),
+                    declaration: "void *data".to_string(),
+                    offset: Some(56),
+                },
+            ]
+        );
+
+        assert_eq!(
+            diff.to_string(),
+            "+ struct Light\n\
+             - struct Lamp\n\
+             ~ struct Object (size 48 -> 72)\n    \
+             ~ loc: float loc[3] -> double loc[3]\n    \
+             ~ flag: offset 44 -> 64\n    \
+             + void *data at 56\n"
+        );
+
+        // The other way round, and against itself
+        let back = new.diff(&old);
+        assert_eq!(
+            (back.added, back.removed),
+            (strings(&["Lamp"]), strings(&["Light"]))
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+}
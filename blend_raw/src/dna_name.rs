@@ -0,0 +1,110 @@
+// DNA Name logic for Rust port of test_blendfile_dna.py
+use crate::error::{FormatError, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DnaName {
+    pub name_full: String,
+}
+
+impl DnaName {
+    /// Wrap a name without validating it; malformed brackets are ignored by the accessors
+    pub fn new(name: &str) -> Self {
+        Self {
+            name_full: name.to_string(),
+        }
+    }
+
+    /// Validate `name` as identifier, optional `[N]` dimensions and optional `:bits` suffix
+    pub fn parse(name: &str) -> Result<Self> {
+        let malformed =
+            |why: &str| FormatError::DnaError(format!("malformed DNA name {name:?}: {why}"));
+        if name.is_empty() {
+            return Err(malformed("empty name"));
+        }
+
+        let base_end = name.find(['[', ':']).unwrap_or(name.len());
+        if name[..base_end].contains(']') {
+            return Err(malformed("unbalanced ']'"));
+        }
+        let parsed = Self::new(name);
+        if parsed.name_only().is_empty() {
+            return Err(malformed("no identifier"));
+        }
+
+        let mut rest = &name[base_end..];
+        while let Some(inner) = rest.strip_prefix('[') {
+            let end = inner.find(']').ok_or_else(|| malformed("unbalanced '['"))?;
+            if inner[..end].parse::<usize>().is_err() {
+                return Err(malformed("non-numeric dimension"));
+            }
+            rest = &inner[end + 1..];
+        }
+        if let Some(bits) = rest.strip_prefix(':') {
+            if bits.parse::<u32>().is_err() {
+                return Err(malformed("invalid bit width"));
+            }
+            rest = "";
+        }
+        if !rest.is_empty() {
+            return Err(malformed("unexpected characters after dimensions"));
+        }
+
+        Ok(parsed)
+    }
+    pub fn name_only(&self) -> String {
+        let mut s = self.name_full.as_str();
+        // Remove pointer and method pointer syntax
+        if s.starts_with("(*") {
+            s = &s[2..];
+            if let Some(end) = s.find(")()") {
+                s = &s[..end];
+            }
+        } else {
+            // `**mat` is a pointer to pointers
+            s = s.trim_start_matches('*');
+        }
+        // Remove array brackets and bit widths
+        if let Some(idx) = s.find(['[', ':']) {
+            s = &s[..idx];
+        }
+        s.to_string()
+    }
+    pub fn is_pointer(&self) -> bool {
+        self.name_full.starts_with('*') || self.name_full.starts_with("(*")
+    }
+    pub fn is_method_pointer(&self) -> bool {
+        self.name_full.starts_with("(*")
+    }
+    pub fn array_size(&self) -> usize {
+        self.element_count()
+    }
+
+    /// Number of elements: the product of all dimensions, 1 for scalars
+    pub fn element_count(&self) -> usize {
+        self.dimensions().iter().product()
+    }
+
+    /// Array dimensions in declaration order, e.g. `[2, 4]` for `*texpaintslot[2][4]`
+    pub fn dimensions(&self) -> Vec<usize> {
+        let mut dimensions = Vec::new();
+        let mut s = self.name_full.as_str();
+        while let Some(start) = s.find('[') {
+            if let Some(end) = s[start + 1..].find(']') {
+                let num = &s[start + 1..start + 1 + end];
+                if let Ok(n) = num.parse::<usize>() {
+                    dimensions.push(n);
+                }
+                s = &s[start + 1 + end + 1..];
+            } else {
+                break;
+            }
+        }
+        dimensions
+    }
+
+    /// Width of a bit-field style name such as `flag:1`
+    pub fn bit_width(&self) -> Option<u32> {
+        let (_, bits) = self.name_full.rsplit_once(':')?;
+        bits.parse().ok()
+    }
+}
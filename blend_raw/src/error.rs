@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FormatError {
+    IoError(String),
+    InvalidFormat(String),
+    UnsupportedVersion(String),
+    DnaError(String),
+    BlockError(String),
+    ParseError(String),
+    /// A field lies in or past a struct field whose type has no known size
+    IncompleteStruct {
+        struct_name: String,
+        field: String,
+    },
+}
+
+/// The error parameter defaults to `FormatError`, so a glob import of this
+/// alias still accepts `Result<T, OtherError>`
+pub type Result<T, E = FormatError> = std::result::Result<T, E>;
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::IoError(msg) => write!(f, "IO error: {msg}"),
+            FormatError::InvalidFormat(msg) => write!(f, "Invalid format: {msg}"),
+            FormatError::UnsupportedVersion(msg) => write!(f, "Unsupported version: {msg}"),
+            FormatError::DnaError(msg) => write!(f, "DNA error: {msg}"),
+            FormatError::BlockError(msg) => write!(f, "Block error: {msg}"),
+            FormatError::ParseError(msg) => write!(f, "Parse error: {msg}"),
+            FormatError::IncompleteStruct { struct_name, field } => write!(
+                f,
+                "Incomplete struct: {struct_name}.{field} follows a field of unknown type"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(err: io::Error) -> Self {
+        FormatError::IoError(err.to_string())
+    }
+}
+
+impl From<std::str::Utf8Error> for FormatError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        FormatError::ParseError(err.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for FormatError {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        FormatError::ParseError(err.to_string())
+    }
+}
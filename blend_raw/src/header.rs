@@ -1,5 +1,5 @@
-use crate::error::{BlendFileError, Result};
-use std::io::{Read, Seek};
+use crate::error::{FormatError, Result};
+use std::io::Read;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PointerSize {
@@ -16,8 +16,9 @@ impl PointerSize {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Endianness {
+    #[default]
     Little,
     Big,
 }
@@ -31,12 +32,15 @@ pub struct Header {
 }
 
 impl Header {
-    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    /// Size in bytes of the file header preceding the first block
+    pub const SIZE: usize = 12;
+
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
         let mut magic = [0u8; 7];
         reader.read_exact(&mut magic)?;
 
         if &magic != b"BLENDER" {
-            return Err(BlendFileError::InvalidFormat(format!(
+            return Err(FormatError::InvalidFormat(format!(
                 "Invalid magic: {:?}",
                 String::from_utf8_lossy(&magic)
             )));
@@ -49,7 +53,7 @@ impl Header {
             b'_' => PointerSize::Bits32,
             b'-' => PointerSize::Bits64,
             _ => {
-                return Err(BlendFileError::InvalidFormat(format!(
+                return Err(FormatError::InvalidFormat(format!(
                     "Invalid pointer size indicator: {}",
                     pointer_size_byte[0] as char
                 )))
@@ -63,7 +67,7 @@ impl Header {
             b'v' => Endianness::Little,
             b'V' => Endianness::Big,
             _ => {
-                return Err(BlendFileError::InvalidFormat(format!(
+                return Err(FormatError::InvalidFormat(format!(
                     "Invalid endianness indicator: {}",
                     endianness_byte[0] as char
                 )))
@@ -74,9 +78,9 @@ impl Header {
         reader.read_exact(&mut version_bytes)?;
 
         let version_str = String::from_utf8_lossy(&version_bytes);
-        let version = version_str.parse::<u32>().map_err(|_| {
-            BlendFileError::InvalidFormat(format!("Invalid version: {version_str}"))
-        })?;
+        let version = version_str
+            .parse::<u32>()
+            .map_err(|_| FormatError::InvalidFormat(format!("Invalid version: {version_str}")))?;
 
         Ok(Header {
             magic,
@@ -87,6 +91,13 @@ impl Header {
     }
 
     pub fn write_to_writer<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        // The version field is three digits wide
+        if self.version > 999 {
+            return Err(FormatError::InvalidFormat(format!(
+                "version {} doesn't fit the header",
+                self.version
+            )));
+        }
         writer.write_all(&self.magic)?;
 
         let pointer_size_byte = match self.pointer_size {
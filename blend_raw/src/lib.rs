@@ -0,0 +1,13 @@
+//! Low-level reading and writing of the .blend binary format: the file
+//! header, block headers and payloads, and the SDNA catalog.
+
+pub mod block;
+pub mod bytes;
+pub mod dna;
+pub mod dna_diff;
+pub mod dna_io;
+pub mod dna_name;
+pub mod error;
+pub mod header;
+
+pub use error::{FormatError, Result};
@@ -0,0 +1,18 @@
+#!/usr/bin/env sh
+# Check the public API of the workspace crates for semver-breaking changes.
+#
+# The baseline is the commit that added this script (the API audit that made
+# the growing enums and options types non-exhaustive); set BASELINE_REV to
+# compare against a release tag instead. Needs cargo-semver-checks:
+#   cargo install cargo-semver-checks --locked
+set -eu
+
+cd "$(dirname "$0")/.."
+
+baseline=${BASELINE_REV:-$(git log --diff-filter=A --format=%H -- scripts/semver-check.sh | tail -n 1)}
+if [ -z "$baseline" ]; then
+    echo "no baseline commit found; set BASELINE_REV" >&2
+    exit 2
+fi
+
+cargo semver-checks check-release --workspace --baseline-rev "$baseline"
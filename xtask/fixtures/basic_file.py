@@ -0,0 +1,4 @@
+# Factory startup scene with a known frame range
+bpy.context.scene.frame_start = 1
+bpy.context.scene.frame_end = 250
+save()
@@ -0,0 +1,4 @@
+# The default Cube's X location driven by the frame number
+curve = bpy.data.objects["Cube"].driver_add("location", 0)
+curve.driver.expression = "frame / 10"
+save()
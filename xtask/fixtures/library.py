@@ -0,0 +1,2 @@
+# Factory startup scene (Cube, Camera, Light), linked from by other fixtures
+save()
@@ -0,0 +1,7 @@
+# Local factory scene plus the library's Cube linked in through a relative path
+save()
+with bpy.data.libraries.load(library, link=True, relative=True) as (src, dst):
+    dst.objects = ["Cube"]
+for ob in dst.objects:
+    bpy.context.scene.collection.objects.link(ob)
+save()
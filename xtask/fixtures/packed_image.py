@@ -0,0 +1,5 @@
+# A generated image packed into the file
+image = bpy.data.images.new("Packed", 4, 4)
+image.pack()
+image.use_fake_user = True
+save()
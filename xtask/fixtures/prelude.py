@@ -0,0 +1,12 @@
+# Shared by every fixture script: arguments after "--" are
+# <output path> <compress 0|1> <library path>
+import sys
+
+import bpy
+
+out, compress, library = sys.argv[sys.argv.index("--") + 1:][:3]
+compress = compress == "1"
+
+
+def save():
+    bpy.ops.wm.save_as_mainfile(filepath=out, compress=compress)
@@ -0,0 +1,5 @@
+# One color strip in the sequence editor
+editor = bpy.context.scene.sequence_editor_create()
+strips = getattr(editor, "strips", None) or editor.sequences
+strips.new_effect(name="Color", type="COLOR", channel=1, frame_start=1, frame_end=50)
+save()
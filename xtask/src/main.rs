@@ -0,0 +1,172 @@
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+#[derive(Parser)]
+#[command(name = "xtask")]
+#[command(about = "Development tasks for the workspace")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate the .blend test fixtures with headless Blender
+    GenFixtures {
+        /// Blender executable; repeat for several versions. Defaults to
+        /// $BLENDER_BIN, which may list several paths like $PATH does.
+        #[arg(long = "blender", value_name = "PATH")]
+        blenders: Vec<PathBuf>,
+
+        /// Output directory (defaults to blend_file_reader/tests/test_blend_files)
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+}
+
+/// One generated file: `script` runs after the shared prelude
+struct Fixture {
+    file: &'static str,
+    script: &'static str,
+    compress: bool,
+}
+
+const PRELUDE: &str = include_str!("../fixtures/prelude.py");
+
+/// Written in order; `library.blend` comes first because others link to it
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        file: "library.blend",
+        script: include_str!("../fixtures/library.py"),
+        compress: false,
+    },
+    Fixture {
+        file: "basic_file.blend",
+        script: include_str!("../fixtures/basic_file.py"),
+        compress: false,
+    },
+    Fixture {
+        file: "basic_file_compressed.blend",
+        script: include_str!("../fixtures/basic_file.py"),
+        compress: true,
+    },
+    Fixture {
+        file: "linked_cube.blend",
+        script: include_str!("../fixtures/linked_cube.py"),
+        compress: false,
+    },
+    Fixture {
+        file: "linked_cube_compressed.blend",
+        script: include_str!("../fixtures/linked_cube.py"),
+        compress: true,
+    },
+    Fixture {
+        file: "packed_image.blend",
+        script: include_str!("../fixtures/packed_image.py"),
+        compress: false,
+    },
+    Fixture {
+        file: "driver.blend",
+        script: include_str!("../fixtures/driver.py"),
+        compress: false,
+    },
+    Fixture {
+        file: "vse_strip.blend",
+        script: include_str!("../fixtures/vse_strip.py"),
+        compress: false,
+    },
+];
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Commands::GenFixtures { blenders, out } => gen_fixtures(blenders, out),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn gen_fixtures(mut blenders: Vec<PathBuf>, out: Option<PathBuf>) -> Result<(), String> {
+    if blenders.is_empty() {
+        if let Some(paths) = std::env::var_os("BLENDER_BIN") {
+            blenders = std::env::split_paths(&paths).collect();
+        }
+    }
+    if blenders.is_empty() {
+        return Err("no Blender found; pass --blender or set BLENDER_BIN".to_string());
+    }
+
+    let out = out.unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../blend_file_reader/tests/test_blend_files")
+    });
+
+    // The first Blender fills the directory the tests read; others get a
+    // subdirectory per version
+    for (i, blender) in blenders.iter().enumerate() {
+        let version = blender_version(blender)?;
+        let dir = if i == 0 {
+            out.clone()
+        } else {
+            out.join(&version)
+        };
+        std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        println!("Blender {version} -> {}", dir.display());
+
+        let library = dir.join("library.blend");
+        for fixture in FIXTURES {
+            let path = dir.join(fixture.file);
+            run_script(blender, fixture, &path, &library)?;
+            println!("  {}", fixture.file);
+        }
+    }
+    Ok(())
+}
+
+/// "4.2.0" from the first line of `blender --version`
+fn blender_version(blender: &Path) -> Result<String, String> {
+    let output = Command::new(blender)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("{}: {e}", blender.display()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Blender "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string)
+        .ok_or_else(|| format!("{}: can't read the Blender version", blender.display()))
+}
+
+fn run_script(
+    blender: &Path,
+    fixture: &Fixture,
+    path: &Path,
+    library: &Path,
+) -> Result<(), String> {
+    let script = format!("{PRELUDE}\n{}", fixture.script);
+    let status = Command::new(blender)
+        .args([
+            "--background",
+            "--factory-startup",
+            "--python-exit-code",
+            "1",
+        ])
+        .arg("--python-expr")
+        .arg(script)
+        .arg("--")
+        .arg(path)
+        .arg(if fixture.compress { "1" } else { "0" })
+        .arg(library)
+        .status()
+        .map_err(|e| format!("{}: {e}", blender.display()))?;
+    if !status.success() {
+        return Err(format!("{} failed ({status})", fixture.file));
+    }
+    Ok(())
+}